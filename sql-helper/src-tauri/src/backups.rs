@@ -0,0 +1,281 @@
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+use tauri::State;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::profiles::{ActiveProfile, Permission};
+use crate::storage::app_data_subdir;
+use crate::{history, snapshots, templates, workspace};
+
+// Dated zip archives of the user's configuration under `<app data>/backups`. One is made per
+// day while the app runs; the newest `AppSettings::backup_retention` are kept.
+
+const BACKUPS_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "backup-";
+const SETTINGS_FILE: &str = "db_settings.json";
+const DEFAULT_RETENTION: usize = 7;
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// What an archive holds, as top-level folders inside the zip.
+pub const SCOPES: [&str; 4] = ["settings", "snippets", "history", "pinned"];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+    pub scopes: Vec<String>,
+}
+
+/// Archives beyond the newest `retention`, oldest first. Names carry the timestamp, so they
+/// sort chronologically; a retention of 0 keeps everything.
+pub fn expired_backups(names: &[String], retention: usize) -> Vec<String> {
+    let mut sorted: Vec<String> = names.to_vec();
+    sorted.sort();
+    if retention == 0 || sorted.len() <= retention {
+        return Vec::new();
+    }
+    let excess = sorted.len() - retention;
+    sorted.truncate(excess);
+    sorted
+}
+
+/// Scope of a zip entry (`"pinned/a.json"` -> `"pinned"`).
+pub fn entry_scope(entry: &str) -> Option<&'static str> {
+    let first = entry.split('/').next()?;
+    SCOPES.iter().find(|s| **s == first).copied()
+}
+
+fn backups_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_data_subdir(handle, BACKUPS_DIR)
+}
+
+fn retention(handle: &tauri::AppHandle) -> usize {
+    crate::read_db_settings(handle).ok().and_then(|s| s.backup_retention).unwrap_or(DEFAULT_RETENTION)
+}
+
+/// Folder a scope lives in. The settings scope only covers `SETTINGS_FILE` in it.
+fn scope_root(handle: &tauri::AppHandle, scope: &str) -> Result<PathBuf, String> {
+    match scope {
+        "settings" => {
+            let dir = handle.path_resolver().app_config_dir().ok_or("Could not find app config dir")?;
+            fs::create_dir_all(&dir).map_err(|e: std::io::Error| e.to_string())?;
+            Ok(dir)
+        }
+        "snippets" => app_data_subdir(handle, templates::TEMPLATES_DIR),
+        "history" => app_data_subdir(handle, history::HISTORY_DIR),
+        "pinned" => app_data_subdir(handle, snapshots::PINNED_DIR),
+        other => Err(format!("Phạm vi sao lưu không hợp lệ: {}", other)),
+    }
+}
+
+/// `(path, entry name inside the zip)` of every file in `scope`.
+fn scope_files(handle: &tauri::AppHandle, scope: &str) -> Result<Vec<(PathBuf, String)>, String> {
+    let root = scope_root(handle, scope)?;
+    if scope == "settings" {
+        let path = root.join(SETTINGS_FILE);
+        return Ok(if path.exists() { vec![(path, format!("{}/{}", scope, SETTINGS_FILE))] } else { Vec::new() });
+    }
+    let mut files = Vec::new();
+    workspace::collect_files(&root, &mut files);
+    Ok(files.into_iter()
+        .filter_map(|f| {
+            let relative = f.path.strip_prefix(&root).ok()?.to_string_lossy().replace('\\', "/");
+            Some((f.path, format!("{}/{}", scope, relative)))
+        })
+        .collect())
+}
+
+fn write_archive(handle: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e: std::io::Error| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for scope in SCOPES {
+        for (source, entry) in scope_files(handle, scope)? {
+            let content = fs::read(&source).map_err(|e: std::io::Error| e.to_string())?;
+            zip.start_file(entry, options).map_err(|e| e.to_string())?;
+            zip.write_all(&content).map_err(|e: std::io::Error| e.to_string())?;
+        }
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn backup_names(dir: &Path) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e: std::io::Error| e.to_string())? {
+        let path = entry.map_err(|e: std::io::Error| e.to_string())?.path();
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        if path.extension().map_or(false, |e| e == "zip") && stem.starts_with(BACKUP_PREFIX) {
+            names.push(stem);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn describe(dir: &Path, name: &str) -> Result<BackupInfo, String> {
+    let path = dir.join(format!("{}.zip", name));
+    let meta = fs::metadata(&path).map_err(|e: std::io::Error| e.to_string())?;
+    let created: chrono::DateTime<chrono::Local> = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH).into();
+    let archive = ZipArchive::new(File::open(&path).map_err(|e: std::io::Error| e.to_string())?).map_err(|e| e.to_string())?;
+    let scopes = SCOPES.iter()
+        .filter(|s| archive.file_names().any(|n| entry_scope(n) == Some(**s)))
+        .map(|s| s.to_string())
+        .collect();
+    Ok(BackupInfo {
+        name: name.to_string(),
+        created_at: created.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size_bytes: meta.len(),
+        scopes,
+    })
+}
+
+/// Writes a new archive and drops the ones past the retention count.
+pub fn make_backup(handle: &tauri::AppHandle) -> Result<BackupInfo, String> {
+    let dir = backups_dir(handle)?;
+    let name = format!("{}{}", BACKUP_PREFIX, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let path = dir.join(format!("{}.zip", name));
+    if let Err(e) = write_archive(handle, &path) {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+    for old in expired_backups(&backup_names(&dir)?, retention(handle)) {
+        let _ = fs::remove_file(dir.join(format!("{}.zip", old)));
+    }
+    describe(&dir, &name)
+}
+
+fn backup_due(handle: &tauri::AppHandle) -> Result<bool, String> {
+    let dir = backups_dir(handle)?;
+    let newest = match backup_names(&dir)?.pop() {
+        Some(name) => name,
+        None => return Ok(true),
+    };
+    let modified = fs::metadata(dir.join(format!("{}.zip", newest)))
+        .and_then(|m| m.modified())
+        .map_err(|e: std::io::Error| e.to_string())?;
+    Ok(SystemTime::now().duration_since(modified).unwrap_or_default() >= BACKUP_INTERVAL)
+}
+
+/// Checks hourly and backs up when the newest archive is a day old. A retention of 0 turns the
+/// automatic backup off.
+pub fn start_scheduler(handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let job = handle.clone();
+            let outcome = tokio::task::spawn_blocking(move || -> Result<(), String> {
+                if retention(&job) > 0 && backup_due(&job)? {
+                    make_backup(&job)?;
+                }
+                Ok(())
+            }).await;
+            if let Ok(Err(e)) = outcome {
+                eprintln!("Automatic backup failed: {}", e);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Replaces the files of `scope` with the archived ones. Folders are cleared first so files
+/// created after the backup do not linger; for settings only the settings file is touched.
+fn restore_scope(handle: &tauri::AppHandle, archive: &mut ZipArchive<Cursor<Vec<u8>>>, scope: &str) -> Result<(), String> {
+    let root = scope_root(handle, scope)?;
+    if scope != "settings" {
+        let mut existing = Vec::new();
+        workspace::collect_files(&root, &mut existing);
+        for f in existing {
+            fs::remove_file(&f.path).map_err(|e: std::io::Error| e.to_string())?;
+        }
+    }
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() || entry_scope(entry.name()) != Some(scope) {
+            continue;
+        }
+        let relative = match entry.enclosed_name().and_then(|p| p.strip_prefix(scope).ok().map(Path::to_path_buf)) {
+            Some(r) if !r.as_os_str().is_empty() => r,
+            _ => continue,
+        };
+        if scope == "settings" && relative != Path::new(SETTINGS_FILE) {
+            continue;
+        }
+        let target = root.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e: std::io::Error| e.to_string())?;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e: std::io::Error| e.to_string())?;
+        fs::write(&target, content).map_err(|e: std::io::Error| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn create_backup(handle: tauri::AppHandle) -> Result<BackupInfo, String> {
+    make_backup(&handle)
+}
+
+/// Newest first.
+#[tauri::command]
+pub fn list_backups(handle: tauri::AppHandle) -> Result<Vec<BackupInfo>, String> {
+    let dir = backups_dir(&handle)?;
+    let mut backups = backup_names(&dir)?.iter().map(|name| describe(&dir, name)).collect::<Result<Vec<_>, _>>()?;
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Restores one scope of the archive, or all of them when `scope` is None. The current state is
+/// backed up first, so a restore can itself be undone.
+#[tauri::command]
+pub fn restore_backup(handle: tauri::AppHandle, profile: State<'_, ActiveProfile>, name: String, scope: Option<String>) -> Result<Vec<String>, String> {
+    profile.require(Permission::ChangeSettings)?;
+    let scopes: Vec<&str> = match scope.as_deref() {
+        Some(s) => vec![SCOPES.iter().find(|x| **x == s).ok_or_else(|| format!("Phạm vi sao lưu không hợp lệ: {}", s))?],
+        None => SCOPES.to_vec(),
+    };
+    let dir = backups_dir(&handle)?;
+    if !backup_names(&dir)?.contains(&name) {
+        return Err(format!("Không tìm thấy bản sao lưu '{}'", name));
+    }
+    // Read it into memory before the safety backup, whose retention pass may delete this file
+    let bytes = fs::read(dir.join(format!("{}.zip", name))).map_err(|e: std::io::Error| e.to_string())?;
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    make_backup(&handle)?;
+
+    let mut restored = Vec::new();
+    for scope in scopes {
+        if archive.file_names().any(|n| entry_scope(n) == Some(scope)) {
+            restore_scope(&handle, &mut archive, scope)?;
+            restored.push(scope.to_string());
+        }
+    }
+    if restored.iter().any(|s| s == "settings") {
+        let settings = crate::read_db_settings(&handle).ok();
+        profile.set(settings.as_ref().and_then(|s| s.profile).unwrap_or_default());
+        crate::db::set_datetime_format(settings.and_then(|s| s.datetime_format));
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_and_scopes() {
+        let names: Vec<String> = ["backup-20240103-090000", "backup-20240101-090000", "backup-20240102-090000"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(expired_backups(&names, 2), vec!["backup-20240101-090000".to_string()]);
+        assert!(expired_backups(&names, 3).is_empty());
+        assert!(expired_backups(&names, 0).is_empty());
+
+        assert_eq!(entry_scope("pinned/orders.json"), Some("pinned"));
+        assert_eq!(entry_scope("settings/db_settings.json"), Some("settings"));
+        assert_eq!(entry_scope("other/x.json"), None);
+    }
+}
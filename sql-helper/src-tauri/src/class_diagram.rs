@@ -0,0 +1,244 @@
+use tree_sitter::{Node, Parser};
+
+// Mermaid `classDiagram` for one or more Java sources: members with visibility markers plus
+// inheritance (`<|--`) and implementation (`<|..`) edges.
+
+#[derive(Debug, Clone, PartialEq)]
+struct ClassInfo {
+    name: String,
+    /// "interface", "enumeration" or "abstract"; None for a plain class
+    stereotype: Option<&'static str>,
+    extends: Vec<String>,
+    implements: Vec<String>,
+    members: Vec<String>,
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+fn modifiers(node: Node, source: &str) -> Vec<String> {
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).find(|c| c.kind() == "modifiers");
+    match found {
+        Some(m) => {
+            let mut c = m.walk();
+            let list: Vec<String> = m.children(&mut c)
+                .filter(|x| !x.kind().contains("annotation"))
+                .map(|x| text(x, source).to_string())
+                .collect();
+            list
+        }
+        None => Vec::new(),
+    }
+}
+
+/// `+` public, `-` private, `#` protected, `~` package-private. Interface members are public.
+fn visibility(mods: &[String], in_interface: bool) -> &'static str {
+    if mods.iter().any(|m| m == "public") || in_interface {
+        "+"
+    } else if mods.iter().any(|m| m == "private") {
+        "-"
+    } else if mods.iter().any(|m| m == "protected") {
+        "#"
+    } else {
+        "~"
+    }
+}
+
+/// Mermaid writes generics with tildes: `List<Item>` -> `List~Item~`.
+fn mermaid_type(java_type: &str) -> String {
+    java_type.split_whitespace().collect::<Vec<_>>().join(" ").replace(['<', '>'], "~")
+}
+
+/// Base names from a `superclass` / `super_interfaces` / `extends_interfaces` node.
+fn type_names(node: Node, source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "type_list" => names.extend(type_names(child, source)),
+            "type_identifier" | "scoped_type_identifier" => names.push(text(child, source).to_string()),
+            // Drop type arguments from the edge target: Base<T> -> Base
+            "generic_type" => names.push(text(child, source).split('<').next().unwrap_or("").trim().to_string()),
+            _ => {}
+        }
+    }
+    names
+}
+
+fn parameters(node: Node, source: &str) -> String {
+    let params = match node.child_by_field_name("parameters") {
+        Some(p) => p,
+        None => return String::new(),
+    };
+    let mut cursor = params.walk();
+    let list: Vec<String> = params.named_children(&mut cursor)
+        .filter(|p| p.kind() == "formal_parameter" || p.kind() == "spread_parameter")
+        .map(|p| {
+            let ty = p.child_by_field_name("type").map(|t| mermaid_type(text(t, source))).unwrap_or_default();
+            let name = p.child_by_field_name("name").map(|n| text(n, source)).unwrap_or("");
+            format!("{} {}", ty, name).trim().to_string()
+        })
+        .collect();
+    list.join(", ")
+}
+
+/// `$` marks static members and `*` abstract methods (Mermaid classifiers).
+fn classifier(mods: &[String]) -> &'static str {
+    if mods.iter().any(|m| m == "static") {
+        "$"
+    } else if mods.iter().any(|m| m == "abstract") {
+        "*"
+    } else {
+        ""
+    }
+}
+
+fn collect_members(body: Node, source: &str, class_name: &str, in_interface: bool, members: &mut Vec<String>) {
+    let mut cursor = body.walk();
+    for child in body.named_children(&mut cursor) {
+        match child.kind() {
+            "field_declaration" | "constant_declaration" => {
+                let mods = modifiers(child, source);
+                let ty = child.child_by_field_name("type").map(|t| mermaid_type(text(t, source))).unwrap_or_default();
+                let mut c = child.walk();
+                for declarator in child.children_by_field_name("declarator", &mut c) {
+                    if let Some(name) = declarator.child_by_field_name("name") {
+                        members.push(format!("{}{} {}{}", visibility(&mods, in_interface), ty, text(name, source), classifier(&mods)));
+                    }
+                }
+            }
+            "method_declaration" => {
+                let mods = modifiers(child, source);
+                let name = child.child_by_field_name("name").map(|n| text(n, source)).unwrap_or("");
+                let ret = child.child_by_field_name("type").map(|t| mermaid_type(text(t, source))).unwrap_or_default();
+                members.push(format!("{}{}({}){} {}", visibility(&mods, in_interface), name, parameters(child, source), classifier(&mods), ret).trim_end().to_string());
+            }
+            "constructor_declaration" => {
+                let mods = modifiers(child, source);
+                members.push(format!("{}{}({})", visibility(&mods, in_interface), class_name, parameters(child, source)));
+            }
+            "enum_body_declarations" => collect_members(child, source, class_name, in_interface, members),
+            _ => {}
+        }
+    }
+}
+
+fn collect_classes(node: Node, source: &str, classes: &mut Vec<ClassInfo>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = child.kind();
+        if !matches!(kind, "class_declaration" | "interface_declaration" | "enum_declaration") {
+            collect_classes(child, source, classes);
+            continue;
+        }
+        let name = match child.child_by_field_name("name") {
+            Some(n) => text(n, source).to_string(),
+            None => continue,
+        };
+        let is_interface = kind == "interface_declaration";
+        let mods = modifiers(child, source);
+        let stereotype = match kind {
+            "interface_declaration" => Some("interface"),
+            "enum_declaration" => Some("enumeration"),
+            _ if mods.iter().any(|m| m == "abstract") => Some("abstract"),
+            _ => None,
+        };
+
+        let mut extends = Vec::new();
+        let mut implements = Vec::new();
+        let mut c = child.walk();
+        for part in child.named_children(&mut c) {
+            match part.kind() {
+                "superclass" => extends.extend(type_names(part, source)),
+                // An interface's `extends` list is still inheritance
+                "extends_interfaces" => extends.extend(type_names(part, source)),
+                "super_interfaces" => implements.extend(type_names(part, source)),
+                _ => {}
+            }
+        }
+
+        let mut members = Vec::new();
+        if let Some(body) = child.child_by_field_name("body") {
+            if kind == "enum_declaration" {
+                let mut bc = body.walk();
+                for constant in body.named_children(&mut bc).filter(|n| n.kind() == "enum_constant") {
+                    if let Some(n) = constant.child_by_field_name("name") {
+                        members.push(text(n, source).to_string());
+                    }
+                }
+            }
+            collect_members(body, source, &name, is_interface, &mut members);
+        }
+        classes.push(ClassInfo { name, stereotype, extends, implements, members });
+
+        // Nested types
+        if let Some(body) = child.child_by_field_name("body") {
+            collect_classes(body, source, classes);
+        }
+    }
+}
+
+pub fn generate(sources: &[String]) -> Result<String, String> {
+    let mut parser = Parser::new();
+    parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
+    let mut classes = Vec::new();
+    for source in sources {
+        let tree = parser.parse(source, None).ok_or("Failed to parse source")?;
+        collect_classes(tree.root_node(), source, &mut classes);
+    }
+
+    let mut output = String::from("classDiagram\n");
+    for class in &classes {
+        output.push_str(&format!("  class {} {{\n", class.name));
+        if let Some(stereotype) = class.stereotype {
+            output.push_str(&format!("    <<{}>>\n", stereotype));
+        }
+        for member in &class.members {
+            output.push_str(&format!("    {}\n", member));
+        }
+        output.push_str("  }\n");
+    }
+    for class in &classes {
+        for parent in &class.extends {
+            output.push_str(&format!("  {} <|-- {}\n", parent, class.name));
+        }
+        for iface in &class.implements {
+            output.push_str(&format!("  {} <|.. {}\n", iface, class.name));
+        }
+    }
+    Ok(output)
+}
+
+#[tauri::command]
+pub fn generate_class_diagram(sources: Vec<String>) -> Result<String, String> {
+    generate(&sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_diagram() {
+        let source = r#"
+public abstract class OrderService extends BaseService<Order> implements Auditable, Closeable {
+    private static final int LIMIT = 10;
+    protected List<Order> cache;
+    public OrderService(Repo repo) {}
+    public abstract BigDecimal total(String id, int qty);
+    void reset() {}
+}
+interface Auditable extends Named { String audit(); }
+enum Status { OPEN, CLOSED; public boolean done() { return this == CLOSED; } }
+"#;
+        let diagram = generate(&[source.to_string()]).unwrap();
+        assert!(diagram.contains("  class OrderService {\n    <<abstract>>\n    -int LIMIT$\n    #List~Order~ cache\n    +OrderService(Repo repo)\n    +total(String id, int qty)* BigDecimal\n    ~reset() void\n  }"));
+        assert!(diagram.contains("    <<interface>>\n    +audit() String\n"));
+        assert!(diagram.contains("    <<enumeration>>\n    OPEN\n    CLOSED\n    +done() boolean\n"));
+        assert!(diagram.contains("  BaseService <|-- OrderService\n"));
+        assert!(diagram.contains("  Auditable <|.. OrderService\n  Closeable <|.. OrderService\n"));
+        assert!(diagram.contains("  Named <|-- Auditable\n"));
+    }
+}
@@ -0,0 +1,301 @@
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use crate::sql_analysis::{self, Token, TokenKind};
+use crate::templates;
+
+// Turns a prototyped SQL statement into Java code. Literals compared against a column
+// (`ID = 10`), template placeholders (`{id:int}`), `:name` / `@name` variables and `?`
+// become bind parameters named after the column/variable.
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetStyle {
+    Mybatis,
+    Jdbc,
+    Jooq,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SnippetParam {
+    pub name: String,
+    pub java_type: String,
+    /// The literal it replaced, if any, so the caller can show an example value.
+    pub sample: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CodeSnippet {
+    pub code: String,
+    pub parameters: Vec<SnippetParam>,
+}
+
+/// ORDER_ID / order-id / OrderId -> orderId
+fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    let words = name.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty());
+    for (i, word) in words.enumerate() {
+        let all_upper = word.chars().all(|c| !c.is_lowercase());
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            let rest: String = chars.collect();
+            let rest = if all_upper { rest.to_lowercase() } else { rest };
+            if i == 0 {
+                out.extend(first.to_lowercase());
+            } else {
+                out.extend(first.to_uppercase());
+            }
+            out.push_str(&rest);
+        }
+    }
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, 'p');
+    }
+    out
+}
+
+fn java_type_for_kind(kind: &str) -> &'static str {
+    match kind {
+        "int" | "integer" | "long" => "Long",
+        "decimal" | "number" | "float" => "BigDecimal",
+        "bool" | "boolean" => "Boolean",
+        "date" => "java.sql.Date",
+        "datetime" | "timestamp" => "java.sql.Timestamp",
+        "string" | "text" | "str" => "String",
+        _ => "Object",
+    }
+}
+
+fn java_type_for_literal(token: &Token) -> &'static str {
+    match token.kind {
+        TokenKind::Number if token.text.contains('.') => "BigDecimal",
+        TokenKind::Number => "Long",
+        _ => "String",
+    }
+}
+
+/// Unquoted value of a string literal token (`N'it''s'` -> `it's`).
+fn literal_value(token: &Token) -> String {
+    if token.kind != TokenKind::Str {
+        return token.text.clone();
+    }
+    let text = token.text.trim_start_matches(|c| c == 'N' || c == 'n');
+    text.strip_prefix('\'').unwrap_or(text).strip_suffix('\'').unwrap_or(text).replace("''", "'")
+}
+
+const COMPARISONS: [&str; 7] = ["=", "<>", "!=", "<", ">", "<=", ">="];
+
+/// The comparison operator ending right before `tokens[i]` (`<`, `>=`, LIKE, ...) and the index
+/// of the token before it, which should be the column.
+fn comparison_before(tokens: &[Token], i: usize) -> Option<usize> {
+    let prev = i.checked_sub(1)?;
+    if tokens[prev].is_keyword("LIKE") {
+        return prev.checked_sub(1);
+    }
+    if tokens[prev].kind != TokenKind::Symbol {
+        return None;
+    }
+    // Two-char operators are lexed as two adjacent symbols
+    let mut start = prev;
+    if start > 0 && tokens[start - 1].kind == TokenKind::Symbol && tokens[start - 1].end == tokens[start].start {
+        start -= 1;
+    }
+    let op: String = tokens[start..=prev].iter().map(|t| t.text.as_str()).collect();
+    COMPARISONS.contains(&op.as_str()).then(|| start.checked_sub(1)).flatten()
+}
+
+struct Extracted {
+    /// (byte range in the SQL, index into `params`)
+    slots: Vec<(Range<usize>, usize)>,
+    params: Vec<SnippetParam>,
+}
+
+impl Extracted {
+    /// Adds a slot; with `reuse`, repeated names bind the same parameter, otherwise a numbered copy.
+    fn add(&mut self, range: Range<usize>, name: String, java_type: &str, sample: Option<String>, reuse: bool) {
+        let existing = self.params.iter().position(|p| p.name == name);
+        let index = match existing {
+            Some(i) if reuse => i,
+            _ => {
+                let name = match existing {
+                    Some(_) => (2..).map(|n| format!("{}{}", name, n)).find(|n| !self.params.iter().any(|p| &p.name == n)).unwrap_or(name),
+                    None => name,
+                };
+                self.params.push(SnippetParam { name, java_type: java_type.to_string(), sample });
+                self.params.len() - 1
+            }
+        };
+        self.slots.push((range, index));
+    }
+}
+
+fn extract(sql: &str) -> Extracted {
+    let mut out = Extracted { slots: Vec::new(), params: Vec::new() };
+    let placeholders = templates::scan_placeholders(sql);
+    for (range, p) in &placeholders {
+        out.add(range.clone(), camel_case(&p.name), java_type_for_kind(&p.kind), None, true);
+    }
+    let in_placeholder = |t: &Token| placeholders.iter().any(|(r, _)| r.start <= t.start && t.end <= r.end);
+
+    let tokens = sql_analysis::tokenize(sql);
+    let mut positional = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if in_placeholder(token) {
+            continue;
+        }
+        match token.kind {
+            TokenKind::Str | TokenKind::Number => {
+                if let Some(mut col) = comparison_before(&tokens, i).filter(|&c| tokens[c].is_identifier()) {
+                    // `X::int = 1` is named after X, not the cast type
+                    if col >= 3 && tokens[col - 1].is_symbol(":") && tokens[col - 2].is_symbol(":") {
+                        col -= 3;
+                    }
+                    let column = &tokens[col].text;
+                    out.add(token.start..token.end, camel_case(column), java_type_for_literal(token), Some(literal_value(token)), false);
+                }
+            }
+            TokenKind::Word if token.text.starts_with('@') && !token.text.starts_with("@@") => {
+                out.add(token.start..token.end, camel_case(&token.text[1..]), "Object", None, true);
+            }
+            TokenKind::Symbol if token.text == ":" => {
+                let prev_colon = i > 0 && tokens[i - 1].is_symbol(":");
+                match tokens.get(i + 1) {
+                    Some(next) if !prev_colon && next.kind == TokenKind::Word && next.start == token.end => {
+                        out.add(token.start..next.end, camel_case(&next.text), "Object", None, true);
+                    }
+                    _ => {}
+                }
+            }
+            TokenKind::Symbol if token.text == "?" => {
+                positional += 1;
+                out.add(token.start..token.end, format!("param{}", positional), "Object", None, false);
+            }
+            _ => {}
+        }
+    }
+    out.slots.sort_by_key(|(r, _)| r.start);
+    out
+}
+
+/// SQL with every parameter slot replaced by `marker(param)`.
+fn substitute(sql: &str, extracted: &Extracted, marker: impl Fn(&SnippetParam) -> String) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for (range, index) in &extracted.slots {
+        out.push_str(&sql[last..range.start]);
+        out.push_str(&marker(&extracted.params[*index]));
+        last = range.end;
+    }
+    out.push_str(&sql[last..]);
+    out.trim().trim_end_matches(';').trim_end().to_string()
+}
+
+/// Multi-line Java string literal built with `+`, one source line per SQL line.
+fn java_string(sql: &str, indent: &str) -> String {
+    let lines: Vec<String> = sql.lines()
+        .map(|l| l.trim_end().replace('\\', "\\\\").replace('"', "\\\""))
+        .collect();
+    let last = lines.len().saturating_sub(1);
+    lines.iter().enumerate()
+        .map(|(i, l)| if i == last { format!("\"{}\"", l) } else { format!("\"{} \"\n{}    + ", l, indent) })
+        .collect()
+}
+
+fn statement_kind(sql: &str) -> &'static str {
+    let first = sql_analysis::tokenize(sql).into_iter().find(|t| t.kind == TokenKind::Word);
+    match first.map(|t| t.text.to_uppercase()).as_deref() {
+        Some("INSERT") => "insert",
+        Some("UPDATE") | Some("MERGE") => "update",
+        Some("DELETE") => "delete",
+        _ => "select",
+    }
+}
+
+fn jdbc_setter(java_type: &str) -> &'static str {
+    match java_type {
+        "Long" => "setLong",
+        "BigDecimal" => "setBigDecimal",
+        "Boolean" => "setBoolean",
+        "String" => "setString",
+        "java.sql.Date" => "setDate",
+        "java.sql.Timestamp" => "setTimestamp",
+        _ => "setObject",
+    }
+}
+
+fn render_mybatis(sql: &str, extracted: &Extracted) -> String {
+    let body = substitute(sql, extracted, |p| format!("#{{{}}}", p.name));
+    let body = if body.contains('<') || body.contains('&') { format!("<![CDATA[\n{}\n]]>", body) } else { body };
+    let kind = statement_kind(sql);
+    let result = if kind == "select" { " resultType=\"map\"" } else { "" };
+    let indented: Vec<String> = body.lines().map(|l| format!("    {}", l)).collect();
+    format!("<{kind} id=\"TODO\" parameterType=\"map\"{result}>\n{}\n</{kind}>", indented.join("\n"), kind = kind, result = result)
+}
+
+fn render_jdbc(sql: &str, extracted: &Extracted) -> String {
+    let body = substitute(sql, extracted, |_| "?".to_string());
+    let mut code = format!("String sql = {};\ntry (PreparedStatement ps = conn.prepareStatement(sql)) {{\n", java_string(&body, ""));
+    for (n, (_, index)) in extracted.slots.iter().enumerate() {
+        let p = &extracted.params[*index];
+        code.push_str(&format!("    ps.{}({}, {});\n", jdbc_setter(&p.java_type), n + 1, p.name));
+    }
+    if statement_kind(sql) == "select" {
+        code.push_str("    try (ResultSet rs = ps.executeQuery()) {\n        while (rs.next()) {\n        }\n    }\n");
+    } else {
+        code.push_str("    int affected = ps.executeUpdate();\n");
+    }
+    code.push('}');
+    code
+}
+
+fn render_jooq(sql: &str, extracted: &Extracted) -> String {
+    let body = substitute(sql, extracted, |_| "?".to_string());
+    let args: String = extracted.slots.iter().map(|(_, i)| format!(",\n        {}", extracted.params[*i].name)).collect();
+    if statement_kind(sql) == "select" {
+        format!("Result<Record> result = dsl.resultQuery(\n        {}{})\n    .fetch();", java_string(&body, "    "), args)
+    } else {
+        format!("int affected = dsl.query(\n        {}{})\n    .execute();", java_string(&body, "    "), args)
+    }
+}
+
+pub fn generate(query: &str, style: SnippetStyle) -> CodeSnippet {
+    let extracted = extract(query);
+    let code = match style {
+        SnippetStyle::Mybatis => render_mybatis(query, &extracted),
+        SnippetStyle::Jdbc => render_jdbc(query, &extracted),
+        SnippetStyle::Jooq => render_jooq(query, &extracted),
+    };
+    CodeSnippet { code, parameters: extracted.params }
+}
+
+#[tauri::command]
+pub fn generate_code_snippet(query: String, style: SnippetStyle) -> CodeSnippet {
+    generate(&query, style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQL: &str = "SELECT * FROM ORDERS\nWHERE ORDER_ID = 10 AND NOTE LIKE N'%a''b%' AND AMOUNT >= 1.5 AND CUSTOMER_ID = {customer:int};";
+
+    #[test]
+    fn test_extract_parameters() {
+        let snippet = generate(SQL, SnippetStyle::Jdbc);
+        let names: Vec<&str> = snippet.parameters.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["customer", "orderId", "note", "amount"]);
+        assert_eq!(snippet.parameters[2].sample.as_deref(), Some("%a'b%"));
+        assert_eq!(snippet.parameters[3].java_type, "BigDecimal");
+        assert!(snippet.code.contains("\"SELECT * FROM ORDERS \"\n    + \"WHERE ORDER_ID = ? AND NOTE LIKE ? AND AMOUNT >= ? AND CUSTOMER_ID = ?\""));
+        assert!(snippet.code.contains("ps.setLong(1, orderId);"));
+        assert!(snippet.code.contains("ps.setLong(4, customer);"));
+    }
+
+    #[test]
+    fn test_mybatis_escapes_comparisons() {
+        let snippet = generate("SELECT * FROM T WHERE AMOUNT < 100", SnippetStyle::Mybatis);
+        assert!(snippet.code.starts_with("<select id=\"TODO\" parameterType=\"map\" resultType=\"map\">\n    <![CDATA["));
+        assert!(snippet.code.contains("AMOUNT < #{amount}"));
+        let update = generate("UPDATE T SET A = :value WHERE ID = @id AND X::int = 1", SnippetStyle::Mybatis);
+        assert!(update.code.contains("SET A = #{value} WHERE ID = #{id} AND X::int = #{x}"));
+    }
+}
@@ -0,0 +1,63 @@
+use encoding_rs::{Encoding, SHIFT_JIS, WINDOWS_1252};
+use crate::import::is_shift_jis;
+use crate::DbConfig;
+
+// Legacy MSSQL databases that keep Shift-JIS (or another codepage) bytes in varchar columns
+// under a Latin1 collation: tiberius decodes them as Windows-1252 and the grid shows mojibake
+// (`“ú–{` for 日本). With `DbConfig::result_encoding` set, those cells are turned back into
+// their bytes and decoded with the real encoding.
+
+/// The connection's `result_encoding`: `shift_jis` / `sjis` / `cp932`, or any WHATWG label
+/// (`euc-jp`, `gbk`, `big5`, ...). None when unset.
+pub fn result_encoding(config: &DbConfig) -> Result<Option<&'static Encoding>, String> {
+    match config.result_encoding.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+        None => Ok(None),
+        Some(label) if is_shift_jis(Some(label)) => Ok(Some(SHIFT_JIS)),
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .map(Some)
+            .ok_or_else(|| format!("result_encoding không hợp lệ: {}", label)),
+    }
+}
+
+/// varchar / char / text: the columns tiberius decodes with the collation's codepage.
+pub fn is_non_unicode(column_type: tiberius::ColumnType) -> bool {
+    use tiberius::ColumnType::*;
+    matches!(column_type, BigVarChar | BigChar | Text)
+}
+
+/// `text` as read through Windows-1252, decoded again as `encoding`. None when the cell is not
+/// such mojibake (it has characters outside Windows-1252, or its bytes are not valid in
+/// `encoding`), so it is shown unchanged.
+pub fn redecode(text: &str, encoding: &'static Encoding) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    let (bytes, _, unmappable) = WINDOWS_1252.encode(text);
+    if unmappable {
+        return None;
+    }
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(&bytes);
+    (!had_errors).then(|| decoded.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redecode() {
+        let (bytes, _, _) = SHIFT_JIS.encode("日本語ﾃｽﾄ");
+        let (mojibake, _) = WINDOWS_1252.decode_without_bom_handling(&bytes);
+        assert_eq!(redecode(&mojibake, SHIFT_JIS).as_deref(), Some("日本語ﾃｽﾄ"));
+        // Already correct text, plain ASCII and bytes that are not Shift-JIS stay as they are
+        assert_eq!(redecode("日本語", SHIFT_JIS), None);
+        assert_eq!(redecode("ABC 123", SHIFT_JIS), None);
+        assert_eq!(redecode("\u{81}", SHIFT_JIS), None);
+
+        let config = |encoding: &str| DbConfig { result_encoding: Some(encoding.to_string()), ..Default::default() };
+        assert_eq!(result_encoding(&config("cp932")).unwrap(), Some(SHIFT_JIS));
+        assert_eq!(result_encoding(&config("euc-jp")).unwrap(), Some(encoding_rs::EUC_JP));
+        assert_eq!(result_encoding(&config(" ")).unwrap(), None);
+        assert!(result_encoding(&config("klingon")).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tauri::State;
+use crate::{schema, DbConfig};
+
+// Table/column names for editor autocomplete, loaded once per connection and kept in memory.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CompletionTable {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CompletionMetadata {
+    pub connection_id: String,
+    pub loaded_at: String,
+    pub tables: Vec<CompletionTable>,
+}
+
+#[derive(Default)]
+pub struct CompletionCache {
+    entries: Mutex<HashMap<String, Arc<CompletionMetadata>>>,
+}
+
+/// Groups `(schema, table, column)` rows (ordered by table) into tables with their columns.
+pub fn group_catalog(rows: Vec<(String, String, String)>) -> Vec<CompletionTable> {
+    let mut tables: Vec<CompletionTable> = Vec::new();
+    for (schema, table, column) in rows {
+        match tables.last_mut() {
+            Some(last) if last.schema == schema && last.name == table => last.columns.push(column),
+            _ => tables.push(CompletionTable { schema, name: table, columns: vec![column] }),
+        }
+    }
+    tables
+}
+
+async fn load(config: &DbConfig) -> Result<CompletionMetadata, String> {
+    let rows = schema::column_catalog(config).await?;
+    Ok(CompletionMetadata {
+        connection_id: config.id.clone(),
+        loaded_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        tables: group_catalog(rows),
+    })
+}
+
+impl CompletionCache {
+    fn get(&self, connection_id: &str) -> Option<Arc<CompletionMetadata>> {
+        self.entries.lock().unwrap().get(connection_id).cloned()
+    }
+
+    fn insert(&self, metadata: CompletionMetadata) -> Arc<CompletionMetadata> {
+        let metadata = Arc::new(metadata);
+        self.entries.lock().unwrap().insert(metadata.connection_id.clone(), metadata.clone());
+        metadata
+    }
+}
+
+/// Cached metadata for the connection, loaded from the catalog on first use.
+#[tauri::command]
+pub async fn get_completion_metadata(cache: State<'_, CompletionCache>, config: DbConfig) -> Result<CompletionMetadata, String> {
+    let metadata = match cache.get(&config.id) {
+        Some(m) => m,
+        None => cache.insert(load(&config).await?),
+    };
+    Ok((*metadata).clone())
+}
+
+/// Reloads after DDL or when switching databases.
+#[tauri::command]
+pub async fn refresh_completion_metadata(cache: State<'_, CompletionCache>, config: DbConfig) -> Result<CompletionMetadata, String> {
+    let metadata = cache.insert(load(&config).await?);
+    Ok((*metadata).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_catalog() {
+        let row = |s: &str, t: &str, c: &str| (s.to_string(), t.to_string(), c.to_string());
+        let tables = group_catalog(vec![row("dbo", "ORDERS", "ID"), row("dbo", "ORDERS", "STATUS"), row("sales", "ORDERS", "ID")]);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].columns, vec!["ID", "STATUS"]);
+        assert_eq!(tables[1].schema, "sales");
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{db, first_run, DbConfig};
+
+// Pasted connection strings to connections: ADO.NET / ODBC / OLE DB `key=value;` lists, JDBC URLs
+// and driver URLs (`postgres://`, `mysql://`). Keys the app has no setting for are ignored.
+
+/// `Initial Catalog`, `initial_catalog` and `initialCatalog` all become `initialcatalog`.
+fn normalize_key(key: &str) -> String {
+    key.chars().filter(|c| !c.is_whitespace() && *c != '_').flat_map(char::to_lowercase).collect()
+}
+
+/// `key=value` pairs of a `;`-separated list. Values may be wrapped in `{...}` (ODBC, `}}` is a
+/// literal `}`) or in quotes (ADO.NET, a doubled quote is literal).
+fn split_pairs(text: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = text.chars().peekable();
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && *c != ';') {
+            key.push(c);
+        }
+        if chars.next() != Some('=') {
+            continue;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        match chars.peek().copied() {
+            Some(open @ ('{' | '"' | '\'')) => {
+                chars.next();
+                let close = if open == '{' { '}' } else { open };
+                while let Some(c) = chars.next() {
+                    if c == close && chars.next_if_eq(&close).is_none() {
+                        break;
+                    }
+                    value.push(c);
+                }
+                chars.by_ref().take_while(|c| *c != ';').for_each(drop);
+            }
+            _ => {
+                while let Some(c) = chars.next_if(|c| *c != ';') {
+                    value.push(c);
+                }
+                chars.next();
+                value = value.trim_end().to_string();
+            }
+        }
+        if !key.trim().is_empty() {
+            pairs.push((normalize_key(&key), value));
+        }
+    }
+    pairs
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "1" | "on" | "mandatory" | "strict" | "require" | "required" | "verify-ca" | "verify-full"
+        | "verify_ca" | "verify_identity" => Some(true),
+        "false" | "no" | "0" | "off" | "optional" | "disable" | "disabled" => Some(false),
+        _ => None,
+    }
+}
+
+/// Database type from an ODBC `Driver` or OLE DB `Provider` name.
+fn db_type_from_driver(driver: &str) -> Option<&'static str> {
+    let driver = driver.to_lowercase();
+    if ["sql server", "sqlserver", "sqlncli", "sqloledb", "msoledbsql"].iter().any(|d| driver.contains(d)) {
+        Some("mssql")
+    } else if driver.contains("postgres") {
+        Some("postgres")
+    } else if driver.contains("mysql") || driver.contains("mariadb") {
+        Some("mysql")
+    } else if driver.contains("oracle") || driver.contains("oraoledb") {
+        Some("oracle")
+    } else {
+        None
+    }
+}
+
+fn decode(text: &str) -> String {
+    urlencoding::decode(text).map(|s| s.into_owned()).unwrap_or_else(|_| text.to_string())
+}
+
+/// Oracle targets: a `(DESCRIPTION=...)` descriptor, EZConnect `//host:port/service`, or the JDBC
+/// `host:port:SID` form.
+fn set_oracle_target(config: &mut DbConfig, target: &str) {
+    config.db_type = "oracle".to_string();
+    let target = target.trim();
+    if target.starts_with('(') {
+        let upper = target.to_ascii_uppercase();
+        let value = |key: &str| {
+            let start = upper.find(&format!("({}=", key))? + key.len() + 2;
+            let end = start + upper[start..].find(')')?;
+            Some(target[start..end].trim().to_string())
+        };
+        config.host = value("HOST").unwrap_or_default();
+        config.port = value("PORT").and_then(|p| p.parse().ok()).unwrap_or(0);
+        config.oracle_sid = value("SID");
+        config.oracle_service_name = value("SERVICE_NAME");
+        return;
+    }
+    let target = target.trim_start_matches('/');
+    let (address, service) = match target.split_once('/') {
+        Some((address, service)) => (address, Some(service)),
+        None => (target, None),
+    };
+    let mut parts = address.splitn(3, ':');
+    config.host = parts.next().unwrap_or_default().to_string();
+    config.port = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    config.oracle_sid = parts.next().map(String::from);
+    config.oracle_service_name = service.map(String::from);
+}
+
+/// `Server`/`Data Source` values: `tcp:host,port`, `host:port`, `.\INSTANCE`, `(local)`, or an
+/// Oracle target.
+pub fn set_server(config: &mut DbConfig, value: &str) {
+    let value = value.trim();
+    if value.to_ascii_uppercase().starts_with("(DESCRIPTION") || value.starts_with("//") || (!value.starts_with('/') && value.contains('/')) {
+        return set_oracle_target(config, value);
+    }
+    let value = value.get(..4).filter(|p| p.eq_ignore_ascii_case("tcp:")).map_or(value, |_| &value[4..]);
+    let (address, port) = match value.rsplit_once(',').or_else(|| value.rsplit_once(':')) {
+        Some((address, port)) if port.trim().parse::<u16>().is_ok() => (address, port.trim().parse().ok()),
+        _ => (value, None),
+    };
+    let (host, instance) = db::split_instance(address.trim());
+    let host = if matches!(host.to_lowercase().as_str(), "." | "(local)") { "localhost" } else { host };
+    config.host = match instance {
+        Some(instance) => format!("{}\\{}", host, instance),
+        None => host.to_string(),
+    };
+    if let Some(port) = port {
+        config.port = port;
+    }
+}
+
+fn apply_pairs(config: &mut DbConfig, pairs: Vec<(String, String)>) {
+    let mut instance = None;
+    for (key, value) in pairs {
+        match key.as_str() {
+            "server" | "datasource" | "address" | "addr" | "networkaddress" | "host" | "servername" => set_server(config, &value),
+            "port" | "portnumber" => config.port = value.trim().parse().unwrap_or(config.port),
+            "database" | "initialcatalog" | "db" | "dbname" | "databasename" => config.database = value,
+            "userid" | "uid" | "user" | "username" => config.user = value,
+            "password" | "pwd" => config.password = value,
+            "encrypt" | "ssl" | "usessl" | "sslmode" | "requiressl" => config.encrypt = parse_bool(&value).or(config.encrypt),
+            "trustservercertificate" => config.trust_server_certificate = parse_bool(&value),
+            "applicationname" | "app" => config.application_name = Some(value),
+            "applicationintent" => config.readonly_intent = Some(value.trim().eq_ignore_ascii_case("readonly")),
+            "connecttimeout" | "connectiontimeout" | "timeout" | "logintimeout" => config.connect_timeout_secs = value.trim().parse().ok(),
+            "instancename" => instance = Some(value),
+            "failoverpartner" => config.failover_hosts.get_or_insert_with(Vec::new).push(value),
+            "currentschema" | "searchpath" => config.default_schema = Some(value),
+            "servicename" => config.oracle_service_name = Some(value),
+            "sid" => config.oracle_sid = Some(value),
+            "driver" | "provider" => {
+                if let Some(db_type) = db_type_from_driver(&value) {
+                    config.db_type = db_type.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(instance) = instance.filter(|_| !config.host.contains('\\')) {
+        config.host = format!("{}\\{}", config.host, instance);
+    }
+}
+
+/// `jdbc:sqlserver://`, `jdbc:oracle:thin:@`, and `[jdbc:]postgresql|mysql|mariadb://` URLs.
+fn parse_url(config: &mut DbConfig, url: &str) -> Result<(), String> {
+    let url = url.get(..5).filter(|p| p.eq_ignore_ascii_case("jdbc:")).map_or(url, |_| &url[5..]);
+    if url.get(..7).map_or(false, |p| p.eq_ignore_ascii_case("oracle:")) {
+        let (credentials, target) = url.split_once('@').ok_or("URL Oracle thiếu '@host'")?;
+        if let Some((user, password)) = credentials.rsplit(':').next().and_then(|c| c.split_once('/')) {
+            config.user = user.to_string();
+            config.password = password.to_string();
+        }
+        set_oracle_target(config, target);
+        return Ok(());
+    }
+    let (scheme, rest) = url.split_once("://").ok_or("Không nhận ra URL kết nối")?;
+    config.db_type = match scheme.to_lowercase().as_str() {
+        "sqlserver" | "mssql" => "mssql",
+        "postgresql" | "postgres" => "postgres",
+        "mysql" | "mariadb" => "mysql",
+        other => return Err(format!("Loại URL không hỗ trợ: {}", other)),
+    }
+    .to_string();
+    if config.db_type == "mssql" {
+        let (address, properties) = rest.split_once(';').unwrap_or((rest, ""));
+        if !address.is_empty() {
+            set_server(config, address);
+        }
+        apply_pairs(config, split_pairs(properties));
+        return Ok(());
+    }
+
+    let (main, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (authority, database) = main.split_once('/').unwrap_or((main, ""));
+    config.database = decode(database);
+    let address = match authority.rsplit_once('@') {
+        Some((credentials, address)) => {
+            let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+            config.user = decode(user);
+            config.password = decode(password);
+            address
+        }
+        None => authority,
+    };
+    // Further hosts of a multi-host URL are tried in order when the first is down
+    let mut hosts = address.split(',');
+    let address = hosts.next().unwrap_or_default();
+    let others: Vec<String> = hosts.map(str::trim).filter(|h| !h.is_empty()).map(String::from).collect();
+    if !others.is_empty() {
+        config.failover_hosts = Some(others);
+    }
+    match address.rsplit_once(':') {
+        Some((host, port)) => {
+            config.host = host.to_string();
+            config.port = port.parse().unwrap_or(0);
+        }
+        None => config.host = address.to_string(),
+    }
+    let pairs = query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| {
+            let key = normalize_key(&decode(key));
+            let value = decode(value);
+            // Connector/J timeouts are in milliseconds
+            let value = match (config.db_type.as_str(), key.as_str()) {
+                ("mysql", "connecttimeout") => value.parse::<u64>().map_or(value, |ms| (ms / 1000).max(1).to_string()),
+                _ => value,
+            };
+            (key, value)
+        })
+        .collect();
+    apply_pairs(config, pairs);
+    Ok(())
+}
+
+pub fn parse(text: &str, db_type: Option<&str>) -> Result<DbConfig, String> {
+    let text = text.trim();
+    let mut config = DbConfig {
+        db_type: db_type.filter(|t| !t.is_empty()).unwrap_or("mssql").to_string(),
+        verified: Some(false),
+        ..Default::default()
+    };
+    let is_url = text.get(..5).map_or(false, |p| p.eq_ignore_ascii_case("jdbc:"))
+        || text.split_once("://").map_or(false, |(scheme, _)| scheme.chars().all(|c| c.is_ascii_alphanumeric()));
+    if is_url {
+        parse_url(&mut config, text)?;
+    } else {
+        apply_pairs(&mut config, split_pairs(text));
+    }
+    if config.host.trim().is_empty() {
+        return Err("Không nhận ra chuỗi kết nối (thiếu server/host)".to_string());
+    }
+    // Named instances without a port are resolved through the SQL Browser
+    if config.port == 0 && !config.host.contains('\\') {
+        config.port = first_run::default_port(&config.db_type);
+    }
+    config.name = if config.database.is_empty() { config.host.clone() } else { format!("{} ({})", config.database, config.host) };
+    Ok(config)
+}
+
+/// Pre-fills a connection from a pasted ADO.NET/ODBC connection string or JDBC URL. `db_type` is
+/// the fallback when the string does not say (no driver or URL scheme).
+#[tauri::command]
+pub fn parse_connection_string(text: String, db_type: Option<String>) -> Result<DbConfig, String> {
+    parse(&text, db_type.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let c = parse("Data Source=tcp:.\\SQLEXPRESS,1444;Initial Catalog=Sales;User ID=sa;Password={p;a}}ss};TrustServerCertificate=True;ApplicationIntent=ReadOnly", None).unwrap();
+        assert_eq!((c.db_type.as_str(), c.host.as_str(), c.port), ("mssql", "localhost\\SQLEXPRESS", 1444));
+        assert_eq!((c.database.as_str(), c.user.as_str(), c.password.as_str()), ("Sales", "sa", "p;a}ss"));
+        assert_eq!((c.trust_server_certificate, c.readonly_intent), (Some(true), Some(true)));
+        assert_eq!(parse("Server=db01\\PROD;Database=x", None).unwrap().port, 0);
+
+        let c = parse("Driver={PostgreSQL Unicode};Server=pg;Port=5433;Database=app;Uid=u;Pwd=\"a \"\"b\"\"\"", None).unwrap();
+        assert_eq!((c.db_type.as_str(), c.host.as_str(), c.port, c.password.as_str()), ("postgres", "pg", 5433, "a \"b\""));
+
+        let c = parse("jdbc:sqlserver://sql01;instanceName=DEV;databaseName=hr;encrypt=false", None).unwrap();
+        assert_eq!((c.host.as_str(), c.port, c.database.as_str(), c.encrypt), ("sql01\\DEV", 0, "hr", Some(false)));
+        assert_eq!(parse("Server=a;Failover Partner=b", None).unwrap().failover_hosts, Some(vec!["b".to_string()]));
+
+        let c = parse("jdbc:mysql://root:p%40ss@my:3307/shop?useSSL=true&connectTimeout=5000", None).unwrap();
+        assert_eq!((c.db_type.as_str(), c.host.as_str(), c.port, c.password.as_str()), ("mysql", "my", 3307, "p@ss"));
+        assert_eq!((c.encrypt, c.connect_timeout_secs), (Some(true), Some(5)));
+
+        let c = parse("postgres://me@pg,pg-replica:5433/app?currentSchema=sales", None).unwrap();
+        assert_eq!((c.port, c.user.as_str(), c.default_schema.as_deref()), (5432, "me", Some("sales")));
+        assert_eq!(c.failover_hosts, Some(vec!["pg-replica:5433".to_string()]));
+
+        let c = parse("jdbc:oracle:thin:scott/tiger@ora:1522:ORCL", None).unwrap();
+        assert_eq!((c.db_type.as_str(), c.host.as_str(), c.port, c.oracle_sid.as_deref()), ("oracle", "ora", 1522, Some("ORCL")));
+        let c = parse("jdbc:oracle:thin:@//ora/PDB1", None).unwrap();
+        assert_eq!((c.port, c.oracle_service_name.as_deref()), (1521, Some("PDB1")));
+        let c = parse("User Id=app;Data Source=(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=ora2)(PORT=1530))(CONNECT_DATA=(SERVICE_NAME=HR)))", None).unwrap();
+        assert_eq!((c.host.as_str(), c.port, c.oracle_service_name.as_deref()), ("ora2", 1530, Some("HR")));
+
+        assert!(parse("Database=x", None).is_err());
+    }
+}
@@ -0,0 +1,1085 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+use sqlx::{Column, Row as SqlxRow, Connection, TypeInfo};
+use tiberius::{Client, Config, AuthMethod, QueryItem, EncryptionLevel, SqlBrowser};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+use futures::StreamExt;
+use crate::filter_expr::RowFilter;
+use crate::query_params::ParamValue;
+use crate::{codepage, dialect, oracle_db, sql_analysis, ssh_tunnel};
+use crate::{DbConfig, ExecutionStats, QueryResult, ResultSet, ResultSetStats};
+
+/// A connection with the SSH tunnel it goes through (see `ssh_tunnel::tunneled`). The connect
+/// functions below open the tunnel themselves, so no caller can skip it; it closes when the
+/// connection is dropped.
+pub struct Tunneled<C> {
+    conn: C,
+    _tunnel: Option<ssh_tunnel::Tunnel>,
+}
+
+impl<C> std::ops::Deref for Tunneled<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.conn
+    }
+}
+
+impl<C> std::ops::DerefMut for Tunneled<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.conn
+    }
+}
+
+pub type MssqlClient = Tunneled<Client<Compat<TcpStream>>>;
+
+/// Reported to the server so DBAs can identify traffic from this tool.
+pub const DEFAULT_APPLICATION_NAME: &str = "sql-helper";
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+
+/// `auth_method` values for MSSQL; anything else means SQL login with user/password.
+pub const AUTH_AAD_TOKEN: &str = "aad_token";
+/// Token fetched from the signed-in Azure CLI (`az login`) on every connect.
+pub const AUTH_AZURE_CLI: &str = "azure_cli";
+const AZURE_SQL_RESOURCE: &str = "https://database.windows.net/";
+
+fn connect_timeout_secs(config: &DbConfig) -> u64 {
+    config.connect_timeout_secs.filter(|s| *s > 0).unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS)
+}
+
+pub async fn with_connect_timeout<T>(config: &DbConfig, fut: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    let secs = connect_timeout_secs(config);
+    tokio::time::timeout(Duration::from_secs(secs), fut)
+        .await
+        .map_err(|_| format!("Kết nối quá thời gian ({}s)", secs))?
+}
+
+pub fn query_timeout(config: &DbConfig) -> Option<Duration> {
+    config.query_timeout_secs.filter(|s| *s > 0).map(Duration::from_secs)
+}
+
+pub fn query_timeout_error(limit: Duration) -> String {
+    format!("Truy vấn quá thời gian ({}s), đã huỷ", limit.as_secs())
+}
+
+/// Dropping the timed-out future drops its connection, so the server ends the session and rolls back
+/// whatever the statement had started.
+pub async fn with_query_timeout<T>(config: &DbConfig, fut: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    match query_timeout(config) {
+        Some(limit) => tokio::time::timeout(limit, fut)
+            .await
+            .map_err(|_| query_timeout_error(limit))?,
+        None => fut.await,
+    }
+}
+
+fn application_name(config: &DbConfig) -> &str {
+    config.application_name.as_deref()
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or(DEFAULT_APPLICATION_NAME)
+}
+
+/// `SERVER\SQLEXPRESS` -> ("SERVER", Some("SQLEXPRESS")).
+pub fn split_instance(host: &str) -> (&str, Option<&str>) {
+    match host.trim().split_once('\\') {
+        Some((server, instance)) if !instance.trim().is_empty() => (server.trim(), Some(instance.trim())),
+        Some((server, _)) => (server.trim(), None),
+        None => (host.trim(), None),
+    }
+}
+
+/// Named instance whose port is looked up through the SQL Browser (UDP 1434); an explicit
+/// port wins over the browser.
+fn browser_instance(config: &DbConfig) -> Option<&str> {
+    split_instance(&config.host).1.filter(|_| config.port == 0)
+}
+
+pub fn build_mssql_config(config: &DbConfig) -> Result<Config, String> {
+    let mut c = Config::new();
+    c.host(split_instance(&config.host).0);
+    match browser_instance(config) {
+        Some(instance) => c.instance_name(instance),
+        None => c.port(config.port),
+    }
+    c.database(&config.database);
+    c.application_name(application_name(config));
+    if config.readonly_intent.unwrap_or(false) {
+        // ApplicationIntent=ReadOnly, routes to a readable secondary on AlwaysOn listeners
+        c.readonly(true);
+    }
+    let mut tiberius_config = c;
+
+    // Apply credentials from separate fields if provided (overrides URL if conflict)
+    match config.auth_method.as_deref() {
+        Some(AUTH_AAD_TOKEN) | Some(AUTH_AZURE_CLI) => {
+            let token = config.access_token.as_deref().filter(|t| !t.trim().is_empty())
+                .ok_or("Chưa có Azure AD access token")?;
+            tiberius_config.authentication(AuthMethod::aad_token(token.trim()));
+        }
+        _ if !config.user.trim().is_empty() => {
+            tiberius_config.authentication(AuthMethod::sql_server(&config.user, &config.password));
+        }
+        _ => {}
+    }
+
+    // Handle Encryption
+    if let Some(encrypt) = config.encrypt {
+        if encrypt {
+            tiberius_config.encryption(EncryptionLevel::Required);
+        } else {
+            tiberius_config.encryption(EncryptionLevel::NotSupported);
+        }
+    } else {
+        tiberius_config.encryption(EncryptionLevel::Off);
+    }
+
+    // Handle Trust Certificate
+    if config.trust_server_certificate.unwrap_or(true) {
+        tiberius_config.trust_cert();
+    }
+
+    Ok(tiberius_config)
+}
+
+pub fn build_db_url(config: &DbConfig) -> Result<String, String> {
+    let user_enc = urlencoding::encode(&config.user);
+    let pass_enc = urlencoding::encode(&config.password);
+    
+    let mut url = match config.db_type.as_str() {
+        "mssql" => format!("mssql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
+        "mysql" => format!("mysql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
+        "postgres" => format!("postgresql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
+        _ => return Err("Unsupported database type".to_string()),
+    };
+
+    if config.db_type == "mssql" {
+        let mut params = Vec::new();
+        if config.trust_server_certificate.unwrap_or(true) {
+            params.push("trustServerCertificate=true");
+        }
+        if let Some(enc) = config.encrypt {
+            params.push(if enc { "encrypt=true" } else { "encrypt=false" });
+        }
+        if !params.is_empty() {
+            url = format!("{}?{}", url, params.join("&"));
+        }
+    } else if config.db_type == "postgres" {
+        url = format!("{}?application_name={}", url, urlencoding::encode(application_name(config)));
+    }
+
+    Ok(url)
+}
+
+/// Access token for Azure SQL from `az account get-access-token`.
+async fn azure_cli_token() -> Result<String, String> {
+    let program = if cfg!(target_os = "windows") { "az.cmd" } else { "az" };
+    let output = tokio::process::Command::new(program)
+        .args(["account", "get-access-token", "--resource", AZURE_SQL_RESOURCE, "--query", "accessToken", "--output", "tsv"])
+        .output()
+        .await
+        .map_err(|e| format!("Không chạy được Azure CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Azure CLI lỗi: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn connect_mssql_once(config: &DbConfig) -> Result<Client<Compat<TcpStream>>, String> {
+    let tiberius_config = if config.auth_method.as_deref() == Some(AUTH_AZURE_CLI) {
+        let mut with_token = config.clone();
+        with_token.access_token = Some(azure_cli_token().await?);
+        build_mssql_config(&with_token)?
+    } else {
+        build_mssql_config(config)?
+    };
+
+    with_connect_timeout(config, async move {
+        let tcp = if browser_instance(config).is_some() {
+            TcpStream::connect_named(&tiberius_config).await.map_err(|e| format!("Lỗi kết nối mạng (SQL Browser): {}", e))?
+        } else {
+            let tcp = TcpStream::connect(tiberius_config.get_addr()).await.map_err(|e: std::io::Error| format!("Lỗi kết nối mạng (TCP): {}", e))?;
+            tcp.set_nodelay(true).map_err(|e: std::io::Error| e.to_string())?;
+            tcp
+        };
+
+        Client::connect(tiberius_config, tcp.compat_write()).await.map_err(|e: tiberius::error::Error| format!("Lỗi đăng nhập Database: {}", e))
+    }).await
+}
+
+/// Statements run on every new connection: the `default_schema` switch, then each statement of
+/// `init_sql`. Postgres keeps `public` on the search path after the default schema.
+pub fn init_statements(config: &DbConfig) -> Vec<String> {
+    let mut statements = Vec::new();
+    if let Some(schema) = config.default_schema.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        let quoted = dialect::quote_ident(&config.db_type, schema);
+        match config.db_type.as_str() {
+            "postgres" => statements.push(format!("SET search_path TO {}, public", quoted)),
+            "mysql" => statements.push(format!("USE {}", quoted)),
+            "oracle" => statements.push(format!("ALTER SESSION SET CURRENT_SCHEMA = {}", quoted)),
+            _ => {}
+        }
+    }
+    if let Some(sql) = &config.init_sql {
+        statements.extend(sql_analysis::statement_spans(sql, &config.db_type).into_iter().map(|s| s.text));
+    }
+    statements
+}
+
+pub fn init_error(statement: &str, e: impl std::fmt::Display) -> String {
+    format!("Lỗi khi chạy SQL khởi tạo '{}': {}", statement, e)
+}
+
+/// Runs `init_statements` with the simple protocol so SET options outlive the statement.
+async fn init_sqlx<C>(conn: &mut C, config: &DbConfig) -> Result<(), String>
+where
+    for<'c> &'c mut C: sqlx::Executor<'c>,
+{
+    for statement in init_statements(config) {
+        sqlx::Executor::execute(&mut *conn, statement.as_str()).await.map_err(|e| init_error(&statement, e))?;
+    }
+    Ok(())
+}
+
+/// Connects to MSSQL, retrying against `fallback_database` when login to the target database fails.
+pub async fn connect_mssql(config: &DbConfig) -> Result<MssqlClient, String> {
+    let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+    let config = &target;
+    let mut client = match connect_mssql_once(config).await {
+        Ok(client) => client,
+        Err(e) => match config.fallback_database.as_deref().filter(|d| !d.trim().is_empty()) {
+            Some(fallback) => {
+                let mut fallback_config = config.clone();
+                fallback_config.database = fallback.to_string();
+                connect_mssql_once(&fallback_config).await
+                    .map_err(|e2| format!("{} / fallback '{}': {}", e, fallback, e2))?
+            }
+            None => return Err(e),
+        },
+    };
+    // Not through sp_executesql, which would undo SET LANGUAGE and friends when it returns
+    for statement in init_statements(config) {
+        let outcome = match client.simple_query(statement.as_str()).await {
+            Ok(stream) => stream.into_results().await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        outcome.map_err(|e| init_error(&statement, e))?;
+    }
+    Ok(Tunneled { conn: client, _tunnel: tunnel })
+}
+
+pub async fn connect_any(config: &DbConfig) -> Result<Tunneled<sqlx::AnyConnection>, String> {
+    let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+    let url = build_db_url(&target)?;
+    let mut conn = with_connect_timeout(&target, async move {
+        sqlx::AnyConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())
+    }).await?;
+    init_sqlx(&mut conn, &target).await?;
+    Ok(Tunneled { conn, _tunnel: tunnel })
+}
+
+/// A MySQL or Postgres connection opened with its own driver, so parameters bind and cells
+/// decode with that database's types (the Any driver cannot encode dates, nor decode bytes,
+/// UUIDs or decimals, while the mssql feature is enabled).
+pub enum SqlxConnection {
+    Postgres(Tunneled<sqlx::PgConnection>),
+    MySql(Tunneled<sqlx::MySqlConnection>),
+}
+
+pub async fn connect_sqlx(config: &DbConfig) -> Result<SqlxConnection, String> {
+    match config.db_type.as_str() {
+        "postgres" => Ok(SqlxConnection::Postgres(connect_pg(config).await?)),
+        "mysql" => Ok(SqlxConnection::MySql(connect_mysql(config).await?)),
+        _ => Err("Unsupported database type".to_string()),
+    }
+}
+
+pub async fn connect_pg(config: &DbConfig) -> Result<Tunneled<sqlx::PgConnection>, String> {
+    let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+    let url = build_db_url(&target)?;
+    let mut conn = with_connect_timeout(&target, async move {
+        sqlx::PgConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())
+    }).await?;
+    init_sqlx(&mut conn, &target).await?;
+    Ok(Tunneled { conn, _tunnel: tunnel })
+}
+
+pub async fn connect_mysql(config: &DbConfig) -> Result<Tunneled<sqlx::MySqlConnection>, String> {
+    let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+    let url = build_db_url(&target)?;
+    let mut conn = with_connect_timeout(&target, async move {
+        sqlx::MySqlConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())
+    }).await?;
+    init_sqlx(&mut conn, &target).await?;
+    Ok(Tunneled { conn, _tunnel: tunnel })
+}
+
+/// A dropped receiver means the stream was cancelled; the caller stops reading from the server.
+fn cancelled() -> String {
+    "Đã huỷ truy vấn".to_string()
+}
+
+/// Rows forwarded while the query is still running (see `FetchOptions::batch_sink`).
+#[derive(Clone, Debug)]
+pub struct RowBatch {
+    /// 0 for the first result set of a batch.
+    pub result_index: usize,
+    pub columns: Vec<String>,
+    /// See `QueryResult::column_types`.
+    pub column_types: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Batches a streaming query may run ahead of its consumer before it waits.
+const BATCH_QUEUE: usize = 4;
+
+/// Channel for `FetchOptions::batch_sink`. It is bounded, so a slow consumer holds the query back
+/// instead of buffering the whole result; dropping the receiver cancels the query.
+pub fn batch_channel() -> (tokio::sync::mpsc::Sender<RowBatch>, tokio::sync::mpsc::Receiver<RowBatch>) {
+    tokio::sync::mpsc::channel(BATCH_QUEUE)
+}
+
+/// Per-execution knobs for `run_query_with`; the default fetches every row unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    /// `filter_expr` expression evaluated on each row as it arrives; non-matching rows are dropped.
+    pub row_filter: Option<String>,
+    /// When set, rows are sent here in batches instead of being kept in the returned result,
+    /// so memory stays bounded by a few batches (see `batch_channel`).
+    pub batch_sink: Option<tokio::sync::mpsc::Sender<RowBatch>>,
+    /// Rows per batch; 0 means `DEFAULT_BATCH_SIZE`.
+    pub batch_size: usize,
+    /// Positional values for the query's native placeholders (`@P1`, `$1`, `?`, `:1`).
+    pub params: Vec<ParamValue>,
+    /// Cells that are written back into a table rather than shown: binary values in full and
+    /// datetimes in `CANONICAL_DATETIME_FORMAT` whatever the display format.
+    pub canonical: bool,
+}
+
+/// Keeps rows for the result, or forwards them batch by batch when streaming.
+pub struct RowCollector<'a> {
+    options: &'a FetchOptions,
+    result_index: usize,
+    column_types: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// Counted as rows arrive, since streamed rows leave `rows`.
+    row_count: u64,
+    bytes: u64,
+    first_row: Option<Instant>,
+}
+
+impl<'a> RowCollector<'a> {
+    pub fn new(options: &'a FetchOptions) -> Self {
+        Self::for_result_set(options, 0)
+    }
+
+    pub fn for_result_set(options: &'a FetchOptions, result_index: usize) -> Self {
+        RowCollector { options, result_index, column_types: Vec::new(), rows: Vec::new(), row_count: 0, bytes: 0, first_row: None }
+    }
+
+    /// Types sent along with each batch; set once the result set's metadata is known.
+    pub fn set_column_types(&mut self, column_types: &[String]) {
+        self.column_types = column_types.to_vec();
+    }
+
+    /// Waits while the consumer is `BATCH_QUEUE` batches behind.
+    pub async fn push(&mut self, columns: &[String], row: Vec<String>) -> Result<(), String> {
+        match self.add(columns, row) {
+            Some((sink, batch)) => sink.send(batch).await.map_err(|_| cancelled()),
+            None => Ok(()),
+        }
+    }
+
+    /// `push` for queries running on the blocking pool (Oracle).
+    pub fn push_blocking(&mut self, columns: &[String], row: Vec<String>) -> Result<(), String> {
+        match self.add(columns, row) {
+            Some((sink, batch)) => sink.blocking_send(batch).map_err(|_| cancelled()),
+            None => Ok(()),
+        }
+    }
+
+    /// Keeps the row; returns a full batch when streaming.
+    fn add(&mut self, columns: &[String], row: Vec<String>) -> Option<(&'a tokio::sync::mpsc::Sender<RowBatch>, RowBatch)> {
+        self.first_row.get_or_insert_with(Instant::now);
+        self.row_count += 1;
+        self.bytes += row.iter().map(|c| c.len() as u64).sum::<u64>();
+        self.rows.push(row);
+        let batch_size = if self.options.batch_size == 0 { DEFAULT_BATCH_SIZE } else { self.options.batch_size };
+        if self.rows.len() < batch_size {
+            return None;
+        }
+        self.take_batch(columns)
+    }
+
+    fn take_batch(&mut self, columns: &[String]) -> Option<(&'a tokio::sync::mpsc::Sender<RowBatch>, RowBatch)> {
+        let sink = self.options.batch_sink.as_ref()?;
+        let rows = std::mem::take(&mut self.rows);
+        Some((sink, RowBatch { result_index: self.result_index, columns: columns.to_vec(), column_types: self.column_types.clone(), rows }))
+    }
+
+    /// Timing of the result set so far, relative to when the query was sent.
+    pub fn stats(&self, sent: Instant) -> ResultSetStats {
+        ResultSetStats {
+            rows: self.row_count,
+            bytes: self.bytes,
+            first_row_ms: self.first_row.map(|t| t.duration_since(sent).as_millis() as u64),
+            elapsed_ms: sent.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Remaining rows for the result (empty when streaming, after the last batch is sent).
+    pub async fn finish(mut self, columns: &[String]) -> Result<Vec<Vec<String>>, String> {
+        if let Some((sink, batch)) = self.take_batch(columns).filter(|(_, b)| !b.rows.is_empty()) {
+            sink.send(batch).await.map_err(|_| cancelled())?;
+        }
+        Ok(self.rows)
+    }
+
+    /// `finish` for queries running on the blocking pool (Oracle).
+    pub fn finish_blocking(mut self, columns: &[String]) -> Result<Vec<Vec<String>>, String> {
+        if let Some((sink, batch)) = self.take_batch(columns).filter(|(_, b)| !b.rows.is_empty()) {
+            sink.blocking_send(batch).map_err(|_| cancelled())?;
+        }
+        Ok(self.rows)
+    }
+}
+
+/// Totals over the result sets; `total_ms` is the round trip until `run_query_with` adds the
+/// connection time.
+pub fn execution_stats(sent: Instant, result_sets: Vec<ResultSetStats>) -> ExecutionStats {
+    let elapsed = sent.elapsed().as_millis() as u64;
+    ExecutionStats {
+        round_trip_ms: elapsed,
+        total_ms: elapsed,
+        rows: result_sets.iter().map(|s| s.rows).sum(),
+        bytes: result_sets.iter().map(|s| s.bytes).sum(),
+        result_sets,
+    }
+}
+
+/// Executes `query` on a fresh connection and returns all rows as display strings.
+pub async fn run_query(config: &DbConfig, query: &str) -> Result<QueryResult, String> {
+    run_query_with(config, query, &FetchOptions::default()).await
+}
+
+pub fn compile_filter(options: &FetchOptions, columns: &[String]) -> Result<Option<RowFilter>, String> {
+    options.row_filter.as_deref()
+        .filter(|f| !f.trim().is_empty())
+        .map(|f| RowFilter::compile(f, columns))
+        .transpose()
+}
+
+/// Rows for queries; for single DML/DDL statements, the affected row count instead.
+pub async fn run_query_with(config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    timed_query(config, query, async {
+        if sql_analysis::is_non_query(query, &config.db_type) {
+            let affected = execute_non_query(config, query, &options.params).await?;
+            Ok(QueryResult { affected_rows: Some(affected), ..Default::default() })
+        } else {
+            fetch_rows(config, query, options).await
+        }
+    }).await
+}
+
+/// Runs `run` under the query timeout and fills in the statement kind and total time.
+pub async fn timed_query(config: &DbConfig, query: &str, run: impl Future<Output = Result<QueryResult, String>>) -> Result<QueryResult, String> {
+    let started = Instant::now();
+    let mut result = with_query_timeout(config, run).await?;
+    result.statement_kind = Some(sql_analysis::statement_kind(query).to_string());
+    let stats = result.stats.get_or_insert_with(|| execution_stats(started, Vec::new()));
+    stats.total_ms = started.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+async fn execute_non_query(config: &DbConfig, query: &str, params: &[ParamValue]) -> Result<u64, String> {
+    match config.db_type.as_str() {
+        "oracle" => oracle_db::execute(config, query, params).await,
+        "mssql" => execute_mssql(&mut connect_mssql(config).await?, query, params).await,
+        _ => execute_sqlx(&mut connect_sqlx(config).await?, query, params).await,
+    }
+}
+
+pub async fn execute_mssql(client: &mut MssqlClient, query: &str, params: &[ParamValue]) -> Result<u64, String> {
+    let result = client.execute(query, &mssql_params(params)).await.map_err(|e: tiberius::error::Error| e.to_string())?;
+    Ok(result.total())
+}
+
+/// Binds `params` to a sqlx query of a concrete database (see `SqlxConnection`).
+macro_rules! bind_params {
+    ($query:expr, $params:expr) => {{
+        let mut query = $query;
+        for p in $params {
+            query = match p {
+                ParamValue::Null => query.bind(None::<String>),
+                ParamValue::Text(s) => query.bind(s.as_str()),
+                ParamValue::Int(n) => query.bind(*n),
+                ParamValue::Float(f) => query.bind(*f),
+                ParamValue::Bool(b) => query.bind(*b),
+                ParamValue::Date(d) => query.bind(*d),
+                ParamValue::DateTime(dt) => query.bind(*dt),
+            };
+        }
+        query
+    }};
+}
+
+/// Display string of one sqlx cell: each type is tried in turn, NULL shows as "[NULL]" and a
+/// value no listed type decodes shows as "???".
+macro_rules! decode_cell {
+    ($row:expr, $i:expr, $($ty:ty => $render:expr),+ $(,)?) => {{
+        $(
+            if let Ok(value) = $row.try_get::<Option<$ty>, usize>($i) {
+                return value.map_or_else(|| "[NULL]".to_string(), $render);
+            }
+        )+
+        "???".to_string()
+    }};
+}
+
+pub async fn execute_sqlx(conn: &mut SqlxConnection, query: &str, params: &[ParamValue]) -> Result<u64, String> {
+    // Without parameters use the simple protocol, which also accepts DDL MySQL cannot prepare
+    let affected = match conn {
+        SqlxConnection::Postgres(conn) if params.is_empty() => sqlx::Executor::execute(&mut **conn, query).await.map(|r| r.rows_affected()),
+        SqlxConnection::MySql(conn) if params.is_empty() => sqlx::Executor::execute(&mut **conn, query).await.map(|r| r.rows_affected()),
+        SqlxConnection::Postgres(conn) => bind_params!(sqlx::query::<sqlx::Postgres>(query), params).execute(&mut **conn).await.map(|r| r.rows_affected()),
+        SqlxConnection::MySql(conn) => bind_params!(sqlx::query::<sqlx::MySql>(query), params).execute(&mut **conn).await.map(|r| r.rows_affected()),
+    };
+    affected.map_err(|e: sqlx::Error| e.to_string())
+}
+
+fn mssql_params(params: &[ParamValue]) -> Vec<&dyn tiberius::ToSql> {
+    params.iter().map(|p| p as &dyn tiberius::ToSql).collect()
+}
+
+async fn fetch_rows(config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    if config.db_type == "oracle" {
+        return oracle_db::run_query(config, query, options).await;
+    }
+    if config.db_type == "mssql" {
+        return fetch_mssql(&mut connect_mssql(config).await?, config, query, options).await;
+    }
+    fetch_sqlx(&mut connect_sqlx(config).await?, query, options).await
+}
+
+/// `config` only supplies `result_encoding`; the client is already connected.
+pub async fn fetch_mssql(client: &mut MssqlClient, config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    let encoding = codepage::result_encoding(config)?;
+    let sent = Instant::now();
+    let mut results = client.query(query, &mssql_params(&options.params)).await.map_err(|e: tiberius::error::Error| e.to_string())?;
+
+    // Each result set of the batch starts with its metadata; the row filter only applies to the first
+    let mut sets: Vec<ResultSet> = Vec::new();
+    let mut set_stats = Vec::new();
+    let mut columns = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
+    // Columns re-decoded with `encoding`
+    let mut legacy: Vec<bool> = Vec::new();
+    let mut rows = RowCollector::new(options);
+    let mut started = false;
+    let mut filter = None;
+
+    while let Some(item) = results.next().await {
+        match item.map_err(|e: tiberius::error::Error| e.to_string())? {
+            QueryItem::Metadata(meta) => {
+                if started {
+                    set_stats.push(rows.stats(sent));
+                    sets.push(ResultSet { columns: columns.clone(), rows: rows.finish(&columns).await?, column_types: column_types.clone(), ..Default::default() });
+                    rows = RowCollector::for_result_set(options, sets.len());
+                }
+                columns = meta.columns().iter().map(|c| c.name().to_string()).collect();
+                column_types = meta.columns().iter().map(|c| mssql_type_name(c.column_type()).to_string()).collect();
+                legacy = meta.columns().iter().map(|c| encoding.is_some() && codepage::is_non_unicode(c.column_type())).collect();
+                rows.set_column_types(&column_types);
+                filter = if sets.is_empty() { compile_filter(options, &columns)? } else { None };
+                started = true;
+            }
+            QueryItem::Row(row) => {
+                let row_data: Vec<String> = (0..columns.len())
+                    .map(|i| {
+                        let cell = mssql_cell(&row, i, options.canonical);
+                        match encoding.filter(|_| legacy[i]) {
+                            Some(encoding) => codepage::redecode(&cell, encoding).unwrap_or(cell),
+                            None => cell,
+                        }
+                    })
+                    .collect();
+                if filter.as_ref().map_or(true, |f| f.matches(&row_data)) {
+                    rows.push(&columns, row_data).await?;
+                }
+            },
+        }
+    }
+
+    if started {
+        set_stats.push(rows.stats(sent));
+        sets.push(ResultSet { columns: columns.clone(), rows: rows.finish(&columns).await?, column_types, ..Default::default() });
+    }
+    let mut sets = sets.into_iter();
+    let first = sets.next().unwrap_or_default();
+    let stats = Some(execution_stats(sent, set_stats));
+    Ok(QueryResult { columns: first.columns, rows: first.rows, column_types: first.column_types, more_results: sets.collect(), stats, ..Default::default() })
+}
+
+/// Bytes shown in a binary cell before it is cut off; the full value can be saved with
+/// `export_cell_bytes`.
+const BINARY_PREVIEW_BYTES: usize = 32;
+
+/// `0x`-prefixed uppercase hex, as SSMS shows it; long values end with "..." and their size.
+pub fn render_binary(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().take(BINARY_PREVIEW_BYTES).map(|b| format!("{:02X}", b)).collect();
+    if bytes.len() > BINARY_PREVIEW_BYTES {
+        format!("0x{}... ({} bytes)", hex, bytes.len())
+    } else {
+        format!("0x{}", hex)
+    }
+}
+
+/// The preview, or every byte in a canonical cell (see `FetchOptions::canonical`).
+pub fn binary_cell(bytes: &[u8], canonical: bool) -> String {
+    if canonical {
+        format!("0x{}", bytes.iter().map(|b| format!("{:02X}", b)).collect::<String>())
+    } else {
+        render_binary(bytes)
+    }
+}
+
+/// Exact text of a scaled integer, e.g. (-1234, 3) -> "-1.234". tiberius' own Display for
+/// `Numeric` breaks on negative values and scale 0.
+fn format_scaled(value: i128, scale: u8) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let digits = if digits.len() <= scale { format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits) } else { digits };
+    let sign = if value < 0 { "-" } else { "" };
+    if scale == 0 {
+        format!("{}{}", sign, digits)
+    } else {
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        format!("{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+pub const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// `AppSettings::datetime_format`; cell rendering happens deep inside every fetch path,
+/// so the setting is process-wide rather than threaded through `FetchOptions`.
+static DATETIME_FORMAT: std::sync::RwLock<Option<String>> = std::sync::RwLock::new(None);
+
+/// chrono panics when formatting with an invalid specifier, so formats are checked up front.
+pub fn validate_datetime_format(format: &str) -> Result<(), String> {
+    use chrono::format::{Item, StrftimeItems};
+    if format.trim().is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Định dạng ngày giờ không hợp lệ: {}", format));
+    }
+    Ok(())
+}
+
+/// None (or an invalid format) restores `DEFAULT_DATETIME_FORMAT`.
+pub fn set_datetime_format(format: Option<String>) {
+    *DATETIME_FORMAT.write().unwrap() = format.filter(|f| validate_datetime_format(f).is_ok());
+}
+
+/// The active format, for reading rendered cells back (see `sorting::date_formats`).
+pub fn datetime_format() -> String {
+    DATETIME_FORMAT.read().unwrap().clone().unwrap_or_else(|| DEFAULT_DATETIME_FORMAT.to_string())
+}
+
+pub fn format_datetime(dt: &chrono::NaiveDateTime) -> String {
+    let format = DATETIME_FORMAT.read().unwrap();
+    dt.format(format.as_deref().unwrap_or(DEFAULT_DATETIME_FORMAT)).to_string()
+}
+
+/// Local time as stored, followed by its offset (`+07:00`).
+pub fn format_datetime_offset<Tz: chrono::TimeZone>(dt: &chrono::DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    format!("{} {}", format_datetime(&dt.naive_local()), dt.format("%:z"))
+}
+
+/// Datetimes of canonical cells, with every fractional digit; `import` reads them back.
+pub const CANONICAL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// `format_datetime`, or the canonical text.
+pub fn datetime_cell(dt: &chrono::NaiveDateTime, canonical: bool) -> String {
+    if canonical {
+        dt.format(CANONICAL_DATETIME_FORMAT).to_string()
+    } else {
+        format_datetime(dt)
+    }
+}
+
+/// `format_datetime_offset`, or the canonical text followed by the offset.
+pub fn datetime_offset_cell<Tz: chrono::TimeZone>(dt: &chrono::DateTime<Tz>, canonical: bool) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if canonical {
+        format!("{} {}", dt.naive_local().format(CANONICAL_DATETIME_FORMAT), dt.format("%:z"))
+    } else {
+        format_datetime_offset(dt)
+    }
+}
+
+pub fn format_date(d: &chrono::NaiveDate) -> String {
+    d.format("%Y-%m-%d").to_string()
+}
+
+/// Fractional seconds only when present, e.g. `08:30:00` or `08:30:00.1234567`.
+pub fn format_time(t: &chrono::NaiveTime) -> String {
+    t.format("%H:%M:%S%.f").to_string()
+}
+
+/// Display string of one MSSQL cell: each type is tried in turn, anything else shows as NULL.
+fn mssql_cell(row: &tiberius::Row, i: usize, canonical: bool) -> String {
+    if let Ok(Some(s)) = row.try_get::<&str, usize>(i) {
+        return s.trim_end().to_string();
+    }
+    if let Ok(Some(n)) = row.try_get::<i64, usize>(i) {
+        return n.to_string();
+    }
+    if let Ok(Some(n)) = row.try_get::<i32, usize>(i) {
+        return n.to_string();
+    }
+    if let Ok(Some(f)) = row.try_get::<f64, usize>(i) {
+        // money arrives as f64; show its four decimals as SSMS does
+        if matches!(row.columns()[i].column_type(), tiberius::ColumnType::Money | tiberius::ColumnType::Money4) {
+            return format!("{:.4}", f);
+        }
+        return f.to_string();
+    }
+    if let Ok(Some(b)) = row.try_get::<bool, usize>(i) {
+        return b.to_string();
+    }
+    if let Ok(Some(n)) = row.try_get::<tiberius::numeric::Numeric, usize>(i) {
+        return format_scaled(n.value(), n.scale());
+    }
+    if let Ok(Some(dt)) = row.try_get::<chrono::NaiveDateTime, usize>(i) {
+        return datetime_cell(&dt, canonical);
+    }
+    if let Ok(Some(dt)) = row.try_get::<chrono::DateTime<chrono::FixedOffset>, usize>(i) {
+        return datetime_offset_cell(&dt, canonical);
+    }
+    if let Ok(Some(d)) = row.try_get::<chrono::NaiveDate, usize>(i) {
+        return format_date(&d);
+    }
+    if let Ok(Some(t)) = row.try_get::<chrono::NaiveTime, usize>(i) {
+        return format_time(&t);
+    }
+    // uniqueidentifier, upper case as SSMS shows it
+    if let Ok(Some(id)) = row.try_get::<tiberius::Uuid, usize>(i) {
+        return id.hyphenated().to_string().to_uppercase();
+    }
+    if let Ok(Some(bytes)) = row.try_get::<&[u8], usize>(i) {
+        return binary_cell(bytes, canonical);
+    }
+    "[NULL]".to_string()
+}
+
+/// SQL Server type name for a TDS column type; the nullable wire variants (`Intn`, `Floatn`, ...)
+/// do not carry the declared size, so they map to the widest type.
+pub fn mssql_type_name(column_type: tiberius::ColumnType) -> &'static str {
+    use tiberius::ColumnType::*;
+    match column_type {
+        Null => "null",
+        Bit | Bitn => "bit",
+        Int1 => "tinyint",
+        Int2 => "smallint",
+        Int4 => "int",
+        Int8 | Intn => "bigint",
+        Float4 => "real",
+        Float8 | Floatn => "float",
+        Money | Money4 => "money",
+        Decimaln => "decimal",
+        Numericn => "numeric",
+        Datetime | Datetime4 | Datetimen => "datetime",
+        Daten => "date",
+        Timen => "time",
+        Datetime2 => "datetime2",
+        DatetimeOffsetn => "datetimeoffset",
+        Guid => "uniqueidentifier",
+        BigVarBin | BigBinary | Image => "varbinary",
+        BigVarChar | BigChar => "varchar",
+        NVarchar | NChar => "nvarchar",
+        Text => "text",
+        NText => "ntext",
+        Xml => "xml",
+        Udt => "udt",
+        SSVariant => "sql_variant",
+    }
+}
+
+fn pg_cell(row: &sqlx::postgres::PgRow, i: usize, canonical: bool) -> String {
+    decode_cell!(row, i,
+        String => |s: String| s.trim_end().to_string(),
+        i64 => |n: i64| n.to_string(),
+        i32 => |n: i32| n.to_string(),
+        f64 => |f: f64| f.to_string(),
+        bool => |b: bool| b.to_string(),
+        sqlx::types::BigDecimal => |d: sqlx::types::BigDecimal| d.to_string(),
+        sqlx::types::Uuid => |id: sqlx::types::Uuid| id.hyphenated().to_string(),
+        chrono::NaiveDateTime => |dt: chrono::NaiveDateTime| datetime_cell(&dt, canonical),
+        // timestamptz keeps no offset; show it in the local zone
+        chrono::DateTime<chrono::Local> => |dt: chrono::DateTime<chrono::Local>| datetime_offset_cell(&dt, canonical),
+        chrono::NaiveDate => |d: chrono::NaiveDate| format_date(&d),
+        chrono::NaiveTime => |t: chrono::NaiveTime| format_time(&t),
+        Vec<u8> => |b: Vec<u8>| binary_cell(&b, canonical),
+    )
+}
+
+fn mysql_cell(row: &sqlx::mysql::MySqlRow, i: usize, canonical: bool) -> String {
+    // TIMESTAMP arrives in UTC (sqlx pins the session time zone) and also decodes as
+    // NaiveDateTime, so it is told apart from DATETIME by the column type
+    if row.column(i).type_info().name() == "TIMESTAMP" {
+        if let Ok(Some(dt)) = row.try_get::<Option<chrono::DateTime<chrono::Utc>>, usize>(i) {
+            return datetime_offset_cell(&dt.with_timezone(&chrono::Local), canonical);
+        }
+    }
+    decode_cell!(row, i,
+        String => |s: String| s.trim_end().to_string(),
+        i64 => |n: i64| n.to_string(),
+        i32 => |n: i32| n.to_string(),
+        f64 => |f: f64| f.to_string(),
+        bool => |b: bool| b.to_string(),
+        sqlx::types::BigDecimal => |d: sqlx::types::BigDecimal| d.to_string(),
+        chrono::NaiveDateTime => |dt: chrono::NaiveDateTime| datetime_cell(&dt, canonical),
+        chrono::NaiveDate => |d: chrono::NaiveDate| format_date(&d),
+        chrono::NaiveTime => |t: chrono::NaiveTime| format_time(&t),
+        Vec<u8> => |b: Vec<u8>| binary_cell(&b, canonical),
+    )
+}
+
+/// MySQL/Postgres through sqlx.
+pub async fn fetch_sqlx(conn: &mut SqlxConnection, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    match conn {
+        SqlxConnection::Postgres(conn) => {
+            let stream = bind_params!(sqlx::query::<sqlx::Postgres>(query), &options.params).fetch(&mut **conn);
+            collect_sqlx_rows(stream, options, pg_cell).await
+        }
+        SqlxConnection::MySql(conn) => {
+            let stream = bind_params!(sqlx::query::<sqlx::MySql>(query), &options.params).fetch(&mut **conn);
+            collect_sqlx_rows(stream, options, mysql_cell).await
+        }
+    }
+}
+
+async fn collect_sqlx_rows<R: SqlxRow>(mut stream: impl futures::Stream<Item = Result<R, sqlx::Error>> + Unpin, options: &FetchOptions, cell: fn(&R, usize, bool) -> String) -> Result<QueryResult, String> {
+    let mut columns = Vec::new();
+    let mut column_types = Vec::new();
+    let mut rows = RowCollector::new(options);
+    let mut filter = None;
+    // The query goes out when the stream is first polled
+    let sent = Instant::now();
+
+    while let Some(row) = stream.next().await {
+        let row = row.map_err(|e: sqlx::Error| e.to_string())?;
+        if columns.is_empty() {
+            for col in row.columns() {
+                columns.push(col.name().to_string());
+                column_types.push(col.type_info().name().to_lowercase());
+            }
+            rows.set_column_types(&column_types);
+            filter = compile_filter(options, &columns)?;
+        }
+
+        let row_data: Vec<String> = (0..columns.len()).map(|i| cell(&row, i, options.canonical)).collect();
+        if filter.as_ref().map_or(true, |f| f.matches(&row_data)) {
+            rows.push(&columns, row_data).await?;
+        }
+    }
+
+    let stats = Some(execution_stats(sent, vec![rows.stats(sent)]));
+    let rows = rows.finish(&columns).await?;
+    Ok(QueryResult { columns, rows, column_types, stats, ..Default::default() })
+}
+
+fn no_cell(row: usize, column: &str) -> String {
+    format!("Không tìm thấy ô dòng {} cột {}", row + 1, column)
+}
+
+fn null_or_not_binary(column: &str, is_null: bool) -> String {
+    if is_null {
+        format!("Ô cột {} là NULL", column)
+    } else {
+        format!("Cột {} không phải kiểu nhị phân", column)
+    }
+}
+
+/// Full bytes of one binary cell (`row` counted from 0 in the first result set), which the grid
+/// only shows as a preview.
+pub async fn fetch_cell_bytes(config: &DbConfig, query: &str, row: usize, column: &str) -> Result<Vec<u8>, String> {
+    if config.db_type == "oracle" {
+        return oracle_db::fetch_cell_bytes(config, query, row, column).await;
+    }
+    if config.db_type == "mssql" {
+        let mut client = connect_mssql(config).await?;
+        let mut stream = client.query(query, &[]).await.map_err(|e| e.to_string())?.into_row_stream().skip(row);
+        let found = stream.next().await.ok_or_else(|| no_cell(row, column))?.map_err(|e| e.to_string())?;
+        let index = found.columns().iter().position(|c| c.name() == column).ok_or_else(|| no_cell(row, column))?;
+        return match found.try_get::<&[u8], usize>(index) {
+            Ok(Some(bytes)) => Ok(bytes.to_vec()),
+            other => Err(null_or_not_binary(column, matches!(other, Ok(None)))),
+        };
+    }
+    let value = match connect_sqlx(config).await? {
+        SqlxConnection::Postgres(mut conn) => {
+            let found = sqlx::query::<sqlx::Postgres>(query).fetch(&mut *conn).skip(row).next().await;
+            let found = found.ok_or_else(|| no_cell(row, column))?.map_err(|e| e.to_string())?;
+            found.try_get::<Option<Vec<u8>>, &str>(column)
+        }
+        SqlxConnection::MySql(mut conn) => {
+            let found = sqlx::query::<sqlx::MySql>(query).fetch(&mut *conn).skip(row).next().await;
+            let found = found.ok_or_else(|| no_cell(row, column))?.map_err(|e| e.to_string())?;
+            found.try_get::<Option<Vec<u8>>, &str>(column)
+        }
+    };
+    match value {
+        Ok(Some(bytes)) => Ok(bytes),
+        Ok(None) => Err(null_or_not_binary(column, true)),
+        Err(sqlx::Error::ColumnNotFound(_)) => Err(no_cell(row, column)),
+        Err(_) => Err(null_or_not_binary(column, false)),
+    }
+}
+
+/// Rows `skip..skip + take` of the first result set (after `options.row_filter`) and whether more
+/// rows follow. Rows are streamed and the query is stopped as soon as the window is full, so the
+/// rest of a huge result is never read. Later result sets are only kept when the first one ends
+/// inside the window.
+pub async fn fetch_window(config: &DbConfig, query: &str, options: &FetchOptions, skip: usize, take: usize) -> Result<(QueryResult, bool), String> {
+    fetch_window_with(query, options, skip, take, |options| async move { run_query_with(config, query, &options).await }).await
+}
+
+/// `fetch_window` with the query run by `run`, e.g. on an already open connection. `run` owns the
+/// streaming options it is given; dropping them when the query ends lets the collector finish.
+pub async fn fetch_window_with<F, Fut>(query: &str, options: &FetchOptions, skip: usize, take: usize, run: F) -> Result<(QueryResult, bool), String>
+where
+    F: FnOnce(FetchOptions) -> Fut,
+    Fut: Future<Output = Result<QueryResult, String>>,
+{
+    let (sender, mut receiver) = batch_channel();
+    let options = FetchOptions { batch_sink: Some(sender), batch_size: (take + 1).min(DEFAULT_BATCH_SIZE), ..options.clone() };
+    let fetch = run(options);
+    let collect = async move {
+        let mut window = QueryResult::default();
+        let mut later: Vec<Vec<Vec<String>>> = Vec::new();
+        let mut seen = 0;
+        while let Some(batch) = receiver.recv().await {
+            if batch.result_index > 0 {
+                later.resize_with(later.len().max(batch.result_index), Vec::new);
+                later[batch.result_index - 1].extend(batch.rows);
+                continue;
+            }
+            if window.columns.is_empty() {
+                window.columns = batch.columns;
+                window.column_types = batch.column_types;
+            }
+            for row in batch.rows {
+                if seen == skip + take {
+                    // Returning drops the receiver, which cancels the rest of the fetch
+                    return (window, later, true);
+                }
+                if seen >= skip {
+                    window.rows.push(row);
+                }
+                seen += 1;
+            }
+        }
+        (window, later, false)
+    };
+    let (fetched, (mut window, later, has_more)) = tokio::join!(fetch, collect);
+    window.statement_kind = Some(sql_analysis::statement_kind(query).to_string());
+    if has_more {
+        return Ok((window, true));
+    }
+    // Rows went through the channel; the result still carries columns, counts and empty sets
+    let fetched = fetched?;
+    if window.columns.is_empty() {
+        window.columns = fetched.columns;
+        window.column_types = fetched.column_types;
+    }
+    window.affected_rows = fetched.affected_rows;
+    window.more_results = fetched.more_results;
+    window.stats = fetched.stats;
+    for (set, rows) in window.more_results.iter_mut().zip(later) {
+        set.rows = rows;
+    }
+    Ok((window, false))
+}
+
+/// Complete display value of one cell (`row` counted from 0 in the first result set, after
+/// `options.row_filter`), for cells cut by `result_ops::truncate_long_cells`.
+pub async fn fetch_full_cell(config: &DbConfig, query: &str, options: &FetchOptions, row: usize, column: &str) -> Result<String, String> {
+    let (mut window, _) = fetch_window(config, query, options, row, 1).await?;
+    let index = window.columns.iter().position(|c| c == column).ok_or_else(|| no_cell(row, column))?;
+    match window.rows.pop() {
+        Some(mut cells) => Ok(cells.swap_remove(index)),
+        None => Err(no_cell(row, column)),
+    }
+}
+
+/// Runs the statements in one transaction and returns the affected row count of each.
+/// With `expect_single_row`, any statement not affecting exactly one row rolls everything back
+/// (used for grid edits, where 0 rows means the row was changed by someone else).
+pub async fn execute_in_transaction(config: &DbConfig, statements: &[String], expect_single_row: bool) -> Result<Vec<u64>, String> {
+    let check = |i: usize, affected: u64| -> Result<(), String> {
+        if expect_single_row && affected != 1 {
+            Err(format!("Câu lệnh #{} ảnh hưởng {} dòng (mong đợi 1), đã rollback", i + 1, affected))
+        } else {
+            Ok(())
+        }
+    };
+
+    if config.db_type == "oracle" {
+        return oracle_db::execute_in_transaction(config, statements, expect_single_row).await;
+    }
+    if config.db_type == "mssql" {
+        let mut client = connect_mssql(config).await?;
+        client.simple_query("BEGIN TRANSACTION").await.map_err(|e| e.to_string())?
+            .into_results().await.map_err(|e| e.to_string())?;
+
+        let mut counts = Vec::new();
+        for (i, statement) in statements.iter().enumerate() {
+            let outcome = match client.execute(statement.as_str(), &[]).await {
+                Ok(done) => {
+                    let affected: u64 = done.rows_affected().iter().sum();
+                    check(i, affected).map(|_| affected)
+                }
+                Err(e) => Err(format!("Câu lệnh #{}: {}", i + 1, e)),
+            };
+            match outcome {
+                Ok(affected) => counts.push(affected),
+                Err(e) => {
+                    if let Ok(stream) = client.simple_query("IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION").await {
+                        stream.into_results().await.ok();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        client.simple_query("COMMIT TRANSACTION").await.map_err(|e| e.to_string())?
+            .into_results().await.map_err(|e| e.to_string())?;
+        return Ok(counts);
+    }
+
+    // Same body for both drivers; `$db` picks the driver the statements are sent with
+    macro_rules! run_in_transaction {
+        ($conn:expr, $db:ty) => {{
+            let mut tx = $conn.begin().await.map_err(|e: sqlx::Error| e.to_string())?;
+            let mut counts = Vec::new();
+            for (i, statement) in statements.iter().enumerate() {
+                // Dropping `tx` on the error paths rolls the transaction back
+                let done = sqlx::query::<$db>(statement).execute(&mut tx).await.map_err(|e| format!("Câu lệnh #{}: {}", i + 1, e))?;
+                check(i, done.rows_affected())?;
+                counts.push(done.rows_affected());
+            }
+            tx.commit().await.map_err(|e: sqlx::Error| e.to_string())?;
+            Ok(counts)
+        }};
+    }
+
+    match connect_sqlx(config).await? {
+        SqlxConnection::Postgres(mut conn) => run_in_transaction!(conn, sqlx::Postgres),
+        SqlxConnection::MySql(mut conn) => run_in_transaction!(conn, sqlx::MySql),
+    }
+}
@@ -0,0 +1,287 @@
+use std::time::Instant;
+use serde::Serialize;
+use sqlx::Row as SqlxRow;
+use tokio::net::TcpStream;
+use crate::result_ops::NULL_CELL;
+use crate::{db, ssh_tunnel, DbConfig, QueryResult};
+
+const ROUND_TRIPS: usize = 3;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProbeResult {
+    pub tcp_connect_ms: f64,
+    /// Connection setup after TCP: TLS handshake + authentication.
+    pub login_ms: f64,
+    pub round_trip_ms: f64,
+    pub sample_rows: usize,
+    pub sample_bytes: usize,
+    pub fetch_ms: f64,
+    pub throughput_kb_per_sec: f64,
+}
+
+fn ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Server-generated rows of ~100 bytes so throughput does not depend on user tables.
+fn sample_query(db_type: &str) -> &'static str {
+    match db_type {
+        "mssql" => "SELECT TOP 10000 REPLICATE('x', 100) AS v FROM sys.all_columns a CROSS JOIN sys.all_columns b",
+        "postgres" => "SELECT repeat('x', 100) AS v FROM generate_series(1, 10000)",
+        _ => "SELECT REPEAT('x', 100) AS v FROM information_schema.columns a CROSS JOIN information_schema.columns b LIMIT 10000",
+    }
+}
+
+/// Measures TCP connect, login, trivial-query round trip and fetch throughput separately,
+/// to tell "network slow" apart from "server slow".
+#[tauri::command]
+pub async fn probe_connection(config: DbConfig) -> Result<ProbeResult, String> {
+    // Opened first so the timings below measure the forwarded connection, not the SSH setup
+    let (config, _tunnel) = ssh_tunnel::tunneled(&config).await?;
+    let started = Instant::now();
+    let tcp = TcpStream::connect((config.host.as_str(), config.port)).await
+        .map_err(|e| format!("Lỗi kết nối mạng (TCP): {}", e))?;
+    let tcp_connect_ms = ms(started);
+    drop(tcp);
+
+    let mut round_trips = Vec::new();
+    let sample_rows;
+    let sample_bytes;
+    let fetch_ms;
+    let login_ms;
+
+    if config.db_type == "mssql" {
+        let started = Instant::now();
+        let mut client = db::connect_mssql(&config).await?;
+        login_ms = (ms(started) - tcp_connect_ms).max(0.0);
+
+        for _ in 0..ROUND_TRIPS {
+            let started = Instant::now();
+            client.simple_query("SELECT 1").await.map_err(|e| e.to_string())?
+                .into_results().await.map_err(|e| e.to_string())?;
+            round_trips.push(ms(started));
+        }
+
+        let started = Instant::now();
+        let rows = client.simple_query(sample_query("mssql")).await.map_err(|e| e.to_string())?
+            .into_first_result().await.map_err(|e| e.to_string())?;
+        fetch_ms = ms(started);
+        sample_rows = rows.len();
+        sample_bytes = rows.iter().map(|r| r.get::<&str, _>(0).map_or(0, |s| s.len())).sum();
+    } else {
+        let started = Instant::now();
+        let mut conn = db::connect_any(&config).await?;
+        login_ms = (ms(started) - tcp_connect_ms).max(0.0);
+
+        for _ in 0..ROUND_TRIPS {
+            let started = Instant::now();
+            sqlx::query("SELECT 1").fetch_all(&mut *conn).await.map_err(|e| e.to_string())?;
+            round_trips.push(ms(started));
+        }
+
+        let started = Instant::now();
+        let rows = sqlx::query(sample_query(&config.db_type)).fetch_all(&mut *conn).await.map_err(|e| e.to_string())?;
+        fetch_ms = ms(started);
+        sample_rows = rows.len();
+        sample_bytes = rows.iter().map(|r| r.try_get::<String, usize>(0).map_or(0, |s| s.len())).sum();
+    }
+
+    let round_trip_ms = round_trips.iter().sum::<f64>() / round_trips.len() as f64;
+    let throughput_kb_per_sec = if fetch_ms > 0.0 { (sample_bytes as f64 / 1024.0) / (fetch_ms / 1000.0) } else { 0.0 };
+
+    Ok(ProbeResult { tcp_connect_ms, login_ms, round_trip_ms, sample_rows, sample_bytes, fetch_ms, throughput_kb_per_sec })
+}
+
+fn top_queries_sql(db_type: &str, by: &str, limit: u32) -> Result<String, String> {
+    let sql = match db_type {
+        "mssql" => {
+            let order = match by {
+                "cpu" => "qs.total_worker_time",
+                "duration" => "qs.total_elapsed_time",
+                "reads" => "qs.total_logical_reads",
+                "executions" => "qs.execution_count",
+                _ => return Err(format!("Tiêu chí không hỗ trợ: {}", by)),
+            };
+            format!(
+                "SELECT TOP {limit} qs.execution_count, \
+                   qs.total_worker_time / 1000 AS total_cpu_ms, \
+                   qs.total_elapsed_time / 1000 AS total_duration_ms, \
+                   qs.total_elapsed_time / qs.execution_count / 1000 AS avg_duration_ms, \
+                   qs.total_logical_reads, qs.last_execution_time, \
+                   DB_NAME(st.dbid) AS database_name, \
+                   SUBSTRING(st.text, (qs.statement_start_offset / 2) + 1, \
+                     ((CASE qs.statement_end_offset WHEN -1 THEN DATALENGTH(st.text) ELSE qs.statement_end_offset END \
+                       - qs.statement_start_offset) / 2) + 1) AS statement_text \
+                 FROM sys.dm_exec_query_stats qs \
+                 CROSS APPLY sys.dm_exec_sql_text(qs.sql_handle) st \
+                 ORDER BY {order} DESC"
+            )
+        }
+        "postgres" => {
+            // pg_stat_statements has no CPU split, total execution time is the closest measure
+            let order = match by {
+                "cpu" | "duration" => "total_exec_time",
+                "reads" => "shared_blks_read",
+                "executions" => "calls",
+                _ => return Err(format!("Tiêu chí không hỗ trợ: {}", by)),
+            };
+            format!(
+                "SELECT calls AS execution_count, \
+                   round(total_exec_time::numeric, 2)::text AS total_duration_ms, \
+                   round(mean_exec_time::numeric, 2)::text AS avg_duration_ms, \
+                   shared_blks_read, rows, query AS statement_text \
+                 FROM pg_stat_statements ORDER BY {order} DESC LIMIT {limit}"
+            )
+        }
+        "mysql" => {
+            let order = match by {
+                "cpu" | "duration" => "SUM_TIMER_WAIT",
+                "reads" => "SUM_ROWS_EXAMINED",
+                "executions" => "COUNT_STAR",
+                _ => return Err(format!("Tiêu chí không hỗ trợ: {}", by)),
+            };
+            format!(
+                "SELECT COUNT_STAR AS execution_count, \
+                   CAST(ROUND(SUM_TIMER_WAIT / 1000000000, 2) AS CHAR) AS total_duration_ms, \
+                   CAST(ROUND(AVG_TIMER_WAIT / 1000000000, 2) AS CHAR) AS avg_duration_ms, \
+                   SUM_ROWS_EXAMINED, SUM_ROWS_SENT, SCHEMA_NAME AS database_name, DIGEST_TEXT AS statement_text \
+                 FROM performance_schema.events_statements_summary_by_digest \
+                 ORDER BY {order} DESC LIMIT {limit}"
+            )
+        }
+        _ => return Err("Unsupported database type".to_string()),
+    };
+    Ok(sql)
+}
+
+/// Most expensive statements from the server's own statistics
+/// (`sys.dm_exec_query_stats`, `pg_stat_statements`, `performance_schema`).
+/// `by` is one of "cpu", "duration", "reads", "executions".
+#[tauri::command]
+pub async fn get_top_queries(config: DbConfig, by: String, limit: Option<u32>) -> Result<QueryResult, String> {
+    let sql = top_queries_sql(&config.db_type, &by, limit.unwrap_or(20))?;
+    db::run_query(&config, &sql).await
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ServerInfo {
+    /// Version number, e.g. "15.0.4375.4" or "16.2".
+    pub version: Option<String>,
+    /// Full banner: `@@VERSION`, `version()` or `v$version`.
+    pub description: Option<String>,
+    pub edition: Option<String>,
+    /// Default collation of the current database; the database character set on Oracle.
+    pub collation: Option<String>,
+    pub database: Option<String>,
+    pub user: Option<String>,
+}
+
+/// One row: version, description, edition, collation, database, user.
+fn server_info_sql(db_type: &str) -> Result<&'static str, String> {
+    Ok(match db_type {
+        "mssql" => "SELECT CAST(SERVERPROPERTY('ProductVersion') AS nvarchar(128)), @@VERSION, \
+                    CAST(SERVERPROPERTY('Edition') AS nvarchar(128)), \
+                    CAST(DATABASEPROPERTYEX(DB_NAME(), 'Collation') AS nvarchar(128)), DB_NAME(), SUSER_SNAME()",
+        "postgres" => "SELECT current_setting('server_version'), version(), NULL::text, \
+                       (SELECT datcollate::text FROM pg_database WHERE datname = current_database()), \
+                       current_database()::text, current_user::text",
+        "mysql" => "SELECT VERSION(), CONCAT(@@version_comment, ' ', VERSION(), ' (', @@version_compile_os, ')'), \
+                    @@version_comment, @@collation_database, DATABASE(), CURRENT_USER()",
+        "oracle" => "SELECT (SELECT version FROM product_component_version WHERE ROWNUM = 1), \
+                     (SELECT banner FROM v$version WHERE ROWNUM = 1), NULL, \
+                     (SELECT value FROM nls_database_parameters WHERE parameter = 'NLS_CHARACTERSET'), \
+                     SYS_CONTEXT('USERENV', 'DB_NAME'), USER FROM dual",
+        _ => return Err("Unsupported database type".to_string()),
+    })
+}
+
+/// Version, edition, collation and the current database/user, for the connection tooltip.
+#[tauri::command]
+pub async fn get_server_info(config: DbConfig) -> Result<ServerInfo, String> {
+    let sql = server_info_sql(&config.db_type)?;
+    let result = db::run_query(&config, sql).await?;
+    let row = result.rows.first().ok_or_else(|| "Không đọc được thông tin server".to_string())?;
+    let cell = |i: usize| row.get(i).and_then(|s| non_empty(s.trim()));
+    Ok(ServerInfo { version: cell(0), description: cell(1), edition: cell(2), collation: cell(3), database: cell(4), user: cell(5) })
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MissingIndexSuggestion {
+    pub table: String,
+    pub equality_columns: Option<String>,
+    pub inequality_columns: Option<String>,
+    pub included_columns: Option<String>,
+    pub avg_user_impact: f64,
+    pub user_seeks: i64,
+    pub improvement_measure: f64,
+    pub create_statement: String,
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() || value == NULL_CELL { None } else { Some(value.to_string()) }
+}
+
+/// Builds `CREATE INDEX` from the column lists reported by `sys.dm_db_missing_index_details`
+/// (lists are already bracket-quoted, e.g. `[CUSTOMER_ID], [STATUS]`).
+pub fn missing_index_statement(table: &str, equality: Option<&str>, inequality: Option<&str>, included: Option<&str>) -> String {
+    let key_columns: Vec<&str> = [equality, inequality].iter().flatten().copied().collect();
+    let key_list = key_columns.join(", ");
+    let name_part: String = key_list.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == ',')
+        .collect::<String>()
+        .replace(',', "_");
+    let table_name = table.rsplit('.').next().unwrap_or(table).trim_matches(|c| c == '[' || c == ']');
+    let mut sql = format!("CREATE NONCLUSTERED INDEX [IX_{}_{}] ON {} ({})", table_name, name_part, table, key_list);
+    if let Some(inc) = included {
+        sql.push_str(&format!(" INCLUDE ({})", inc));
+    }
+    sql
+}
+
+/// MSSQL only: missing-index DMV suggestions, most impactful first, with ready-to-run CREATE INDEX.
+#[tauri::command]
+pub async fn get_missing_index_suggestions(config: DbConfig, limit: Option<u32>) -> Result<Vec<MissingIndexSuggestion>, String> {
+    if config.db_type != "mssql" {
+        return Err("Chỉ hỗ trợ MSSQL".to_string());
+    }
+    let sql = format!(
+        "SELECT TOP {} d.statement, d.equality_columns, d.inequality_columns, d.included_columns, \
+           s.avg_user_impact, s.user_seeks, \
+           s.avg_total_user_cost * s.avg_user_impact * (s.user_seeks + s.user_scans) AS improvement_measure \
+         FROM sys.dm_db_missing_index_details d \
+         JOIN sys.dm_db_missing_index_groups g ON g.index_handle = d.index_handle \
+         JOIN sys.dm_db_missing_index_group_stats s ON s.group_handle = g.index_group_handle \
+         WHERE d.database_id = DB_ID() \
+         ORDER BY improvement_measure DESC",
+        limit.unwrap_or(50)
+    );
+    let result = db::run_query(&config, &sql).await?;
+
+    Ok(result.rows.iter().map(|row| {
+        let cell = |i: usize| row.get(i).map(|s| s.as_str()).unwrap_or(NULL_CELL);
+        let equality = non_empty(cell(1));
+        let inequality = non_empty(cell(2));
+        let included = non_empty(cell(3));
+        MissingIndexSuggestion {
+            table: cell(0).to_string(),
+            create_statement: missing_index_statement(cell(0), equality.as_deref(), inequality.as_deref(), included.as_deref()),
+            equality_columns: equality,
+            inequality_columns: inequality,
+            included_columns: included,
+            avg_user_impact: cell(4).parse().unwrap_or(0.0),
+            user_seeks: cell(5).parse().unwrap_or(0),
+            improvement_measure: cell(6).parse().unwrap_or(0.0),
+        }
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_index_statement() {
+        let sql = missing_index_statement("[Shop].[dbo].[ORDERS]", Some("[CUSTOMER_ID]"), Some("[ORDER_DATE]"), Some("[TOTAL]"));
+        assert_eq!(sql, "CREATE NONCLUSTERED INDEX [IX_ORDERS_CUSTOMER_ID_ORDER_DATE] ON [Shop].[dbo].[ORDERS] ([CUSTOMER_ID], [ORDER_DATE]) INCLUDE ([TOTAL])");
+    }
+}
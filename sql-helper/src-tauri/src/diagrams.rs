@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::PathBuf;
+use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use crate::java_parser::{JavaParser, MermaidOptions};
+use crate::storage::{self, app_data_subdir, safe_file_name};
+
+const DIAGRAMS_DIR: &str = "diagrams";
+
+/// A generated Mermaid diagram saved to the gallery together with what it was generated from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedDiagram {
+    pub name: String,
+    pub saved_at: String,
+    /// Path of the Java file the diagram was generated from.
+    pub source_ref: String,
+    pub mermaid: String,
+    pub options: MermaidOptions,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiagramInfo {
+    pub name: String,
+    pub saved_at: String,
+    pub source_ref: String,
+    pub method_name: Option<String>,
+}
+
+fn info(diagram: &SavedDiagram) -> DiagramInfo {
+    DiagramInfo {
+        name: diagram.name.clone(),
+        saved_at: diagram.saved_at.clone(),
+        source_ref: diagram.source_ref.clone(),
+        method_name: diagram.options.method_name.clone(),
+    }
+}
+
+fn diagram_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(handle, DIAGRAMS_DIR)?.join(format!("{}.json", safe_file_name(name))))
+}
+
+fn store(handle: &tauri::AppHandle, diagram: &SavedDiagram) -> Result<(), String> {
+    storage::write_json(&diagram_path(handle, &diagram.name)?, diagram)
+}
+
+/// Reads a Java source as UTF-8, falling back to Shift-JIS for older projects.
+pub fn read_source(path: &str) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Không thể đọc file: {}", e))?;
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(text),
+        Err(e) => Ok(SHIFT_JIS.decode(e.as_bytes()).0.to_string()),
+    }
+}
+
+/// Case-insensitive match on name, source path, method and diagram text.
+fn matches_search(diagram: &SavedDiagram, search: &str) -> bool {
+    let needle = search.trim().to_lowercase();
+    needle.is_empty()
+        || [diagram.name.as_str(), diagram.source_ref.as_str(), diagram.options.method_name.as_deref().unwrap_or(""), diagram.mermaid.as_str()]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&needle))
+}
+
+#[tauri::command]
+pub fn save_diagram(handle: tauri::AppHandle, name: String, source_ref: String, mermaid: String, options: MermaidOptions) -> Result<DiagramInfo, String> {
+    let diagram = SavedDiagram {
+        name,
+        saved_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        source_ref,
+        mermaid,
+        options,
+    };
+    store(&handle, &diagram)?;
+    Ok(info(&diagram))
+}
+
+#[tauri::command]
+pub fn list_diagrams(handle: tauri::AppHandle, search: Option<String>) -> Result<Vec<DiagramInfo>, String> {
+    let dir = app_data_subdir(&handle, DIAGRAMS_DIR)?;
+    let mut found = Vec::new();
+    for name in storage::list_json_names(&dir)? {
+        match storage::read_json::<SavedDiagram>(&dir.join(format!("{}.json", name))) {
+            Ok(diagram) if matches_search(&diagram, search.as_deref().unwrap_or("")) => found.push(info(&diagram)),
+            Ok(_) => {}
+            Err(e) => eprintln!("Skipping unreadable diagram {}: {}", name, e),
+        }
+    }
+    // Newest first
+    found.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+    Ok(found)
+}
+
+#[tauri::command]
+pub fn load_diagram(handle: tauri::AppHandle, name: String) -> Result<SavedDiagram, String> {
+    let path = diagram_path(&handle, &name)?;
+    if !path.exists() {
+        return Err(format!("Không tìm thấy diagram: {}", name));
+    }
+    storage::read_json(&path)
+}
+
+#[tauri::command]
+pub fn delete_diagram(handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    fs::remove_file(diagram_path(&handle, &name)?).map_err(|e| e.to_string())
+}
+
+/// Re-reads the original source and regenerates the diagram with its saved options.
+#[tauri::command]
+pub fn regenerate_diagram(handle: tauri::AppHandle, name: String) -> Result<SavedDiagram, String> {
+    let mut diagram = load_diagram(handle.clone(), name)?;
+    let source = read_source(&diagram.source_ref)?;
+    diagram.mermaid = JavaParser::generate_with_options(&source, &diagram.options)?;
+    diagram.saved_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    store(&handle, &diagram)?;
+    Ok(diagram)
+}
@@ -0,0 +1,305 @@
+// Literal/identifier quoting per db_type ("mssql", "mysql", "postgres", "oracle").
+
+use serde::Serialize;
+use crate::sql_analysis::{self, Token, TokenKind};
+
+pub fn quote_string(db_type: &str, value: &str) -> String {
+    match db_type {
+        // N'' keeps Japanese text intact regardless of the column collation
+        "mssql" => format!("N'{}'", value.replace('\'', "''")),
+        "mysql" => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''")),
+        _ => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+pub fn quote_bool(db_type: &str, value: bool) -> String {
+    match (db_type, value) {
+        ("mssql" | "oracle", true) => "1".to_string(),
+        ("mssql" | "oracle", false) => "0".to_string(),
+        (_, true) => "TRUE".to_string(),
+        (_, false) => "FALSE".to_string(),
+    }
+}
+
+pub fn quote_ident(db_type: &str, name: &str) -> String {
+    match db_type {
+        "mssql" => format!("[{}]", name.replace(']', "]]")),
+        "mysql" => format!("`{}`", name.replace('`', "``")),
+        _ => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+/// Quotes a possibly schema-qualified name such as `dbo.ORDERS`.
+pub fn quote_qualified(db_type: &str, name: &str) -> String {
+    name.split('.').map(|part| quote_ident(db_type, part.trim_matches(|c| c == '[' || c == ']' || c == '"' || c == '`'))).collect::<Vec<_>>().join(".")
+}
+
+/// Restricts a SELECT to rows `offset..offset + limit`. Appends the paging clause when the outer
+/// query has none of its own, otherwise pages over the query as a derived table.
+pub fn page_query(db_type: &str, query: &str, offset: u64, limit: u64) -> String {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let has = |keywords: &[&str]| sql_analysis::has_top_level_keyword(query, keywords);
+    match db_type {
+        "mssql" => {
+            let fetch = format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit);
+            // OFFSET needs an ORDER BY and cannot be combined with TOP
+            if has(&["TOP", "OFFSET"]) {
+                format!("SELECT * FROM ({}) AS page_q ORDER BY (SELECT NULL) {}", query, fetch)
+            } else if has(&["ORDER"]) {
+                format!("{} {}", query, fetch)
+            } else {
+                format!("{} ORDER BY (SELECT NULL) {}", query, fetch)
+            }
+        }
+        "oracle" => {
+            let fetch = format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit);
+            if has(&["OFFSET", "FETCH"]) {
+                format!("SELECT * FROM ({}) page_q {}", query, fetch)
+            } else {
+                format!("{} {}", query, fetch)
+            }
+        }
+        _ => {
+            if has(&["LIMIT", "OFFSET", "FETCH"]) {
+                format!("SELECT * FROM ({}) AS page_q LIMIT {} OFFSET {}", query, limit, offset)
+            } else {
+                format!("{} LIMIT {} OFFSET {}", query, limit, offset)
+            }
+        }
+    }
+}
+
+/// Caps a plain interactive SELECT at `limit` rows (`TOP n` / `FETCH FIRST` / `LIMIT n`).
+/// Returns None, leaving the query as is, for anything else: scripts, CTEs, statements that
+/// already limit themselves, `SELECT ... INTO`, locking reads and MSSQL set operations.
+pub fn limit_query(db_type: &str, query: &str, limit: u64) -> Option<String> {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let tokens = sql_analysis::tokenize(query);
+    if !tokens.first()?.is_keyword("SELECT") || sql_analysis::statement_spans(query, db_type).len() != 1 {
+        return None;
+    }
+    let has = |keywords: &[&str]| sql_analysis::has_top_level_keyword(query, keywords);
+    if has(&["TOP", "LIMIT", "OFFSET", "FETCH", "INTO", "FOR"]) {
+        return None;
+    }
+    match db_type {
+        "mssql" => {
+            // TOP would only apply to the first branch of a UNION
+            if has(&["UNION", "INTERSECT", "EXCEPT"]) {
+                return None;
+            }
+            let at = match tokens.get(1) {
+                Some(t) if t.is_keyword("DISTINCT") || t.is_keyword("ALL") => t.end,
+                _ => tokens[0].end,
+            };
+            Some(format!("{} TOP {}{}", &query[..at], limit, &query[at..]))
+        }
+        // On its own line so a trailing `--` comment cannot swallow it
+        "oracle" => Some(format!("{}\nFETCH FIRST {} ROWS ONLY", query, limit)),
+        _ => Some(format!("{}\nLIMIT {}", query, limit)),
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Translation {
+    pub sql: String,
+    /// Constructs that were left as they are and need porting by hand.
+    pub warnings: Vec<String>,
+}
+
+fn now_call(db_type: &str) -> &'static str {
+    match db_type {
+        "mssql" => "GETDATE()",
+        "oracle" => "SYSDATE",
+        _ => "NOW()",
+    }
+}
+
+fn row_cap(db_type: &str, count: &str) -> String {
+    match db_type {
+        "oracle" => format!(" FETCH FIRST {} ROWS ONLY", count),
+        _ => format!(" LIMIT {}", count),
+    }
+}
+
+/// Rewrites one statement; `edits` are `(start, end, replacement)` byte ranges of `sql`.
+fn translate_statement(sql: &str, from: &str, to: &str, warnings: &mut Vec<String>) -> String {
+    let tokens = sql_analysis::tokenize(sql);
+    let mut depths = Vec::with_capacity(tokens.len());
+    let mut depth = 0;
+    for t in &tokens {
+        if t.is_symbol(")") {
+            depth -= 1;
+        }
+        depths.push(depth);
+        if t.is_symbol("(") {
+            depth += 1;
+        }
+    }
+    let is_call = |i: usize| tokens.get(i + 1).map_or(false, |t| t.is_symbol("("));
+    let is_empty_call = |i: usize| is_call(i) && tokens.get(i + 2).map_or(false, |t| t.is_symbol(")"));
+    // Last token before the `)` closing the level of `tokens[i]`, or of the statement
+    let level_end = |i: usize| -> &Token {
+        let close = (i..tokens.len()).find(|&j| depths[j] < depths[i]).unwrap_or(tokens.len());
+        &tokens[close - 1]
+    };
+    let same_level_keyword = |range: std::ops::Range<usize>, at: usize, keywords: &[&str]| {
+        range.filter(|&j| depths[j] == depths[at]).any(|j| keywords.iter().any(|k| tokens[j].is_keyword(k)))
+    };
+
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    for (i, t) in tokens.iter().enumerate() {
+        let word = if t.kind == TokenKind::Word { t.text.to_uppercase() } else { String::new() };
+        match word.as_str() {
+            "GETDATE" | "SYSDATETIME" | "NOW" if is_empty_call(i) && now_call(to) != format!("{}()", word) => {
+                edits.push((t.start, tokens[i + 2].end, now_call(to).to_string()));
+            }
+            "SYSDATE" if from == "oracle" && to != "oracle" && !is_call(i) => edits.push((t.start, t.end, now_call(to).to_string())),
+            // MySQL's one-argument ISNULL(x) is a test, not a default
+            "ISNULL" if from == "mssql" && to != "mssql" && is_call(i) => edits.push((t.start, t.end, "COALESCE".to_string())),
+            "IFNULL" if to != "mysql" && is_call(i) => edits.push((t.start, t.end, "COALESCE".to_string())),
+            "NVL" if to != "oracle" && is_call(i) => edits.push((t.start, t.end, "COALESCE".to_string())),
+            "LEN" if to != "mssql" && is_call(i) => edits.push((t.start, t.end, if to == "mysql" { "CHAR_LENGTH" } else { "LENGTH" }.to_string())),
+            "LENGTH" | "CHAR_LENGTH" if to == "mssql" && is_call(i) => edits.push((t.start, t.end, "LEN".to_string())),
+            "TOP" if from == "mssql" && to != "mssql" => {
+                let (count, last) = match (tokens.get(i + 1), tokens.get(i + 2), tokens.get(i + 3)) {
+                    (Some(n), _, _) if n.kind == TokenKind::Number => (n.text.clone(), i + 1),
+                    (Some(open), Some(n), Some(close)) if open.is_symbol("(") && n.kind == TokenKind::Number && close.is_symbol(")") => (n.text.clone(), i + 3),
+                    _ => {
+                        warnings.push("TOP với biểu thức/biến chưa được chuyển".to_string());
+                        continue;
+                    }
+                };
+                let tail = tokens.get(last + 1);
+                if tail.map_or(false, |n| n.is_keyword("PERCENT") || n.is_keyword("WITH")) {
+                    warnings.push("TOP ... PERCENT / WITH TIES chưa được chuyển".to_string());
+                    continue;
+                }
+                if same_level_keyword(i..tokens.len(), i, &["UNION", "EXCEPT", "INTERSECT"]) {
+                    warnings.push("TOP trong UNION/EXCEPT/INTERSECT chưa được chuyển".to_string());
+                    continue;
+                }
+                edits.push((t.start, tail.map_or(tokens[last].end, |n| n.start), String::new()));
+                let end = level_end(i).end;
+                edits.push((end, end, row_cap(to, &count)));
+            }
+            "LIMIT" if from != "mssql" && (to == "mssql" || to == "oracle") => {
+                let count = match tokens.get(i + 1) {
+                    Some(n) if n.kind == TokenKind::Number && !tokens.get(i + 2).map_or(false, |x| x.is_symbol(",") || x.is_keyword("OFFSET")) => n,
+                    _ => {
+                        warnings.push("LIMIT với OFFSET chưa được chuyển".to_string());
+                        continue;
+                    }
+                };
+                if to == "oracle" {
+                    edits.push((t.start, count.end, row_cap(to, &count.text).trim_start().to_string()));
+                    continue;
+                }
+                // TOP goes after the SELECT [DISTINCT] of the same query level
+                let select = (0..i).rev().find(|&j| depths[j] == depths[i] && tokens[j].is_keyword("SELECT"));
+                match select {
+                    Some(j) if !same_level_keyword(j..i, i, &["UNION", "EXCEPT", "INTERSECT"]) => {
+                        let at = match tokens.get(j + 1) {
+                            Some(d) if d.is_keyword("DISTINCT") || d.is_keyword("ALL") => d.end,
+                            _ => tokens[j].end,
+                        };
+                        edits.push((at, at, format!(" TOP {}", count.text)));
+                        let before = if i > 0 { tokens[i - 1].end } else { t.start };
+                        edits.push((before, count.end, String::new()));
+                    }
+                    _ => warnings.push("LIMIT của UNION/EXCEPT/INTERSECT chưa được chuyển".to_string()),
+                }
+            }
+            _ => {}
+        }
+        if t.kind == TokenKind::QuotedIdent {
+            let quote = sql.as_bytes()[t.start];
+            // Double quotes are strings in MySQL
+            let is_ident = !(quote == b'"' && from == "mysql");
+            if is_ident && quote_ident(to, &t.text) != sql[t.start..t.end] {
+                edits.push((t.start, t.end, quote_ident(to, &t.text)));
+            }
+        }
+    }
+
+    let mut out = sql.to_string();
+    edits.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    for (start, end, text) in edits {
+        out.replace_range(start..end, &text);
+    }
+    out
+}
+
+/// Ports common constructs of a script from `from` to `to`: TOP ⇄ LIMIT/FETCH FIRST,
+/// GETDATE()/NOW()/SYSDATE, ISNULL/IFNULL/NVL → COALESCE, LEN ⇄ LENGTH and identifier quoting.
+/// Everything else is kept as written.
+pub fn translate(sql: &str, from: &str, to: &str) -> Translation {
+    if from == to {
+        return Translation { sql: sql.to_string(), warnings: Vec::new() };
+    }
+    let mut translation = Translation::default();
+    let mut last = 0;
+    // Spans come in order with character offsets, so their byte offsets are found in one pass
+    let mut chars = sql.char_indices().enumerate();
+    for span in sql_analysis::statement_spans(sql, from) {
+        let start = chars.find(|(n, _)| *n == span.start).map_or(sql.len(), |(_, (i, _))| i);
+        translation.sql.push_str(&sql[last..start]);
+        translation.sql.push_str(&translate_statement(&span.text, from, to, &mut translation.warnings));
+        last = start + span.text.len();
+    }
+    translation.sql.push_str(&sql[last..]);
+    translation.warnings.dedup();
+    translation
+}
+
+#[tauri::command]
+pub fn translate_sql(sql: String, from: String, to: String) -> Translation {
+    translate(&sql, &from, &to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote_string("mssql", "O'Neil"), "N'O''Neil'");
+        assert_eq!(quote_string("mysql", "a\\b"), "'a\\\\b'");
+        assert_eq!(quote_ident("postgres", "Order"), "\"Order\"");
+        assert_eq!(quote_qualified("mssql", "dbo.[ORDERS]"), "[dbo].[ORDERS]");
+    }
+
+    #[test]
+    fn test_page_query() {
+        assert_eq!(page_query("mssql", "SELECT * FROM t ORDER BY id;", 100, 50), "SELECT * FROM t ORDER BY id OFFSET 100 ROWS FETCH NEXT 50 ROWS ONLY");
+        assert_eq!(page_query("mssql", "SELECT * FROM t", 0, 50), "SELECT * FROM t ORDER BY (SELECT NULL) OFFSET 0 ROWS FETCH NEXT 50 ROWS ONLY");
+        assert_eq!(page_query("mssql", "SELECT TOP 10 * FROM t ORDER BY id", 0, 5), "SELECT * FROM (SELECT TOP 10 * FROM t ORDER BY id) AS page_q ORDER BY (SELECT NULL) OFFSET 0 ROWS FETCH NEXT 5 ROWS ONLY");
+        assert_eq!(page_query("postgres", "SELECT * FROM t WHERE x IN (SELECT y FROM u LIMIT 3)", 20, 10), "SELECT * FROM t WHERE x IN (SELECT y FROM u LIMIT 3) LIMIT 10 OFFSET 20");
+        assert_eq!(page_query("mysql", "SELECT * FROM t LIMIT 5", 0, 2), "SELECT * FROM (SELECT * FROM t LIMIT 5) AS page_q LIMIT 2 OFFSET 0");
+        assert_eq!(page_query("oracle", "SELECT * FROM t", 10, 10), "SELECT * FROM t OFFSET 10 ROWS FETCH NEXT 10 ROWS ONLY");
+    }
+
+    #[test]
+    fn test_limit_query() {
+        assert_eq!(limit_query("mssql", "SELECT DISTINCT a FROM t;", 100).as_deref(), Some("SELECT DISTINCT TOP 100 a FROM t"));
+        assert_eq!(limit_query("mysql", "select * from t -- all", 100).as_deref(), Some("select * from t -- all\nLIMIT 100"));
+        assert_eq!(limit_query("oracle", "SELECT * FROM t", 5).as_deref(), Some("SELECT * FROM t\nFETCH FIRST 5 ROWS ONLY"));
+        assert_eq!(limit_query("postgres", "SELECT * FROM (SELECT * FROM t LIMIT 3) x", 100).as_deref(), Some("SELECT * FROM (SELECT * FROM t LIMIT 3) x\nLIMIT 100"));
+        assert_eq!(limit_query("mssql", "SELECT TOP 10 * FROM t", 100), None);
+        assert_eq!(limit_query("mssql", "SELECT a FROM t UNION SELECT a FROM u", 100), None);
+        assert_eq!(limit_query("mysql", "SELECT * FROM t FOR UPDATE", 100), None);
+        assert_eq!(limit_query("mysql", "UPDATE t SET a = 1", 100), None);
+        assert_eq!(limit_query("mysql", "SELECT 1; SELECT 2", 100), None);
+    }
+
+    #[test]
+    fn test_translate() {
+        let sql = "SELECT TOP 10 [Name], ISNULL(Qty, 0), LEN(Code)\nFROM [dbo].[受注] WHERE Created < GETDATE() ORDER BY Id;";
+        assert_eq!(translate(sql, "mssql", "postgres").sql, "SELECT \"Name\", COALESCE(Qty, 0), LENGTH(Code)\nFROM \"dbo\".\"受注\" WHERE Created < NOW() ORDER BY Id LIMIT 10;");
+        assert_eq!(translate("SELECT * FROM t WHERE id IN (SELECT TOP (3) id FROM u ORDER BY d)", "mssql", "mysql").sql, "SELECT * FROM t WHERE id IN (SELECT id FROM u ORDER BY d LIMIT 3)");
+        assert_eq!(translate("SELECT DISTINCT `a` FROM t WHERE IFNULL(b, 1) = 1 LIMIT 5", "mysql", "mssql").sql, "SELECT DISTINCT TOP 5 [a] FROM t WHERE COALESCE(b, 1) = 1");
+        let union = translate("SELECT TOP 1 a FROM t UNION SELECT a FROM u", "mssql", "postgres");
+        assert_eq!(union.sql, "SELECT TOP 1 a FROM t UNION SELECT a FROM u");
+        assert_eq!(union.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::oneshot;
+
+pub const DEFAULT_PRIORITY: i32 = 0;
+
+/// Emitted as `queue://position` whenever a waiting query moves; position 0 means it started.
+#[derive(Serialize, Clone, Debug)]
+pub struct QueuePosition {
+    pub ticket: String,
+    pub connection_id: String,
+    pub position: usize,
+}
+
+struct Waiter {
+    ticket: String,
+    priority: i32,
+    seq: u64,
+    window: tauri::Window,
+    wake: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct ConnectionQueue {
+    running: Option<String>,
+    waiting: Vec<Waiter>,
+}
+
+impl ConnectionQueue {
+    /// Higher priority first, then first come first served.
+    fn sort(&mut self) {
+        self.waiting.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.seq.cmp(&b.seq)));
+    }
+
+    fn emit_positions(&self, connection_id: &str) {
+        for (i, w) in self.waiting.iter().enumerate() {
+            w.window.emit("queue://position", QueuePosition { ticket: w.ticket.clone(), connection_id: connection_id.to_string(), position: i + 1 }).ok();
+        }
+    }
+}
+
+/// Serializes statements per connection for single-user databases (opt-in via `DbConfig::use_queue`).
+#[derive(Default)]
+pub struct ExecQueue {
+    queues: Mutex<HashMap<String, ConnectionQueue>>,
+    seq: Mutex<u64>,
+}
+
+/// Holds the connection's slot; the next waiter is started when dropped.
+pub struct QueueSlot<'a> {
+    queue: &'a ExecQueue,
+    connection_id: String,
+}
+
+impl ExecQueue {
+    pub async fn acquire(&self, window: &tauri::Window, connection_id: &str, ticket: &str, priority: i32) -> QueueSlot<'_> {
+        let rx = {
+            let mut queues = self.queues.lock().unwrap();
+            let queue = queues.entry(connection_id.to_string()).or_default();
+            if queue.running.is_none() {
+                queue.running = Some(ticket.to_string());
+                None
+            } else {
+                let seq = {
+                    let mut s = self.seq.lock().unwrap();
+                    *s += 1;
+                    *s
+                };
+                let (tx, rx) = oneshot::channel();
+                queue.waiting.push(Waiter { ticket: ticket.to_string(), priority, seq, window: window.clone(), wake: tx });
+                queue.sort();
+                queue.emit_positions(connection_id);
+                Some(rx)
+            }
+        };
+        if let Some(rx) = rx {
+            // The sender is only dropped without sending if the queue itself goes away
+            rx.await.ok();
+        }
+        window.emit("queue://position", QueuePosition { ticket: ticket.to_string(), connection_id: connection_id.to_string(), position: 0 }).ok();
+        QueueSlot { queue: self, connection_id: connection_id.to_string() }
+    }
+
+    fn release(&self, connection_id: &str) {
+        let mut queues = self.queues.lock().unwrap();
+        let queue = match queues.get_mut(connection_id) {
+            Some(q) => q,
+            None => return,
+        };
+        queue.running = None;
+        // Waiters whose command was dropped have closed receivers; skip them
+        while !queue.waiting.is_empty() {
+            let next = queue.waiting.remove(0);
+            if next.wake.send(()).is_ok() {
+                queue.running = Some(next.ticket);
+                break;
+            }
+        }
+        if queue.running.is_none() {
+            queues.remove(connection_id);
+        } else {
+            queue.emit_positions(connection_id);
+        }
+    }
+
+    pub fn status(&self, connection_id: &str) -> QueueStatus {
+        let queues = self.queues.lock().unwrap();
+        match queues.get(connection_id) {
+            Some(q) => QueueStatus {
+                running: q.running.clone(),
+                waiting: q.waiting.iter().map(|w| w.ticket.clone()).collect(),
+            },
+            None => QueueStatus { running: None, waiting: Vec::new() },
+        }
+    }
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.queue.release(&self.connection_id);
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct QueueStatus {
+    pub running: Option<String>,
+    pub waiting: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_queue_status(queue: State<'_, ExecQueue>, connection_id: String) -> QueueStatus {
+    queue.status(&connection_id)
+}
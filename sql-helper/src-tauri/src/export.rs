@@ -0,0 +1,903 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::Instant;
+use encoding_rs::SHIFT_JIS;
+use rust_xlsxwriter::{Color, Format, FormatBorder, Note, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use crate::history::QueryHistory;
+use crate::import::is_shift_jis;
+use crate::profiles::ActiveProfile;
+use crate::result_ops::{display_width, NULL_CELL};
+use crate::result_store::ResultStore;
+use crate::shutdown::ActiveOperations;
+use crate::snapshots::{self, CellNote};
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+// File exports done in Rust, so large results never pass through the webview.
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const RECORD_BUFFER_BYTES: usize = 1024;
+const DEFAULT_HEADER_COLOR: u32 = 0x4F46E5;
+const DEFAULT_SHEET_NAME: &str = "Result";
+/// Data rows per sheet (Excel's limit minus the header).
+pub const XLSX_MAX_ROWS: usize = 1_048_575;
+const XLSX_MAX_DIGITS: usize = 15;
+const XLSX_WIDTH_PADDING: usize = 2;
+const XLSX_MIN_WIDTH: f64 = 6.0;
+const XLSX_MAX_WIDTH: f64 = 80.0;
+
+/// Where the exported rows come from.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ExportSource {
+    /// Rows the frontend already has.
+    Result { result: QueryResult },
+    /// A result kept with `hold_result`.
+    Held { handle: String },
+    /// A pinned snapshot; its cell notes become comments in Excel exports.
+    Pinned { name: String },
+    /// Runs the query again and writes rows as they stream in; only the first result set is exported.
+    /// `confirm_dangerous` is the `query_guard` confirmation for production connections.
+    Query {
+        config: DbConfig,
+        query: String,
+        #[serde(default)]
+        confirm_dangerous: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CsvExportOptions {
+    /// Defaults to ','; use '\t' for TSV.
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// "necessary" (default), "always", "non_numeric" or "never".
+    #[serde(default)]
+    pub quote_style: Option<String>,
+    /// "utf-8" (default) or "shift_jis".
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// UTF-8 only: write a BOM so Excel detects the encoding.
+    #[serde(default)]
+    pub bom: bool,
+    /// "crlf" (default) or "lf".
+    #[serde(default)]
+    pub line_ending: Option<String>,
+    /// Defaults to true.
+    #[serde(default)]
+    pub include_header: Option<bool>,
+    /// Written for NULL cells; defaults to an empty field.
+    #[serde(default)]
+    pub null_value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct XlsxExportOptions {
+    /// `#RRGGBB`; defaults to `AppSettings::excel_header_color`.
+    #[serde(default)]
+    pub header_color: Option<String>,
+    #[serde(default)]
+    pub sheet_name: Option<String>,
+    /// Leading columns kept visible when scrolling right (the header row is always frozen).
+    #[serde(default)]
+    pub freeze_columns: Option<u16>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct JsonExportOptions {
+    /// "array" (default) or "ndjson", one object per line.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// One field of a fixed-width record.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FixedWidthField {
+    pub column: String,
+    /// Byte position in the record, from 0.
+    pub offset: usize,
+    /// In Shift-JIS bytes: full-width characters take 2, ASCII and half-width kana 1.
+    pub length: usize,
+    /// "left" (default) or "right", e.g. for zero-padded numbers.
+    #[serde(default)]
+    pub align: Option<String>,
+    /// Fills the rest of the field; defaults to a space.
+    #[serde(default)]
+    pub pad: Option<char>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct FixedWidthOptions {
+    pub fields: Vec<FixedWidthField>,
+    /// Defaults to the end of the last field; bytes no field covers are spaces.
+    #[serde(default)]
+    pub record_length: Option<usize>,
+    /// "crlf" (default), "lf" or "none" (records back to back).
+    #[serde(default)]
+    pub line_ending: Option<String>,
+    /// Cut values longer than their field instead of failing; a double-byte character is never split.
+    #[serde(default)]
+    pub truncate: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportProgress {
+    pub path: String,
+    pub rows_written: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportSummary {
+    pub path: String,
+    pub rows_written: u64,
+    pub bytes_written: u64,
+    pub elapsed_ms: u128,
+}
+
+/// Formats each record with the csv crate into a small buffer, then re-encodes it on its way to `out`.
+pub struct CsvSink<W: Write> {
+    out: W,
+    format: csv::WriterBuilder,
+    shift_jis: bool,
+    null_value: String,
+    pub rows_written: u64,
+    pub bytes_written: u64,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(mut out: W, options: &CsvExportOptions) -> Result<Self, String> {
+        let delimiter = options.delimiter.unwrap_or(',');
+        if !delimiter.is_ascii() {
+            return Err(format!("Ký tự phân cách phải là ASCII: '{}'", delimiter));
+        }
+        let quote_style = match options.quote_style.as_deref().unwrap_or("necessary") {
+            "necessary" => csv::QuoteStyle::Necessary,
+            "always" => csv::QuoteStyle::Always,
+            "non_numeric" => csv::QuoteStyle::NonNumeric,
+            "never" => csv::QuoteStyle::Never,
+            other => return Err(format!("Kiểu quote không hợp lệ: {}", other)),
+        };
+        let terminator = match options.line_ending.as_deref().unwrap_or("crlf") {
+            "crlf" => csv::Terminator::CRLF,
+            "lf" => csv::Terminator::Any(b'\n'),
+            other => return Err(format!("Kiểu xuống dòng không hợp lệ: {}", other)),
+        };
+        let shift_jis = is_shift_jis(options.encoding.as_deref());
+        let mut bytes_written = 0;
+        if options.bom && !shift_jis {
+            out.write_all(UTF8_BOM).map_err(|e| format!("Không thể ghi file: {}", e))?;
+            bytes_written += UTF8_BOM.len() as u64;
+        }
+        let mut format = csv::WriterBuilder::new();
+        format.delimiter(delimiter as u8).quote_style(quote_style).terminator(terminator).buffer_capacity(RECORD_BUFFER_BYTES);
+        Ok(CsvSink {
+            out,
+            format,
+            shift_jis,
+            null_value: options.null_value.clone().unwrap_or_default(),
+            rows_written: 0,
+            bytes_written,
+        })
+    }
+
+    fn write_record<'a>(&mut self, cells: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        let mut record = self.format.from_writer(Vec::new());
+        record.write_record(cells).map_err(|e| e.to_string())?;
+        let utf8 = record.into_inner().map_err(|e| e.to_string())?;
+        let bytes = if self.shift_jis {
+            let text = String::from_utf8(utf8).map_err(|e| e.to_string())?;
+            let (encoded, _, had_errors) = SHIFT_JIS.encode(&text);
+            if had_errors {
+                return Err(format!("Dòng {} có ký tự không chuyển được sang Shift-JIS", self.rows_written + 1));
+            }
+            encoded.into_owned()
+        } else {
+            utf8
+        };
+        self.out.write_all(&bytes).map_err(|e| format!("Không thể ghi file: {}", e))?;
+        self.bytes_written += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub fn header(&mut self, columns: &[String]) -> Result<(), String> {
+        self.write_record(columns.iter().map(String::as_str))
+    }
+
+    pub fn row(&mut self, row: &[String]) -> Result<(), String> {
+        let null_value = std::mem::take(&mut self.null_value);
+        let result = self.write_record(row.iter().map(|c| if c == NULL_CELL { null_value.as_str() } else { c.as_str() }));
+        self.null_value = null_value;
+        self.rows_written += 1;
+        result
+    }
+
+    pub fn finish(mut self) -> Result<(u64, u64), String> {
+        self.out.flush().map_err(|e| format!("Không thể ghi file: {}", e))?;
+        Ok((self.rows_written, self.bytes_written))
+    }
+}
+
+/// How a column's cells are written to JSON, from its `QueryResult::column_types` entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum JsonKind {
+    Integer,
+    Float,
+    Decimal,
+    Boolean,
+    Json,
+    Text,
+}
+
+fn json_kind(column_type: &str) -> JsonKind {
+    match column_type.trim_end_matches(" unsigned") {
+        "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "int2" | "int4" | "int8" | "oid" => JsonKind::Integer,
+        "real" | "float" | "double" | "double precision" | "float4" | "float8" | "binary_float" | "binary_double" => JsonKind::Float,
+        "decimal" | "numeric" | "number" | "money" | "smallmoney" => JsonKind::Decimal,
+        "bit" | "bool" | "boolean" => JsonKind::Boolean,
+        "json" | "jsonb" => JsonKind::Json,
+        _ => JsonKind::Text,
+    }
+}
+
+/// The typed value of a cell. Decimals with more digits than an f64 keeps stay strings, and so
+/// does anything that does not parse as its column type.
+fn json_value(cell: &str, kind: JsonKind) -> serde_json::Value {
+    use serde_json::Value;
+    if cell == NULL_CELL {
+        return Value::Null;
+    }
+    let typed = match kind {
+        JsonKind::Integer => cell.parse::<i64>().map(Value::from).or_else(|_| cell.parse::<u64>().map(Value::from)).ok(),
+        JsonKind::Float => cell.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number),
+        JsonKind::Decimal => cell.parse::<i64>().ok().map(Value::from)
+            .or_else(|| as_number(cell).and_then(serde_json::Number::from_f64).map(Value::Number)),
+        JsonKind::Boolean => match cell {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        JsonKind::Json => serde_json::from_str(cell).ok(),
+        JsonKind::Text => None,
+    };
+    typed.unwrap_or_else(|| Value::String(cell.to_string()))
+}
+
+/// Object keys in column order; repeated column names get `_2`, `_3`, ... like joined results.
+fn json_keys(columns: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(columns.len());
+    for name in columns {
+        let mut key = name.clone();
+        let mut n = 2;
+        while keys.contains(&key) {
+            key = format!("{}_{}", name, n);
+            n += 1;
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// Writes rows as JSON objects, either inside one array or as newline-delimited JSON.
+/// Objects are built by hand so keys keep the column order.
+pub struct JsonSink<W: Write> {
+    out: W,
+    ndjson: bool,
+    keys: Vec<String>,
+    kinds: Vec<JsonKind>,
+    pub rows_written: u64,
+    pub bytes_written: u64,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(out: W, options: &JsonExportOptions) -> Result<Self, String> {
+        let ndjson = match options.format.as_deref().unwrap_or("array") {
+            "array" => false,
+            "ndjson" => true,
+            other => return Err(format!("Định dạng JSON không hợp lệ: {}", other)),
+        };
+        let mut sink = JsonSink { out, ndjson, keys: Vec::new(), kinds: Vec::new(), rows_written: 0, bytes_written: 0 };
+        if !ndjson {
+            sink.write("[")?;
+        }
+        Ok(sink)
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        self.out.write_all(text.as_bytes()).map_err(|e| format!("Không thể ghi file: {}", e))?;
+        self.bytes_written += text.len() as u64;
+        Ok(())
+    }
+
+    /// Sets the keys and value types; only the first call counts, so it can be repeated per batch.
+    pub fn columns(&mut self, columns: &[String], column_types: &[String]) {
+        if self.keys.is_empty() {
+            self.keys = json_keys(columns);
+            self.kinds = (0..columns.len()).map(|c| column_types.get(c).map_or(JsonKind::Text, |t| json_kind(t))).collect();
+        }
+    }
+
+    pub fn row(&mut self, row: &[String]) -> Result<(), String> {
+        let mut line = String::from(match (self.ndjson, self.rows_written) {
+            (true, _) => "",
+            (false, 0) => "\n",
+            (false, _) => ",\n",
+        });
+        line.push('{');
+        for (c, (key, cell)) in self.keys.iter().zip(row).enumerate() {
+            if c > 0 {
+                line.push(',');
+            }
+            let value = json_value(cell, self.kinds.get(c).copied().unwrap_or(JsonKind::Text));
+            line.push_str(&serde_json::to_string(key).map_err(|e| e.to_string())?);
+            line.push(':');
+            line.push_str(&serde_json::to_string(&value).map_err(|e| e.to_string())?);
+        }
+        line.push('}');
+        if self.ndjson {
+            line.push('\n');
+        }
+        self.write(&line)?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(u64, u64), String> {
+        if !self.ndjson {
+            self.write(if self.rows_written > 0 { "\n]\n" } else { "]\n" })?;
+        }
+        self.out.flush().map_err(|e| format!("Không thể ghi file: {}", e))?;
+        Ok((self.rows_written, self.bytes_written))
+    }
+}
+
+/// A field with its source column resolved.
+struct FixedSlot {
+    column: usize,
+    offset: usize,
+    length: usize,
+    right: bool,
+    pad: u8,
+}
+
+/// Writes each row as one Shift-JIS record of `record_length` bytes, fields at their byte
+/// offsets. No header line, as host files have none.
+pub struct FixedWidthSink<W: Write> {
+    out: W,
+    fields: Vec<FixedWidthField>,
+    slots: Vec<FixedSlot>,
+    record_length: usize,
+    terminator: &'static [u8],
+    truncate: bool,
+    pub rows_written: u64,
+    pub bytes_written: u64,
+}
+
+/// How many Shift-JIS bytes of `text` fit in `length` without splitting a character.
+fn fit_prefix(text: &str, length: usize) -> usize {
+    let mut used = 0;
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        let n = SHIFT_JIS.encode(c.encode_utf8(&mut buf)).0.len();
+        if used + n > length {
+            break;
+        }
+        used += n;
+    }
+    used
+}
+
+impl<W: Write> FixedWidthSink<W> {
+    pub fn new(out: W, options: &FixedWidthOptions) -> Result<Self, String> {
+        if options.fields.is_empty() {
+            return Err("Chưa khai báo trường nào".to_string());
+        }
+        let mut fields = options.fields.clone();
+        fields.sort_by_key(|f| f.offset);
+        for f in &fields {
+            if f.length == 0 {
+                return Err(format!("Trường {} có độ dài 0", f.column));
+            }
+            if !matches!(f.align.as_deref().unwrap_or("left"), "left" | "right") {
+                return Err(format!("Căn lề không hợp lệ: {}", f.align.as_deref().unwrap_or("")));
+            }
+            if !f.pad.unwrap_or(' ').is_ascii() {
+                return Err(format!("Ký tự đệm phải là ASCII: '{}'", f.pad.unwrap_or(' ')));
+            }
+        }
+        for pair in fields.windows(2) {
+            if pair[0].offset + pair[0].length > pair[1].offset {
+                return Err(format!("Trường {} (offset {}, {} byte) chồng lên trường {} (offset {})",
+                    pair[0].column, pair[0].offset, pair[0].length, pair[1].column, pair[1].offset));
+            }
+        }
+        let end = fields.iter().map(|f| f.offset + f.length).max().unwrap_or(0);
+        let record_length = options.record_length.unwrap_or(end);
+        if record_length < end {
+            return Err(format!("Độ dài bản ghi {} nhỏ hơn vị trí cuối của trường ({} byte)", record_length, end));
+        }
+        let terminator: &'static [u8] = match options.line_ending.as_deref().unwrap_or("crlf") {
+            "crlf" => b"\r\n",
+            "lf" => b"\n",
+            "none" => b"",
+            other => return Err(format!("Kiểu xuống dòng không hợp lệ: {}", other)),
+        };
+        Ok(FixedWidthSink { out, fields, slots: Vec::new(), record_length, terminator, truncate: options.truncate, rows_written: 0, bytes_written: 0 })
+    }
+
+    /// Finds each field's column (case-insensitive); only the first call counts, so it can be
+    /// repeated per batch.
+    pub fn columns(&mut self, columns: &[String]) -> Result<(), String> {
+        if !self.slots.is_empty() {
+            return Ok(());
+        }
+        self.slots = self.fields.iter()
+            .map(|f| {
+                let column = columns.iter().position(|c| c.eq_ignore_ascii_case(&f.column))
+                    .ok_or_else(|| format!("Kết quả không có cột: {}", f.column))?;
+                Ok(FixedSlot { column, offset: f.offset, length: f.length, right: f.align.as_deref() == Some("right"), pad: f.pad.unwrap_or(' ') as u8 })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(())
+    }
+
+    pub fn row(&mut self, row: &[String]) -> Result<(), String> {
+        let line = self.rows_written + 1;
+        let mut record = vec![b' '; self.record_length];
+        for (slot, field) in self.slots.iter().zip(&self.fields) {
+            let cell = row.get(slot.column).map(String::as_str).unwrap_or(NULL_CELL);
+            // NULL leaves the field blank, whatever its padding
+            if cell == NULL_CELL {
+                continue;
+            }
+            let (encoded, _, had_errors) = SHIFT_JIS.encode(cell);
+            if had_errors {
+                return Err(format!("Dòng {}, cột {}: có ký tự không chuyển được sang Shift-JIS", line, field.column));
+            }
+            let mut value: &[u8] = &encoded;
+            if value.len() > slot.length {
+                if !self.truncate {
+                    return Err(format!("Dòng {}, cột {}: {} byte vượt quá độ dài trường ({} byte)", line, field.column, value.len(), slot.length));
+                }
+                value = &value[..fit_prefix(cell, slot.length)];
+            }
+            let target = &mut record[slot.offset..slot.offset + slot.length];
+            let padding = slot.length - value.len();
+            if slot.right {
+                target[..padding].fill(slot.pad);
+                target[padding..].copy_from_slice(value);
+            } else {
+                target[..value.len()].copy_from_slice(value);
+                target[value.len()..].fill(slot.pad);
+            }
+        }
+        record.extend_from_slice(self.terminator);
+        self.out.write_all(&record).map_err(|e| format!("Không thể ghi file: {}", e))?;
+        self.bytes_written += record.len() as u64;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(u64, u64), String> {
+        self.out.flush().map_err(|e| format!("Không thể ghi file: {}", e))?;
+        Ok((self.rows_written, self.bytes_written))
+    }
+}
+
+pub fn create_file(path: &str) -> Result<BufWriter<File>, String> {
+    File::create(path).map(BufWriter::new).map_err(|e| format!("Không thể tạo file: {}", e))
+}
+
+/// Rows already held on this side (every source except `Query`), plus the cell notes of a
+/// pinned snapshot.
+fn stored_rows(window: &tauri::Window, store: &ResultStore, source: ExportSource) -> Result<(Arc<QueryResult>, Vec<CellNote>), String> {
+    match source {
+        ExportSource::Result { result } => Ok((Arc::new(result), Vec::new())),
+        ExportSource::Held { handle } => Ok((store.get(&handle)?, Vec::new())),
+        ExportSource::Pinned { name } => {
+            let handle = window.app_handle();
+            let notes = snapshots::load_notes(&handle, &name)?;
+            Ok((Arc::new(snapshots::load_snapshot(&handle, &name)?.result), notes))
+        }
+        ExportSource::Query { .. } => Err("Nguồn query phải được chạy lại".to_string()),
+    }
+}
+
+/// Runs `query` again and passes the rows of its first result set to `on_rows` batch by batch,
+/// emitting `export://progress`. Returns the columns, which are known even without rows.
+async fn stream_rows(window: &tauri::Window, profile: &ActiveProfile, config: &DbConfig, query: &str, confirmed: bool, path: &str, mut on_rows: impl FnMut(&db::RowBatch) -> Result<(), String>) -> Result<Vec<String>, String> {
+    profile.check_sql(query, config)?;
+    query_guard::check(config, query, confirmed)?;
+    let started = Instant::now();
+    let (sender, receiver) = db::batch_channel();
+    let fetch = db::FetchOptions { batch_sink: Some(sender), ..Default::default() };
+    let run = async {
+        let result = db::run_query_with(config, query, &fetch).await;
+        // Closes the channel so the writer below finishes
+        drop(fetch);
+        result
+    };
+    let write = async {
+        // Owned here so a failed write drops it, which stops the query
+        let mut receiver = receiver;
+        let mut rows_written: u64 = 0;
+        while let Some(batch) = receiver.recv().await {
+            if batch.result_index > 0 {
+                continue;
+            }
+            on_rows(&batch)?;
+            rows_written += batch.rows.len() as u64;
+            window.emit("export://progress", ExportProgress { path: path.to_string(), rows_written }).ok();
+        }
+        Ok::<u64, String>(rows_written)
+    };
+    let (result, written) = tokio::join!(run, write);
+    // A write failure drops the receiver, which the query reports as cancelled; show the cause
+    let error = written.as_ref().err().or(result.as_ref().err()).cloned();
+    let rows = written.as_ref().ok().copied().filter(|_| error.is_none());
+    window.state::<QueryHistory>().record_summary(config, query, started.elapsed(), rows, None, error);
+    written?;
+    Ok(result?.columns)
+}
+
+async fn write_csv(window: &tauri::Window, store: &ResultStore, profile: &ActiveProfile, source: ExportSource, path: &str, options: &CsvExportOptions) -> Result<(u64, u64), String> {
+    let mut sink = CsvSink::new(create_file(path)?, options)?;
+    let include_header = options.include_header.unwrap_or(true);
+    if let ExportSource::Query { config, query, confirm_dangerous } = &source {
+        let mut header_written = false;
+        let columns = stream_rows(window, profile, config, query, *confirm_dangerous, path, |batch| {
+            if include_header && !header_written {
+                sink.header(&batch.columns)?;
+                header_written = true;
+            }
+            batch.rows.iter().try_for_each(|row| sink.row(row))
+        }).await?;
+        if include_header && !header_written {
+            sink.header(&columns)?;
+        }
+        return sink.finish();
+    }
+    let (result, _) = stored_rows(window, store, source)?;
+    write_result(sink, &result, include_header)
+}
+
+fn write_result<W: Write>(mut sink: CsvSink<W>, result: &QueryResult, include_header: bool) -> Result<(u64, u64), String> {
+    if include_header {
+        sink.header(&result.columns)?;
+    }
+    for row in &result.rows {
+        sink.row(row)?;
+    }
+    sink.finish()
+}
+
+async fn write_json(window: &tauri::Window, store: &ResultStore, profile: &ActiveProfile, source: ExportSource, path: &str, options: &JsonExportOptions) -> Result<(u64, u64), String> {
+    let mut sink = JsonSink::new(create_file(path)?, options)?;
+    if let ExportSource::Query { config, query, confirm_dangerous } = &source {
+        stream_rows(window, profile, config, query, *confirm_dangerous, path, |batch| {
+            sink.columns(&batch.columns, &batch.column_types);
+            batch.rows.iter().try_for_each(|row| sink.row(row))
+        }).await?;
+        return sink.finish();
+    }
+    let (result, _) = stored_rows(window, store, source)?;
+    write_json_result(sink, &result)
+}
+
+fn write_json_result<W: Write>(mut sink: JsonSink<W>, result: &QueryResult) -> Result<(u64, u64), String> {
+    sink.columns(&result.columns, &result.column_types);
+    for row in &result.rows {
+        sink.row(row)?;
+    }
+    sink.finish()
+}
+
+async fn write_fixed_width(window: &tauri::Window, store: &ResultStore, profile: &ActiveProfile, source: ExportSource, path: &str, options: &FixedWidthOptions) -> Result<(u64, u64), String> {
+    let mut sink = FixedWidthSink::new(create_file(path)?, options)?;
+    if let ExportSource::Query { config, query, confirm_dangerous } = &source {
+        let columns = stream_rows(window, profile, config, query, *confirm_dangerous, path, |batch| {
+            sink.columns(&batch.columns)?;
+            batch.rows.iter().try_for_each(|row| sink.row(row))
+        }).await?;
+        // Checks the layout against the columns even when there were no rows
+        sink.columns(&columns)?;
+        return sink.finish();
+    }
+    let (result, _) = stored_rows(window, store, source)?;
+    write_fixed_width_result(sink, &result)
+}
+
+fn write_fixed_width_result<W: Write>(mut sink: FixedWidthSink<W>, result: &QueryResult) -> Result<(u64, u64), String> {
+    sink.columns(&result.columns)?;
+    for row in &result.rows {
+        sink.row(row)?;
+    }
+    sink.finish()
+}
+
+/// Cells written as Excel numbers. Codes with leading zeros, and values with more digits than
+/// an f64 keeps, stay text so nothing is altered.
+fn as_number(cell: &str) -> Option<f64> {
+    let unsigned = cell.strip_prefix('-').unwrap_or(cell);
+    let integer = unsigned.split('.').next().unwrap_or("");
+    let digits = unsigned.chars().filter(char::is_ascii_digit).count();
+    let plain = unsigned.chars().all(|c| c.is_ascii_digit() || c == '.') && unsigned.matches('.').count() <= 1;
+    if !plain || integer.is_empty() || (integer.len() > 1 && integer.starts_with('0')) || digits > XLSX_MAX_DIGITS {
+        return None;
+    }
+    cell.parse().ok()
+}
+
+/// Widths in Excel character units, from the widest line in each column. Full-width characters
+/// count double, so Japanese text is not cut off.
+pub fn column_widths(columns: &[String], rows: &[Vec<String>]) -> Vec<f64> {
+    columns.iter().enumerate()
+        .map(|(c, name)| {
+            let widest = rows.iter()
+                .filter_map(|row| row.get(c))
+                .filter(|cell| cell.as_str() != NULL_CELL)
+                .map(|cell| display_width(cell))
+                .fold(display_width(name), usize::max);
+            ((widest + XLSX_WIDTH_PADDING) as f64).clamp(XLSX_MIN_WIDTH, XLSX_MAX_WIDTH)
+        })
+        .collect()
+}
+
+/// `#RRGGBB` (or `RRGGBB`) as an RGB value.
+fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+pub fn header_color(handle: &tauri::AppHandle, options: &XlsxExportOptions) -> u32 {
+    options.header_color.clone()
+        .or_else(|| crate::read_db_settings(handle).ok().and_then(|s| s.excel_header_color))
+        .and_then(|c| parse_color(&c))
+        .unwrap_or(DEFAULT_HEADER_COLOR)
+}
+
+fn xlsx_error(e: XlsxError) -> String {
+    format!("Lỗi ghi Excel: {}", e)
+}
+
+/// One sheet: styled header row, frozen below the header, auto-filter and fitted column widths.
+/// Notes on pinned rows go on the first cell of the row.
+pub fn write_xlsx(path: &str, columns: &[String], rows: &[Vec<String>], notes: &[CellNote], options: &XlsxExportOptions, header_color: u32) -> Result<(), String> {
+    if rows.len() > XLSX_MAX_ROWS {
+        return Err(format!("Excel chỉ chứa được {} dòng, kết quả có {} dòng; hãy xuất CSV", XLSX_MAX_ROWS, rows.len()));
+    }
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(options.sheet_name.as_deref().unwrap_or(DEFAULT_SHEET_NAME)).map_err(xlsx_error)?;
+
+    let header = Format::new()
+        .set_bold()
+        .set_font_color(Color::White)
+        .set_background_color(Color::RGB(header_color))
+        .set_border(FormatBorder::Thin);
+    for (c, name) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, c as u16, name, &header).map_err(xlsx_error)?;
+    }
+    for (r, row) in rows.iter().enumerate() {
+        let r = r as u32 + 1;
+        for (c, cell) in row.iter().enumerate().filter(|(_, cell)| cell.as_str() != NULL_CELL) {
+            match as_number(cell) {
+                Some(n) => sheet.write_number(r, c as u16, n),
+                None => sheet.write_string(r, c as u16, cell),
+            }.map_err(xlsx_error)?;
+        }
+    }
+    for (c, width) in column_widths(columns, rows).into_iter().enumerate() {
+        sheet.set_column_width(c as u16, width).map_err(xlsx_error)?;
+    }
+    sheet.set_freeze_panes(1, options.freeze_columns.unwrap_or(0)).map_err(xlsx_error)?;
+    if !columns.is_empty() && !rows.is_empty() {
+        sheet.autofilter(0, 0, rows.len() as u32, columns.len() as u16 - 1).map_err(xlsx_error)?;
+    }
+    for note in notes.iter().filter(|n| n.row < rows.len()) {
+        let column = note.column.as_ref()
+            .and_then(|name| columns.iter().position(|c| c.eq_ignore_ascii_case(name)))
+            .unwrap_or(0);
+        sheet.insert_note(note.row as u32 + 1, column as u16, &Note::new(&note.text)).map_err(xlsx_error)?;
+    }
+    workbook.save(path).map_err(xlsx_error)
+}
+
+/// Saves the full value of one binary cell, which the grid only shows as a hex preview, by
+/// running the query again. Returns the number of bytes written.
+#[tauri::command]
+pub async fn export_cell_bytes(profile: State<'_, ActiveProfile>, config: DbConfig, query: String, row: usize, column: String, path: String, confirm_dangerous: Option<bool>) -> Result<u64, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let bytes = db::fetch_cell_bytes(&config, &query, row, &column).await?;
+    std::fs::write(&path, &bytes).map_err(|e| format!("Không thể ghi file: {}", e))?;
+    Ok(bytes.len() as u64)
+}
+
+/// Writes a result to CSV. A partially written file is removed when the export fails.
+#[tauri::command]
+pub async fn export_result_csv(window: tauri::Window, ops: State<'_, ActiveOperations>, store: State<'_, ResultStore>, profile: State<'_, ActiveProfile>, source: ExportSource, path: String, options: Option<CsvExportOptions>) -> Result<ExportSummary, String> {
+    let _op = ops.begin();
+    let started = Instant::now();
+    let options = options.unwrap_or_default();
+    match write_csv(&window, &store, &profile, source, &path, &options).await {
+        Ok((rows_written, bytes_written)) => Ok(ExportSummary { path, rows_written, bytes_written, elapsed_ms: started.elapsed().as_millis() }),
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Writes a result as fixed-width Shift-JIS records (host system files): each field at its byte
+/// offset, padded to its byte length. Values too long for their field fail the export unless
+/// `truncate` is set; a partially written file is removed.
+#[tauri::command]
+pub async fn export_result_fixed_width(window: tauri::Window, ops: State<'_, ActiveOperations>, store: State<'_, ResultStore>, profile: State<'_, ActiveProfile>, source: ExportSource, path: String, options: FixedWidthOptions) -> Result<ExportSummary, String> {
+    let _op = ops.begin();
+    let started = Instant::now();
+    match write_fixed_width(&window, &store, &profile, source, &path, &options).await {
+        Ok((rows_written, bytes_written)) => Ok(ExportSummary { path, rows_written, bytes_written, elapsed_ms: started.elapsed().as_millis() }),
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Writes a result as a JSON array of objects or as NDJSON. Values keep their database type
+/// (numbers, booleans, JSON columns, null) where the result carries `column_types`; results
+/// without them are written as strings, with NULL still as null.
+#[tauri::command]
+pub async fn export_result_json(window: tauri::Window, ops: State<'_, ActiveOperations>, store: State<'_, ResultStore>, profile: State<'_, ActiveProfile>, source: ExportSource, path: String, options: Option<JsonExportOptions>) -> Result<ExportSummary, String> {
+    let _op = ops.begin();
+    let started = Instant::now();
+    let options = options.unwrap_or_default();
+    match write_json(&window, &store, &profile, source, &path, &options).await {
+        Ok((rows_written, bytes_written)) => Ok(ExportSummary { path, rows_written, bytes_written, elapsed_ms: started.elapsed().as_millis() }),
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Writes a result to an .xlsx file. The header color falls back to `excel_header_color` in the
+/// settings. Query sources are collected first, since the workbook is built in memory anyway.
+#[tauri::command]
+pub async fn export_result_xlsx(window: tauri::Window, ops: State<'_, ActiveOperations>, store: State<'_, ResultStore>, profile: State<'_, ActiveProfile>, source: ExportSource, path: String, options: Option<XlsxExportOptions>) -> Result<ExportSummary, String> {
+    let _op = ops.begin();
+    let started = Instant::now();
+    let options = options.unwrap_or_default();
+    let (result, notes) = match &source {
+        ExportSource::Query { config, query, confirm_dangerous } => {
+            let mut rows: Vec<Vec<String>> = Vec::new();
+            let columns = stream_rows(&window, &profile, config, query, *confirm_dangerous, &path, |batch| {
+                if rows.len() + batch.rows.len() > XLSX_MAX_ROWS {
+                    return Err(format!("Excel chỉ chứa được {} dòng; hãy xuất CSV", XLSX_MAX_ROWS));
+                }
+                rows.extend_from_slice(&batch.rows);
+                Ok(())
+            }).await?;
+            (Arc::new(QueryResult { columns, rows, ..Default::default() }), Vec::new())
+        }
+        _ => stored_rows(&window, &store, source)?,
+    };
+    let color = header_color(&window.app_handle(), &options);
+    if let Err(e) = write_xlsx(&path, &result.columns, &result.rows, &notes, &options, color) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    let bytes_written = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    Ok(ExportSummary { path, rows_written: result.rows.len() as u64, bytes_written, elapsed_ms: started.elapsed().as_millis() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_sink() {
+        let result = QueryResult {
+            columns: vec!["ID".to_string(), "NAME".to_string()],
+            rows: vec![vec!["1".to_string(), "山田, 太郎".to_string()], vec!["2".to_string(), NULL_CELL.to_string()]],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let options = CsvExportOptions { line_ending: Some("lf".to_string()), bom: true, ..Default::default() };
+        write_result(CsvSink::new(&mut out, &options).unwrap(), &result, true).unwrap();
+        assert_eq!(out, "\u{FEFF}ID,NAME\n1,\"山田, 太郎\"\n2,\n".as_bytes());
+
+        let mut out = Vec::new();
+        let options = CsvExportOptions { encoding: Some("shift_jis".to_string()), delimiter: Some('\t'), null_value: Some("NULL".to_string()), ..Default::default() };
+        let (rows, bytes) = write_result(CsvSink::new(&mut out, &options).unwrap(), &result, false).unwrap();
+        assert_eq!((rows, bytes as usize), (2, out.len()));
+        assert_eq!(SHIFT_JIS.decode(&out).0, "1\t山田, 太郎\r\n2\tNULL\r\n");
+
+        let emoji = QueryResult { columns: vec!["A".to_string()], rows: vec![vec!["🍣".to_string()]], ..Default::default() };
+        let err = write_result(CsvSink::new(Vec::new(), &options).unwrap(), &emoji, false).unwrap_err();
+        assert!(err.contains("Shift-JIS"));
+    }
+
+    #[test]
+    fn test_xlsx_cells() {
+        assert_eq!(as_number("-12.5"), Some(-12.5));
+        assert_eq!(as_number("0.25"), Some(0.25));
+        assert_eq!(as_number("00123"), None);
+        assert_eq!(as_number("1234567890123456"), None);
+        assert_eq!(as_number("1e5"), None);
+
+        let columns = vec!["ID".to_string(), "氏名".to_string()];
+        let rows = vec![vec!["1".to_string(), "山田太郎".to_string()], vec!["2".to_string(), NULL_CELL.to_string()]];
+        assert_eq!(column_widths(&columns, &rows), vec![6.0, 10.0]);
+        assert_eq!(parse_color("#4f46e5"), Some(0x4F46E5));
+    }
+
+    #[test]
+    fn test_fixed_width_sink() {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let field = |column: &str, offset: usize, length: usize, align: Option<&str>, pad: Option<char>| FixedWidthField {
+            column: column.to_string(), offset, length, align: align.map(String::from), pad,
+        };
+        let result = QueryResult {
+            columns: strings(&["ID", "NAME", "KANA"]),
+            rows: vec![strings(&["7", "山田", "ﾔﾏﾀﾞ"]), strings(&["12", NULL_CELL, "ｽｽﾞｷ"])],
+            ..Default::default()
+        };
+        let options = FixedWidthOptions {
+            fields: vec![field("name", 4, 6, None, None), field("ID", 0, 3, Some("right"), Some('0')), field("KANA", 11, 5, None, None)],
+            record_length: Some(18),
+            line_ending: Some("lf".to_string()),
+            truncate: false,
+        };
+        let mut out = Vec::new();
+        let (rows, bytes) = write_fixed_width_result(FixedWidthSink::new(&mut out, &options).unwrap(), &result).unwrap();
+        assert_eq!((rows, bytes as usize), (2, 38));
+        let text = SHIFT_JIS.decode(&out).0.into_owned();
+        assert_eq!(text, "007 山田   ﾔﾏﾀﾞ   \n012        ｽｽﾞｷ   \n");
+
+        // 山田A太郎 is 9 bytes in a 6-byte field; 太 would straddle the end, so 山田A is padded instead
+        let long = QueryResult { columns: strings(&["ID", "NAME", "KANA"]), rows: vec![strings(&["1", "山田A太郎", ""])], ..Default::default() };
+        let err = write_fixed_width_result(FixedWidthSink::new(Vec::new(), &options).unwrap(), &long).unwrap_err();
+        assert!(err.contains("9 byte"));
+        let mut out = Vec::new();
+        write_fixed_width_result(FixedWidthSink::new(&mut out, &FixedWidthOptions { truncate: true, ..options.clone() }).unwrap(), &long).unwrap();
+        assert_eq!(&out[4..10], SHIFT_JIS.encode("山田A ").0.as_ref());
+
+        let overlapping = FixedWidthOptions { fields: vec![field("ID", 0, 3, None, None), field("NAME", 2, 4, None, None)], ..Default::default() };
+        assert!(FixedWidthSink::new(Vec::new(), &overlapping).is_err());
+        let missing = FixedWidthOptions { fields: vec![field("CODE", 0, 3, None, None)], ..Default::default() };
+        assert!(FixedWidthSink::new(Vec::new(), &missing).unwrap().columns(&result.columns).is_err());
+    }
+
+    #[test]
+    fn test_json_sink() {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let result = QueryResult {
+            columns: strings(&["ID", "AMOUNT", "ACTIVE", "NAME", "ID", "META"]),
+            column_types: strings(&["bigint", "decimal", "bit", "nvarchar", "int", "jsonb"]),
+            rows: vec![
+                strings(&["1", "12.50", "true", "007", "2", "{\"a\":1}"]),
+                strings(&["3", "12345678901234567.89", "0", NULL_CELL, "x", "not json"]),
+            ],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let options = JsonExportOptions { format: Some("ndjson".to_string()) };
+        let (rows, bytes) = write_json_result(JsonSink::new(&mut out, &options).unwrap(), &result).unwrap();
+        assert_eq!((rows, bytes as usize), (2, out.len()));
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "{\"ID\":1,\"AMOUNT\":12.5,\"ACTIVE\":true,\"NAME\":\"007\",\"ID_2\":2,\"META\":{\"a\":1}}\n",
+            "{\"ID\":3,\"AMOUNT\":\"12345678901234567.89\",\"ACTIVE\":false,\"NAME\":null,\"ID_2\":\"x\",\"META\":\"not json\"}\n",
+        ));
+
+        let mut out = Vec::new();
+        let untyped = QueryResult { columns: strings(&["N"]), rows: vec![strings(&["1"])], ..Default::default() };
+        write_json_result(JsonSink::new(&mut out, &JsonExportOptions::default()).unwrap(), &untyped).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[\n{\"N\":\"1\"}\n]\n");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::sessions::{self, Session};
+use crate::{conn_string, db, ssh_tunnel, DbConfig, QueryResult};
+
+// Connections with `failover_hosts` (mirrored MSSQL pairs, read replicas): the configured host is
+// tried first, then each fallback in order, and the query runs on the first login that succeeds.
+// Only the login is retried on the next host, never a statement that already reached a server.
+
+/// The connection itself, then one copy per fallback host with `host`/`port` replaced.
+pub fn candidates(config: &DbConfig) -> Vec<DbConfig> {
+    let mut all = vec![config.clone()];
+    for entry in config.failover_hosts.iter().flatten().map(|h| h.trim()).filter(|h| !h.is_empty()) {
+        let mut candidate = config.clone();
+        // A named instance without a port goes through the SQL Browser
+        if entry.contains('\\') {
+            candidate.port = 0;
+        }
+        conn_string::set_server(&mut candidate, entry);
+        all.push(candidate);
+    }
+    if let Some(secs) = config.failover_timeout_secs.filter(|s| *s > 0 && all.len() > 1) {
+        for candidate in &mut all {
+            candidate.connect_timeout_secs = Some(secs);
+        }
+    }
+    all
+}
+
+/// `host:port`, or just the host for named instances resolved through the SQL Browser.
+pub fn host_label(config: &DbConfig) -> String {
+    if config.port == 0 { config.host.clone() } else { format!("{}:{}", config.host, config.port) }
+}
+
+/// The login that succeeded, kept open so the query runs on it instead of logging in again.
+pub struct Served {
+    /// The candidate that accepted the login.
+    pub host: DbConfig,
+    /// `host` as routed through its tunnel (see `ssh_tunnel::tunneled`), for extra connections
+    /// such as metadata lookups; valid while this value lives.
+    pub target: DbConfig,
+    /// Owns the tunnel.
+    session: Session,
+}
+
+impl Served {
+    /// `db::run_query_with` on the open connection. Use once: a cancelled or timed-out query can
+    /// leave the connection mid-statement.
+    pub async fn run_query_with(&mut self, query: &str, options: &db::FetchOptions) -> Result<QueryResult, String> {
+        db::timed_query(&self.target, query, sessions::run(&mut self.session, &self.target, query, options)).await
+    }
+
+    pub async fn run_query(&mut self, query: &str) -> Result<QueryResult, String> {
+        self.run_query_with(query, &db::FetchOptions::default()).await
+    }
+}
+
+/// Connects to the first candidate that accepts a login. Without fallback hosts this is the
+/// connection itself.
+pub async fn connect(config: &DbConfig) -> Result<Served, String> {
+    let mut errors = Vec::new();
+    let candidates = candidates(config);
+    let single = candidates.len() == 1;
+    for candidate in candidates {
+        let attempt = async {
+            let (target, tunnel) = ssh_tunnel::tunneled(&candidate).await?;
+            let session = sessions::connect_target(&target, tunnel).await?;
+            Ok::<_, String>((target, session))
+        };
+        match attempt.await {
+            Ok((target, session)) => return Ok(Served { host: candidate, target, session }),
+            Err(e) if single => return Err(e),
+            Err(e) => errors.push(format!("{}: {}", host_label(&candidate), e)),
+        }
+    }
+    Err(format!("Không kết nối được host nào: {}", errors.join(" / ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let config = DbConfig {
+            db_type: "mssql".to_string(),
+            host: "sql-a".to_string(),
+            port: 1433,
+            failover_hosts: Some(vec!["sql-b".to_string(), "sql-c,1444".to_string(), "sql-d\\MIRROR".to_string(), " ".to_string()]),
+            failover_timeout_secs: Some(3),
+            ..Default::default()
+        };
+        let labels: Vec<String> = candidates(&config).iter().map(host_label).collect();
+        assert_eq!(labels, vec!["sql-a:1433", "sql-b:1433", "sql-c:1444", "sql-d\\MIRROR"]);
+        assert!(candidates(&config).iter().all(|c| c.connect_timeout_secs == Some(3)));
+        let single = DbConfig { failover_timeout_secs: Some(3), ..Default::default() };
+        assert_eq!(candidates(&single)[0].connect_timeout_secs, None);
+    }
+}
@@ -0,0 +1,359 @@
+use std::cmp::Ordering;
+use crate::result_ops::{compare_cells, NULL_CELL};
+
+// Small expression language for filtering rows by column values, e.g.
+//   STATUS = 'A' AND (AMOUNT >= 100 OR NOTE LIKE '%urgent%') AND DELETED_AT IS NULL
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(String),
+    Op(String),
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare { column: usize, op: String, value: String },
+    IsNull { column: usize, negated: bool },
+    Like { column: usize, pattern: String, negated: bool },
+    In { column: usize, values: Vec<String>, negated: bool },
+}
+
+/// A filter compiled against a fixed column list.
+#[derive(Debug, Clone)]
+pub struct RowFilter {
+    expr: Expr,
+}
+
+impl RowFilter {
+    pub fn compile(text: &str, columns: &[String]) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err("Filter expression is empty".to_string());
+        }
+        let mut parser = ExprParser { tokens, pos: 0, columns };
+        let expr = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!("Unexpected token in filter: {:?}", parser.tokens[parser.pos]));
+        }
+        Ok(RowFilter { expr })
+    }
+
+    pub fn matches(&self, row: &[String]) -> bool {
+        eval(&self.expr, row)
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == '\'' {
+            // 'it''s' style escaping, same as SQL
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string in filter".to_string()),
+                    Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                        value.push('\'');
+                        i += 2;
+                    }
+                    Some('\'') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(ch) => {
+                        value.push(*ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if c == '[' || c == '"' || c == '`' {
+            let close = match c { '[' => ']', other => other };
+            let start = i + 1;
+            let end = chars[start..].iter().position(|&ch| ch == close)
+                .map(|p| start + p)
+                .ok_or("Unterminated quoted column name in filter")?;
+            tokens.push(Token::Ident(chars[start..end].iter().collect()));
+            i = end + 1;
+        } else if "=<>!".contains(c) {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if two == "<=" || two == ">=" || two == "<>" || two == "!=" {
+                tokens.push(Token::Op(two));
+                i += 2;
+            } else if c == '!' {
+                return Err("Unexpected '!' in filter".to_string());
+            } else {
+                tokens.push(Token::Op(c.to_string()));
+                i += 1;
+            }
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).map_or(false, |n| n.is_ascii_digit())) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Num(chars[start..i].iter().collect()));
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            return Err(format!("Unexpected character '{}' in filter", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct ExprParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    columns: &'a [String],
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        if self.tokens.get(self.pos) == Some(&Token::LParen) {
+            self.pos += 1;
+            let expr = self.parse_or()?;
+            if self.next() != Some(Token::RParen) {
+                return Err("Missing ')' in filter".to_string());
+            }
+            return Ok(expr);
+        }
+
+        let column = match self.next() {
+            Some(Token::Ident(name)) => self.resolve_column(&name)?,
+            other => return Err(format!("Expected column name in filter, found {:?}", other)),
+        };
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            if !self.eat_keyword("NULL") {
+                return Err("Expected NULL after IS".to_string());
+            }
+            return Ok(Expr::IsNull { column, negated });
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("LIKE") {
+            let pattern = self.parse_value()?;
+            return Ok(Expr::Like { column, pattern, negated });
+        }
+        if self.eat_keyword("IN") {
+            if self.next() != Some(Token::LParen) {
+                return Err("Expected '(' after IN".to_string());
+            }
+            let mut values = vec![self.parse_value()?];
+            loop {
+                match self.next() {
+                    Some(Token::Comma) => values.push(self.parse_value()?),
+                    Some(Token::RParen) => break,
+                    other => return Err(format!("Expected ',' or ')' in IN list, found {:?}", other)),
+                }
+            }
+            return Ok(Expr::In { column, values, negated });
+        }
+        if negated {
+            return Err("Expected LIKE or IN after NOT".to_string());
+        }
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            other => return Err(format!("Expected comparison operator in filter, found {:?}", other)),
+        };
+        let value = self.parse_value()?;
+        Ok(Expr::Compare { column, op, value })
+    }
+
+    fn parse_value(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Str(s)) | Some(Token::Num(s)) => Ok(s),
+            Some(Token::Ident(s)) if s.eq_ignore_ascii_case("NULL") => Ok(NULL_CELL.to_string()),
+            Some(Token::Ident(s)) => Ok(s),
+            other => Err(format!("Expected value in filter, found {:?}", other)),
+        }
+    }
+
+    fn resolve_column(&self, name: &str) -> Result<usize, String> {
+        self.columns.iter()
+            .position(|c| c.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown column in filter: {}", name))
+    }
+}
+
+fn cell(row: &[String], column: usize) -> &str {
+    row.get(column).map(|s| s.as_str()).unwrap_or(NULL_CELL)
+}
+
+fn eval(expr: &Expr, row: &[String]) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, row) && eval(b, row),
+        Expr::Or(a, b) => eval(a, row) || eval(b, row),
+        Expr::Not(e) => !eval(e, row),
+        Expr::IsNull { column, negated } => (cell(row, *column) == NULL_CELL) != *negated,
+        Expr::Compare { column, op, value } => {
+            let v = cell(row, *column);
+            if v == NULL_CELL || value == NULL_CELL {
+                return false;
+            }
+            let ord = compare_cells(v, value);
+            match op.as_str() {
+                "=" => ord == Ordering::Equal,
+                "<>" | "!=" => ord != Ordering::Equal,
+                "<" => ord == Ordering::Less,
+                "<=" => ord != Ordering::Greater,
+                ">" => ord == Ordering::Greater,
+                ">=" => ord != Ordering::Less,
+                _ => false,
+            }
+        }
+        Expr::Like { column, pattern, negated } => {
+            let v = cell(row, *column);
+            if v == NULL_CELL {
+                return false;
+            }
+            like_match(&v.to_lowercase(), &pattern.to_lowercase()) != *negated
+        }
+        Expr::In { column, values, negated } => {
+            let v = cell(row, *column);
+            if v == NULL_CELL {
+                return false;
+            }
+            values.iter().any(|x| compare_cells(v, x) == Ordering::Equal) != *negated
+        }
+    }
+}
+
+/// SQL LIKE matching with `%` and `_` wildcards.
+pub fn like_match(text: &str, pattern: &str) -> bool {
+    let t: Vec<char> = text.chars().collect();
+    let p: Vec<char> = pattern.chars().collect();
+    let (mut ti, mut pi) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '_' || p[pi] == t[ti]) {
+            ti += 1;
+            pi += 1;
+        } else if pi < p.len() && p[pi] == '%' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '%' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<String> {
+        vec!["STATUS".to_string(), "AMOUNT".to_string(), "NOTE".to_string()]
+    }
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_compare_and_logic() {
+        let f = RowFilter::compile("status = 'A' AND (amount >= 100 OR note LIKE '%urgent%')", &columns()).unwrap();
+        assert!(f.matches(&row(&["A", "150", ""])));
+        assert!(f.matches(&row(&["A", "9", "very URGENT"])));
+        assert!(!f.matches(&row(&["B", "150", ""])));
+        // numeric, not lexical, comparison
+        assert!(!f.matches(&row(&["A", "99", ""])));
+    }
+
+    #[test]
+    fn test_null_and_in() {
+        let f = RowFilter::compile("NOTE IS NULL AND STATUS NOT IN ('X', 'Y')", &columns()).unwrap();
+        assert!(f.matches(&row(&["A", "1", NULL_CELL])));
+        assert!(!f.matches(&row(&["X", "1", NULL_CELL])));
+        assert!(!f.matches(&row(&["A", "1", "memo"])));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(RowFilter::compile("UNKNOWN = 1", &columns()).is_err());
+        assert!(RowFilter::compile("STATUS = 'A", &columns()).is_err());
+        assert!(RowFilter::compile("(STATUS = 'A'", &columns()).is_err());
+    }
+}
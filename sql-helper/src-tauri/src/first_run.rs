@@ -0,0 +1,275 @@
+use std::time::Duration;
+use serde::Serialize;
+use tokio::net::TcpStream;
+use crate::{schema, DbConfig};
+
+// First-run helper: find database servers on this machine and pre-fill connections for them.
+
+const LOCAL_HOST: &str = "localhost";
+const PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+const DEFAULT_PORTS: [(&str, u16); 4] = [("mssql", 1433), ("mysql", 3306), ("postgres", 5432), ("oracle", 1521)];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DetectedServer {
+    pub db_type: String,
+    pub port: Option<u16>,
+    /// SQL Server instance name (None for the default instance) or Oracle SID.
+    pub instance: Option<String>,
+    /// From the Windows service state; None when only found by port.
+    pub running: Option<bool>,
+    /// How it was found: "port", "service", "registry".
+    pub sources: Vec<String>,
+    /// Suggested connection; user/password still need to be filled in.
+    pub config: DbConfig,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SetupStepResult {
+    pub step: String,
+    pub ok: bool,
+    pub message: String,
+}
+
+/// `(name, type, data)` of the values in `reg query` output.
+pub fn parse_reg_values(output: &str) -> Vec<(String, String, String)> {
+    output.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let kind = parts.next().filter(|k| k.starts_with("REG_"))?;
+            Some((name.to_string(), kind.to_string(), parts.collect::<Vec<_>>().join(" ")))
+        })
+        .collect()
+}
+
+/// `(service name, running)` pairs from `sc query state= all`.
+pub fn parse_sc_services(output: &str) -> Vec<(String, bool)> {
+    let mut services = Vec::new();
+    let mut current: Option<String> = None;
+    for line in output.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("SERVICE_NAME:") {
+            current = Some(name.trim().to_string());
+        } else if line.starts_with("STATE") {
+            if let Some(name) = current.take() {
+                services.push((name, line.contains("RUNNING")));
+            }
+        }
+    }
+    services
+}
+
+/// Database engine and instance for a Windows service name, if it is one.
+pub fn classify_service(name: &str) -> Option<(&'static str, Option<String>)> {
+    let lower = name.to_lowercase();
+    if lower == "mssqlserver" {
+        Some(("mssql", None))
+    } else if let Some(instance) = name.strip_prefix("MSSQL$") {
+        Some(("mssql", Some(instance.to_string())))
+    } else if lower.starts_with("mysql") || lower.starts_with("mariadb") {
+        Some(("mysql", None))
+    } else if lower.starts_with("postgresql") {
+        Some(("postgres", None))
+    } else if let Some(sid) = name.strip_prefix("OracleService") {
+        Some(("oracle", Some(sid.to_string())))
+    } else {
+        None
+    }
+}
+
+pub fn default_port(db_type: &str) -> u16 {
+    DEFAULT_PORTS.iter().find(|(t, _)| *t == db_type).map(|(_, p)| *p).unwrap_or(0)
+}
+
+pub fn propose_config(db_type: &str, port: Option<u16>, instance: Option<&str>) -> DbConfig {
+    let label = match db_type {
+        "mssql" => "SQL Server",
+        "mysql" => "MySQL",
+        "postgres" => "PostgreSQL",
+        _ => "Oracle",
+    };
+    let name = match instance {
+        Some(i) => format!("{} ({}) - local", label, i),
+        None => format!("{} - local", label),
+    };
+    let mut config = DbConfig {
+        id: format!("local-{}-{}", db_type, instance.unwrap_or("default").to_lowercase()),
+        name,
+        db_type: db_type.to_string(),
+        host: LOCAL_HOST.to_string(),
+        port: port.unwrap_or(0),
+        verified: Some(false),
+        ..Default::default()
+    };
+    match db_type {
+        "mssql" => {
+            // Named instances on a dynamic port are found through the SQL Browser
+            if let (Some(instance), None) = (instance, port) {
+                config.host = format!("{}\\{}", LOCAL_HOST, instance);
+            }
+            config.user = "sa".to_string();
+            config.trust_server_certificate = Some(true);
+            config.encrypt = Some(false);
+        }
+        "mysql" => config.user = "root".to_string(),
+        "postgres" => {
+            config.user = "postgres".to_string();
+            config.database = "postgres".to_string();
+        }
+        _ => {
+            config.user = "system".to_string();
+            config.oracle_sid = instance.map(String::from);
+        }
+    }
+    config
+}
+
+async fn port_open(port: u16) -> bool {
+    matches!(tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((LOCAL_HOST, port))).await, Ok(Ok(_)))
+}
+
+#[cfg(target_os = "windows")]
+fn run_command(program: &str, args: &[&str]) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = std::process::Command::new(program).args(args).creation_flags(CREATE_NO_WINDOW).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `(instance name, TCP port)` of the installed SQL Server instances, from the registry.
+#[cfg(target_os = "windows")]
+fn registry_sql_instances() -> Vec<(String, Option<u16>)> {
+    const ROOT: &str = r"HKLM\SOFTWARE\Microsoft\Microsoft SQL Server";
+    let names = run_command("reg", &["query", &format!(r"{}\Instance Names\SQL", ROOT)]).unwrap_or_default();
+    parse_reg_values(&names).into_iter()
+        .map(|(name, _, id)| {
+            let tcp = run_command("reg", &["query", &format!(r"{}\{}\MSSQLServer\SuperSocketNetLib\Tcp\IPAll", ROOT, id)]).unwrap_or_default();
+            let values = parse_reg_values(&tcp);
+            let port = ["TcpPort", "TcpDynamicPorts"].iter()
+                .find_map(|key| values.iter().find(|(n, _, _)| n == key).and_then(|(_, _, v)| v.split(',').next()?.trim().parse().ok()));
+            (name, port)
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn windows_services() -> Vec<(String, bool)> {
+    parse_sc_services(&run_command("sc", &["query", "state=", "all"]).unwrap_or_default())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn registry_sql_instances() -> Vec<(String, Option<u16>)> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn windows_services() -> Vec<(String, bool)> {
+    Vec::new()
+}
+
+fn add_source(servers: &mut Vec<DetectedServer>, db_type: &str, instance: Option<String>, port: Option<u16>, running: Option<bool>, source: &str) {
+    let same = |s: &DetectedServer| s.db_type == db_type
+        && (s.instance.as_deref().map(str::to_lowercase) == instance.as_deref().map(str::to_lowercase)
+            || (port.is_some() && s.port == port));
+    match servers.iter_mut().find(|s| same(s)) {
+        Some(s) => {
+            s.port = s.port.or(port);
+            s.running = s.running.or(running);
+            if !s.sources.iter().any(|x| x == source) {
+                s.sources.push(source.to_string());
+            }
+            s.config = propose_config(db_type, s.port, s.instance.as_deref());
+        }
+        None => servers.push(DetectedServer {
+            db_type: db_type.to_string(),
+            port,
+            config: propose_config(db_type, port, instance.as_deref()),
+            instance,
+            running,
+            sources: vec![source.to_string()],
+        }),
+    }
+}
+
+/// Looks for local servers: registered SQL Server instances and database services (Windows),
+/// then listeners on the default ports. Named instances on dynamic ports may report no port.
+#[tauri::command]
+pub async fn detect_local_servers() -> Result<Vec<DetectedServer>, String> {
+    let (instances, services) = tokio::task::spawn_blocking(|| (registry_sql_instances(), windows_services()))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut servers = Vec::new();
+    for (name, port) in instances {
+        let instance = (name != "MSSQLSERVER").then_some(name);
+        let port = port.or_else(|| instance.is_none().then(|| default_port("mssql")));
+        add_source(&mut servers, "mssql", instance, port, None, "registry");
+    }
+    for (name, running) in services {
+        if let Some((db_type, instance)) = classify_service(&name) {
+            let port = (db_type != "mssql" || instance.is_none()).then(|| default_port(db_type));
+            add_source(&mut servers, db_type, instance, port, Some(running), "service");
+        }
+    }
+    let probes = DEFAULT_PORTS.iter().map(|(db_type, port)| async move { (*db_type, *port, port_open(*port).await) });
+    for (db_type, port, open) in futures::future::join_all(probes).await {
+        if open {
+            add_source(&mut servers, db_type, None, Some(port), None, "port");
+        }
+    }
+    Ok(servers)
+}
+
+/// Checks one step of the wizard: "network" (port reachable), "login" (credentials accepted)
+/// or "database" (the chosen database exists and is accessible).
+#[tauri::command]
+pub async fn validate_setup_step(config: DbConfig, step: String) -> Result<SetupStepResult, String> {
+    let outcome: Result<String, String> = match step.as_str() {
+        "network" => {
+            let port = if config.port == 0 { default_port(&config.db_type) } else { config.port };
+            let connect = TcpStream::connect((config.host.as_str(), port));
+            match tokio::time::timeout(Duration::from_secs(5), connect).await {
+                Ok(Ok(_)) => Ok(format!("{}:{} phản hồi", config.host, port)),
+                Ok(Err(e)) => Err(format!("Không kết nối được {}:{} ({})", config.host, port, e)),
+                Err(_) => Err(format!("{}:{} không phản hồi (tường lửa hoặc server chưa bật TCP/IP?)", config.host, port)),
+            }
+        }
+        "login" => crate::test_connection(config.clone()).await,
+        "database" => {
+            if config.database.trim().is_empty() {
+                Ok("Dùng database mặc định của tài khoản".to_string())
+            } else {
+                let databases = schema::list_databases(config.clone()).await?;
+                if databases.iter().any(|d| d.eq_ignore_ascii_case(config.database.trim())) {
+                    Ok(format!("Database '{}' tồn tại", config.database))
+                } else {
+                    Err(format!("Không tìm thấy database '{}' (có: {})", config.database, databases.join(", ")))
+                }
+            }
+        }
+        other => return Err(format!("Bước không hợp lệ: {}", other)),
+    };
+    let ok = outcome.is_ok();
+    let message = outcome.unwrap_or_else(|e| e);
+    Ok(SetupStepResult { step, ok, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_windows_output() {
+        let reg = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Microsoft SQL Server\\Instance Names\\SQL\r\n    MSSQLSERVER    REG_SZ    MSSQL15.MSSQLSERVER\r\n    SQLEXPRESS    REG_SZ    MSSQL15.SQLEXPRESS\r\n";
+        let values = parse_reg_values(reg);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1], ("SQLEXPRESS".to_string(), "REG_SZ".to_string(), "MSSQL15.SQLEXPRESS".to_string()));
+
+        let sc = "SERVICE_NAME: MSSQL$SQLEXPRESS\r\nDISPLAY_NAME: SQL Server (SQLEXPRESS)\r\n        TYPE               : 10  WIN32_OWN_PROCESS\r\n        STATE              : 4  RUNNING\r\n\r\nSERVICE_NAME: postgresql-x64-15\r\n        STATE              : 1  STOPPED\r\n";
+        let services = parse_sc_services(sc);
+        assert_eq!(services, vec![("MSSQL$SQLEXPRESS".to_string(), true), ("postgresql-x64-15".to_string(), false)]);
+        assert_eq!(classify_service("MSSQL$SQLEXPRESS"), Some(("mssql", Some("SQLEXPRESS".to_string()))));
+        assert_eq!(classify_service("postgresql-x64-15"), Some(("postgres", None)));
+        assert_eq!(classify_service("Spooler"), None);
+        assert_eq!(propose_config("mssql", None, Some("SQLEXPRESS")).host, "localhost\\SQLEXPRESS");
+        assert_eq!(propose_config("mssql", Some(1433), None).host, "localhost");
+    }
+}
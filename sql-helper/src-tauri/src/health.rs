@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::Serialize;
+use tauri::{Manager, State};
+use crate::tasks::BackgroundTasks;
+use crate::{db, DbConfig};
+
+// Background pings of the verified connections, so the connection list shows live health instead
+// of the last manual test. One monitor runs at a time; it re-reads the settings on every round.
+
+const MONITOR_ID: &str = "health-monitor";
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+const MIN_INTERVAL_SECS: u64 = 10;
+/// Round trips above this count as "slow".
+pub const SLOW_LATENCY_MS: u64 = 1000;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConnectionHealth {
+    pub connection_id: String,
+    pub name: String,
+    /// "online", "slow" or "offline".
+    pub status: &'static str,
+    /// Round trip of `SELECT 1`, connecting not included.
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+/// Latest status per connection id.
+#[derive(Default)]
+pub struct HealthStatuses(Mutex<HashMap<String, ConnectionHealth>>);
+
+fn status(ping: &Result<u64, String>) -> &'static str {
+    match ping {
+        Ok(ms) if *ms > SLOW_LATENCY_MS => "slow",
+        Ok(_) => "online",
+        Err(_) => "offline",
+    }
+}
+
+fn connection_id(config: &DbConfig) -> String {
+    if config.id.is_empty() { config.name.clone() } else { config.id.clone() }
+}
+
+async fn ping(config: &DbConfig) -> Result<u64, String> {
+    let sql = if config.db_type == "oracle" { "SELECT 1 FROM dual" } else { "SELECT 1" };
+    let result = db::with_connect_timeout(config, db::run_query(config, sql)).await?;
+    Ok(result.stats.map_or(0, |s| s.round_trip_ms))
+}
+
+/// Pings every verified connection concurrently and stores the results; returns those whose
+/// status differs from the previous round (or that are new).
+async fn check_all(handle: &tauri::AppHandle) -> Vec<ConnectionHealth> {
+    let connections: Vec<DbConfig> = crate::load_db_settings(handle.clone())
+        .map(|s| s.connections)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.verified.unwrap_or(false))
+        .collect();
+    let pings = futures::future::join_all(connections.iter().map(ping)).await;
+    let checked_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let statuses = handle.state::<HealthStatuses>();
+    let mut statuses = statuses.0.lock().unwrap();
+    let mut previous = std::mem::take(&mut *statuses);
+    let mut changed = Vec::new();
+    for (config, ping) in connections.iter().zip(pings) {
+        let health = ConnectionHealth {
+            connection_id: connection_id(config),
+            name: config.name.clone(),
+            status: status(&ping),
+            latency_ms: ping.as_ref().ok().copied(),
+            error: ping.err(),
+            checked_at: checked_at.clone(),
+        };
+        if previous.remove(&health.connection_id).map_or(true, |p| p.status != health.status) {
+            changed.push(health.clone());
+        }
+        statuses.insert(health.connection_id.clone(), health);
+    }
+    changed
+}
+
+/// Starts (or restarts with the new interval) the monitor. Each round emits
+/// `health://status` for every connection whose status changed and `health://checked` with all
+/// current statuses, which carries the latencies.
+#[tauri::command]
+pub fn start_health_monitor(window: tauri::Window, tasks: State<'_, BackgroundTasks>, interval_secs: Option<u64>) -> String {
+    let interval = Duration::from_secs(interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS).max(MIN_INTERVAL_SECS));
+    let handle = tauri::async_runtime::spawn(async move {
+        let app = window.app_handle();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for health in check_all(&app).await {
+                window.emit("health://status", health).ok();
+            }
+            window.emit("health://checked", get_connection_health(app.state::<HealthStatuses>())).ok();
+        }
+    });
+    tasks.register(MONITOR_ID.to_string(), handle);
+    MONITOR_ID.to_string()
+}
+
+#[tauri::command]
+pub fn stop_health_monitor(tasks: State<'_, BackgroundTasks>, statuses: State<'_, HealthStatuses>) -> bool {
+    statuses.0.lock().unwrap().clear();
+    tasks.cancel(MONITOR_ID)
+}
+
+/// Statuses from the monitor's last round, sorted by connection name.
+#[tauri::command]
+pub fn get_connection_health(statuses: State<'_, HealthStatuses>) -> Vec<ConnectionHealth> {
+    let mut all: Vec<ConnectionHealth> = statuses.0.lock().unwrap().values().cloned().collect();
+    all.sort_by(|a, b| a.name.cmp(&b.name));
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        assert_eq!(status(&Ok(12)), "online");
+        assert_eq!(status(&Ok(SLOW_LATENCY_MS + 1)), "slow");
+        assert_eq!(status(&Err("timeout".to_string())), "offline");
+        let config = DbConfig { name: "本番".to_string(), ..Default::default() };
+        assert_eq!(connection_id(&config), "本番");
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::storage::app_data_subdir;
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+// Every executed statement, appended as one JSON line to `<app data>/history/history.jsonl`.
+// Recording never fails a query; write errors are only logged.
+
+pub const HISTORY_DIR: &str = "history";
+const HISTORY_FILE: &str = "history.jsonl";
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub executed_at: String,
+    pub connection_id: String,
+    pub connection_name: String,
+    pub db_type: String,
+    pub sql: String,
+    pub duration_ms: u64,
+    /// Rows returned (all result sets); None for failed statements.
+    pub row_count: Option<u64>,
+    #[serde(default)]
+    pub affected_rows: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct HistorySearch {
+    /// Case-insensitive substring of the SQL.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    /// `executed_at` lower bound, e.g. "2024-05-01" or "2024-05-01 13:00:00".
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub errors_only: bool,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HistoryPage {
+    /// Newest first.
+    pub entries: Vec<HistoryEntry>,
+    pub total: usize,
+    pub has_more: bool,
+}
+
+impl HistorySearch {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.text.as_ref().map_or(true, |t| entry.sql.to_lowercase().contains(&t.to_lowercase()))
+            && self.connection_id.as_ref().map_or(true, |c| &entry.connection_id == c)
+            && self.since.as_ref().map_or(true, |s| entry.executed_at.as_str() >= s.as_str())
+            && (!self.errors_only || entry.error.is_some())
+    }
+}
+
+/// Entries that survive a prune: those at or after `cutoff` (an `executed_at` value), then only
+/// the newest `keep_last` of them.
+pub fn prune_entries(entries: Vec<HistoryEntry>, cutoff: Option<&str>, keep_last: Option<usize>) -> Vec<HistoryEntry> {
+    let mut kept: Vec<HistoryEntry> = entries.into_iter()
+        .filter(|e| cutoff.map_or(true, |c| e.executed_at.as_str() >= c))
+        .collect();
+    if let Some(keep) = keep_last {
+        let excess = kept.len().saturating_sub(keep);
+        kept.drain(..excess);
+    }
+    kept
+}
+
+/// Append-only history file; the path is resolved once at startup by `init`.
+#[derive(Default)]
+pub struct QueryHistory {
+    file: Mutex<Option<PathBuf>>,
+    counter: AtomicU64,
+}
+
+impl QueryHistory {
+    pub fn init(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        let path = app_data_subdir(handle, HISTORY_DIR)?.join(HISTORY_FILE);
+        *self.file.lock().unwrap() = Some(path);
+        Ok(())
+    }
+
+    fn path(&self) -> Result<PathBuf, String> {
+        self.file.lock().unwrap().clone().ok_or_else(|| "Lịch sử truy vấn chưa sẵn sàng".to_string())
+    }
+
+    pub fn record(&self, config: &DbConfig, sql: &str, elapsed: Duration, result: &Result<QueryResult, String>) {
+        let row_count = result.as_ref().ok()
+            .map(|r| (r.rows.len() + r.more_results.iter().map(|s| s.rows.len()).sum::<usize>()) as u64);
+        let affected = result.as_ref().ok().and_then(|r| r.affected_rows);
+        self.record_summary(config, sql, elapsed, row_count, affected, result.as_ref().err().cloned());
+    }
+
+    /// For callers that do not hold a `QueryResult` (streamed queries).
+    pub fn record_summary(&self, config: &DbConfig, sql: &str, elapsed: Duration, row_count: Option<u64>, affected_rows: Option<u64>, error: Option<String>) {
+        let now = chrono::Local::now();
+        let entry = HistoryEntry {
+            id: format!("{}-{}", now.timestamp_millis(), self.counter.fetch_add(1, Ordering::Relaxed)),
+            executed_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            connection_id: config.id.clone(),
+            connection_name: config.name.clone(),
+            db_type: config.db_type.clone(),
+            sql: sql.to_string(),
+            duration_ms: elapsed.as_millis() as u64,
+            row_count,
+            affected_rows,
+            error,
+        };
+        if let Err(e) = self.append(&entry) {
+            eprintln!("Failed to record query history: {}", e);
+        }
+    }
+
+    fn append(&self, entry: &HistoryEntry) -> Result<(), String> {
+        let guard = self.file.lock().unwrap();
+        let path = guard.as_ref().ok_or("History not initialized")?;
+        let line = serde_json::to_string(entry).map_err(|e: serde_json::Error| e.to_string())?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|e: std::io::Error| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e: std::io::Error| e.to_string())
+    }
+
+    fn load(&self) -> Result<Vec<HistoryEntry>, String> {
+        read_entries(&self.path()?)
+    }
+
+    /// Rewrites the file with the surviving entries; holds the lock so no append is lost.
+    fn prune(&self, cutoff: Option<&str>, keep_last: Option<usize>) -> Result<usize, String> {
+        let guard = self.file.lock().unwrap();
+        let path = guard.as_ref().ok_or("History not initialized")?;
+        let entries = read_entries(path)?;
+        let before = entries.len();
+        let kept = prune_entries(entries, cutoff, keep_last);
+        let mut content = String::new();
+        for entry in &kept {
+            content.push_str(&serde_json::to_string(entry).map_err(|e: serde_json::Error| e.to_string())?);
+            content.push('\n');
+        }
+        fs::write(path, content).map_err(|e: std::io::Error| e.to_string())?;
+        Ok(before - kept.len())
+    }
+}
+
+/// All entries, oldest first. Lines that fail to parse (e.g. a torn last write) are skipped.
+fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e: std::io::Error| e.to_string())?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+#[tauri::command]
+pub fn search_history(history: State<'_, QueryHistory>, search: Option<HistorySearch>) -> Result<HistoryPage, String> {
+    let search = search.unwrap_or_default();
+    let limit = search.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let matched: Vec<HistoryEntry> = history.load()?.into_iter().rev().filter(|e| search.matches(e)).collect();
+    let total = matched.len();
+    let entries: Vec<HistoryEntry> = matched.into_iter().skip(search.offset).take(limit).collect();
+    Ok(HistoryPage { has_more: search.offset + entries.len() < total, entries, total })
+}
+
+/// Runs a history entry again, on `config` or else on the connection it originally ran on.
+#[tauri::command]
+pub async fn rerun_history_entry(handle: tauri::AppHandle, history: State<'_, QueryHistory>, profile: State<'_, ActiveProfile>, id: String, config: Option<DbConfig>, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    let entry = history.load()?.into_iter().find(|e| e.id == id)
+        .ok_or_else(|| format!("Không tìm thấy mục lịch sử '{}'", id))?;
+    let config = match config {
+        Some(c) => c,
+        None => crate::load_db_settings(handle)?.connections.into_iter().find(|c| c.id == entry.connection_id)
+            .ok_or_else(|| format!("Kết nối '{}' không còn tồn tại", entry.connection_name))?,
+    };
+    profile.check_sql(&entry.sql, &config)?;
+    query_guard::check(&config, &entry.sql, confirm_dangerous.unwrap_or(false))?;
+    let started = Instant::now();
+    let result = db::run_query(&config, &entry.sql).await;
+    history.record(&config, &entry.sql, started.elapsed(), &result);
+    result
+}
+
+/// Drops entries older than `older_than_days` and/or all but the newest `keep_last`.
+/// Returns how many were removed.
+#[tauri::command]
+pub fn prune_history(history: State<'_, QueryHistory>, older_than_days: Option<u64>, keep_last: Option<usize>) -> Result<usize, String> {
+    let cutoff = older_than_days
+        .map(|d| (chrono::Local::now() - chrono::Duration::days(d as i64)).format("%Y-%m-%d %H:%M:%S").to_string());
+    history.prune(cutoff.as_deref(), keep_last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, executed_at: &str, sql: &str, error: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            executed_at: executed_at.to_string(),
+            connection_id: "dev".to_string(),
+            connection_name: "Dev".to_string(),
+            db_type: "mssql".to_string(),
+            sql: sql.to_string(),
+            duration_ms: 5,
+            row_count: error.is_none().then_some(1),
+            affected_rows: None,
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_search_and_prune() {
+        let entries = vec![
+            entry("1", "2024-04-30 10:00:00", "SELECT * FROM orders", None),
+            entry("2", "2024-05-01 09:00:00", "select id from Orders where x", Some("Invalid column name 'x'")),
+            entry("3", "2024-05-02 09:00:00", "SELECT * FROM customers", None),
+        ];
+        let search = HistorySearch { text: Some("ORDERS".to_string()), since: Some("2024-05-01".to_string()), ..Default::default() };
+        let found: Vec<&str> = entries.iter().filter(|e| search.matches(e)).map(|e| e.id.as_str()).collect();
+        assert_eq!(found, vec!["2"]);
+        let errors = HistorySearch { errors_only: true, ..Default::default() };
+        assert_eq!(entries.iter().filter(|e| errors.matches(e)).count(), 1);
+
+        let kept = prune_entries(entries.clone(), Some("2024-05-01 00:00:00"), None);
+        assert_eq!(kept.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["2", "3"]);
+        let kept = prune_entries(entries, None, Some(1));
+        assert_eq!(kept.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["3"]);
+    }
+}
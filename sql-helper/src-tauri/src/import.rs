@@ -0,0 +1,774 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use encoding_rs::SHIFT_JIS;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tiberius::{ColumnData, IntoSql, TokenRow};
+use tauri::State;
+use crate::db::MssqlClient;
+use crate::profiles::{ActiveProfile, Permission};
+use crate::query_params::{self, ParamValue, QueryParam};
+use crate::result_ops::NULL_CELL;
+use crate::shutdown::ActiveOperations;
+use crate::{db, dialect, oracle_db, query_guard, schema, ssh_tunnel, DbConfig};
+
+const DEFAULT_BATCH_SIZE: usize = 5000;
+/// Multi-row INSERT batches are kept small to stay under placeholder/packet limits.
+const DEFAULT_INSERT_BATCH_SIZE: usize = 500;
+const COPY_CHUNK_BYTES: usize = 1024 * 1024;
+/// SQL Server accepts at most 1000 rows in one VALUES list.
+const MAX_ROWS_PER_INSERT: usize = 1000;
+const DEFAULT_MAX_ERRORS: usize = 100;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportProgress {
+    pub table: String,
+    pub rows_done: usize,
+    pub total_rows: usize,
+    pub batch: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportSummary {
+    pub table: String,
+    pub rows_imported: usize,
+    pub batches: usize,
+    pub elapsed_ms: u128,
+}
+
+pub struct CsvData {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Accepted spellings of Shift-JIS in `encoding` parameters; anything else means UTF-8.
+pub fn is_shift_jis(encoding: Option<&str>) -> bool {
+    matches!(encoding.map(|e| e.to_lowercase()).as_deref(), Some("shift_jis") | Some("shift-jis") | Some("sjis") | Some("cp932"))
+}
+
+/// Decodes raw file bytes as UTF-8 (BOM stripped) or Shift-JIS.
+pub fn decode_text(bytes: &[u8], encoding: Option<&str>) -> Result<String, String> {
+    if is_shift_jis(encoding) {
+        let (decoded, _, had_errors) = SHIFT_JIS.decode(bytes);
+        if had_errors {
+            return Err("File có ký tự không hợp lệ (Shift-JIS encoding)".to_string());
+        }
+        return Ok(decoded.into_owned());
+    }
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|e| format!("File không phải UTF-8: {}", e))
+}
+
+pub fn read_csv_file(path: &str, encoding: Option<&str>, delimiter: Option<char>) -> Result<CsvData, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Không thể đọc file: {}", e))?;
+    let text = decode_text(&bytes, encoding)?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter.unwrap_or(',') as u8)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers = reader.headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        rows.push(record.iter().map(|v| v.to_string()).collect());
+    }
+    Ok(CsvData { headers, rows })
+}
+
+/// `decimal(18,2)` -> ("decimal", ["18", "2"]); types are compared in lowercase.
+fn split_type(data_type: &str) -> (String, Vec<String>) {
+    let lower = data_type.trim().to_lowercase();
+    match lower.split_once('(') {
+        Some((base, args)) => (base.trim().to_string(), args.trim_end_matches(')').split(',').map(|a| a.trim().to_string()).collect()),
+        None => (lower, Vec::new()),
+    }
+}
+
+/// Bulk load writes no money values (tiberius has no encoder for them), so tables with such a
+/// column have to go through INSERTs.
+pub fn bulk_unsupported(data_type: &str) -> bool {
+    matches!(split_type(data_type).0.as_str(), "money" | "smallmoney")
+}
+
+/// Columns a bulk load row carries, in ordinal order, with lowercase declared types. The
+/// request only sends columns clients can write, so IDENTITY, computed and rowversion columns
+/// are left out.
+pub fn bulk_columns(table_columns: &[schema::ColumnInfo]) -> Vec<(String, String)> {
+    table_columns.iter()
+        .filter(|c| !c.generated)
+        .map(|c| (c.name.clone(), c.data_type.trim().to_lowercase()))
+        .collect()
+}
+
+/// `-12.345` at scale 2 -> -1235 (rounded half away from zero, as SQL Server does).
+fn parse_scaled(value: &str, scale: u32) -> Option<i128> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if (int_part.is_empty() && frac_part.is_empty()) || !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut scaled: i128 = 0;
+    for c in int_part.chars().chain(frac_part.chars().chain(std::iter::repeat('0')).take(scale as usize)) {
+        scaled = scaled.checked_mul(10)?.checked_add(c.to_digit(10)? as i128)?;
+    }
+    if frac_part.chars().nth(scale as usize).map_or(false, |c| c >= '5') {
+        scaled += 1;
+    }
+    Some(if negative { -scaled } else { scaled })
+}
+
+fn parse_datetime(value: &str) -> Option<chrono::NaiveDateTime> {
+    let as_type = |t: &str| QueryParam { value: Some(value.to_string()), param_type: Some(t.to_string()) }.to_value(0);
+    match as_type("datetime").or_else(|_| as_type("date")) {
+        Ok(ParamValue::DateTime(dt)) => Some(dt),
+        Ok(ParamValue::Date(d)) => d.and_hms_opt(0, 0, 0),
+        // A value with an offset keeps its local time, as SQL Server converts datetimeoffset
+        _ => parse_datetime_offset(value).map(|dt| dt.naive_local()),
+    }
+}
+
+/// `2024-01-02 03:04:05 +09:00` (as the grid shows datetimeoffset), with or without the space or `T`.
+fn parse_datetime_offset(value: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    ["%Y-%m-%d %H:%M:%S%.f %:z", "%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M:%S%.f%:z", "%Y/%m/%d %H:%M:%S%.f %:z"].iter()
+        .find_map(|f| chrono::DateTime::parse_from_str(value, f).ok())
+}
+
+fn parse_time(value: &str) -> Option<chrono::NaiveTime> {
+    ["%H:%M:%S%.f", "%H:%M"].iter().find_map(|f| chrono::NaiveTime::parse_from_str(value, f).ok())
+}
+
+/// `0x0A1B` (as the grid shows binary cells) or bare hex.
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    let hex = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// `time` at the column's scale (0-7); tiberius only rescales datetime2 itself.
+fn scaled_time(time: chrono::NaiveTime, scale: u8) -> tiberius::time::Time {
+    use chrono::Timelike;
+    let nanos = time.num_seconds_from_midnight() as u64 * 1_000_000_000 + time.nanosecond() as u64;
+    tiberius::time::Time::new(nanos / 10u64.pow(9 - scale as u32), scale)
+}
+
+/// Days since 1900-01-01 and 1/300 s ticks for `datetime`, minutes for `smalldatetime`.
+fn legacy_datetime(dt: chrono::NaiveDateTime, small: bool) -> Option<ColumnData<'static>> {
+    use chrono::Timelike;
+    let epoch = chrono::NaiveDate::from_ymd_opt(1900, 1, 1)?;
+    let mut days = (dt.date() - epoch).num_days();
+    let millis = dt.time().num_seconds_from_midnight() as i64 * 1000 + dt.time().nanosecond() as i64 / 1_000_000;
+    if small {
+        let mut minutes = (millis + 30_000) / 60_000;
+        if minutes == 24 * 60 {
+            days += 1;
+            minutes = 0;
+        }
+        let days = u16::try_from(days).ok()?;
+        return Some(ColumnData::SmallDateTime(Some(tiberius::time::SmallDateTime::new(days, minutes as u16))));
+    }
+    let mut ticks = (millis * 300 + 500) / 1000;
+    if ticks == 300 * 86_400 {
+        days += 1;
+        ticks = 0;
+    }
+    Some(ColumnData::DateTime(Some(tiberius::time::DateTime::new(i32::try_from(days).ok()?, ticks as u32))))
+}
+
+/// Converts a CSV cell into the wire type bulk load expects for the destination column, which
+/// must match the column's type exactly (tiberius refuses a string for a decimal column).
+/// `data_type` is the declared type; the scale of decimal and time types is read from it.
+pub fn to_column_data(value: &str, data_type: &str) -> Result<ColumnData<'static>, String> {
+    let (base, args) = split_type(data_type);
+    let arg = |i: usize| args.get(i).and_then(|a| a.parse::<u8>().ok());
+    // decimal(p,s) without a scale means s = 0; time types default to 7 digits
+    let scale = match base.as_str() {
+        "decimal" | "numeric" => arg(1).unwrap_or(0).min(37),
+        _ => arg(0).unwrap_or(7).min(7),
+    };
+    let is_text = matches!(base.as_str(), "char" | "varchar" | "nchar" | "nvarchar" | "text" | "ntext");
+    if value == NULL_CELL || (value.trim().is_empty() && !is_text) {
+        return Ok(match base.as_str() {
+            "int" => ColumnData::I32(None),
+            "bigint" => ColumnData::I64(None),
+            "smallint" => ColumnData::I16(None),
+            "tinyint" => ColumnData::U8(None),
+            "bit" => ColumnData::Bit(None),
+            "float" => ColumnData::F64(None),
+            "real" => ColumnData::F32(None),
+            "decimal" | "numeric" => ColumnData::Numeric(None),
+            "date" => ColumnData::Date(None),
+            "time" => ColumnData::Time(None),
+            "datetime" => ColumnData::DateTime(None),
+            "smalldatetime" => ColumnData::SmallDateTime(None),
+            "datetime2" => ColumnData::DateTime2(None),
+            "datetimeoffset" => ColumnData::DateTimeOffset(None),
+            "uniqueidentifier" => ColumnData::Guid(None),
+            "binary" | "varbinary" | "image" => ColumnData::Binary(None),
+            _ => ColumnData::String(None),
+        });
+    }
+    let v = value.trim();
+    let bad = |e: &dyn std::fmt::Display| format!("Giá trị '{}' không hợp lệ cho kiểu {}: {}", value, data_type, e);
+    let invalid = || format!("Giá trị '{}' không hợp lệ cho kiểu {}", value, data_type);
+    Ok(match base.as_str() {
+        "int" => ColumnData::I32(Some(v.parse().map_err(|e| bad(&e))?)),
+        "bigint" => ColumnData::I64(Some(v.parse().map_err(|e| bad(&e))?)),
+        "smallint" => ColumnData::I16(Some(v.parse().map_err(|e| bad(&e))?)),
+        "tinyint" => ColumnData::U8(Some(v.parse().map_err(|e| bad(&e))?)),
+        "bit" => ColumnData::Bit(Some(matches!(v.to_lowercase().as_str(), "1" | "true" | "y" | "yes"))),
+        "float" => ColumnData::F64(Some(v.parse().map_err(|e| bad(&e))?)),
+        "real" => ColumnData::F32(Some(v.parse().map_err(|e| bad(&e))?)),
+        "decimal" | "numeric" => {
+            let scaled = parse_scaled(v, scale as u32).ok_or_else(invalid)?;
+            ColumnData::Numeric(Some(tiberius::numeric::Numeric::new_with_scale(scaled, scale)))
+        }
+        "date" => parse_datetime(v).ok_or_else(invalid)?.date().into_sql(),
+        "time" => ColumnData::Time(Some(scaled_time(parse_time(v).ok_or_else(invalid)?, scale))),
+        "datetime" | "smalldatetime" => legacy_datetime(parse_datetime(v).ok_or_else(invalid)?, base == "smalldatetime").ok_or_else(invalid)?,
+        "datetime2" => parse_datetime(v).ok_or_else(invalid)?.into_sql(),
+        "datetimeoffset" => match parse_datetime_offset(v).ok_or_else(invalid)?.into_sql() {
+            ColumnData::DateTimeOffset(Some(dto)) => {
+                let utc = dto.datetime2();
+                let increments = utc.time().increments() / 10u64.pow(7 - scale as u32);
+                let time = tiberius::time::Time::new(increments, scale);
+                ColumnData::DateTimeOffset(Some(tiberius::time::DateTimeOffset::new(tiberius::time::DateTime2::new(utc.date(), time), dto.offset())))
+            }
+            other => other,
+        },
+        "uniqueidentifier" => ColumnData::Guid(Some(tiberius::Uuid::parse_str(v.trim_start_matches('{').trim_end_matches('}')).map_err(|e| bad(&e))?)),
+        "binary" | "varbinary" | "image" => ColumnData::Binary(Some(Cow::Owned(parse_hex(v).ok_or_else(invalid)?))),
+        "money" | "smallmoney" => return Err(format!("Bulk load không hỗ trợ kiểu {}", base)),
+        _ => ColumnData::String(Some(Cow::Owned(value.to_string()))),
+    })
+}
+
+/// Loads a CSV into an MSSQL table with TDS bulk load (the BCP protocol) instead of row-by-row INSERTs.
+/// Each batch is its own bulk load request, so progress is reported per committed batch.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_csv_bulk(window: tauri::Window, ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, config: DbConfig, path: String, table: String, batch_size: Option<usize>, encoding: Option<String>) -> Result<ImportSummary, String> {
+    profile.require_for(&config, Permission::Import)?;
+    let _op = ops.begin();
+    if config.db_type != "mssql" {
+        return Err("Bulk insert chỉ hỗ trợ MSSQL".to_string());
+    }
+    let started = std::time::Instant::now();
+    let csv = read_csv_file(&path, encoding.as_deref(), None)?;
+    let batch_size = batch_size.filter(|b| *b > 0).unwrap_or(DEFAULT_BATCH_SIZE);
+
+    // Declared types carry the scale the decimal and time values are encoded with
+    let target_columns = bulk_columns(&schema::describe_table(config.clone(), None, table.clone()).await?);
+    if let Some((column, data_type)) = target_columns.iter().find(|(_, t)| bulk_unsupported(t)) {
+        return Err(format!("Bulk load không hỗ trợ cột {} kiểu {}; hãy dùng import_csv", column, data_type));
+    }
+    let mut client = db::connect_mssql(&config).await?;
+
+    let header_pos: HashMap<String, usize> = csv.headers.iter().enumerate().map(|(i, h)| (h.to_lowercase(), i)).collect();
+    let mapping: Vec<Option<usize>> = target_columns.iter().map(|(c, _)| header_pos.get(&c.to_lowercase()).copied()).collect();
+    if mapping.iter().all(|m| m.is_none()) {
+        return Err("Không có cột CSV nào khớp với bảng đích".to_string());
+    }
+
+    let total_rows = csv.rows.len();
+    let mut rows_done = 0;
+    let mut batches = 0;
+
+    for chunk in csv.rows.chunks(batch_size) {
+        let mut req = client.bulk_insert(&table).await.map_err(|e| e.to_string())?;
+        for (offset, record) in chunk.iter().enumerate() {
+            let mut row = TokenRow::new();
+            for ((_, data_type), source) in target_columns.iter().zip(&mapping) {
+                let value = source.and_then(|i| record.get(i)).map(|s| s.as_str()).unwrap_or("");
+                let data = to_column_data(value, data_type)
+                    .map_err(|e| format!("Dòng {}: {}", rows_done + offset + 2, e))?;
+                row.push(data);
+            }
+            req.send(row).await.map_err(|e| e.to_string())?;
+        }
+        req.finalize().await.map_err(|e| e.to_string())?;
+
+        rows_done += chunk.len();
+        batches += 1;
+        window.emit("import://progress", ImportProgress { table: table.clone(), rows_done, total_rows, batch: batches }).ok();
+    }
+
+    Ok(ImportSummary { table, rows_imported: rows_done, batches, elapsed_ms: started.elapsed().as_millis() })
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CopyOptions {
+    /// Server-side name of the file encoding, e.g. "UTF8" or "SJIS".
+    pub encoding: Option<String>,
+    /// Text that represents NULL in the file (COPY default for CSV is an unquoted empty string).
+    pub null_string: Option<String>,
+    pub delimiter: Option<char>,
+    pub header: Option<bool>,
+}
+
+fn quote_pg_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn copy_with_clause(options: &CopyOptions) -> String {
+    let mut parts = vec!["FORMAT csv".to_string()];
+    parts.push(format!("HEADER {}", options.header.unwrap_or(true)));
+    if let Some(d) = options.delimiter {
+        parts.push(format!("DELIMITER {}", quote_pg_literal(&d.to_string())));
+    }
+    if let Some(n) = &options.null_string {
+        parts.push(format!("NULL {}", quote_pg_literal(n)));
+    }
+    if let Some(enc) = options.encoding.as_deref().filter(|e| !e.trim().is_empty()) {
+        parts.push(format!("ENCODING {}", quote_pg_literal(enc)));
+    }
+    format!("WITH ({})", parts.join(", "))
+}
+
+/// Streams a CSV file into a Postgres table with `COPY ... FROM STDIN`.
+/// The file bytes are sent untouched; the server handles decoding via the ENCODING option.
+#[tauri::command]
+pub async fn import_csv_copy(window: tauri::Window, ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, config: DbConfig, path: String, table: String, options: Option<CopyOptions>) -> Result<ImportSummary, String> {
+    profile.require_for(&config, Permission::Import)?;
+    let _op = ops.begin();
+    if config.db_type != "postgres" {
+        return Err("COPY chỉ hỗ trợ PostgreSQL".to_string());
+    }
+    let started = std::time::Instant::now();
+    let options = options.unwrap_or_default();
+    let bytes = std::fs::read(&path).map_err(|e| format!("Không thể đọc file: {}", e))?;
+    let total = bytes.len();
+
+    let mut conn = db::connect_pg(&config).await?;
+    let statement = format!("COPY {} FROM STDIN {}", table, copy_with_clause(&options));
+    let mut copy = conn.copy_in_raw(&statement).await.map_err(|e| e.to_string())?;
+
+    let mut sent = 0;
+    let mut batches = 0;
+    for chunk in bytes.chunks(COPY_CHUNK_BYTES) {
+        if let Err(e) = copy.send(chunk).await {
+            copy.abort(e.to_string()).await.ok();
+            return Err(e.to_string());
+        }
+        sent += chunk.len();
+        batches += 1;
+        // Progress is byte-based: row counts are only known once the server finishes
+        window.emit("import://progress", ImportProgress { table: table.clone(), rows_done: sent, total_rows: total, batch: batches }).ok();
+    }
+    let rows = copy.finish().await.map_err(|e| e.to_string())?;
+
+    Ok(ImportSummary { table, rows_imported: rows as usize, batches, elapsed_ms: started.elapsed().as_millis() })
+}
+
+/// Writes a table or query to a CSV file with `COPY ... TO STDOUT`.
+#[tauri::command]
+pub async fn export_csv_copy(ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, config: DbConfig, source: String, path: String, options: Option<CopyOptions>) -> Result<u64, String> {
+    if config.db_type != "postgres" {
+        return Err("COPY chỉ hỗ trợ PostgreSQL".to_string());
+    }
+    // A bare name is copied as a table, anything else is treated as a query
+    let trimmed = source.trim().trim_end_matches(';');
+    let is_query = trimmed.contains(char::is_whitespace);
+    // COPY (DELETE ... RETURNING *) writes, so the query goes through the same check as any other
+    let checked = if is_query { trimmed.to_string() } else { format!("SELECT * FROM {}", trimmed) };
+    profile.check_sql(&checked, &config)?;
+    query_guard::check(&config, &checked, false)?;
+    let _op = ops.begin();
+    let options = options.unwrap_or_default();
+    let target = if is_query { format!("({})", trimmed) } else { trimmed.to_string() };
+    let statement = format!("COPY {} TO STDOUT {}", target, copy_with_clause(&options));
+
+    let mut conn = db::connect_pg(&config).await?;
+    let mut stream = conn.copy_out_raw(&statement).await.map_err(|e| e.to_string())?;
+    let mut file = std::fs::File::create(&path).map_err(|e| format!("Không thể tạo file: {}", e))?;
+    let mut written: u64 = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+        written += chunk.len() as u64;
+    }
+    Ok(written)
+}
+
+/// Source column (CSV header or query column) → table column, for `import_csv` and `transfer`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ColumnMapping {
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CsvImportOptions {
+    /// "utf-8" (default) or "shift_jis".
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Defaults to every CSV header whose name matches a table column (case-insensitive).
+    #[serde(default)]
+    pub mapping: Option<Vec<ColumnMapping>>,
+    /// Rows per INSERT; capped by the driver's parameter limit.
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// Cell text imported as NULL besides "[NULL]"; empty cells of non-text columns are always NULL.
+    #[serde(default)]
+    pub null_value: Option<String>,
+    /// The import stops once more rows than this have failed; defaults to 100.
+    #[serde(default)]
+    pub max_errors: Option<usize>,
+}
+
+/// A CSV row that was not imported; `line` counts the header as line 1.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CsvImportSummary {
+    pub table: String,
+    pub rows_imported: usize,
+    pub rows_failed: usize,
+    pub batches: usize,
+    pub errors: Vec<RowError>,
+    /// Set when `max_errors` was exceeded and the remaining rows were skipped.
+    pub stopped: bool,
+    pub elapsed_ms: u128,
+}
+
+/// Parameter type (see `QueryParam`) that a cell is parsed as before binding, from the column's
+/// declared type. Everything else is bound as text and converted by the server.
+fn param_types(db_type: &str, data_type: &str) -> &'static [&'static str] {
+    let base = data_type.split('(').next().unwrap_or("").trim().to_lowercase();
+    match base.as_str() {
+        "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "int2" | "int4" | "int8"
+        | "smallserial" | "serial" | "bigserial" => &["int"],
+        "real" | "float" | "double" | "double precision" | "float4" | "float8" | "binary_float" | "binary_double" => &["float"],
+        "bit" | "bool" | "boolean" => &["bool"],
+        // Oracle DATE carries a time of day
+        "date" if db_type == "oracle" => &["datetime", "date"],
+        "date" => &["date"],
+        "datetime" | "datetime2" | "smalldatetime" | "timestamp" | "timestamp without time zone" => &["datetime"],
+        _ => &["string"],
+    }
+}
+
+fn is_text_type(data_type: &str) -> bool {
+    let t = data_type.to_lowercase();
+    t.contains("char") || t.contains("text") || t.contains("clob")
+}
+
+/// The value bound for one CSV cell of a column declared as `data_type`.
+pub fn import_value(db_type: &str, cell: &str, data_type: &str, null_value: Option<&str>) -> Result<ParamValue, String> {
+    if cell == NULL_CELL || null_value == Some(cell) || (cell.trim().is_empty() && !is_text_type(data_type)) {
+        return Ok(ParamValue::Null);
+    }
+    let types = param_types(db_type, data_type);
+    if types.contains(&"datetime") {
+        if let Some(dt) = parse_datetime_offset(cell.trim()) {
+            return Ok(ParamValue::DateTime(dt.naive_local()));
+        }
+    }
+    types.iter()
+        .find_map(|t| QueryParam { value: Some(cell.to_string()), param_type: Some(t.to_string()) }.to_value(0).ok())
+        .ok_or_else(|| format!("Giá trị '{}' không hợp lệ cho kiểu {}", cell, data_type))
+}
+
+/// Rows per INSERT statement under the parameter limits (2100 on SQL Server, 65535 elsewhere).
+pub fn rows_per_statement(db_type: &str, columns: usize) -> usize {
+    let max_params = if db_type == "mssql" { 2000 } else { 65000 };
+    (max_params / columns.max(1)).clamp(1, MAX_ROWS_PER_INSERT)
+}
+
+/// Parameterized INSERT of `rows` rows. Postgres does not convert text parameters implicitly,
+/// so there each value is cast to its column type; Oracle has no multi-row VALUES, so it gets
+/// `INSERT ALL`.
+pub fn insert_statement(db_type: &str, table: &str, columns: &[(String, String)], rows: usize) -> String {
+    let column_list = columns.iter().map(|(c, _)| dialect::quote_ident(db_type, c)).collect::<Vec<_>>().join(", ");
+    let values = columns.iter()
+        .map(|(_, data_type)| if db_type == "postgres" { format!("CAST(? AS {})", data_type) } else { "?".to_string() })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let target = format!("{} ({})", table, column_list);
+    let sql = if db_type == "oracle" && rows > 1 {
+        format!("INSERT ALL {} SELECT 1 FROM DUAL", vec![format!("INTO {} VALUES ({})", target, values); rows].join(" "))
+    } else {
+        format!("INSERT INTO {} VALUES {}", target, vec![format!("({})", values); rows].join(", "))
+    };
+    query_params::native_placeholders(&sql, db_type)
+}
+
+/// One connection kept open for the whole import. The Oracle one holds its own SSH tunnel; the
+/// others get theirs from the `db` connect functions.
+pub enum ImportConnection {
+    Mssql(MssqlClient),
+    Sqlx(db::SqlxConnection),
+    Oracle(Arc<Mutex<oracle::Connection>>, Option<ssh_tunnel::Tunnel>),
+}
+
+impl ImportConnection {
+    pub async fn open(config: &DbConfig) -> Result<Self, String> {
+        Ok(match config.db_type.as_str() {
+            "mssql" => ImportConnection::Mssql(db::connect_mssql(config).await?),
+            "oracle" => {
+                let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+                let conn = db::with_connect_timeout(config, oracle_db::blocking(move || oracle_db::connect(&target))).await?;
+                ImportConnection::Oracle(Arc::new(Mutex::new(conn)), tunnel)
+            }
+            _ => ImportConnection::Sqlx(db::connect_sqlx(config).await?),
+        })
+    }
+
+    pub async fn execute(&mut self, sql: &str, params: &[ParamValue]) -> Result<u64, String> {
+        match self {
+            ImportConnection::Mssql(client) => db::execute_mssql(client, sql, params).await,
+            ImportConnection::Sqlx(conn) => db::execute_sqlx(conn, sql, params).await,
+            ImportConnection::Oracle(conn, _) => {
+                let (conn, sql, params) = (conn.clone(), sql.to_string(), params.to_vec());
+                oracle_db::blocking(move || oracle_db::execute_on(&conn.lock().unwrap(), &sql, &params)).await
+            }
+        }
+    }
+}
+
+/// Target columns paired with the source (CSV or query) column each one is read from.
+pub fn map_columns(headers: &[String], table_columns: &[schema::ColumnInfo], mapping: Option<&[ColumnMapping]>) -> Result<Vec<(usize, schema::ColumnInfo)>, String> {
+    let header_pos = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name.trim()));
+    let column = |name: &str| table_columns.iter().find(|c| c.name.eq_ignore_ascii_case(name.trim()));
+    let mapped: Vec<(usize, schema::ColumnInfo)> = match mapping {
+        Some(mapping) => mapping.iter()
+            .map(|m| {
+                let source = header_pos(&m.source).ok_or_else(|| format!("Nguồn không có cột: {}", m.source))?;
+                let target = column(&m.target).ok_or_else(|| format!("Bảng không có cột: {}", m.target))?;
+                Ok((source, target.clone()))
+            })
+            .collect::<Result<_, String>>()?,
+        None => headers.iter().enumerate()
+            .filter_map(|(i, h)| column(h).map(|c| (i, c.clone())))
+            .collect(),
+    };
+    if mapped.is_empty() {
+        return Err("Không có cột nguồn nào khớp với bảng đích".to_string());
+    }
+    Ok(mapped)
+}
+
+/// Imports a CSV into any supported database with batched, parameterized INSERTs. Cells are
+/// converted to the target column types first; a row that fails conversion, or that makes its
+/// batch fail on the server, is reported in the summary (and as `import://row-error`) while the
+/// other rows of the batch are inserted one by one.
+#[tauri::command]
+pub async fn import_csv(window: tauri::Window, ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, config: DbConfig, path: String, table: String, options: Option<CsvImportOptions>) -> Result<CsvImportSummary, String> {
+    profile.require_for(&config, Permission::Import)?;
+    let _op = ops.begin();
+    let started = std::time::Instant::now();
+    let options = options.unwrap_or_default();
+    let csv = read_csv_file(&path, options.encoding.as_deref(), options.delimiter)?;
+    let table_columns = schema::describe_table(config.clone(), None, table.clone()).await?;
+    let mapped = map_columns(&csv.headers, &table_columns, options.mapping.as_deref())?;
+    let columns: Vec<(String, String)> = mapped.iter().map(|(_, c)| (c.name.clone(), c.data_type.clone())).collect();
+
+    let mut conn = ImportConnection::open(&config).await?;
+    let db_type = config.db_type.as_str();
+    let chunk_size = options.batch_size.filter(|b| *b > 0).unwrap_or(DEFAULT_INSERT_BATCH_SIZE).min(rows_per_statement(db_type, columns.len()));
+    let max_errors = options.max_errors.unwrap_or(DEFAULT_MAX_ERRORS);
+    let single_row_sql = insert_statement(db_type, &table, &columns, 1);
+
+    let total_rows = csv.rows.len();
+    let (mut rows_done, mut rows_imported, mut batches) = (0, 0, 0);
+    let mut errors: Vec<RowError> = Vec::new();
+    let report = |errors: &mut Vec<RowError>, line: usize, message: String| {
+        let error = RowError { line, message };
+        window.emit("import://row-error", &error).ok();
+        errors.push(error);
+    };
+
+    for chunk in csv.rows.chunks(chunk_size) {
+        let mut ready: Vec<(usize, Vec<ParamValue>)> = Vec::with_capacity(chunk.len());
+        for (offset, record) in chunk.iter().enumerate() {
+            let line = rows_done + offset + 2;
+            let values = mapped.iter()
+                .map(|(source, column)| {
+                    let cell = record.get(*source).map(String::as_str).unwrap_or("");
+                    import_value(db_type, cell, &column.data_type, options.null_value.as_deref())
+                        .map_err(|e| format!("Cột {}: {}", column.name, e))
+                })
+                .collect::<Result<Vec<_>, _>>();
+            match values {
+                Ok(values) => ready.push((line, values)),
+                Err(e) => report(&mut errors, line, e),
+            }
+        }
+
+        if !ready.is_empty() {
+            let sql = insert_statement(db_type, &table, &columns, ready.len());
+            let params: Vec<ParamValue> = ready.iter().flat_map(|(_, v)| v.iter().cloned()).collect();
+            if conn.execute(&sql, &params).await.is_ok() {
+                rows_imported += ready.len();
+            } else {
+                // Find the offending rows; the failed statement inserted nothing
+                for (line, values) in &ready {
+                    match conn.execute(&single_row_sql, values).await {
+                        Ok(_) => rows_imported += 1,
+                        Err(e) => report(&mut errors, *line, e),
+                    }
+                }
+            }
+        }
+
+        rows_done += chunk.len();
+        batches += 1;
+        window.emit("import://progress", ImportProgress { table: table.clone(), rows_done, total_rows, batch: batches }).ok();
+        if errors.len() > max_errors {
+            break;
+        }
+    }
+
+    Ok(CsvImportSummary {
+        table,
+        rows_imported,
+        rows_failed: errors.len(),
+        batches,
+        stopped: rows_done < total_rows,
+        errors,
+        elapsed_ms: started.elapsed().as_millis(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_statement_and_values() {
+        let columns = vec![("ID".to_string(), "integer".to_string()), ("NAME".to_string(), "character varying(20)".to_string())];
+        assert_eq!(insert_statement("postgres", "public.items", &columns, 2),
+            "INSERT INTO public.items (\"ID\", \"NAME\") VALUES (CAST($1 AS integer), CAST($2 AS character varying(20))), (CAST($3 AS integer), CAST($4 AS character varying(20)))");
+        assert_eq!(insert_statement("oracle", "ITEMS", &columns, 2),
+            "INSERT ALL INTO ITEMS (\"ID\", \"NAME\") VALUES (:1, :2) INTO ITEMS (\"ID\", \"NAME\") VALUES (:3, :4) SELECT 1 FROM DUAL");
+        assert_eq!(rows_per_statement("mssql", 3), 666);
+        assert_eq!(rows_per_statement("mysql", 3), MAX_ROWS_PER_INSERT);
+
+        assert_eq!(import_value("mssql", " 42 ", "int", None), Ok(ParamValue::Int(42)));
+        assert_eq!(import_value("mssql", "", "int", None), Ok(ParamValue::Null));
+        assert_eq!(import_value("mssql", "", "nvarchar(10)", None), Ok(ParamValue::Text(String::new())));
+        assert_eq!(import_value("mssql", "-", "decimal(10,2)", Some("-")), Ok(ParamValue::Null));
+        assert!(import_value("mssql", "abc", "bigint", None).unwrap_err().contains("bigint"));
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(import_value("oracle", "2024-05-01", "DATE", None), Ok(ParamValue::Date(date)));
+        assert_eq!(import_value("postgres", "2024/05/01 10:30:00", "timestamp(3) without time zone", None),
+            Ok(ParamValue::DateTime(date.and_hms_opt(10, 30, 0).unwrap())));
+    }
+
+    #[test]
+    fn test_bulk_decimal() {
+        let numeric = |value: &str, data_type: &str| match to_column_data(value, data_type) {
+            Ok(ColumnData::Numeric(Some(n))) => (n.value(), n.scale()),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(numeric("1234.5", "decimal(18,2)"), (123450, 2));
+        assert_eq!(numeric("-0.125", "numeric(10,2)"), (-13, 2));
+        assert_eq!(numeric("42", "decimal(18,0)"), (42, 0));
+        assert_eq!(numeric(".5", "decimal"), (1, 0));
+        assert_eq!(to_column_data("", "decimal(18,2)"), Ok(ColumnData::Numeric(None)));
+        assert!(to_column_data("1,5", "decimal(18,2)").is_err());
+    }
+
+    #[test]
+    fn test_bulk_columns_skip_identity() {
+        let column = |name: &str, data_type: &str, generated: bool| schema::ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable: !generated,
+            default_value: None,
+            primary_key: name == "ID",
+            references: None,
+            generated,
+        };
+        let table = vec![column("ID", "int", true), column("NAME", "NVARCHAR(50)", false), column("TOTAL", "decimal(18,2)", true)];
+        assert_eq!(bulk_columns(&table), vec![("NAME".to_string(), "nvarchar(50)".to_string())]);
+    }
+
+    #[test]
+    fn test_bulk_money() {
+        assert!(bulk_unsupported("money") && bulk_unsupported("SMALLMONEY"));
+        assert!(to_column_data("12.50", "money").is_err());
+    }
+
+    #[test]
+    fn test_bulk_datetime() {
+        // 1900-01-03 00:00:01.5 -> 2 days, 450 ticks of 1/300 s
+        assert_eq!(to_column_data("1900-01-03 00:00:01.5", "datetime"),
+            Ok(ColumnData::DateTime(Some(tiberius::time::DateTime::new(2, 450)))));
+        assert_eq!(to_column_data("1899-12-31", "datetime"),
+            Ok(ColumnData::DateTime(Some(tiberius::time::DateTime::new(-1, 0)))));
+        assert_eq!(to_column_data("[NULL]", "datetime"), Ok(ColumnData::DateTime(None)));
+        assert!(to_column_data("03/01/1900", "datetime").is_err());
+    }
+
+    #[test]
+    fn test_bulk_smalldatetime() {
+        // Seconds round to the nearest minute
+        assert_eq!(to_column_data("1900-01-02 10:30:29", "smalldatetime"),
+            Ok(ColumnData::SmallDateTime(Some(tiberius::time::SmallDateTime::new(1, 630)))));
+        assert_eq!(to_column_data("1900-01-02 23:59:30", "smalldatetime"),
+            Ok(ColumnData::SmallDateTime(Some(tiberius::time::SmallDateTime::new(2, 0)))));
+        assert!(to_column_data("1899-12-31 00:00:00", "smalldatetime").is_err());
+    }
+
+    #[test]
+    fn test_bulk_datetimeoffset() {
+        // Stored as UTC plus the offset in minutes, at the column's scale
+        match to_column_data("2024-01-02 09:30:00.5 +09:00", "datetimeoffset(3)") {
+            Ok(ColumnData::DateTimeOffset(Some(dto))) => {
+                assert_eq!(dto.offset(), 540);
+                assert_eq!(dto.datetime2().time().scale(), 3);
+                assert_eq!(dto.datetime2().time().increments(), 30 * 60 * 1000 + 500);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(to_column_data("", "datetimeoffset"), Ok(ColumnData::DateTimeOffset(None)));
+        assert!(to_column_data("2024-01-02 09:30:00", "datetimeoffset").is_err());
+    }
+
+    #[test]
+    fn test_bulk_time() {
+        match to_column_data("08:30:15.25", "time(2)") {
+            Ok(ColumnData::Time(Some(t))) => assert_eq!((t.increments(), t.scale()), ((8 * 3600 + 30 * 60 + 15) * 100 + 25, 2)),
+            other => panic!("{:?}", other),
+        }
+        match to_column_data("08:30", "time") {
+            Ok(ColumnData::Time(Some(t))) => assert_eq!((t.increments(), t.scale()), ((8 * 3600 + 30 * 60) * 10_000_000, 7)),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bulk_uniqueidentifier() {
+        let id = tiberius::Uuid::parse_str("6f9619ff-8b86-d011-b42d-00c04fc964ff").unwrap();
+        assert_eq!(to_column_data("6F9619FF-8B86-D011-B42D-00C04FC964FF", "uniqueidentifier"), Ok(ColumnData::Guid(Some(id))));
+        assert_eq!(to_column_data("{6f9619ff-8b86-d011-b42d-00c04fc964ff}", "uniqueidentifier"), Ok(ColumnData::Guid(Some(id))));
+        assert!(to_column_data("not-a-guid", "uniqueidentifier").is_err());
+    }
+
+    #[test]
+    fn test_bulk_varbinary() {
+        assert_eq!(to_column_data("0x0A1BFF", "varbinary(10)"), Ok(ColumnData::Binary(Some(Cow::Owned(vec![0x0A, 0x1B, 0xFF])))));
+        assert_eq!(to_column_data("", "varbinary(max)"), Ok(ColumnData::Binary(None)));
+        // A preview cut by the grid cannot be imported
+        assert!(to_column_data("0x0A1B... (300 bytes)", "varbinary(max)").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use serde::Serialize;
+use tree_sitter::{Node, Parser};
+use crate::{diagrams, java_sql};
+
+// Class -> external service dependencies across a project, for impact analysis when a
+// downstream API changes. A call counts as external the same way the flowchart decides it
+// (any receiver other than `this`); the receiver is resolved statically to a type through
+// the class's fields, parameters and locals, or taken as a class name for `Type.method()`.
+
+/// JDK/utility receivers that say nothing about service dependencies.
+const IGNORED_TYPES: [&str; 22] = [
+    "String", "StringBuilder", "Math", "Objects", "Optional", "Arrays", "Collections", "List", "Map", "Set",
+    "ArrayList", "HashMap", "HashSet", "Integer", "Long", "Double", "Boolean", "BigDecimal", "System",
+    "Logger", "Log", "Stream",
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DependencyEdge {
+    pub class_name: String,
+    pub service: String,
+    /// Distinct methods called on the service, in first-seen order.
+    pub methods: Vec<String>,
+    pub call_count: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DependencyReport {
+    pub files_scanned: usize,
+    pub classes: Vec<String>,
+    pub services: Vec<String>,
+    /// `matrix[class][service]` = number of call sites.
+    pub matrix: Vec<Vec<usize>>,
+    pub edges: Vec<DependencyEdge>,
+    pub mermaid: String,
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+/// `List<Order>` -> `List`, `com.acme.PaymentClient` -> `PaymentClient`
+fn base_type(java_type: &str) -> String {
+    let raw = java_type.split('<').next().unwrap_or("").trim();
+    raw.rsplit('.').next().unwrap_or(raw).to_string()
+}
+
+/// Variable name -> declared type for every field, parameter and local in the class.
+/// Scopes are flattened; `var` declarations stay unresolved.
+fn collect_types(node: Node, source: &str, types: &mut HashMap<String, String>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "field_declaration" | "local_variable_declaration" => {
+                if let Some(ty) = child.child_by_field_name("type").map(|t| base_type(text(t, source))) {
+                    let mut c = child.walk();
+                    for declarator in child.children_by_field_name("declarator", &mut c) {
+                        if let Some(name) = declarator.child_by_field_name("name") {
+                            if ty != "var" {
+                                types.insert(text(name, source).to_string(), ty.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            "formal_parameter" => {
+                if let (Some(ty), Some(name)) = (child.child_by_field_name("type"), child.child_by_field_name("name")) {
+                    types.insert(text(name, source).to_string(), base_type(text(ty, source)));
+                }
+            }
+            // Nested classes are analysed on their own
+            "class_declaration" | "interface_declaration" | "enum_declaration" => continue,
+            _ => {}
+        }
+        collect_types(child, source, types);
+    }
+}
+
+/// Service name for a call receiver, or None for internal / unresolvable receivers.
+fn resolve_receiver(object: Node, source: &str, types: &HashMap<String, String>) -> Option<String> {
+    let name = match object.kind() {
+        "identifier" => text(object, source),
+        // this.paymentClient.charge()
+        "field_access" if object.child_by_field_name("object").map_or(false, |o| o.kind() == "this") => {
+            text(object.child_by_field_name("field")?, source)
+        }
+        _ => return None,
+    };
+    let service = match types.get(name) {
+        Some(ty) => ty.clone(),
+        // Static call: PaymentGateway.charge()
+        None if name.starts_with(|c: char| c.is_ascii_uppercase()) => name.to_string(),
+        None => return None,
+    };
+    (!IGNORED_TYPES.contains(&service.as_str())).then_some(service)
+}
+
+/// Bean accessors: `getItems`, `setName`, `isActive`.
+fn is_accessor(method: &str) -> bool {
+    ["get", "set", "is"].iter().any(|p| {
+        method.strip_prefix(p).map_or(false, |rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+    })
+}
+
+fn collect_calls(node: Node, source: &str, types: &HashMap<String, String>, calls: &mut Vec<(String, String)>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "method_invocation" => {
+                let object = child.child_by_field_name("object");
+                let name = child.child_by_field_name("name");
+                if let (Some(object), Some(name)) = (object, name) {
+                    let method = text(name, source);
+                    // getters/setters on entities and DTOs are not service dependencies
+                    if !is_accessor(method) {
+                        if let Some(service) = resolve_receiver(object, source, types) {
+                            calls.push((service, method.to_string()));
+                        }
+                    }
+                }
+            }
+            "class_declaration" | "interface_declaration" | "enum_declaration" => continue,
+            _ => {}
+        }
+        collect_calls(child, source, types, calls);
+    }
+}
+
+/// (class, service, method) for every external call site in the source.
+fn class_calls(node: Node, source: &str, out: &mut Vec<(String, String, String)>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if matches!(child.kind(), "class_declaration" | "enum_declaration") {
+            let class_name = child.child_by_field_name("name").map(|n| text(n, source).to_string()).unwrap_or_default();
+            if let Some(body) = child.child_by_field_name("body") {
+                let mut types = HashMap::new();
+                collect_types(body, source, &mut types);
+                let mut calls = Vec::new();
+                collect_calls(body, source, &types, &mut calls);
+                for (service, method) in calls {
+                    if service != class_name {
+                        out.push((class_name.clone(), service, method));
+                    }
+                }
+                class_calls(body, source, out);
+            }
+        } else {
+            class_calls(child, source, out);
+        }
+    }
+}
+
+fn mermaid(edges: &[DependencyEdge], classes: &[String], services: &[String]) -> String {
+    let mut output = String::from("flowchart LR\n");
+    for (i, class) in classes.iter().enumerate() {
+        output.push_str(&format!("    C{}[\"{}\"]\n", i, class));
+    }
+    for (i, service) in services.iter().enumerate() {
+        output.push_str(&format!("    S{}{{{{\"{}\"}}}}:::service\n", i, service));
+    }
+    for edge in edges {
+        let c = classes.iter().position(|c| *c == edge.class_name).unwrap_or(0);
+        let s = services.iter().position(|s| *s == edge.service).unwrap_or(0);
+        output.push_str(&format!("    C{} -->|\"{}\"| S{}\n", c, edge.methods.join(", "), s));
+    }
+    output.push_str("    classDef service fill:#fff3e0,stroke:#ef6c00\n");
+    output
+}
+
+pub fn analyze_sources(sources: &[String]) -> Result<DependencyReport, String> {
+    let mut parser = Parser::new();
+    parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
+    let mut sites = Vec::new();
+    for source in sources {
+        let tree = parser.parse(source, None).ok_or("Failed to parse source")?;
+        class_calls(tree.root_node(), source, &mut sites);
+    }
+
+    let mut grouped: BTreeMap<(String, String), DependencyEdge> = BTreeMap::new();
+    for (class_name, service, method) in sites {
+        let edge = grouped.entry((class_name.clone(), service.clone())).or_insert_with(|| DependencyEdge {
+            class_name,
+            service,
+            methods: Vec::new(),
+            call_count: 0,
+        });
+        edge.call_count += 1;
+        if !edge.methods.contains(&method) {
+            edge.methods.push(method);
+        }
+    }
+    let edges: Vec<DependencyEdge> = grouped.into_values().collect();
+    let classes: Vec<String> = edges.iter().map(|e| e.class_name.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+    let services: Vec<String> = edges.iter().map(|e| e.service.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+
+    let mut matrix = vec![vec![0; services.len()]; classes.len()];
+    for edge in &edges {
+        let c = classes.iter().position(|c| *c == edge.class_name).unwrap_or(0);
+        let s = services.iter().position(|s| *s == edge.service).unwrap_or(0);
+        matrix[c][s] = edge.call_count;
+    }
+    let mermaid = mermaid(&edges, &classes, &services);
+    Ok(DependencyReport { files_scanned: sources.len(), classes, services, matrix, edges, mermaid })
+}
+
+#[tauri::command]
+pub fn analyze_dependencies(project_dir: String) -> Result<DependencyReport, String> {
+    let mut files = Vec::new();
+    java_sql::java_files(&PathBuf::from(&project_dir), &mut files)?;
+    files.sort();
+    let sources: Vec<String> = files.iter().filter_map(|f| diagrams::read_source(&f.to_string_lossy()).ok()).collect();
+    analyze_sources(&sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_sources() {
+        let order = r#"
+class OrderService {
+    private PaymentClient paymentClient;
+    private final Logger log = Logger.get();
+    void place(Order order, InventoryApi inventory) {
+        inventory.reserve(order.getItems());
+        this.paymentClient.charge(order);
+        paymentClient.charge(order);
+        String key = AuditGateway.record("placed");
+        log.info(key);
+        helper();
+    }
+}
+"#;
+        let report = analyze_sources(&[order.to_string()]).unwrap();
+        assert_eq!(report.classes, vec!["OrderService"]);
+        assert_eq!(report.services, vec!["AuditGateway", "InventoryApi", "PaymentClient"]);
+        assert_eq!(report.matrix, vec![vec![1, 1, 2]]);
+        let payment = report.edges.iter().find(|e| e.service == "PaymentClient").unwrap();
+        assert_eq!(payment.methods, vec!["charge"]);
+        assert!(report.mermaid.contains("    C0 -->|\"charge\"| S2\n"));
+        assert!(report.mermaid.contains("    S0{{\"AuditGateway\"}}:::service\n"));
+    }
+}
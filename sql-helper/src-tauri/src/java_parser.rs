@@ -1,6 +1,6 @@
 
 use std::collections::{HashMap, HashSet};
-use tree_sitter::{Parser, Node};
+use tree_sitter::{Parser, Node, Tree};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct MethodNode {
@@ -9,8 +9,23 @@ pub struct MethodNode {
     pub range: (usize, usize), 
     pub modifiers: Vec<String>,
     pub return_type: String,
+    /// Annotation names without `@` or arguments (`Test`, `Override`, ...).
+    pub annotations: Vec<String>,
+    /// Innermost enclosing class.
+    pub class_name: String,
 }
 
+impl MethodNode {
+    /// JUnit/TestNG test methods, and any method of a `*Test` / `*Tests` class.
+    pub fn is_test(&self) -> bool {
+        self.annotations.iter().any(|a| TEST_ANNOTATIONS.contains(&a.as_str()))
+            || self.class_name.ends_with("Test")
+            || self.class_name.ends_with("Tests")
+    }
+}
+
+const TEST_ANNOTATIONS: [&str; 4] = ["Test", "ParameterizedTest", "RepeatedTest", "TestFactory"];
+
 #[derive(Debug, serde::Serialize)]
 pub struct CallGraph {
     // Map of Method Name -> Method Details
@@ -19,6 +34,64 @@ pub struct CallGraph {
     pub calls: HashMap<String, Vec<String>>,
 }
 
+/// Settings a diagram was generated with, kept so saved diagrams can be regenerated.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct MermaidOptions {
+    /// Only this method; otherwise every public/protected method.
+    pub method_name: Option<String>,
+    /// With `method_name`: also render every internal method it transitively calls,
+    /// linking each call site to the callee's subgraph.
+    #[serde(default)]
+    pub follow_calls: bool,
+    /// Default mode only: leave out test methods (see `MethodNode::is_test`).
+    #[serde(default)]
+    pub exclude_tests: bool,
+    /// Show only what this test exercises: the test plus every internal method it transitively calls.
+    #[serde(default)]
+    pub test_method: Option<String>,
+    /// Render leading `// 1. validate input` comments in method bodies as phase nodes.
+    #[serde(default)]
+    pub phase_comments: bool,
+    /// Extra precondition calls from settings, styled like the built-in validators:
+    /// `Receiver.*`, `Receiver.method` or a bare method name.
+    #[serde(default)]
+    pub validators: Vec<String>,
+    #[serde(default)]
+    pub granularity: Granularity,
+}
+
+/// Which call-free statements become nodes of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// Only calls and returns (the original behaviour).
+    #[default]
+    Calls,
+    /// Every expression statement and local declaration.
+    All,
+    /// Statements that change state: assignments, `++`/`--` and initialized declarations.
+    Smart,
+}
+
+/// Statement node labels are cut to this many characters.
+const STATEMENT_LABEL_MAX: usize = 60;
+
+/// Built-in precondition/assertion utilities.
+const VALIDATION_CALLS: [&str; 7] = [
+    "Objects.requireNonNull*", "Assert.*", "Assertions.*", "Preconditions.*", "Validate.*", "Guard.*", "Objects.checkIndex",
+];
+
+/// `pattern` against `Receiver.method`: `X.*` matches any method on X, a trailing `*` is a prefix match,
+/// and a pattern without a dot matches the method name alone.
+fn matches_call(pattern: &str, qualified: &str) -> bool {
+    let pattern = pattern.trim();
+    let target = if pattern.contains('.') { qualified } else { qualified.rsplit('.').next().unwrap_or(qualified) };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => target.starts_with(prefix),
+        None => target == pattern,
+    }
+}
+
 pub struct JavaParser;
 
 impl JavaParser {
@@ -27,6 +100,11 @@ impl JavaParser {
         parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
         
         let tree = parser.parse(source, None).ok_or("Failed to parse source")?;
+        Ok(Self::parse_tree(&tree, source))
+    }
+
+    /// Builds the call graph from an already parsed tree (used by incremental re-parsing).
+    pub fn parse_tree(tree: &Tree, source: &str) -> CallGraph {
         let root_node = tree.root_node();
 
         let mut methods = HashMap::new();
@@ -35,7 +113,7 @@ impl JavaParser {
         // Pass 1: Collect all method declarations
         let mut method_declarations = Vec::new(); // Store nodes to process later
 
-        Self::collect_method_declarations(root_node, source, &mut methods, &mut method_declarations);
+        Self::collect_method_declarations(root_node, source, "", &mut methods, &mut method_declarations);
 
         let method_names: HashSet<String> = methods.keys().cloned().collect();
 
@@ -45,15 +123,16 @@ impl JavaParser {
              method_calls.insert(name.clone(), calls);
         }
 
-        Ok(CallGraph {
+        CallGraph {
             nodes: methods,
             calls: method_calls,
-        })
+        }
     }
 
     fn collect_method_declarations<'a>(
         node: Node<'a>, 
         source: &str, 
+        class_name: &str,
         methods: &mut HashMap<String, MethodNode>,
         declarations: &mut Vec<(String, Node<'a>)>
     ) {
@@ -66,6 +145,7 @@ impl JavaParser {
                     
                     // Extract modifiers
                     let mut modifiers = Vec::new();
+                    let mut annotations = Vec::new();
                     
                     let mut modifiers_node = child.child_by_field_name("modifiers");
                     if modifiers_node.is_none() {
@@ -78,6 +158,13 @@ impl JavaParser {
                         
                         let mut mod_cursor = modifiers_node.walk();
                         for mod_child in modifiers_node.children(&mut mod_cursor) {
+                            if mod_child.kind() == "marker_annotation" || mod_child.kind() == "annotation" {
+                                if let Some(name) = mod_child.child_by_field_name("name") {
+                                    let name = &source[name.byte_range()];
+                                    // @org.junit.Test -> Test
+                                    annotations.push(name.rsplit('.').next().unwrap_or(name).to_string());
+                                }
+                            }
                             let mod_text = &source[mod_child.byte_range().start..mod_child.byte_range().end].trim().to_string();
                             // eprintln!("  Modifier child kind: {}, Text: {}", mod_child.kind(), mod_text);
                             modifiers.push(mod_text.to_string());
@@ -101,6 +188,8 @@ impl JavaParser {
                         range: (child.byte_range().start, child.byte_range().end),
                         modifiers,
                         return_type,
+                        annotations,
+                        class_name: class_name.to_string(),
                     };
                     methods.insert(name.to_string(), method_node);
                     declarations.push((name.to_string(), child));
@@ -108,9 +197,10 @@ impl JavaParser {
             } else if child.kind() == "class_declaration" {
                 // Recurse into nested classes if needed (though requirement says "same class")
                 // For now, let's just recurse to find methods inside the main class body
-                 Self::collect_method_declarations(child, source, methods, declarations);
+                 let nested_name = child.child_by_field_name("name").map(|n| &source[n.byte_range()]).unwrap_or(class_name);
+                 Self::collect_method_declarations(child, source, nested_name, methods, declarations);
             } else if child.kind() == "class_body" {
-                 Self::collect_method_declarations(child, source, methods, declarations);
+                 Self::collect_method_declarations(child, source, class_name, methods, declarations);
             }
         }
     }
@@ -166,9 +256,54 @@ impl JavaParser {
         }
     }
 
-    pub fn generate_mermaid(graph: &CallGraph, source: &str, method_name: Option<String>) -> String {
-        let mut output = String::from("flowchart TD\n");
-        
+    pub fn generate_with_options(source: &str, options: &MermaidOptions) -> Result<String, String> {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
+        let tree = parser.parse(source, None).ok_or("Failed to parse source")?;
+        Ok(Self::render_tree(&tree, source, options))
+    }
+
+    /// Call graph + flowchart for an already parsed tree.
+    pub fn render_tree(tree: &Tree, source: &str, options: &MermaidOptions) -> String {
+        let graph = Self::parse_tree(tree, source);
+        if let Some(test) = &options.test_method {
+            let targets = Self::reachable_methods(&graph, test);
+            return Self::render_methods(&graph, source, tree, targets, true, options);
+        }
+        match &options.method_name {
+            Some(entry) if options.follow_calls => {
+                let targets = Self::reachable_methods(&graph, entry);
+                Self::render_methods(&graph, source, tree, targets, true, options)
+            }
+            _ => {
+                let targets = Self::target_methods(&graph, options.method_name.clone(), options.exclude_tests);
+                Self::render_methods(&graph, source, tree, targets, false, options)
+            }
+        }
+    }
+
+    /// The entry method followed by every internal method it reaches, in call order (breadth-first).
+    fn reachable_methods(graph: &CallGraph, entry: &str) -> Vec<String> {
+        let mut order = Vec::new();
+        if !graph.nodes.contains_key(entry) {
+            return order;
+        }
+        let mut seen = HashSet::new();
+        let mut queue = std::collections::VecDeque::from([entry.to_string()]);
+        seen.insert(entry.to_string());
+        while let Some(method) = queue.pop_front() {
+            for callee in graph.calls.get(&method).into_iter().flatten() {
+                if seen.insert(callee.clone()) {
+                    queue.push_back(callee.clone());
+                }
+            }
+            order.push(method);
+        }
+        order
+    }
+
+    /// The requested method, or by default every public and protected method.
+    fn target_methods(graph: &CallGraph, method_name: Option<String>, exclude_tests: bool) -> Vec<String> {
         let mut target_methods: Vec<String> = Vec::new();
 
         if let Some(name) = method_name {
@@ -182,11 +317,15 @@ impl JavaParser {
                     node.modifiers.contains(&"public".to_string()) || 
                     node.modifiers.contains(&"protected".to_string())
                 })
+                .filter(|(_, node)| !(exclude_tests && node.is_test()))
                 .map(|(name, _)| name.clone())
                 .collect();
             target_methods.sort();
         }
+        target_methods
+    }
 
+    pub fn generate_mermaid(graph: &CallGraph, source: &str, method_name: Option<String>) -> String {
         // We need a fresh parser to traverse bodies for Control Flow logic
         let mut parser = Parser::new();
         if parser.set_language(tree_sitter_java::language()).is_err() {
@@ -196,6 +335,19 @@ impl JavaParser {
              Some(t) => t,
              None => return "error: parse failed".to_string(),
         };
+        Self::generate_mermaid_for_tree(graph, source, &tree, method_name)
+    }
+
+    /// Same as `generate_mermaid`, reusing an existing parse tree of `source`.
+    pub fn generate_mermaid_for_tree(graph: &CallGraph, source: &str, tree: &Tree, method_name: Option<String>) -> String {
+        let target_methods = Self::target_methods(graph, method_name, false);
+        Self::render_methods(graph, source, tree, target_methods, false, &MermaidOptions::default())
+    }
+
+    /// One subgraph per target method. With `link_calls`, internal call nodes get a dotted edge
+    /// to the start node of the callee's subgraph when that callee is rendered too.
+    fn render_methods(graph: &CallGraph, source: &str, tree: &Tree, target_methods: Vec<String>, link_calls: bool, options: &MermaidOptions) -> String {
+        let mut output = String::from("flowchart TD\n");
         let root_node = tree.root_node();
 
         // We need to map method names to their nodes to start traversal
@@ -207,6 +359,11 @@ impl JavaParser {
             graph,
             output: &mut output,
             node_counter: 0,
+            start_ids: HashMap::new(),
+            call_sites: Vec::new(),
+            phase_comments: options.phase_comments,
+            validators: &options.validators,
+            granularity: options.granularity,
         };
 
         for method_name in target_methods {
@@ -222,12 +379,23 @@ impl JavaParser {
                  }
              }
         }
+
+        if link_calls {
+            for (node_id, callee) in std::mem::take(&mut generator.call_sites) {
+                if let Some(start_id) = generator.start_ids.get(&callee) {
+                    generator.output.push_str(&format!("  {} -.-> {}\n", node_id, start_id));
+                }
+            }
+        }
         
         // Styles
         output.push_str("  classDef public fill:#f9f,stroke:#333,stroke-width:2px;\n");
         output.push_str("  classDef internal fill:#e1f5fe,stroke:#01579b,stroke-width:1px;\n"); // Light Blue
         output.push_str("  classDef external fill:#ffe0b2,stroke:#e65100,stroke-width:1px,stroke-dasharray: 5 5;\n"); // Orange, dashed
         output.push_str("  classDef decision fill:#fff9c4,stroke:#fbc02d,stroke-width:1px,shape:rhombus;\n"); // Yellow Diamond
+        output.push_str("  classDef phase fill:#eeeeee,stroke:#616161,stroke-width:1px;\n"); // Grey, comment phases
+        output.push_str("  classDef validation fill:#e8f5e9,stroke:#2e7d32,stroke-width:1px;\n"); // Green, preconditions
+        output.push_str("  classDef statement fill:#ffffff,stroke:#9e9e9e,stroke-width:1px;\n"); // Plain statements
 
         output
     }
@@ -262,6 +430,13 @@ struct FlowGenerator<'a> {
     graph: &'a CallGraph,
     output: &'a mut String,
     node_counter: usize,
+    /// Method name -> id of its start node.
+    start_ids: HashMap<String, String>,
+    /// (node id, callee) for every internal call node.
+    call_sites: Vec<(String, String)>,
+    phase_comments: bool,
+    validators: &'a [String],
+    granularity: Granularity,
 }
 
 impl<'a> FlowGenerator<'a> {
@@ -276,6 +451,7 @@ impl<'a> FlowGenerator<'a> {
         
         let start_id = self.next_id();
         self.output.push_str(&format!("    {}([\"{}\"]):::public\n", start_id, method_name));
+        self.start_ids.insert(method_name.to_string(), start_id.clone());
 
         if let Some(body) = method_node.child_by_field_name("body") {
             let end_nodes = self.traverse_block(body, vec![start_id]);
@@ -292,9 +468,22 @@ impl<'a> FlowGenerator<'a> {
     fn traverse_block(&mut self, block_node: Node, mut prev_ids: Vec<String>) -> Vec<String> {
         let mut cursor = block_node.walk();
         let children: Vec<Node> = block_node.children(&mut cursor).collect();
+        let mut phase: Vec<String> = Vec::new();
+        let mut phase_offset = 0;
         
         for child in children {
              if !child.is_named() { continue; }
+             if self.phase_comments && Self::is_leading_line_comment(child, self.source) {
+                 if phase.is_empty() {
+                     phase_offset = child.start_byte();
+                 }
+                 phase.push(self.source[child.byte_range()].trim_start_matches('/').trim().to_string());
+                 continue;
+             }
+             if !phase.is_empty() {
+                 prev_ids = self.emit_phase(&phase.join(" "), phase_offset, prev_ids);
+                 phase.clear();
+             }
              let next_ids = self.dispatch_node(child, prev_ids.clone(), None);
              if next_ids != prev_ids {
                  prev_ids = next_ids;
@@ -303,6 +492,74 @@ impl<'a> FlowGenerator<'a> {
         prev_ids
     }
 
+    /// A `//` comment on its own line (not trailing a statement).
+    fn is_leading_line_comment(node: Node, source: &str) -> bool {
+        let is_line_comment = matches!(node.kind(), "line_comment" | "comment") && source[node.byte_range()].starts_with("//");
+        is_line_comment && node.prev_sibling().map_or(true, |p| p.end_position().row < node.start_position().row)
+    }
+
+    /// Node class for a call: `validation` for precondition checks, else external/internal.
+    fn call_style(&self, is_external: bool, raw_text: &str) -> &'static str {
+        let qualified: String = raw_text.split('(').next().unwrap_or("").chars().filter(|c| !c.is_whitespace()).collect();
+        let is_validation = VALIDATION_CALLS.iter().any(|p| matches_call(p, &qualified))
+            || self.validators.iter().any(|p| matches_call(p, &qualified));
+        if is_validation {
+            "validation"
+        } else if is_external {
+            "external"
+        } else {
+            "internal"
+        }
+    }
+
+    fn shows_statement(&self, node: Node) -> bool {
+        match self.granularity {
+            Granularity::Calls => false,
+            Granularity::All => node.kind() != "return_statement",
+            Granularity::Smart => match node.kind() {
+                "expression_statement" => node.named_child(0).map_or(false, |e| {
+                    e.kind() == "assignment_expression" || e.kind() == "update_expression"
+                }),
+                "local_variable_declaration" => {
+                    let mut cursor = node.walk();
+                    let has_value = node.children_by_field_name("declarator", &mut cursor).any(|d| d.child_by_field_name("value").is_some());
+                    has_value
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn emit_statement(&mut self, node: Node, prev_ids: Vec<String>, label: Option<String>) -> Vec<String> {
+        let text = self.source[node.byte_range()].split_whitespace().collect::<Vec<_>>().join(" ").replace('"', "'");
+        let text = if text.chars().count() > STATEMENT_LABEL_MAX {
+            format!("{}...", text.chars().take(STATEMENT_LABEL_MAX).collect::<String>())
+        } else {
+            text
+        };
+        let node_id = self.next_id();
+        self.output.push_str(&format!("    {}[\"{}\"]:::statement\n", node_id, text));
+        self.output.push_str(&format!("    click {} call onNodeClick(\"offset-{}\") \"Scroll to source\"\n", node_id, node.start_byte()));
+        let arrow = match &label {
+            Some(l) => format!("-->|{}|", l),
+            None => "-->".to_string(),
+        };
+        for prev in &prev_ids {
+            self.output.push_str(&format!("    {} {} {}\n", prev, arrow, node_id));
+        }
+        vec![node_id]
+    }
+
+    fn emit_phase(&mut self, text: &str, offset: usize, prev_ids: Vec<String>) -> Vec<String> {
+        let node_id = self.next_id();
+        self.output.push_str(&format!("    {}[/\"{}\"/]:::phase\n", node_id, text.replace('"', "'")));
+        self.output.push_str(&format!("    click {} call onNodeClick(\"offset-{}\") \"Scroll to source\"\n", node_id, offset));
+        for prev in &prev_ids {
+            self.output.push_str(&format!("    {} --> {}\n", prev, node_id));
+        }
+        vec![node_id]
+    }
+
     fn traverse_node_with_label(&mut self, node: Node, prev_ids: Vec<String>, label: Option<String>) -> Vec<String> {
         let mut current_ids = prev_ids;
         let mut current_label = label;
@@ -353,6 +610,9 @@ impl<'a> FlowGenerator<'a> {
         let is_return = node.kind() == "return_statement";
         
         if calls.is_empty() && !is_return {
+            if self.shows_statement(node) {
+                return self.emit_statement(node, prev_ids, label);
+            }
             return prev_ids;
         }
 
@@ -362,10 +622,13 @@ impl<'a> FlowGenerator<'a> {
         for (name, is_external, raw_text, offset) in calls {
              let node_id = self.next_id();
              let text_label = if is_external { format!("External: {}", raw_text) } else { name.clone() };
-             let style = if is_external { "external" } else { "internal" };
+             let style = self.call_style(is_external, &raw_text);
              
              let safe_label = text_label.replace('"', "'");
              self.output.push_str(&format!("    {}[\"{}\"]:::{}\n", node_id, safe_label, style));
+             if !is_external {
+                 self.call_sites.push((node_id.clone(), name.clone()));
+             }
              
              // Add click action
              self.output.push_str(&format!("    click {} call onNodeClick(\"offset-{}\") \"Scroll to source\"\n", node_id, offset));
@@ -413,9 +676,12 @@ impl<'a> FlowGenerator<'a> {
         for (name, is_external, raw_text, offset) in cond_calls {
              let node_id = self.next_id();
              let text_label = if is_external { format!("External: {}", raw_text) } else { name.clone() };
-             let style = if is_external { "external" } else { "internal" };
+             let style = self.call_style(is_external, &raw_text);
              let safe_label = text_label.replace('"', "'");
              self.output.push_str(&format!("    {}[\"{}\"]:::{}\n", node_id, safe_label, style));
+             if !is_external {
+                 self.call_sites.push((node_id.clone(), name.clone()));
+             }
              
              // Add click action
              self.output.push_str(&format!("    click {} call onNodeClick(\"offset-{}\") \"Scroll to source\"\n", node_id, offset));
@@ -708,4 +974,150 @@ mod tests {
         assert!(mermaid_private.contains("([\"privateMethod\"])"));
         assert!(!mermaid_private.contains("([\"publicMethod\"])"));
     }
+
+    #[test]
+    fn test_follow_calls_from_entry() {
+        let source = r#"
+        class Checkout {
+            public void submit() {
+                validate();
+                save();
+            }
+            private void validate() {
+                normalize();
+            }
+            private void normalize() {}
+            private void save() {}
+            public void unrelated() {}
+        }
+        "#;
+        let options = MermaidOptions { method_name: Some("submit".to_string()), follow_calls: true, ..Default::default() };
+        let mermaid = JavaParser::generate_with_options(source, &options).unwrap();
+
+        for method in ["submit", "validate", "save", "normalize"] {
+            assert!(mermaid.contains(&format!("  subgraph {}\n", method)));
+        }
+        assert!(!mermaid.contains("unrelated"));
+        // validate call node (N2) in submit links to validate's start node
+        assert!(mermaid.contains("    N2[\"validate\"]:::internal\n"));
+        let validate_start = mermaid.lines().find(|l| l.contains("([\"validate\"])")).unwrap().trim().split('(').next().unwrap().to_string();
+        assert!(mermaid.contains(&format!("  N2 -.-> {}\n", validate_start)));
+    }
+
+    #[test]
+    fn test_exclude_and_root_at_tests() {
+        let source = r#"
+        class OrderServiceTest {
+            @Test
+            public void placesOrder() {
+                givenStock();
+            }
+            @org.junit.jupiter.api.Test
+            public void rejectsEmpty() {}
+            private void givenStock() {
+                seed();
+            }
+            private void seed() {}
+        }
+        class OrderService {
+            public void place() {}
+        }
+        "#;
+        let graph = JavaParser::parse(source).expect("Parse failed");
+        assert_eq!(graph.nodes["placesOrder"].annotations, vec!["Test"]);
+        assert_eq!(graph.nodes["rejectsEmpty"].annotations, vec!["Test"]);
+        assert_eq!(graph.nodes["place"].class_name, "OrderService");
+        assert!(!graph.nodes["place"].is_test());
+
+        let options = MermaidOptions { exclude_tests: true, ..Default::default() };
+        let mermaid = JavaParser::generate_with_options(source, &options).unwrap();
+        assert!(mermaid.contains("([\"place\"])"));
+        assert!(!mermaid.contains("([\"placesOrder\"])"));
+
+        let options = MermaidOptions { test_method: Some("placesOrder".to_string()), ..Default::default() };
+        let mermaid = JavaParser::generate_with_options(source, &options).unwrap();
+        for method in ["placesOrder", "givenStock", "seed"] {
+            assert!(mermaid.contains(&format!("  subgraph {}\n", method)));
+        }
+        assert!(!mermaid.contains("rejectsEmpty"));
+        assert!(!mermaid.contains("([\"place\"])"));
+    }
+
+    #[test]
+    fn test_phase_comments() {
+        let source = r#"
+        class Importer {
+            public void run() {
+                // 1. validate input
+                // (rejects empty files)
+                check();
+                // 2. persist
+                save(); // trailing note
+            }
+            private void check() {}
+            private void save() {}
+        }
+        "#;
+        let options = MermaidOptions { method_name: Some("run".to_string()), phase_comments: true, ..Default::default() };
+        let mermaid = JavaParser::generate_with_options(source, &options).unwrap();
+        assert!(mermaid.contains("    N2[/\"1. validate input (rejects empty files)\"/]:::phase\n"));
+        assert!(mermaid.contains("    N1 --> N2\n    N3[\"check\"]"));
+        assert!(mermaid.contains("    N4[/\"2. persist\"/]:::phase\n"));
+        assert!(!mermaid.contains("trailing note"));
+
+        let plain = JavaParser::generate_with_options(source, &MermaidOptions { method_name: Some("run".to_string()), ..Default::default() }).unwrap();
+        assert!(!plain.contains(":::phase\n"));
+    }
+
+    #[test]
+    fn test_validation_calls_styled() {
+        let source = r#"
+        class Orders {
+            public void place(Order order) {
+                Objects.requireNonNull(order, "order");
+                Assert.isTrue(order.qty > 0);
+                checkStock(order);
+                repo.save(order);
+            }
+            private void checkStock(Order order) {}
+        }
+        "#;
+        let options = MermaidOptions { method_name: Some("place".to_string()), validators: vec!["checkStock".to_string()], ..Default::default() };
+        let mermaid = JavaParser::generate_with_options(source, &options).unwrap();
+        assert!(mermaid.contains("[\"External: Objects.requireNonNull(order, 'order')\"]:::validation\n"));
+        assert!(mermaid.contains("[\"External: Assert.isTrue(order.qty > 0)\"]:::validation\n"));
+        assert!(mermaid.contains("[\"checkStock\"]:::validation\n"));
+        assert!(mermaid.contains("[\"External: repo.save(order)\"]:::external\n"));
+    }
+
+    #[test]
+    fn test_statement_granularity() {
+        let source = r#"
+        class Pricing {
+            public int total(int qty) {
+                int sum;
+                int price = qty * 100;
+                price += 5;
+                log(price);
+                return price;
+            }
+            private void log(int v) {}
+        }
+        "#;
+        let render = |granularity| {
+            let options = MermaidOptions { method_name: Some("total".to_string()), granularity, ..Default::default() };
+            JavaParser::generate_with_options(source, &options).unwrap()
+        };
+        let calls = render(Granularity::Calls);
+        assert!(!calls.contains(":::statement\n"));
+
+        let smart = render(Granularity::Smart);
+        assert!(smart.contains("[\"int price = qty * 100;\"]:::statement\n"));
+        assert!(smart.contains("[\"price += 5;\"]:::statement\n"));
+        assert!(!smart.contains("int sum;"));
+        assert!(smart.contains("[\"log\"]:::internal\n"));
+
+        let all = render(Granularity::All);
+        assert!(all.contains("[\"int sum;\"]:::statement\n"));
+    }
 }
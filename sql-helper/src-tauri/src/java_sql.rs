@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tree_sitter::{Node, Parser};
+use crate::sql_analysis::{self, Token, TokenKind};
+use crate::{schema, DbConfig};
+
+// Finds SQL embedded in Java string literals (including `"..." + var + "..."` concatenations,
+// where non-literal operands become `?`) and checks its tables/columns against a live schema.
+
+const SKIPPED_DIRS: [&str; 5] = ["target", "build", "out", "bin", "node_modules"];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct JavaSql {
+    pub sql: String,
+    /// 1-based line of the (first) literal.
+    pub line: usize,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SqlIssue {
+    pub file: String,
+    pub line: usize,
+    pub method: Option<String>,
+    pub sql: String,
+    /// "table" or "column"
+    pub kind: String,
+    pub identifier: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SqlCheckReport {
+    pub files_scanned: usize,
+    pub statements_checked: usize,
+    pub issues: Vec<SqlIssue>,
+}
+
+/// Starts with a DML keyword and has a FROM/INTO/SET/... clause, so log messages like
+/// "Select order " + id are not mistaken for SQL.
+fn looks_like_sql(text: &str) -> bool {
+    const STARTERS: [&str; 6] = ["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "WITH"];
+    const CLAUSES: [&str; 4] = ["FROM", "INTO", "SET", "USING"];
+    let tokens = sql_analysis::tokenize(text);
+    tokens.first().map_or(false, |t| STARTERS.iter().any(|k| t.is_keyword(k)))
+        && tokens.iter().any(|t| CLAUSES.iter().any(|k| t.is_keyword(k)))
+}
+
+/// Contents of a Java string literal or text block with escapes resolved.
+fn unescape_java(literal: &str) -> String {
+    let inner = if literal.starts_with("\"\"\"") {
+        literal.trim_start_matches("\"\"\"").trim_end_matches("\"\"\"")
+    } else {
+        literal.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(literal)
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn node_text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+fn is_concat(node: Node, source: &str) -> bool {
+    node.kind() == "binary_expression" && node.child_by_field_name("operator").map_or(false, |op| node_text(op, source) == "+")
+}
+
+/// Flattens a `+` chain into `out`; returns whether any operand was a string literal.
+fn concat_parts(node: Node, source: &str, out: &mut String) -> bool {
+    match node.kind() {
+        "string_literal" | "text_block" => {
+            out.push_str(&unescape_java(node_text(node, source)));
+            true
+        }
+        "parenthesized_expression" if node.named_child(0).map_or(false, |n| is_concat(n, source)) => {
+            node.named_child(0).map_or(false, |inner| concat_parts(inner, source, out))
+        }
+        _ if is_concat(node, source) => {
+            let left = node.child_by_field_name("left").map_or(false, |n| concat_parts(n, source, out));
+            let right = node.child_by_field_name("right").map_or(false, |n| concat_parts(n, source, out));
+            left || right
+        }
+        _ => {
+            out.push('?');
+            false
+        }
+    }
+}
+
+fn collect_sql(node: Node, source: &str, method: Option<&str>, found: &mut Vec<JavaSql>) {
+    let mut text = String::new();
+    let is_literal = matches!(node.kind(), "string_literal" | "text_block");
+    if (is_literal || is_concat(node, source)) && concat_parts(node, source, &mut text) && looks_like_sql(&text) {
+        found.push(JavaSql { sql: text.trim().to_string(), line: node.start_position().row + 1, method: method.map(String::from) });
+        return;
+    }
+
+    let name;
+    let method = match node.kind() {
+        "method_declaration" | "constructor_declaration" => {
+            name = node.child_by_field_name("name").map(|n| node_text(n, source).to_string());
+            name.as_deref()
+        }
+        _ => method,
+    };
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_sql(child, source, method, found);
+    }
+}
+
+pub fn extract_sql(source: &str) -> Result<Vec<JavaSql>, String> {
+    let mut parser = Parser::new();
+    parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
+    let tree = parser.parse(source, None).ok_or("Failed to parse source")?;
+    let mut found = Vec::new();
+    collect_sql(tree.root_node(), source, None, &mut found);
+    Ok(found)
+}
+
+pub fn java_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e: std::io::Error| format!("{}: {}", dir.display(), e))? {
+        let path = entry.map_err(|e: std::io::Error| e.to_string())?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                java_files(&path, out)?;
+            }
+        } else if name.ends_with(".java") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Lower-cased table name (both `table` and `schema.table`) -> lower-cased columns.
+#[derive(Default)]
+pub struct Catalog {
+    tables: HashMap<String, HashSet<String>>,
+}
+
+impl Catalog {
+    pub fn from_columns(columns: Vec<(String, String, String)>) -> Self {
+        let mut tables: HashMap<String, HashSet<String>> = HashMap::new();
+        for (schema, table, column) in columns {
+            let column = column.to_lowercase();
+            tables.entry(table.to_lowercase()).or_default().insert(column.clone());
+            tables.entry(format!("{}.{}", schema, table).to_lowercase()).or_default().insert(column);
+        }
+        Catalog { tables }
+    }
+
+    /// Columns of `db.schema.table` / `schema.table` / `table`, trying the most specific form first.
+    fn columns(&self, name: &str) -> Option<&HashSet<String>> {
+        let lower = name.to_lowercase();
+        let parts: Vec<&str> = lower.split('.').collect();
+        (0..parts.len()).find_map(|skip| self.tables.get(&parts[skip..].join(".")))
+    }
+}
+
+/// Names that are defined by the statement itself: CTEs, temp tables and table variables.
+fn is_local_table(name: &str, ctes: &[String]) -> bool {
+    name.starts_with('#') || name.starts_with('@') || ctes.iter().any(|c| c.eq_ignore_ascii_case(name))
+}
+
+fn cte_names(tokens: &[Token]) -> Vec<String> {
+    tokens.windows(3)
+        .filter(|w| w[0].is_identifier() && w[1].is_keyword("AS") && w[2].is_symbol("("))
+        .map(|w| w[0].text.clone())
+        .collect()
+}
+
+fn is_comparison_start(token: &Token) -> bool {
+    ["=", "<", ">", "!"].iter().any(|s| token.is_symbol(s))
+        || ["IN", "LIKE", "IS", "BETWEEN"].iter().any(|k| token.is_keyword(k))
+}
+
+/// (kind, identifier, message) for every unknown table or column in `sql`.
+pub fn check_statement(sql: &str, catalog: &Catalog) -> Vec<(String, String, String)> {
+    let tokens = sql_analysis::tokenize(sql);
+    let ctes = cte_names(&tokens);
+    let refs = sql_analysis::table_refs(sql);
+    let mut issues = Vec::new();
+
+    // alias or bare table name -> known columns
+    let mut scopes: HashMap<String, &HashSet<String>> = HashMap::new();
+    for r in &refs {
+        if is_local_table(&r.name, &ctes) {
+            continue;
+        }
+        match catalog.columns(&r.name) {
+            Some(columns) => {
+                let bare = r.name.rsplit('.').next().unwrap_or(&r.name).to_lowercase();
+                scopes.insert(bare, columns);
+                if let Some(alias) = &r.alias {
+                    scopes.insert(alias.to_lowercase(), columns);
+                }
+            }
+            None => {
+                if !issues.iter().any(|(_, id, _): &(String, String, String)| id.eq_ignore_ascii_case(&r.name)) {
+                    issues.push(("table".to_string(), r.name.clone(), format!("Unknown table: {}", r.name)));
+                }
+            }
+        }
+    }
+
+    let mut unknown_column = |qualifier: Option<&str>, column: &str| {
+        let shown = match qualifier {
+            Some(q) => format!("{}.{}", q, column),
+            None => column.to_string(),
+        };
+        if !issues.iter().any(|(_, id, _)| id.eq_ignore_ascii_case(&shown)) {
+            issues.push(("column".to_string(), shown.clone(), format!("Unknown column: {}", shown)));
+        }
+    };
+
+    // Qualified references: alias.column
+    for i in 0..tokens.len().saturating_sub(2) {
+        let (q, dot, col) = (&tokens[i], &tokens[i + 1], &tokens[i + 2]);
+        let starts_chain = i == 0 || !tokens[i - 1].is_symbol(".");
+        let ends_chain = !tokens.get(i + 3).map_or(false, |t| t.is_symbol(".") || t.is_symbol("("));
+        if !(q.is_identifier() && dot.is_symbol(".") && col.is_identifier() && starts_chain && ends_chain) {
+            continue;
+        }
+        if let Some(columns) = scopes.get(&q.text.to_lowercase()) {
+            if !columns.contains(&col.text.to_lowercase()) {
+                unknown_column(Some(&q.text), &col.text);
+            }
+        }
+    }
+
+    // Unqualified columns are only unambiguous with a single known table
+    let single = match refs.as_slice() {
+        [only] => catalog.columns(&only.name).filter(|_| !is_local_table(&only.name, &ctes)),
+        _ => None,
+    };
+    if let Some(columns) = single {
+        let mut candidates: Vec<&Token> = Vec::new();
+        for (i, t) in tokens.iter().enumerate() {
+            let prev_dot = i > 0 && tokens[i - 1].is_symbol(".");
+            let next = tokens.get(i + 1);
+            let bare = t.is_identifier() && !t.text.starts_with('@') && !prev_dot && !next.map_or(false, |n| n.is_symbol(".") || n.is_symbol("("));
+            if bare && next.map_or(false, is_comparison_start) && !t.is_keyword("NOT") {
+                candidates.push(t);
+            }
+        }
+        // INSERT INTO t (a, b, ...)
+        if let Some(into) = tokens.iter().position(|t| t.is_keyword("INTO")) {
+            if let Some((_, next)) = sql_analysis::read_qualified_name(&tokens, into + 1) {
+                if tokens.get(next).map_or(false, |t| t.is_symbol("(")) {
+                    candidates.extend(tokens[next + 1..].iter().take_while(|t| !t.is_symbol(")")).filter(|t| t.is_identifier()));
+                }
+            }
+        }
+        for t in candidates {
+            if t.kind == TokenKind::Word && t.text.chars().all(|c| !c.is_alphabetic()) {
+                continue;
+            }
+            if !columns.contains(&t.text.to_lowercase()) {
+                unknown_column(None, &t.text);
+            }
+        }
+    }
+    issues
+}
+
+#[tauri::command]
+pub async fn check_sql_against_schema(project_dir: String, config: DbConfig) -> Result<SqlCheckReport, String> {
+    let root = PathBuf::from(&project_dir);
+    let mut files = Vec::new();
+    java_files(&root, &mut files)?;
+    files.sort();
+    let catalog = Catalog::from_columns(schema::column_catalog(&config).await?);
+
+    let mut report = SqlCheckReport { files_scanned: files.len(), ..Default::default() };
+    for file in files {
+        let source = match fs::read_to_string(&file) {
+            Ok(s) => s,
+            Err(_) => continue, // non UTF-8 sources are skipped rather than failing the whole scan
+        };
+        let relative = file.strip_prefix(&root).unwrap_or(&file).to_string_lossy().to_string();
+        for found in extract_sql(&source)? {
+            report.statements_checked += 1;
+            for (kind, identifier, message) in check_statement(&found.sql, &catalog) {
+                report.issues.push(SqlIssue {
+                    file: relative.clone(),
+                    line: found.line,
+                    method: found.method.clone(),
+                    sql: found.sql.clone(),
+                    kind,
+                    identifier,
+                    message,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_sql_from_concatenation() {
+        let source = r#"
+class OrderDao {
+    List<Order> find(long id) {
+        String sql = "SELECT o.ID, o.NAME FROM ORDERS o "
+            + "WHERE o.ID = " + id + " AND o.STATUS = \"A\"";
+        log.info("Select order " + id);
+        return jdbc.query(sql);
+    }
+}"#;
+        let found = extract_sql(source).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].sql, "SELECT o.ID, o.NAME FROM ORDERS o WHERE o.ID = ? AND o.STATUS = \"A\"");
+        assert_eq!(found[0].line, 4);
+        assert_eq!(found[0].method.as_deref(), Some("find"));
+    }
+
+    #[test]
+    fn test_check_statement() {
+        let catalog = Catalog::from_columns(vec![
+            ("dbo".to_string(), "ORDERS".to_string(), "ID".to_string()),
+            ("dbo".to_string(), "ORDERS".to_string(), "STATUS".to_string()),
+        ]);
+        let issues = check_statement("SELECT o.ID, o.TOTAL FROM dbo.ORDERS o JOIN ITEMS i ON i.ORDER_ID = o.ID", &catalog);
+        let ids: Vec<&str> = issues.iter().map(|(_, id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["ITEMS", "o.TOTAL"]);
+
+        let issues = check_statement("UPDATE ORDERS SET STATE = ? WHERE ID = ?", &catalog);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].1, "STATE");
+        assert!(check_statement("WITH x AS (SELECT ID FROM ORDERS) SELECT * FROM x", &catalog).is_empty());
+        assert!(check_statement("INSERT INTO ORDERS (ID, STATUS) VALUES (?, ?)", &catalog).is_empty());
+    }
+}
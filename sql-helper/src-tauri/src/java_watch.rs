@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+use tauri::State;
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+use crate::diagrams::read_source;
+use crate::java_parser::{JavaParser, MermaidOptions};
+use crate::tasks::BackgroundTasks;
+
+const POLL_INTERVAL_MS: u64 = 500;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MermaidResult {
+    pub watch_id: String,
+    pub path: String,
+    pub mermaid: Option<String>,
+    pub error: Option<String>,
+    pub generated_at: String,
+}
+
+fn point_at(text: &[u8], byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let column = byte - before.iter().rposition(|&b| b == b'\n').map_or(0, |p| p + 1);
+    Point { row, column }
+}
+
+/// Single edit covering everything between the common prefix and suffix of `old` and `new`.
+fn input_edit(old: &str, new: &str) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+    let (a, b) = (old.as_bytes(), new.as_bytes());
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let max_suffix = a.len().min(b.len()) - prefix;
+    let suffix = a.iter().rev().zip(b.iter().rev()).take(max_suffix).take_while(|(x, y)| x == y).count();
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte: a.len() - suffix,
+        new_end_byte: b.len() - suffix,
+        start_position: point_at(a, prefix),
+        old_end_position: point_at(a, a.len() - suffix),
+        new_end_position: point_at(b, b.len() - suffix),
+    })
+}
+
+/// Keeps the previous tree so edits only re-parse the changed region.
+struct IncrementalParser {
+    parser: Parser,
+    tree: Option<Tree>,
+    source: String,
+}
+
+impl IncrementalParser {
+    fn new() -> Result<Self, String> {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_java::language()).map_err(|e| e.to_string())?;
+        Ok(IncrementalParser { parser, tree: None, source: String::new() })
+    }
+
+    /// Returns false when the source is unchanged.
+    fn update(&mut self, source: String) -> Result<bool, String> {
+        if self.tree.is_some() && source == self.source {
+            return Ok(false);
+        }
+        if let (Some(tree), Some(edit)) = (self.tree.as_mut(), input_edit(&self.source, &source)) {
+            tree.edit(&edit);
+        }
+        let tree = self.parser.parse(&source, self.tree.as_ref()).ok_or("Failed to parse source")?;
+        self.tree = Some(tree);
+        self.source = source;
+        Ok(true)
+    }
+
+    fn render(&self, options: &MermaidOptions) -> Option<String> {
+        self.tree.as_ref().map(|tree| JavaParser::render_tree(tree, &self.source, options))
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    Path::new(path).metadata().and_then(|m| m.modified()).ok()
+}
+
+fn result(watch_id: &str, path: &str, outcome: Result<Option<String>, String>) -> MermaidResult {
+    let (mermaid, error) = match outcome {
+        Ok(m) => (m, None),
+        Err(e) => (None, Some(e)),
+    };
+    MermaidResult {
+        watch_id: watch_id.to_string(),
+        path: path.to_string(),
+        mermaid,
+        error,
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// Emits `java-watch://diagram` with a fresh `MermaidResult` now and every time the file's
+/// contents change. Read/parse failures are emitted with `error` set and the watch keeps going.
+#[tauri::command]
+pub fn watch_java_file(window: tauri::Window, tasks: State<'_, BackgroundTasks>, path: String, options: MermaidOptions) -> Result<String, String> {
+    let mut parser = IncrementalParser::new()?;
+    parser.update(read_source(&path)?)?;
+    let watch_id = tasks.next_id("java-watch");
+    window.emit("java-watch://diagram", result(&watch_id, &path, Ok(parser.render(&options)))).ok();
+
+    let id = watch_id.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut last_modified = modified(&path);
+        let mut ticker = tokio::time::interval(Duration::from_millis(POLL_INTERVAL_MS));
+        loop {
+            ticker.tick().await;
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            let outcome = read_source(&path).and_then(|source| parser.update(source));
+            match outcome {
+                Ok(true) => { window.emit("java-watch://diagram", result(&id, &path, Ok(parser.render(&options)))).ok(); }
+                Ok(false) => {}
+                Err(e) => { window.emit("java-watch://diagram", result(&id, &path, Err(e))).ok(); }
+            }
+        }
+    });
+    tasks.register(watch_id.clone(), handle);
+    Ok(watch_id)
+}
+
+#[tauri::command]
+pub fn unwatch_java_file(tasks: State<'_, BackgroundTasks>, watch_id: String) -> bool {
+    tasks.cancel(&watch_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_update_matches_full_parse() {
+        let before = "class A {\n  public void run() {\n    step1();\n  }\n  void step1() {}\n}\n";
+        let after = "class A {\n  public void run() {\n    step1();\n    step2();\n  }\n  void step1() {}\n  void step2() {}\n}\n";
+        let mut parser = IncrementalParser::new().unwrap();
+        assert!(parser.update(before.to_string()).unwrap());
+        assert!(!parser.update(before.to_string()).unwrap());
+        assert!(parser.update(after.to_string()).unwrap());
+        let options = MermaidOptions::default();
+        assert_eq!(parser.render(&options).unwrap(), JavaParser::generate_with_options(after, &options).unwrap());
+    }
+
+    #[test]
+    fn test_input_edit() {
+        let edit = input_edit("ab\ncd", "ab\nxcd").unwrap();
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (3, 3, 4));
+        assert_eq!(edit.start_position, Point { row: 1, column: 0 });
+        assert!(input_edit("same", "same").is_none());
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use rusqlite::types::Value;
+use tauri::State;
+use crate::result_ops::NULL_CELL;
+use crate::result_store::{ResultHandle, ResultStore};
+use crate::sql_analysis::{tokenize, TokenKind};
+use crate::QueryResult;
+
+/// Named results live in the `ResultStore` under `@name`, so they can also be paged/released.
+fn named_handle(name: &str) -> String {
+    format!("@{}", name.trim_start_matches('@'))
+}
+
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// SQLite rejects duplicate column names, which joined results can contain.
+pub fn unique_columns(columns: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    columns.iter().map(|c| {
+        let mut name = c.clone();
+        let mut n = 2;
+        while !seen.insert(name.to_lowercase()) {
+            name = format!("{}_{}", c, n);
+            n += 1;
+        }
+        name
+    }).collect()
+}
+
+fn load_table(conn: &rusqlite::Connection, table: &str, result: &QueryResult) -> Result<(), String> {
+    let columns = unique_columns(&result.columns);
+    let defs: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+    conn.execute(&format!("CREATE TABLE {} ({})", quote(table), defs.join(", ")), [])
+        .map_err(|e| e.to_string())?;
+
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut stmt = conn.prepare(&format!("INSERT INTO {} VALUES ({})", quote(table), placeholders))
+        .map_err(|e| e.to_string())?;
+    for row in &result.rows {
+        let values: Vec<Value> = (0..columns.len()).map(|i| match row.get(i) {
+            Some(v) if v != NULL_CELL => Value::Text(v.clone()),
+            _ => Value::Null,
+        }).collect();
+        stmt.execute(rusqlite::params_from_iter(values)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn value_to_cell(value: Value) -> String {
+    match value {
+        Value::Null => NULL_CELL.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(s) => s,
+        Value::Blob(b) => format!("<Binary: {} bytes>", b.len()),
+    }
+}
+
+/// Runs `sql` against an in-memory SQLite database holding every `@name` result it references.
+/// Cells are loaded as text, so numeric comparisons need an explicit CAST.
+pub fn run_local(store: &ResultStore, sql: &str) -> Result<QueryResult, String> {
+    let conn = rusqlite::Connection::open_in_memory().map_err(|e| e.to_string())?;
+
+    // Replace @name references (outside strings/comments) with quoted table names
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut last = 0;
+    let mut loaded = HashSet::new();
+    for token in tokenize(sql) {
+        if token.kind != TokenKind::Word || !token.text.starts_with('@') || token.text.len() < 2 {
+            continue;
+        }
+        let name = &token.text[1..];
+        if loaded.insert(name.to_lowercase()) {
+            let result = store.get(&named_handle(name))
+                .map_err(|_| format!("Named result not found: @{}", name))?;
+            load_table(&conn, name, &result)?;
+        }
+        rewritten.push_str(&sql[last..token.start]);
+        rewritten.push_str(&quote(name));
+        last = token.end;
+    }
+    rewritten.push_str(&sql[last..]);
+    query_rows(&conn, &rewritten)
+}
+
+/// Runs one statement and collects its rows as grid cells.
+pub fn query_rows(conn: &rusqlite::Connection, sql: &str) -> Result<QueryResult, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows = Vec::new();
+    let mut query_rows = stmt.query([]).map_err(|e| e.to_string())?;
+    while let Some(row) = query_rows.next().map_err(|e| e.to_string())? {
+        let mut cells = Vec::with_capacity(columns.len());
+        for i in 0..columns.len() {
+            cells.push(value_to_cell(row.get::<_, Value>(i).map_err(|e| e.to_string())?));
+        }
+        rows.push(cells);
+    }
+    Ok(QueryResult { columns, rows, ..Default::default() })
+}
+
+/// Registers a result under `@name` for later local queries, replacing any previous one.
+#[tauri::command]
+pub fn register_named_result(store: State<'_, ResultStore>, name: String, result: Option<QueryResult>, handle: Option<String>) -> Result<ResultHandle, String> {
+    let name = name.trim_start_matches('@');
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("Invalid result name: {}", name));
+    }
+    let result = match (result, handle) {
+        (Some(r), _) => r,
+        (None, Some(h)) => (*store.get(&h)?).clone(),
+        (None, None) => return Err("Either result or handle is required".to_string()),
+    };
+    Ok(store.insert_as(&named_handle(name), result))
+}
+
+#[tauri::command]
+pub fn run_local_query(store: State<'_, ResultStore>, sql: String) -> Result<QueryResult, String> {
+    run_local(&store, &sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_query_on_named_result() {
+        let store = ResultStore::default();
+        store.insert_as("@prev", QueryResult {
+            columns: vec!["ID".to_string(), "NAME".to_string()],
+            rows: vec![
+                vec!["1".to_string(), "a".to_string()],
+                vec!["2".to_string(), NULL_CELL.to_string()],
+            ],
+            ..Default::default()
+        });
+        let r = run_local(&store, "SELECT ID FROM @prev WHERE NAME IS NULL AND '@prev' <> ''").unwrap();
+        assert_eq!(r.columns, vec!["ID"]);
+        assert_eq!(r.rows, vec![vec!["2".to_string()]]);
+        assert!(run_local(&store, "SELECT * FROM @missing").is_err());
+    }
+}
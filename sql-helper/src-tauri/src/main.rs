@@ -5,28 +5,124 @@ use std::fs::{File, self};
 use std::io::{Read, Write};
 use encoding_rs::SHIFT_JIS;
 use serde::{Deserialize, Serialize};
-use sqlx::{Column, Row as SqlxRow, Connection};
-use tiberius::{Client, Config, AuthMethod, QueryItem, EncryptionLevel};
-use tokio::net::TcpStream;
-use tokio_util::compat::TokioAsyncWriteCompatExt;
-use futures::StreamExt;
-use chrono;
+use tauri::Manager;
+mod backups;
+mod class_diagram;
+mod code_snippet;
+mod codepage;
+mod completion;
+mod conn_string;
+mod db;
+mod diagnostics;
+mod diagrams;
+mod dialect;
+mod exec_queue;
+mod export;
+mod failover;
+mod filter_expr;
+mod first_run;
+mod health;
+mod history;
+mod import;
+mod java_deps;
 mod java_parser;
+mod java_sql;
+mod java_watch;
+mod local_query;
+mod oracle_db;
+mod pg_notify;
+mod procedures;
+mod profile;
+mod profiles;
+mod query_guard;
+mod query_page;
+mod query_params;
+mod query_plan;
+mod query_stream;
+mod result_diff;
+mod result_ops;
+mod result_search;
+mod result_store;
+mod row_edit;
+mod row_guard;
+mod scheduler;
+mod schema;
+mod scratchpad;
+mod script;
+mod secrets;
+mod session_recorder;
+mod sessions;
+mod shutdown;
+mod snapshots;
+mod sorting;
+mod sql_analysis;
+mod sql_syntax;
+mod ssh_tunnel;
+mod storage;
+mod table_watch;
+mod tasks;
+mod templates;
+mod text_normalize;
+mod transfer;
+mod workspace;
 use java_parser::JavaParser;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct DbConfig {
     pub id: String,
     pub name: String,
-    pub db_type: String, // "mssql", "mysql", "postgres"
+    pub db_type: String, // "mssql", "mysql", "postgres", "oracle"
     pub host: String,
     pub port: u16,
     pub user: String,
+    /// Empty in the settings file when the password is in the credential store (see `secrets`).
     pub password: String,
     pub database: String,
     pub trust_server_certificate: Option<bool>,
     pub encrypt: Option<bool>,
     pub verified: Option<bool>,
+    pub application_name: Option<String>,
+    pub connect_timeout_secs: Option<u64>,
+    /// Abort a query that has not finished after this many seconds (e.g. blocked on a lock). None/0 = no limit.
+    pub query_timeout_secs: Option<u64>,
+    pub readonly_intent: Option<bool>,
+    pub fallback_database: Option<String>,
+    /// Run statements for this connection one at a time, in priority order.
+    pub use_queue: Option<bool>,
+    /// Oracle: connect by SID. Otherwise the service name (falling back to `database`) is used.
+    pub oracle_sid: Option<String>,
+    pub oracle_service_name: Option<String>,
+    /// MSSQL: "sql" (default), "aad_token" (uses `access_token`) or "azure_cli".
+    pub auth_method: Option<String>,
+    pub access_token: Option<String>,
+    /// Jump host (`host` or `host:port`). When set, the DB host/port are reached through an SSH port-forward.
+    pub ssh_host: Option<String>,
+    pub ssh_user: Option<String>,
+    /// Path to the private key used for the jump host.
+    pub ssh_key: Option<String>,
+    /// Pinned jump host key fingerprint (`SHA256:...`, as `ssh-keygen -lf` prints it). When unset
+    /// the key must be in `~/.ssh/known_hosts`.
+    pub ssh_host_key: Option<String>,
+    /// Production database: DELETE/UPDATE without WHERE, TRUNCATE and DROP need an explicit
+    /// confirm (see `query_guard`).
+    pub production: Option<bool>,
+    /// Only SELECT/EXPLAIN-style reads are accepted; imports and row edits are refused too.
+    pub read_only: Option<bool>,
+    /// Credential store entry holding the password.
+    pub password_ref: Option<String>,
+    /// Schema unqualified names resolve to, set on connect: `search_path` (Postgres), `USE`
+    /// (MySQL), `CURRENT_SCHEMA` (Oracle). MSSQL always uses the database user's default schema.
+    pub default_schema: Option<String>,
+    /// Statements run on every new connection, e.g. `SET LANGUAGE japanese`.
+    pub init_sql: Option<String>,
+    /// Hosts tried in order when `host` does not accept a login (mirror partner, read replicas):
+    /// `host`, `host:port`, `host,port` or `host\INSTANCE`. Without a port the connection's port is used.
+    pub failover_hosts: Option<Vec<String>>,
+    /// Connect timeout for each host when `failover_hosts` is set; None uses `connect_timeout_secs`.
+    pub failover_timeout_secs: Option<u64>,
+    /// MSSQL: encoding of the bytes stored in varchar/char/text columns when it differs from the
+    /// collation's, e.g. "shift_jis" under a Latin1 collation (see `codepage`).
+    pub result_encoding: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -34,12 +130,107 @@ pub struct AppSettings {
     pub connections: Vec<DbConfig>,
     pub global_log_path: Option<String>,
     pub translate_file_path: Option<String>,
+    /// Extra precondition calls highlighted in flow diagrams (see `MermaidOptions::validators`).
+    pub validation_calls: Option<Vec<String>>,
+    /// Startup cleanup of temp/spill/crash files; None uses `CleanupPolicy::default()`.
+    pub cleanup_policy: Option<workspace::CleanupPolicy>,
+    /// Usage profile for this workspace; None means `Profile::Full`.
+    pub profile: Option<profiles::Profile>,
+    /// Daily backups to keep; 0 turns the automatic backup off. None keeps 7.
+    pub backup_retention: Option<usize>,
+    /// Header fill for Excel exports, `#RRGGBB`.
+    pub excel_header_color: Option<String>,
+    /// chrono format for datetime cells; None uses `db::DEFAULT_DATETIME_FORMAT`.
+    pub datetime_format: Option<String>,
+    /// Rows `execute_query` returns before stopping with a continuation; 0 turns the guard off.
+    /// None uses `row_guard::DEFAULT_MAX_ROWS`.
+    pub max_rows: Option<usize>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct QueryResult {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<String>>,
+    /// Set for simple single-table SELECTs whose key columns are all present in the result.
+    #[serde(default)]
+    pub source_table: Option<String>,
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    #[serde(default)]
+    pub editable: bool,
+    /// Result sets after the first one, for batches with several SELECTs.
+    #[serde(default)]
+    pub more_results: Vec<ResultSet>,
+    /// Rows inserted/updated/deleted when the statement returns no rows.
+    #[serde(default)]
+    pub affected_rows: Option<u64>,
+    /// See `sql_analysis::statement_kind`.
+    #[serde(default)]
+    pub statement_kind: Option<String>,
+    /// Row cap injected by `execute_query`'s `row_limit`, when the query was rewritten.
+    #[serde(default)]
+    pub row_limit_applied: Option<u64>,
+    /// Lowercase database type of each column ("int", "nvarchar", "numeric", ...); empty for
+    /// results built on this side rather than read from the server.
+    #[serde(default)]
+    pub column_types: Vec<String>,
+    /// `(row, column)` of cells cut by `result_ops::truncate_long_cells`; see `get_full_cell`.
+    #[serde(default)]
+    pub truncated_cells: Vec<(usize, usize)>,
+    /// Display width of each column's widest value, header included (see
+    /// `result_ops::display_width`); filled for results shown in the grid.
+    #[serde(default)]
+    pub display_widths: Vec<usize>,
+    /// The `max_rows` guard stopped the result early; `continuation` fetches the next chunk
+    /// (see `row_guard::fetch_more_rows`).
+    #[serde(default)]
+    pub partial: bool,
+    #[serde(default)]
+    pub continuation: Option<String>,
+    /// Timing and size of the fetch; None for results built on this side.
+    #[serde(default)]
+    pub stats: Option<ExecutionStats>,
+    /// Host that ran the query, for connections with `failover_hosts`.
+    #[serde(default)]
+    pub served_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ExecutionStats {
+    /// From sending the query to its last row; connecting is not counted except for statements
+    /// that return no rows.
+    pub round_trip_ms: u64,
+    /// Including the connection; equal to `round_trip_ms` when the result was fetched on an
+    /// open session.
+    pub total_ms: u64,
+    pub rows: u64,
+    /// Size of the returned cells as text; a rough measure of the data received, not the bytes
+    /// on the wire.
+    pub bytes: u64,
+    /// One entry per result set, in order.
+    pub result_sets: Vec<ResultSetStats>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResultSetStats {
+    pub rows: u64,
+    pub bytes: u64,
+    /// Since the query was sent; None when the set has no rows.
+    pub first_row_ms: Option<u64>,
+    /// Since the query was sent, up to the set's last row.
+    pub elapsed_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    #[serde(default)]
+    pub column_types: Vec<String>,
+    #[serde(default)]
+    pub truncated_cells: Vec<(usize, usize)>,
+    #[serde(default)]
+    pub display_widths: Vec<usize>,
 }
 
 #[tauri::command]
@@ -77,9 +268,12 @@ fn parse_java_graph(source: String) -> Result<java_parser::CallGraph, String> {
 }
 
 #[tauri::command]
-fn generate_mermaid_graph(source: String, method_name: Option<String>) -> Result<String, String> {
-    let graph = JavaParser::parse(&source)?;
-    Ok(JavaParser::generate_mermaid(&graph, &source, method_name))
+fn generate_mermaid_graph(source: String, method_name: Option<String>, options: Option<java_parser::MermaidOptions>) -> Result<String, String> {
+    let mut options = options.unwrap_or_default();
+    if method_name.is_some() {
+        options.method_name = method_name;
+    }
+    JavaParser::generate_with_options(&source, &options)
 }
 
 #[tauri::command]
@@ -101,170 +295,90 @@ fn read_log_file(path: String) -> Result<String, String> {
     Ok(decoded.to_string())
 }
 
-fn build_mssql_config(config: &DbConfig) -> Result<Config, String> {
-    let mut c = Config::new();
-    c.host(&config.host);
-    c.port(config.port);
-    c.database(&config.database);
-    let mut tiberius_config = c;
-
-    // Apply credentials from separate fields if provided (overrides URL if conflict)
-    if !config.user.trim().is_empty() {
-        tiberius_config.authentication(AuthMethod::sql_server(&config.user, &config.password));
-    }
-
-    // Handle Encryption
-    if let Some(encrypt) = config.encrypt {
-        if encrypt {
-            tiberius_config.encryption(EncryptionLevel::Required);
-        } else {
-            tiberius_config.encryption(EncryptionLevel::NotSupported);
-        }
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn execute_query(window: tauri::Window, ops: tauri::State<'_, shutdown::ActiveOperations>, profile: tauri::State<'_, profiles::ActiveProfile>, recorder: tauri::State<'_, session_recorder::SessionRecorder>, history: tauri::State<'_, history::QueryHistory>, queue: tauri::State<'_, exec_queue::ExecQueue>, continuations: tauri::State<'_, row_guard::Continuations>, results: tauri::State<'_, result_store::ResultStore>, config: DbConfig, query: String, ticket: Option<String>, priority: Option<i32>, normalize: Option<text_normalize::NormalizeOptions>, row_filter: Option<String>, row_limit: Option<u64>, max_cell_chars: Option<usize>, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let _op = ops.begin();
+    let _slot = if config.use_queue.unwrap_or(false) {
+        let ticket = ticket.unwrap_or_else(|| format!("{}-{}", config.id, chrono::Local::now().timestamp_millis()));
+        Some(queue.acquire(&window, &config.id, &ticket, priority.unwrap_or(exec_queue::DEFAULT_PRIORITY)).await)
     } else {
-        tiberius_config.encryption(EncryptionLevel::Off);
-    }
-
-    // Handle Trust Certificate
-    if config.trust_server_certificate.unwrap_or(true) {
-        tiberius_config.trust_cert();
-    }
-
-    Ok(tiberius_config)
-}
-
-fn build_db_url(config: &DbConfig) -> Result<String, String> {
-    let user_enc = urlencoding::encode(&config.user);
-    let pass_enc = urlencoding::encode(&config.password);
-    
-    let mut url = match config.db_type.as_str() {
-        "mssql" => format!("mssql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
-        "mysql" => format!("mysql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
-        "postgres" => format!("postgresql://{}:{}@{}:{}/{}", user_enc, pass_enc, config.host, config.port, urlencoding::encode(&config.database)),
-        _ => return Err("Unsupported database type".to_string()),
+        None
     };
-
-    if config.db_type == "mssql" {
-        let mut params = Vec::new();
-        if config.trust_server_certificate.unwrap_or(true) {
-            params.push("trustServerCertificate=true");
-        }
-        if let Some(enc) = config.encrypt {
-            params.push(if enc { "encrypt=true" } else { "encrypt=false" });
-        }
-        if !params.is_empty() {
-            url = format!("{}?{}", url, params.join("&"));
-        }
+    // Only the interactive editor passes `row_limit`; exports and scripts run the SQL as written
+    let limited = row_limit.filter(|n| *n > 0).and_then(|n| dialect::limit_query(&config.db_type, &query, n).map(|q| (n, q)));
+    let executed = limited.as_ref().map_or(query.as_str(), |(_, q)| q.as_str());
+    let options = db::FetchOptions { row_filter: row_filter.clone(), ..Default::default() };
+    let max_rows = row_guard::max_rows(&window.app_handle());
+    let started = std::time::Instant::now();
+    let mut served = failover::connect(&config).await?;
+    let fetched = if max_rows == 0 {
+        served.run_query_with(executed, &options).await.map(|r| (r, false))
+    } else {
+        let on_served = &mut served;
+        db::fetch_window_with(executed, &options, 0, max_rows, |options| async move { on_served.run_query_with(executed, &options).await }).await
+    };
+    let has_more = matches!(fetched, Ok((_, true)));
+    let result = fetched.map(|(r, _)| r);
+    recorder.record(&config, executed, Vec::new(), result.as_ref().err().cloned());
+    history.record(&config, executed, started.elapsed(), &result);
+    let mut result = result?;
+    result.row_limit_applied = limited.as_ref().map(|(n, _)| *n);
+    result.served_by = config.failover_hosts.as_ref().filter(|h| !h.is_empty()).map(|_| failover::host_label(&served.host));
+    detect_editability(&served.target, &query, &mut result).await;
+    if let Some(options) = &normalize {
+        text_normalize::normalize_result(&mut result, options);
     }
-
-    Ok(url)
+    // Kept for `filter_result`/`search_result`, so large grids are filtered here instead of in JS.
+    // Stored before long cells are cut, so matches past the cut are still found
+    results.insert_as(result_store::LATEST_HANDLE, result.clone());
+    let max_cell_chars = max_cell_chars.unwrap_or(result_ops::DEFAULT_MAX_CELL_CHARS);
+    result_ops::truncate_long_cells(&mut result, max_cell_chars);
+    result_ops::set_display_widths(&mut result);
+    if has_more {
+        row_guard::mark_partial(&continuations, &mut result, &served.host, executed, row_filter, normalize, max_cell_chars);
+    }
+    Ok(result)
 }
 
+/// Untruncated value of a cell listed in `truncated_cells`, by running the query again with the
+/// same `row_filter`. Session-only objects such as #temp tables are not visible from here.
 #[tauri::command]
-async fn execute_query(config: DbConfig, query: String) -> Result<QueryResult, String> {
-    if config.db_type == "mssql" {
-        let tiberius_config = build_mssql_config(&config)?;
-        
-        let tcp = TcpStream::connect(tiberius_config.get_addr()).await.map_err(|e: std::io::Error| format!("Lỗi kết nối mạng (TCP): {}", e))?;
-        tcp.set_nodelay(true).map_err(|e: std::io::Error| e.to_string())?;
-
-        let mut client = Client::connect(tiberius_config, tcp.compat_write()).await.map_err(|e: tiberius::error::Error| format!("Lỗi đăng nhập Database: {}", e))?;
-        
-        // Execute query
-        let mut results = client.query(query, &[]).await.map_err(|e: tiberius::error::Error| e.to_string())?;
-        
-        let mut columns = Vec::new();
-        let mut rows = Vec::new();
-        let mut first_row = true;
-
-        while let Some(item) = results.next().await {
-            match item.map_err(|e: tiberius::error::Error| e.to_string())? {
-                QueryItem::Row(row) => {
-                    if first_row {
-                        for col in row.columns() {
-                            columns.push(col.name().to_string());
-                        }
-                        first_row = false;
-                    }
-
-                    let mut row_data = Vec::new();
-                    for i in 0..columns.len() {
-                        let val: String = match row.try_get::<&str, usize>(i) {
-                            Ok(Some(s)) => s.trim_end().to_string(),
-                            _ => match row.try_get::<i64, usize>(i) {
-                                Ok(Some(n)) => n.to_string(),
-                                _ => match row.try_get::<i32, usize>(i) {
-                                    Ok(Some(n)) => n.to_string(),
-                                    _ => match row.try_get::<f64, usize>(i) {
-                                        Ok(Some(f)) => f.to_string(),
-                                        _ => match row.try_get::<bool, usize>(i) {
-                                            Ok(Some(b)) => b.to_string(),
-                                            _ => match row.try_get::<chrono::NaiveDateTime, usize>(i) {
-                                                Ok(Some(dt)) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-                                                _ => "[NULL]".to_string()
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        };
-                        row_data.push(val);
-                    }
-                    rows.push(row_data);
-                },
-                _ => {}
-            }
-        }
-
-        return Ok(QueryResult { columns, rows });
-    }
-
-    // Existing SQLX logic for MySQL/Postgres
-    let url = build_db_url(&config)?;
-    let mut columns = Vec::new();
-    let mut rows = Vec::new();
-
-    let mut conn = sqlx::AnyConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())?;
-    let results = sqlx::query(&query).fetch_all(&mut conn).await.map_err(|e: sqlx::Error| e.to_string())?;
-
-    if !results.is_empty() {
-        for col in results[0].columns() {
-            columns.push(col.name().to_string());
-        }
-
-        for row in results {
-            let mut row_data = Vec::new();
-            for i in 0..columns.len() {
-                let val: String = row.try_get::<Option<String>, usize>(i).map(|s| s.unwrap_or_else(|| "[NULL]".to_string())).map(|s| s.trim_end().to_string())
-                    .or_else(|_| row.try_get::<Option<i64>, usize>(i).map(|v| v.map(|n| n.to_string()).unwrap_or_else(|| "[NULL]".to_string())))
-                    .or_else(|_| row.try_get::<Option<i32>, usize>(i).map(|v| v.map(|n| n.to_string()).unwrap_or_else(|| "[NULL]".to_string())))
-                    .or_else(|_| row.try_get::<Option<f64>, usize>(i).map(|v| v.map(|n| n.to_string()).unwrap_or_else(|| "[NULL]".to_string())))
-                    .or_else(|_| row.try_get::<Option<bool>, usize>(i).map(|v| v.map(|b| b.to_string()).unwrap_or_else(|| "[NULL]".to_string())))
-                    .unwrap_or_else(|_| "???".to_string());
-                row_data.push(val);
-            }
-            rows.push(row_data);
-        }
-    }
+async fn get_full_cell(profile: tauri::State<'_, profiles::ActiveProfile>, config: DbConfig, query: String, row_filter: Option<String>, row: usize, column: String) -> Result<String, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, false)?;
+    let options = db::FetchOptions { row_filter, ..Default::default() };
+    db::fetch_full_cell(&config, &query, &options, row, &column).await
+}
 
-    Ok(QueryResult { columns, rows })
+/// Fills `source_table`/`key_columns`/`editable` for simple single-table SELECTs.
+/// Metadata lookup failures just leave the result read-only.
+async fn detect_editability(config: &DbConfig, query: &str, result: &mut QueryResult) {
+    let table = match sql_analysis::single_table_source(query) {
+        Some(t) => t,
+        None => return,
+    };
+    let keys = schema::primary_key_columns(config, &table).await.unwrap_or_default();
+    result.editable = !keys.is_empty() && keys.iter().all(|k| result.columns.iter().any(|c| c.eq_ignore_ascii_case(k)));
+    result.source_table = Some(table);
+    result.key_columns = keys;
 }
 
 #[tauri::command]
 async fn test_connection(config: DbConfig) -> Result<String, String> {
     if config.db_type == "mssql" {
-        let tiberius_config = build_mssql_config(&config)?;
-        let tcp = TcpStream::connect(tiberius_config.get_addr()).await.map_err(|e: std::io::Error| format!("Lỗi kết nối mạng: {}", e))?;
-        let _client = Client::connect(tiberius_config, tcp.compat_write()).await.map_err(|e: tiberius::error::Error| format!("Lỗi đăng nhập: {}", e))?;
+        let _client = db::connect_mssql(&config).await?;
         return Ok("Kết nối thành công (MSSQL)!".to_string());
     }
 
-    let url = build_db_url(&config)?;
     match config.db_type.as_str() {
-        "mysql" => {
-            sqlx::mysql::MySqlConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())?;
+        "mysql" | "postgres" => {
+            db::connect_any(&config).await?;
         },
-        "postgres" => {
-            sqlx::postgres::PgConnection::connect(&url).await.map_err(|e: sqlx::Error| e.to_string())?;
+        "oracle" => {
+            oracle_db::test_connection(&config).await?;
         },
         _ => return Err("Unsupported database type".to_string()),
     }
@@ -273,18 +387,48 @@ async fn test_connection(config: DbConfig) -> Result<String, String> {
 }
 
 #[tauri::command]
-fn save_db_settings(handle: tauri::AppHandle, settings: AppSettings) -> Result<(), String> {
+fn save_db_settings(handle: tauri::AppHandle, profile: tauri::State<'_, profiles::ActiveProfile>, mut settings: AppSettings) -> Result<(), String> {
+    profile.require(profiles::Permission::ChangeSettings)?;
+    if let Some(format) = &settings.datetime_format {
+        db::validate_datetime_format(format)?;
+    }
+    let previous = read_db_settings(&handle).map(|s| s.connections).unwrap_or_default();
+    secrets::save_passwords(&previous, &mut settings.connections);
+    write_db_settings(&handle, &settings)?;
+    profile.set(settings.profile.unwrap_or_default());
+    db::set_datetime_format(settings.datetime_format);
+    Ok(())
+}
+
+fn write_db_settings(handle: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
     let path = handle.path_resolver().app_config_dir().ok_or("Could not find app config dir")?;
     fs::create_dir_all(&path).map_err(|e: std::io::Error| e.to_string())?;
     let config_path = path.join("db_settings.json");
-    let content = serde_json::to_string_pretty(&settings).map_err(|e: serde_json::Error| e.to_string())?;
+    let content = serde_json::to_string_pretty(settings).map_err(|e: serde_json::Error| e.to_string())?;
     let mut file = File::create(config_path).map_err(|e: std::io::Error| e.to_string())?;
-    file.write_all(content.as_bytes()).map_err(|e: std::io::Error| e.to_string())?;
+    file.write_all(content.as_bytes()).map_err(|e: std::io::Error| e.to_string())
+}
+
+/// Moves passwords still stored in plain text (settings from before `secrets`) into the
+/// credential store.
+fn migrate_passwords(handle: &tauri::AppHandle) -> Result<(), String> {
+    let mut settings = read_db_settings(handle)?;
+    if secrets::store_passwords(&mut settings.connections) {
+        write_db_settings(handle, &settings)?;
+    }
     Ok(())
 }
 
+/// Settings with the passwords filled in from the credential store.
 #[tauri::command]
 fn load_db_settings(handle: tauri::AppHandle) -> Result<AppSettings, String> {
+    let mut settings = read_db_settings(&handle)?;
+    secrets::resolve_passwords(&mut settings.connections);
+    Ok(settings)
+}
+
+/// Settings as stored in db_settings.json (or the defaults), passwords not resolved.
+fn read_db_settings(handle: &tauri::AppHandle) -> Result<AppSettings, String> {
     let path = handle.path_resolver().app_config_dir().ok_or("Could not find app config dir")?;
     let config_path = path.join("db_settings.json");
     
@@ -307,9 +451,17 @@ fn load_db_settings(handle: tauri::AppHandle) -> Result<AppSettings, String> {
                 trust_server_certificate: Some(true),
                 encrypt: Some(false),
                 verified: Some(false),
+                ..Default::default()
             }],
             global_log_path: Some("".to_string()),
             translate_file_path: Some(default_translate_path),
+            validation_calls: None,
+            cleanup_policy: None,
+            profile: None,
+            backup_retention: None,
+            excel_header_color: None,
+            datetime_format: None,
+            max_rows: None,
         });
     }
     
@@ -327,15 +479,177 @@ fn load_db_settings(handle: tauri::AppHandle) -> Result<AppSettings, String> {
 
 fn main() {
     tauri::Builder::default()
+        .manage(result_store::ResultStore::default())
+        .manage(row_guard::Continuations::default())
+        .manage(tasks::BackgroundTasks::default())
+        .manage(session_recorder::SessionRecorder::default())
+        .manage(shutdown::ActiveOperations::default())
+        .manage(exec_queue::ExecQueue::default())
+        .manage(sessions::Sessions::default())
+        .manage(completion::CompletionCache::default())
+        .manage(profiles::ActiveProfile::default())
+        .manage(history::QueryHistory::default())
+        .manage(health::HealthStatuses::default())
+        .manage(scheduler::RunningSchedules::default())
+        .setup(|app| {
+            let handle = app.handle();
+            if let Err(e) = handle.state::<history::QueryHistory>().init(&handle) {
+                eprintln!("Query history unavailable: {}", e);
+            }
+            if let Err(e) = migrate_passwords(&handle) {
+                eprintln!("Password migration failed: {}", e);
+            }
+            let settings = read_db_settings(&handle).ok();
+            handle.state::<profiles::ActiveProfile>().set(settings.as_ref().and_then(|s| s.profile).unwrap_or_default());
+            db::set_datetime_format(settings.as_ref().and_then(|s| s.datetime_format.clone()));
+            let policy = settings.and_then(|s| s.cleanup_policy).unwrap_or_default();
+            if let Err(e) = workspace::auto_cleanup(&handle, &policy) {
+                eprintln!("Workspace cleanup failed: {}", e);
+            }
+            backups::start_scheduler(handle.clone());
+            scheduler::start_scheduler(handle);
+            Ok(())
+        })
+        .on_window_event(|event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event.event() {
+                shutdown::on_close_requested(event.window(), api);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             read_log_file, 
             execute_query, 
+            get_full_cell,
             test_connection,
             parse_java_graph,
             generate_mermaid_graph,
             save_db_settings, 
             load_db_settings,
-            open_file
+            open_file,
+            backups::create_backup,
+            backups::list_backups,
+            backups::restore_backup,
+            class_diagram::generate_class_diagram,
+            code_snippet::generate_code_snippet,
+            completion::get_completion_metadata,
+            completion::refresh_completion_metadata,
+            conn_string::parse_connection_string,
+            diagnostics::probe_connection,
+            diagnostics::get_top_queries,
+            diagnostics::get_missing_index_suggestions,
+            diagnostics::get_server_info,
+            diagrams::save_diagram,
+            diagrams::list_diagrams,
+            diagrams::load_diagram,
+            diagrams::delete_diagram,
+            diagrams::regenerate_diagram,
+            dialect::translate_sql,
+            exec_queue::get_queue_status,
+            export::export_cell_bytes,
+            export::export_result_csv,
+            export::export_result_fixed_width,
+            export::export_result_json,
+            export::export_result_xlsx,
+            first_run::detect_local_servers,
+            first_run::validate_setup_step,
+            health::start_health_monitor,
+            health::stop_health_monitor,
+            health::get_connection_health,
+            history::prune_history,
+            history::rerun_history_entry,
+            history::search_history,
+            import::import_csv,
+            import::import_csv_bulk,
+            import::import_csv_copy,
+            import::export_csv_copy,
+            java_deps::analyze_dependencies,
+            java_sql::check_sql_against_schema,
+            java_watch::watch_java_file,
+            java_watch::unwatch_java_file,
+            local_query::register_named_result,
+            local_query::run_local_query,
+            pg_notify::listen_channel,
+            pg_notify::unlisten_channel,
+            procedures::execute_procedure,
+            profile::profile_result,
+            profile::profile_table,
+            profiles::get_active_profile,
+            query_guard::find_dangerous_statements,
+            query_page::execute_query_page,
+            query_params::execute_query_params,
+            query_plan::explain_query,
+            query_plan::generate_plan_mermaid,
+            query_stream::execute_query_stream,
+            result_diff::compare_across_connections,
+            result_diff::diff_results,
+            result_ops::join_results,
+            result_ops::transform_result,
+            result_ops::pivot_result,
+            result_ops::unpivot_result,
+            result_search::filter_result,
+            result_search::search_result,
+            result_store::hold_result,
+            result_store::get_result_page,
+            result_store::release_result,
+            row_edit::preview_row_edits,
+            row_edit::apply_row_edits,
+            row_guard::fetch_more_rows,
+            row_guard::release_continuation,
+            scheduler::save_schedule,
+            scheduler::list_schedules,
+            scheduler::delete_schedule,
+            scheduler::run_schedule_now,
+            schema::list_indexes,
+            schema::get_primary_key,
+            schema::list_databases,
+            schema::get_schema_objects,
+            schema::describe_table,
+            scratchpad::save_to_scratchpad,
+            scratchpad::list_scratchpad_tables,
+            scratchpad::query_scratchpad,
+            scratchpad::drop_scratchpad_table,
+            script::execute_script,
+            script::run_sql_file,
+            session_recorder::start_recording,
+            session_recorder::get_recording_status,
+            session_recorder::stop_recording,
+            session_recorder::list_recorded_sessions,
+            session_recorder::load_recorded_session,
+            session_recorder::delete_recorded_session,
+            session_recorder::replay_session_statement,
+            sessions::open_session,
+            sessions::close_session,
+            sessions::list_sessions,
+            sessions::execute_session_query,
+            shutdown::get_running_tasks,
+            shutdown::confirm_shutdown,
+            snapshots::pin_result,
+            snapshots::list_pinned,
+            snapshots::load_pinned,
+            snapshots::delete_pinned,
+            snapshots::list_pinned_notes,
+            snapshots::set_pinned_note,
+            snapshots::export_pinned_markdown,
+            sorting::sort_result,
+            sql_analysis::split_statements,
+            sql_analysis::get_statement_at,
+            sql_syntax::analyze_sql,
+            sql_syntax::validate_sql,
+            table_watch::watch_table,
+            table_watch::unwatch_table,
+            templates::list_templates,
+            templates::save_template,
+            templates::delete_template,
+            templates::get_template_placeholders,
+            templates::render_template,
+            templates::run_template,
+            templates::resolve_template_shortcut,
+            text_normalize::normalize_cells,
+            text_normalize::normalize_value,
+            transfer::copy_table_data,
+            workspace::get_storage_usage,
+            workspace::clean_workspace,
+            tasks::list_background_tasks,
+            tasks::cancel_background_task
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
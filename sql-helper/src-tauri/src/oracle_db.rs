@@ -0,0 +1,246 @@
+use std::time::Instant;
+use oracle::Connection;
+use crate::db::{self, FetchOptions};
+use crate::query_params::ParamValue;
+use crate::result_ops::NULL_CELL;
+use crate::{ssh_tunnel, DbConfig, QueryResult};
+
+// The `oracle` crate is a blocking OCI binding, so every call runs on the blocking pool. The async
+// entry points open the SSH tunnel (see `ssh_tunnel::tunneled`) and keep it until the call returns.
+
+pub const DEFAULT_PORT: u16 = 1521;
+
+/// EZConnect `//host:port/service` for service names; a full descriptor for SIDs,
+/// which EZConnect cannot express.
+pub fn connect_string(config: &DbConfig) -> String {
+    let port = if config.port == 0 { DEFAULT_PORT } else { config.port };
+    let non_empty = |v: &Option<String>| v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+    match non_empty(&config.oracle_sid) {
+        Some(sid) => format!(
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA=(SID={})))",
+            config.host, port, sid
+        ),
+        None => {
+            let service = non_empty(&config.oracle_service_name).unwrap_or_else(|| config.database.trim().to_string());
+            format!("//{}:{}/{}", config.host, port, service)
+        }
+    }
+}
+
+pub fn connect(config: &DbConfig) -> Result<Connection, String> {
+    let conn = Connection::connect(&config.user, &config.password, connect_string(config))
+        .map_err(|e| format!("Lỗi đăng nhập Database: {}", e))?;
+    // The async timeout cannot stop a blocking call, so let OCI interrupt it on the server as well
+    if let Some(limit) = db::query_timeout(config) {
+        conn.set_call_timeout(Some(limit)).map_err(|e| e.to_string())?;
+    }
+    for statement in db::init_statements(config) {
+        conn.execute(&strip_terminator(&statement), &[]).map_err(|e| db::init_error(&statement, e))?;
+    }
+    Ok(conn)
+}
+
+/// Oracle rejects a trailing `;` on plain SQL, but PL/SQL blocks need theirs.
+fn strip_terminator(sql: &str) -> String {
+    let trimmed = sql.trim();
+    let first = trimmed.split_whitespace().next().unwrap_or("").to_uppercase();
+    if first == "BEGIN" || first == "DECLARE" {
+        trimmed.to_string()
+    } else {
+        trimmed.trim_end_matches(';').trim_end().to_string()
+    }
+}
+
+fn bind_values(params: &[ParamValue]) -> Vec<Box<dyn oracle::sql_type::ToSql>> {
+    params.iter().map(|p| -> Box<dyn oracle::sql_type::ToSql> {
+        match p {
+            ParamValue::Null => Box::new(None::<String>),
+            ParamValue::Text(s) => Box::new(s.clone()),
+            ParamValue::Int(n) => Box::new(*n),
+            ParamValue::Float(f) => Box::new(*f),
+            // No SQL boolean before 23c
+            ParamValue::Bool(b) => Box::new(*b as i64),
+            ParamValue::Date(d) => Box::new(*d),
+            ParamValue::DateTime(dt) => Box::new(*dt),
+        }
+    }).collect()
+}
+
+pub async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| e.to_string())?
+}
+
+pub async fn test_connection(config: &DbConfig) -> Result<(), String> {
+    let (owned, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    db::with_connect_timeout(config, blocking(move || connect(&owned).map(|_| ()))).await
+}
+
+pub async fn run_query(config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    let (config, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    let options = options.clone();
+    let query = query.to_string();
+    blocking(move || query_on(&connect(&config)?, &query, &options)).await
+}
+
+/// Lowercase type name without size, e.g. "number" for NUMBER(10,2).
+fn type_name(oracle_type: &oracle::sql_type::OracleType) -> String {
+    let mut name = oracle_type.to_string().to_lowercase();
+    while let (Some(open), Some(close)) = (name.find('('), name.find(')')) {
+        if close < open {
+            break;
+        }
+        name.replace_range(open..=close, "");
+    }
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn date_cell(row: &oracle::Row, i: usize, column_type: &str, canonical: bool) -> String {
+    let rendered = if column_type == "timestamp with time zone" {
+        row.get::<usize, chrono::DateTime<chrono::FixedOffset>>(i).map(|dt| db::datetime_offset_cell(&dt, canonical))
+    } else {
+        row.get::<usize, chrono::NaiveDateTime>(i).map(|dt| db::datetime_cell(&dt, canonical))
+    };
+    rendered.unwrap_or_else(|_| "???".to_string())
+}
+
+/// Runs on an already open connection; blocking, call from the blocking pool.
+pub fn query_on(conn: &Connection, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    let query = strip_terminator(query);
+    let values = bind_values(&options.params);
+    let refs: Vec<&dyn oracle::sql_type::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+    let sent = Instant::now();
+    let rows = conn.query(&query, &refs).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = rows.column_info().iter().map(|c| c.name().to_string()).collect();
+    let column_types: Vec<String> = rows.column_info().iter().map(|c| type_name(c.oracle_type())).collect();
+    let filter = db::compile_filter(options, &columns)?;
+
+    let mut out = db::RowCollector::new(options);
+    out.set_column_types(&column_types);
+    for row in rows {
+        let row = row.map_err(|e| e.to_string())?;
+        let mut row_data = Vec::with_capacity(columns.len());
+        for (i, column_type) in column_types.iter().enumerate() {
+            let val = match row.get::<usize, Option<String>>(i) {
+                // Text conversion would follow the session's NLS formats instead of the app setting
+                Ok(Some(_)) if column_type.starts_with("date") || column_type.starts_with("timestamp") => date_cell(&row, i, column_type, options.canonical),
+                Ok(Some(s)) => s.trim_end().to_string(),
+                Ok(None) => NULL_CELL.to_string(),
+                // BLOB (RAW already converts to hex text)
+                Err(_) => match row.get::<usize, Option<Vec<u8>>>(i) {
+                    Ok(Some(bytes)) => db::binary_cell(&bytes, options.canonical),
+                    Ok(None) => NULL_CELL.to_string(),
+                    Err(_) => "???".to_string(),
+                },
+            };
+            row_data.push(val);
+        }
+        if filter.as_ref().map_or(true, |f| f.matches(&row_data)) {
+            out.push_blocking(&columns, row_data)?;
+        }
+    }
+    let stats = Some(db::execution_stats(sent, vec![out.stats(sent)]));
+    let rows = out.finish_blocking(&columns)?;
+    Ok(QueryResult { columns, rows, column_types, stats, ..Default::default() })
+}
+
+/// See `db::fetch_cell_bytes`.
+pub async fn fetch_cell_bytes(config: &DbConfig, query: &str, row: usize, column: &str) -> Result<Vec<u8>, String> {
+    let (config, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    let (query, column) = (strip_terminator(query), column.to_string());
+    blocking(move || {
+        let conn = connect(&config)?;
+        let mut rows = conn.query(&query, &[]).map_err(|e| e.to_string())?;
+        let found = rows.nth(row).ok_or_else(|| format!("Không tìm thấy dòng {}", row + 1))?.map_err(|e| e.to_string())?;
+        match found.get::<&str, Option<Vec<u8>>>(&column) {
+            Ok(Some(bytes)) => Ok(bytes),
+            Ok(None) => Err(format!("Ô cột {} là NULL", column)),
+            Err(e) => Err(e.to_string()),
+        }
+    }).await
+}
+
+/// Runs one statement and commits; returns the affected row count.
+pub async fn execute(config: &DbConfig, query: &str, params: &[ParamValue]) -> Result<u64, String> {
+    let (config, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    let params = params.to_vec();
+    let query = query.to_string();
+    blocking(move || execute_on(&connect(&config)?, &query, &params)).await
+}
+
+/// Blocking counterpart of `execute` on an open connection.
+pub fn execute_on(conn: &Connection, query: &str, params: &[ParamValue]) -> Result<u64, String> {
+    let values = bind_values(params);
+    let refs: Vec<&dyn oracle::sql_type::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+    let affected = conn.execute(&strip_terminator(query), &refs).and_then(|stmt| stmt.row_count()).map_err(|e| e.to_string())?;
+    conn.commit().map_err(|e| e.to_string())?;
+    Ok(affected)
+}
+
+/// Runs a PL/SQL block whose last `output_count` placeholders are out binds, commits and returns
+/// their values as text ("[NULL]" for NULL).
+pub async fn call_procedure(config: &DbConfig, block: &str, params: &[ParamValue], output_count: usize) -> Result<Vec<String>, String> {
+    let (config, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    let (block, params) = (block.to_string(), params.to_vec());
+    blocking(move || {
+        let conn = connect(&config)?;
+        let mut values = bind_values(&params);
+        for _ in 0..output_count {
+            values.push(Box::new(oracle::sql_type::OracleType::Varchar2(4000)));
+        }
+        let refs: Vec<&dyn oracle::sql_type::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+        let stmt = conn.execute(&block, &refs).map_err(|e| e.to_string())?;
+        let outputs = (params.len() + 1..=params.len() + output_count)
+            .map(|i| stmt.bind_value::<usize, Option<String>>(i).map(|v| v.unwrap_or_else(|| NULL_CELL.to_string())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        conn.commit().map_err(|e| e.to_string())?;
+        Ok(outputs)
+    }).await
+}
+
+/// Same contract as `db::execute_in_transaction`.
+pub async fn execute_in_transaction(config: &DbConfig, statements: &[String], expect_single_row: bool) -> Result<Vec<u64>, String> {
+    let (config, _tunnel) = ssh_tunnel::tunneled(config).await?;
+    let statements = statements.to_vec();
+    blocking(move || {
+        let conn = connect(&config)?;
+        let mut counts = Vec::new();
+        for (i, statement) in statements.iter().enumerate() {
+            let outcome = conn.execute(&strip_terminator(statement), &[])
+                .map_err(|e| format!("Câu lệnh #{}: {}", i + 1, e))
+                .and_then(|stmt| stmt.row_count().map_err(|e| e.to_string()));
+            match outcome {
+                Ok(affected) if !expect_single_row || affected == 1 => counts.push(affected),
+                Ok(affected) => {
+                    conn.rollback().ok();
+                    return Err(format!("Câu lệnh #{} ảnh hưởng {} dòng (mong đợi 1), đã rollback", i + 1, affected));
+                }
+                Err(e) => {
+                    conn.rollback().ok();
+                    return Err(e);
+                }
+            }
+        }
+        conn.commit().map_err(|e| e.to_string())?;
+        Ok(counts)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_string() {
+        let mut config = DbConfig { db_type: "oracle".to_string(), host: "ora1".to_string(), port: 0, database: "ORCLPDB".to_string(), ..Default::default() };
+        assert_eq!(connect_string(&config), "//ora1:1521/ORCLPDB");
+        config.oracle_sid = Some("LEGACY".to_string());
+        assert_eq!(connect_string(&config), "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=ora1)(PORT=1521))(CONNECT_DATA=(SID=LEGACY)))");
+    }
+
+    #[test]
+    fn test_strip_terminator() {
+        assert_eq!(strip_terminator("SELECT 1 FROM dual;\n"), "SELECT 1 FROM dual");
+        assert_eq!(strip_terminator("begin null; end;"), "begin null; end;");
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use sqlx::postgres::PgListener;
+use tauri::State;
+use crate::tasks::BackgroundTasks;
+use crate::{db, DbConfig};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NotifyEvent {
+    pub subscription_id: String,
+    pub channel: String,
+    pub payload: String,
+    pub process_id: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ListenError {
+    pub subscription_id: String,
+    pub error: String,
+}
+
+/// Holds a dedicated Postgres connection LISTENing on `channel` and forwards every
+/// NOTIFY as a `pg://notify` event. Returns the subscription id used to unsubscribe.
+#[tauri::command]
+pub async fn listen_channel(window: tauri::Window, tasks: State<'_, BackgroundTasks>, config: DbConfig, channel: String) -> Result<String, String> {
+    if config.db_type != "postgres" {
+        return Err("LISTEN/NOTIFY chỉ hỗ trợ PostgreSQL".to_string());
+    }
+    let url = db::build_db_url(&config)?;
+    // Connect up front so bad credentials/channels are reported to the caller
+    let mut listener = PgListener::connect(&url).await.map_err(|e| e.to_string())?;
+    listener.listen(&channel).await.map_err(|e| e.to_string())?;
+
+    let subscription_id = tasks.next_id("listen");
+    let id = subscription_id.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match listener.recv().await {
+                Ok(notification) => {
+                    window.emit("pg://notify", NotifyEvent {
+                        subscription_id: id.clone(),
+                        channel: notification.channel().to_string(),
+                        payload: notification.payload().to_string(),
+                        process_id: notification.process_id(),
+                    }).ok();
+                }
+                Err(e) => {
+                    window.emit("pg://listen-error", ListenError { subscription_id: id.clone(), error: e.to_string() }).ok();
+                    break;
+                }
+            }
+        }
+    });
+    tasks.register(subscription_id.clone(), handle);
+    Ok(subscription_id)
+}
+
+#[tauri::command]
+pub fn unlisten_channel(tasks: State<'_, BackgroundTasks>, subscription_id: String) -> bool {
+    tasks.cancel(&subscription_id)
+}
@@ -0,0 +1,312 @@
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::db::{self, FetchOptions};
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::query_params::{ParamValue, QueryParam};
+use crate::result_ops::NULL_CELL;
+use crate::sql_analysis::{self, TokenKind};
+use crate::{dialect, oracle_db, query_guard, DbConfig, QueryResult, ResultSet};
+
+// Stored procedure calls with OUTPUT / INOUT parameters. The call is generated per db_type so the
+// output values can be read back: local variables plus a trailing SELECT on MSSQL, session
+// variables on MySQL, the row CALL returns on Postgres and out binds on Oracle.
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProcedureParam {
+    /// Parameter name as declared, with or without the leading `@` / `:`.
+    pub name: String,
+    /// Input value and type; ignored for "out".
+    #[serde(flatten)]
+    pub param: QueryParam,
+    /// "in" (default), "out" or "inout".
+    #[serde(default)]
+    pub direction: Option<String>,
+    /// Declared type of the output variable, e.g. "DECIMAL(18,2)"; derived from the param type
+    /// when missing.
+    #[serde(default)]
+    pub sql_type: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OutputValue {
+    pub name: String,
+    /// Display string, "[NULL]" for NULL.
+    pub value: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ProcedureResult {
+    /// Result sets the procedure returned: the first in `columns`/`rows`, the rest in `more_results`.
+    pub result: QueryResult,
+    pub outputs: Vec<OutputValue>,
+    /// The RETURN status of an MSSQL procedure.
+    pub return_value: Option<i64>,
+}
+
+/// The generated call, with `values` bound to its input placeholders in order.
+#[derive(Debug, Default, PartialEq)]
+struct ProcedureCall {
+    sql: String,
+    values: Vec<ParamValue>,
+    /// Output parameter names, in the order their values come back.
+    outputs: Vec<String>,
+    /// MySQL: `SET` of the INOUT session variables, run before the call.
+    setup: Option<(String, Vec<ParamValue>)>,
+    /// MySQL: reads the session variables back after the call.
+    read_outputs: Option<String>,
+}
+
+fn direction(p: &ProcedureParam) -> Result<(bool, bool), String> {
+    match p.direction.as_deref().unwrap_or("in").to_lowercase().as_str() {
+        "in" => Ok((true, false)),
+        "out" | "output" => Ok((false, true)),
+        "inout" | "in_out" => Ok((true, true)),
+        other => Err(format!("Tham số {}: chiều '{}' không hợp lệ", p.name, other)),
+    }
+}
+
+fn param_name(p: &ProcedureParam) -> Result<&str, String> {
+    let name = p.name.trim().trim_start_matches(['@', ':']);
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("Tên tham số không hợp lệ: '{}'", p.name));
+    }
+    Ok(name)
+}
+
+/// Type of the variable holding an output value; user-supplied types are spliced into the SQL,
+/// so they are limited to names, sizes and precisions.
+fn variable_type(db_type: &str, p: &ProcedureParam) -> Result<String, String> {
+    if let Some(t) = p.sql_type.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        if !t.chars().all(|c| c.is_ascii_alphanumeric() || " _(),".contains(c)) {
+            return Err(format!("Kiểu SQL không hợp lệ: '{}'", t));
+        }
+        return Ok(t.to_string());
+    }
+    let ty = p.param.param_type.as_deref().unwrap_or("string").to_lowercase();
+    let mssql = db_type == "mssql";
+    Ok(match ty.as_str() {
+        "int" | "integer" | "bigint" => if mssql { "BIGINT" } else { "NUMBER" },
+        "float" | "double" | "number" => if mssql { "FLOAT" } else { "NUMBER" },
+        "decimal" => if mssql { "DECIMAL(38, 10)" } else { "NUMBER" },
+        "bool" | "boolean" => if mssql { "BIT" } else { "NUMBER(1)" },
+        "date" => "DATE",
+        "datetime" | "timestamp" => if mssql { "DATETIME2" } else { "TIMESTAMP" },
+        _ => if mssql { "NVARCHAR(MAX)" } else { "VARCHAR2(4000)" },
+    }.to_string())
+}
+
+/// Procedure names are spliced in as typed (names, `.` and quoted identifiers only), so the
+/// user's own case and quoting are kept.
+fn procedure_name(procedure: &str) -> Result<String, String> {
+    let tokens = sql_analysis::tokenize(procedure);
+    let valid = tokens.iter().all(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent) || t.is_symbol("."));
+    if tokens.is_empty() || !valid {
+        return Err(format!("Tên procedure không hợp lệ: '{}'", procedure));
+    }
+    Ok(procedure.trim().to_string())
+}
+
+/// Adds `value` to the bound values and returns its placeholder.
+fn bind(values: &mut Vec<ParamValue>, db_type: &str, value: ParamValue) -> String {
+    values.push(value);
+    match db_type {
+        "mssql" => format!("@P{}", values.len()),
+        "postgres" => format!("${}", values.len()),
+        "oracle" => format!(":{}", values.len()),
+        _ => "?".to_string(),
+    }
+}
+
+/// Oracle binds `:n` by order of appearance, so INOUT initial values are bound first and the
+/// outputs, bound as VARCHAR2, last.
+fn build_call(db_type: &str, procedure: &str, params: &[ProcedureParam]) -> Result<ProcedureCall, String> {
+    let name = procedure_name(procedure)?;
+    let mut call = ProcedureCall::default();
+    let mut args = Vec::new();
+    let mut declarations = Vec::new();
+    let mut reads = Vec::new();
+    let mut setup = Vec::new();
+    let mut inits = vec![None; params.len()];
+    if db_type == "oracle" {
+        for (i, p) in params.iter().enumerate() {
+            if direction(p)? == (true, true) {
+                inits[i] = Some(bind(&mut call.values, db_type, p.param.to_value(i)?));
+            }
+        }
+    }
+    for (i, p) in params.iter().enumerate() {
+        let (is_in, is_out) = direction(p)?;
+        let param = param_name(p)?;
+        let value = if is_in { p.param.to_value(i)? } else { ParamValue::Null };
+        match (db_type, is_out) {
+            ("mssql", true) => {
+                let var = format!("@o{}", i + 1);
+                let init = if is_in { format!(" = {}", bind(&mut call.values, db_type, value)) } else { String::new() };
+                declarations.push(format!("DECLARE {} {}{};\n", var, variable_type(db_type, p)?, init));
+                args.push(format!("@{} = {} OUTPUT", param, var));
+                reads.push(format!("{} AS {}", var, dialect::quote_ident(db_type, param)));
+            }
+            ("mssql", false) => args.push(format!("@{} = {}", param, bind(&mut call.values, db_type, value))),
+            ("oracle", true) => {
+                let var = format!("o{}", i + 1);
+                let init = inits[i].as_ref().map_or(String::new(), |p| format!(" := {}", p));
+                declarations.push(format!("  {} {}{};\n", var, variable_type(db_type, p)?, init));
+                args.push(format!("{} => {}", param, var));
+                reads.push(var);
+            }
+            ("oracle", false) => args.push(format!("{} => {}", param, bind(&mut call.values, db_type, value))),
+            ("mysql", true) => {
+                let var = format!("@o{}", i + 1);
+                if is_in {
+                    setup.push((format!("{} = ?", var), value));
+                }
+                args.push(var.clone());
+                reads.push(format!("{} AS {}", var, dialect::quote_ident(db_type, param)));
+            }
+            // Postgres takes NULL for OUT arguments and returns OUT/INOUT values as the CALL's row
+            ("postgres", true) if !is_in => args.push(format!("{} => NULL", param)),
+            ("postgres", _) => {
+                let cast = match &p.sql_type {
+                    Some(_) => format!("::{}", variable_type(db_type, p)?),
+                    None => String::new(),
+                };
+                args.push(format!("{} => {}{}", param, bind(&mut call.values, db_type, value), cast));
+            }
+            _ => args.push(bind(&mut call.values, db_type, value)),
+        }
+        if is_out {
+            call.outputs.push(param.to_string());
+        }
+    }
+
+    call.sql = match db_type {
+        "mssql" => {
+            let args = if args.is_empty() { String::new() } else { format!(" {}", args.join(", ")) };
+            reads.insert(0, "@return_value AS [RETURN_VALUE]".to_string());
+            format!("DECLARE @return_value INT;\n{}EXEC @return_value = {}{};\nSELECT {};", declarations.concat(), name, args, reads.join(", "))
+        }
+        "oracle" => {
+            let declare = if declarations.is_empty() { String::new() } else { format!("DECLARE\n{}", declarations.concat()) };
+            let assignments: String = reads.iter().enumerate().map(|(n, var)| format!("  :{} := {};\n", call.values.len() + n + 1, var)).collect();
+            format!("{}BEGIN\n  {}({});\n{}END;", declare, name, args.join(", "), assignments)
+        }
+        _ => format!("CALL {}({})", name, args.join(", ")),
+    };
+    if db_type == "mysql" && !reads.is_empty() {
+        if !setup.is_empty() {
+            let sql = format!("SET {}", setup.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>().join(", "));
+            call.setup = Some((sql, setup.into_iter().map(|(_, v)| v).collect()));
+        }
+        call.read_outputs = Some(format!("SELECT {}", reads.join(", ")));
+    }
+    Ok(call)
+}
+
+/// Takes the last result set off `result`, shifting the previous ones down.
+fn pop_last_set(result: &mut QueryResult) -> ResultSet {
+    match result.more_results.pop() {
+        Some(set) => set,
+        None => ResultSet {
+            columns: std::mem::take(&mut result.columns),
+            rows: std::mem::take(&mut result.rows),
+            column_types: std::mem::take(&mut result.column_types),
+            ..Default::default()
+        },
+    }
+}
+
+/// Pairs the first row of `set` with its column names.
+fn output_values(set: &ResultSet) -> Vec<OutputValue> {
+    let row = set.rows.first();
+    set.columns.iter().enumerate()
+        .map(|(i, name)| OutputValue { name: name.clone(), value: row.and_then(|r| r.get(i)).cloned().unwrap_or_else(|| NULL_CELL.to_string()) })
+        .collect()
+}
+
+async fn run_call(config: &DbConfig, call: &ProcedureCall) -> Result<ProcedureResult, String> {
+    let options = FetchOptions { params: call.values.clone(), ..Default::default() };
+    match config.db_type.as_str() {
+        "mssql" => {
+            let mut result = db::fetch_mssql(&mut db::connect_mssql(config).await?, config, &call.sql, &options).await?;
+            let mut outputs = output_values(&pop_last_set(&mut result));
+            let return_value = if outputs.is_empty() { None } else { outputs.remove(0).value.parse().ok() };
+            Ok(ProcedureResult { result, outputs, return_value })
+        }
+        "oracle" => {
+            let values = oracle_db::call_procedure(config, &call.sql, &call.values, call.outputs.len()).await?;
+            let outputs = call.outputs.iter().zip(values).map(|(name, value)| OutputValue { name: name.clone(), value }).collect();
+            Ok(ProcedureResult { outputs, ..Default::default() })
+        }
+        "postgres" => {
+            let mut result = db::fetch_sqlx(&mut db::connect_sqlx(config).await?, &call.sql, &options).await?;
+            let outputs = if call.outputs.is_empty() { Vec::new() } else { output_values(&pop_last_set(&mut result)) };
+            Ok(ProcedureResult { result, outputs, return_value: None })
+        }
+        _ => {
+            // Session variables only live on this connection
+            let mut conn = db::connect_sqlx(config).await?;
+            if let Some((sql, values)) = &call.setup {
+                db::execute_sqlx(&mut conn, sql, values).await?;
+            }
+            let result = db::fetch_sqlx(&mut conn, &call.sql, &options).await?;
+            let outputs = match &call.read_outputs {
+                Some(sql) => output_values(&pop_last_set(&mut db::fetch_sqlx(&mut conn, sql, &FetchOptions::default()).await?)),
+                None => Vec::new(),
+            };
+            Ok(ProcedureResult { result, outputs, return_value: None })
+        }
+    }
+}
+
+/// Calls `procedure` with named, typed parameters and returns its result sets together with the
+/// values of its OUTPUT / INOUT parameters (MySQL passes parameters by position, in list order).
+#[tauri::command]
+pub async fn execute_procedure(profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, config: DbConfig, procedure: String, params: Vec<ProcedureParam>, confirm_dangerous: Option<bool>) -> Result<ProcedureResult, String> {
+    let call = build_call(&config.db_type, &procedure, &params)?;
+    profile.check_sql(&call.sql, &config)?;
+    query_guard::check(&config, &call.sql, confirm_dangerous.unwrap_or(false))?;
+    let started = Instant::now();
+    let result = db::with_query_timeout(&config, run_call(&config, &call)).await;
+    history.record(&config, &call.sql, started.elapsed(), &result.as_ref().map(|r| r.result.clone()).map_err(|e| e.clone()));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(name: &str, value: Option<&str>, ty: &str, direction: &str) -> ProcedureParam {
+        ProcedureParam {
+            name: name.to_string(),
+            param: QueryParam { value: value.map(str::to_string), param_type: Some(ty.to_string()) },
+            direction: Some(direction.to_string()),
+            sql_type: None,
+        }
+    }
+
+    #[test]
+    fn test_build_call() {
+        let params = vec![param("@customer_id", Some("42"), "int", "in"), param("total", None, "decimal", "out"), param("note", Some("再送"), "string", "inout")];
+        let call = build_call("mssql", "dbo.usp_order_total", &params).unwrap();
+        assert_eq!(call.sql, "DECLARE @return_value INT;\nDECLARE @o2 DECIMAL(38, 10);\nDECLARE @o3 NVARCHAR(MAX) = @P2;\n\
+                              EXEC @return_value = dbo.usp_order_total @customer_id = @P1, @total = @o2 OUTPUT, @note = @o3 OUTPUT;\n\
+                              SELECT @return_value AS [RETURN_VALUE], @o2 AS [total], @o3 AS [note];");
+        assert_eq!(call.values, vec![ParamValue::Int(42), ParamValue::Text("再送".to_string())]);
+        assert_eq!(call.outputs, vec!["total", "note"]);
+
+        let call = build_call("oracle", "pkg.calc", &params).unwrap();
+        assert_eq!(call.sql, "DECLARE\n  o2 NUMBER;\n  o3 VARCHAR2(4000) := :1;\nBEGIN\n  pkg.calc(customer_id => :2, total => o2, note => o3);\n  :3 := o2;\n  :4 := o3;\nEND;");
+        assert_eq!(call.values, vec![ParamValue::Text("再送".to_string()), ParamValue::Int(42)]);
+
+        let call = build_call("mysql", "calc", &params).unwrap();
+        assert_eq!(call.sql, "CALL calc(?, @o2, @o3)");
+        assert_eq!(call.setup, Some(("SET @o3 = ?".to_string(), vec![ParamValue::Text("再送".to_string())])));
+        assert_eq!(call.read_outputs.as_deref(), Some("SELECT @o2 AS `total`, @o3 AS `note`"));
+
+        assert_eq!(build_call("postgres", "calc", &params).unwrap().sql, "CALL calc(customer_id => $1, total => NULL, note => $2)");
+        assert!(build_call("mssql", "p", &[param("a; DROP", None, "int", "out")]).is_err());
+        assert!(build_call("mssql", "p; DROP TABLE t", &[]).is_err());
+    }
+}
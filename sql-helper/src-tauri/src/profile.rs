@@ -0,0 +1,125 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::Serialize;
+use tauri::State;
+use crate::result_ops::{compare_cells, NULL_CELL};
+use crate::result_store::ResultStore;
+use crate::{db, DbConfig, QueryResult};
+
+const TOP_VALUES: usize = 5;
+const DEFAULT_SAMPLE_ROWS: u32 = 100_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ColumnProfile {
+    pub column: String,
+    pub null_count: usize,
+    pub distinct_count: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub avg_length: f64,
+    pub top_values: Vec<ValueCount>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ResultProfile {
+    pub row_count: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+pub fn profile(result: &QueryResult) -> ResultProfile {
+    let mut columns = Vec::new();
+
+    for (ci, name) in result.columns.iter().enumerate() {
+        let mut null_count = 0;
+        let mut total_len = 0usize;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut min: Option<&str> = None;
+        let mut max: Option<&str> = None;
+
+        for row in &result.rows {
+            let value = row.get(ci).map(|s| s.as_str()).unwrap_or(NULL_CELL);
+            if value == NULL_CELL {
+                null_count += 1;
+                continue;
+            }
+            total_len += value.chars().count();
+            *counts.entry(value).or_insert(0) += 1;
+            if min.map_or(true, |m| compare_cells(value, m) == Ordering::Less) {
+                min = Some(value);
+            }
+            if max.map_or(true, |m| compare_cells(value, m) == Ordering::Greater) {
+                max = Some(value);
+            }
+        }
+
+        let non_null = result.rows.len() - null_count;
+        let mut top: Vec<(&str, usize)> = counts.iter().map(|(v, c)| (*v, *c)).collect();
+        // Most frequent first; ties broken by value so the output is deterministic
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| compare_cells(a.0, b.0)));
+        top.truncate(TOP_VALUES);
+
+        columns.push(ColumnProfile {
+            column: name.clone(),
+            null_count,
+            distinct_count: counts.len(),
+            min: min.map(|s| s.to_string()),
+            max: max.map(|s| s.to_string()),
+            avg_length: if non_null > 0 { total_len as f64 / non_null as f64 } else { 0.0 },
+            top_values: top.into_iter().map(|(value, count)| ValueCount { value: value.to_string(), count }).collect(),
+        });
+    }
+
+    ResultProfile { row_count: result.rows.len(), columns }
+}
+
+#[tauri::command]
+pub fn profile_result(store: State<'_, ResultStore>, handle: String) -> Result<ResultProfile, String> {
+    let result = store.get(&handle)?;
+    Ok(profile(&result))
+}
+
+/// Profiles a table by sampling at most `sample_rows` rows from the server.
+#[tauri::command]
+pub async fn profile_table(config: DbConfig, table: String, sample_rows: Option<u32>) -> Result<ResultProfile, String> {
+    let limit = sample_rows.unwrap_or(DEFAULT_SAMPLE_ROWS);
+    let query = match config.db_type.as_str() {
+        "mssql" => format!("SELECT TOP {} * FROM {}", limit, table),
+        "oracle" => format!("SELECT * FROM {} FETCH FIRST {} ROWS ONLY", table, limit),
+        _ => format!("SELECT * FROM {} LIMIT {}", table, limit),
+    };
+    let result = db::run_query(&config, &query).await?;
+    Ok(profile(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_counts() {
+        let result = QueryResult {
+            columns: vec!["CODE".to_string()],
+            rows: vec![
+                vec!["10".to_string()],
+                vec!["9".to_string()],
+                vec![NULL_CELL.to_string()],
+                vec!["10".to_string()],
+            ],
+            ..Default::default()
+        };
+        let p = profile(&result);
+        let col = &p.columns[0];
+        assert_eq!(col.null_count, 1);
+        assert_eq!(col.distinct_count, 2);
+        assert_eq!(col.min.as_deref(), Some("9"));
+        assert_eq!(col.max.as_deref(), Some("10"));
+        assert_eq!(col.top_values[0].value, "10");
+        assert_eq!(col.top_values[0].count, 2);
+    }
+}
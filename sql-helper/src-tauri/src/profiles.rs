@@ -0,0 +1,129 @@
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::{sql_analysis, DbConfig};
+
+// Usage profiles selected per workspace (`AppSettings::profile`). Checks run in the command
+// handlers, so a restricted profile cannot be bypassed from the webview.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Full,
+    /// Read-only data checks: no DML/DDL, imports, row edits or settings changes.
+    Viewer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    ModifyData,
+    Import,
+    ChangeSettings,
+}
+
+impl Permission {
+    fn describe(self) -> &'static str {
+        match self {
+            Permission::ModifyData => "thay đổi dữ liệu",
+            Permission::Import => "import dữ liệu",
+            Permission::ChangeSettings => "thay đổi cài đặt",
+        }
+    }
+}
+
+impl Profile {
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Full => "full",
+            Profile::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(self, _permission: Permission) -> bool {
+        match self {
+            Profile::Full => true,
+            Profile::Viewer => false,
+        }
+    }
+}
+
+/// Every statement only reads: SELECT/WITH/SHOW/EXPLAIN without `INTO` or data-changing clauses
+/// (catches `SELECT ... INTO new_table` and `WITH ... DELETE`).
+pub fn is_read_only_sql(sql: &str, dialect: &str) -> bool {
+    sql_analysis::statement_spans(sql, dialect).iter().all(|s| {
+        sql_analysis::statement_kind(&s.text) == "select"
+            && !sql_analysis::has_top_level_keyword(&s.text, &["INTO", "INSERT", "UPDATE", "DELETE", "MERGE"])
+    })
+}
+
+#[derive(Default)]
+pub struct ActiveProfile {
+    profile: RwLock<Profile>,
+}
+
+impl ActiveProfile {
+    pub fn get(&self) -> Profile {
+        *self.profile.read().unwrap()
+    }
+
+    pub fn set(&self, profile: Profile) {
+        *self.profile.write().unwrap() = profile;
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<(), String> {
+        let profile = self.get();
+        if profile.allows(permission) {
+            Ok(())
+        } else {
+            Err(format!("Hồ sơ '{}' không cho phép {}", profile.name(), permission.describe()))
+        }
+    }
+
+    /// Like `require`, but a connection flagged `read_only` refuses every write whatever the profile.
+    pub fn require_for(&self, config: &DbConfig, permission: Permission) -> Result<(), String> {
+        if config.read_only.unwrap_or(false) {
+            return Err(format!("Kết nối '{}' chỉ đọc, không cho phép {}", config.name, permission.describe()));
+        }
+        self.require(permission)
+    }
+
+    /// Lets read-only SQL through; anything else needs `ModifyData` on a writable connection.
+    pub fn check_sql(&self, sql: &str, config: &DbConfig) -> Result<(), String> {
+        if is_read_only_sql(sql, &config.db_type) {
+            Ok(())
+        } else {
+            self.require_for(config, Permission::ModifyData)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_active_profile(profile: State<'_, ActiveProfile>) -> Profile {
+    profile.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_sql() {
+        assert!(is_read_only_sql("SELECT * FROM t; WITH x AS (SELECT 1 a) SELECT a FROM x", "mssql"));
+        assert!(!is_read_only_sql("SELECT 1; DELETE FROM t", "mssql"));
+        assert!(!is_read_only_sql("SELECT * INTO backup_t FROM t", "mssql"));
+        assert!(!is_read_only_sql("WITH x AS (SELECT id FROM t) DELETE FROM t WHERE id IN (SELECT id FROM x)", "mssql"));
+        assert!(!is_read_only_sql("EXEC dbo.purge", "mssql"));
+
+        let mut config = DbConfig { name: "prod".to_string(), db_type: "mysql".to_string(), ..Default::default() };
+        let active = ActiveProfile::default();
+        assert!(active.check_sql("UPDATE t SET a = 1", &config).is_ok());
+        config.read_only = Some(true);
+        assert_eq!(active.check_sql("UPDATE t SET a = 1", &config), Err("Kết nối 'prod' chỉ đọc, không cho phép thay đổi dữ liệu".to_string()));
+        assert!(active.check_sql("EXPLAIN SELECT * FROM t", &config).is_ok());
+        config.read_only = None;
+        active.set(Profile::Viewer);
+        assert_eq!(active.check_sql("UPDATE t SET a = 1", &config), Err("Hồ sơ 'viewer' không cho phép thay đổi dữ liệu".to_string()));
+        assert!(active.check_sql("SELECT 1", &config).is_ok());
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+use sqlparser::ast::{AlterTableOperation, SetExpr, Statement};
+use sqlparser::parser::Parser;
+use crate::sql_analysis::{self, StatementSpan};
+use crate::{sql_syntax, DbConfig};
+
+// Pre-execution check for statements that wipe data wholesale. On connections marked
+// `production` they only run when the caller passes an explicit confirm flag.
+
+/// Prefix of the error returned when confirmation is needed, so the UI can ask and retry.
+pub const CONFIRM_REQUIRED: &str = "CONFIRM_REQUIRED";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DangerousStatement {
+    /// "delete_without_where", "update_without_where", "truncate" or "drop".
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub span: StatementSpan,
+}
+
+const VERBS: &[&str] = &["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "TRUNCATE", "DROP"];
+
+/// Statements sqlparser understands are judged on their AST; `ALTER TABLE ... DROP COLUMN` (or a
+/// constraint, key or partition) counts as a drop.
+fn statement_danger(statement: &Statement) -> Option<&'static str> {
+    match statement {
+        Statement::Delete { selection: None, .. } => Some("delete_without_where"),
+        Statement::Update { selection: None, .. } => Some("update_without_where"),
+        Statement::Truncate { .. } => Some("truncate"),
+        Statement::Drop { .. } | Statement::DropFunction { .. } => Some("drop"),
+        Statement::AlterTable { operations, .. } => operations.iter()
+            .any(|op| matches!(op, AlterTableOperation::DropColumn { .. } | AlterTableOperation::DropConstraint { .. }
+                | AlterTableOperation::DropPrimaryKey | AlterTableOperation::DropPartitions { .. }))
+            .then_some("drop"),
+        // `WITH ... UPDATE`
+        Statement::Query(query) => match query.body.as_ref() {
+            SetExpr::Update(statement) => statement_danger(statement),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn danger_kind(sql: &str, dialect: &str) -> Option<&'static str> {
+    match Parser::parse_sql(sql_syntax::dialect_for(dialect).as_ref(), sql) {
+        Ok(statements) => statements.iter().find_map(statement_danger),
+        // T-SQL control flow and vendor syntax sqlparser rejects
+        Err(_) => token_danger_kind(sql),
+    }
+}
+
+/// Fallback: the statement's verb is its first top-level DML/DDL keyword, which looks past a
+/// leading `WITH` list or T-SQL `IF EXISTS (...)`. `ALTER ... DROP` counts as a drop.
+fn token_danger_kind(sql: &str) -> Option<&'static str> {
+    let tokens = sql_analysis::tokenize(sql);
+    let mut depth = 0;
+    let verb = tokens.iter().find(|t| {
+        if t.is_symbol("(") {
+            depth += 1;
+        } else if t.is_symbol(")") {
+            depth -= 1;
+        }
+        depth == 0 && VERBS.iter().any(|k| t.is_keyword(k))
+    })?;
+    let verb = verb.text.to_uppercase();
+    match verb.as_str() {
+        "DELETE" if !sql_analysis::has_top_level_keyword(sql, &["WHERE"]) => Some("delete_without_where"),
+        "UPDATE" if !sql_analysis::has_top_level_keyword(sql, &["WHERE"]) => Some("update_without_where"),
+        "TRUNCATE" => Some("truncate"),
+        "DROP" => Some("drop"),
+        _ => None,
+    }
+}
+
+pub fn dangerous_statements(sql: &str, dialect: &str) -> Vec<DangerousStatement> {
+    sql_analysis::statement_spans(sql, dialect)
+        .into_iter()
+        .filter_map(|span| danger_kind(&span.text, dialect).map(|kind| DangerousStatement { kind, span }))
+        .collect()
+}
+
+fn describe(kind: &str) -> &'static str {
+    match kind {
+        "delete_without_where" => "DELETE không có WHERE",
+        "update_without_where" => "UPDATE không có WHERE",
+        "truncate" => "TRUNCATE",
+        _ => "DROP",
+    }
+}
+
+/// Refuses dangerous statements on production connections unless `confirmed`.
+pub fn check(config: &DbConfig, sql: &str, confirmed: bool) -> Result<(), String> {
+    if confirmed || !config.production.unwrap_or(false) {
+        return Ok(());
+    }
+    let found = dangerous_statements(sql, &config.db_type);
+    if found.is_empty() {
+        return Ok(());
+    }
+    let mut kinds: Vec<&str> = found.iter().map(|s| describe(s.kind)).collect();
+    kinds.sort_unstable();
+    kinds.dedup();
+    Err(format!("{}: '{}' là kết nối production, cần xác nhận để chạy {}", CONFIRM_REQUIRED, config.name, kinds.join(", ")))
+}
+
+/// Lets the editor warn before running.
+#[tauri::command]
+pub fn find_dangerous_statements(sql: String, dialect: String) -> Vec<DangerousStatement> {
+    dangerous_statements(&sql, &dialect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dangerous_statements() {
+        let kinds = |sql: &str| dangerous_statements(sql, "mssql").into_iter().map(|s| s.kind).collect::<Vec<_>>();
+        assert_eq!(kinds("DELETE FROM t; UPDATE t SET a = 1 WHERE id = 2; TRUNCATE TABLE u"), vec!["delete_without_where", "truncate"]);
+        assert_eq!(kinds("UPDATE t SET a = (SELECT b FROM s WHERE s.id = 1)"), vec!["update_without_where"]);
+        assert_eq!(kinds("WITH x AS (SELECT id FROM t WHERE a = 1) DELETE FROM t"), vec!["delete_without_where"]);
+        assert_eq!(kinds("IF OBJECT_ID('t') IS NOT NULL DROP TABLE t"), vec!["drop"]);
+        assert!(kinds("SELECT * FROM t FOR UPDATE; INSERT INTO t SELECT * FROM s; DELETE FROM t WHERE id = 1").is_empty());
+
+        let pg_kinds = |sql: &str| dangerous_statements(sql, "postgres").into_iter().map(|s| s.kind).collect::<Vec<_>>();
+        assert_eq!(pg_kinds("ALTER TABLE t DROP COLUMN note"), vec!["drop"]);
+        assert!(pg_kinds("ALTER TABLE t ALTER COLUMN note DROP DEFAULT").is_empty());
+        assert!(pg_kinds("UPDATE t SET note = 'no where' FROM s WHERE s.id = t.id").is_empty());
+
+        let mut config = DbConfig { name: "prod".to_string(), db_type: "mssql".to_string(), ..Default::default() };
+        assert!(check(&config, "DELETE FROM t", false).is_ok());
+        config.production = Some(true);
+        assert_eq!(check(&config, "DELETE FROM t", false), Err("CONFIRM_REQUIRED: 'prod' là kết nối production, cần xác nhận để chạy DELETE không có WHERE".to_string()));
+        assert!(check(&config, "DELETE FROM t", true).is_ok());
+        assert_eq!(check(&config, "DELETE FROM a; TRUNCATE TABLE b; DELETE FROM c", false),
+            Err("CONFIRM_REQUIRED: 'prod' là kết nối production, cần xác nhận để chạy DELETE không có WHERE, TRUNCATE".to_string()));
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::{db, dialect, query_guard, DbConfig, QueryResult};
+
+pub const MAX_PAGE_SIZE: u64 = 10_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct QueryPage {
+    pub result: QueryResult,
+    pub offset: u64,
+    pub limit: u64,
+    /// At least one more row exists after this page.
+    pub has_more: bool,
+}
+
+/// Fetches one page of a SELECT so large tables can be browsed without loading every row.
+/// One extra row is requested to tell whether a next page exists.
+#[tauri::command]
+pub async fn execute_query_page(profile: State<'_, ActiveProfile>, config: DbConfig, query: String, offset: u64, limit: u64, confirm_dangerous: Option<bool>) -> Result<QueryPage, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let first = query.split_whitespace().next().unwrap_or("").to_uppercase();
+    if first != "SELECT" && first != "WITH" {
+        return Err("Chỉ hỗ trợ phân trang cho câu SELECT".to_string());
+    }
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let paged = dialect::page_query(&config.db_type, &query, offset, limit + 1);
+
+    let mut result = db::run_query(&config, &paged).await?;
+    let has_more = result.rows.len() as u64 > limit;
+    result.rows.truncate(limit as usize);
+    Ok(QueryPage { result, offset, limit, has_more })
+}
@@ -0,0 +1,143 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+use tauri::State;
+use tiberius::{ColumnData, ToSql};
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::sql_analysis::{self, TokenKind};
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+// Typed, positional query parameters bound through the driver instead of being spliced into the SQL.
+
+/// One parameter as sent by the UI: a display string plus its intended type.
+#[derive(Deserialize, Clone, Debug)]
+pub struct QueryParam {
+    /// None binds NULL.
+    pub value: Option<String>,
+    /// "string" (default), "int", "float", "bool", "date", "datetime" or "decimal" (sent as text).
+    #[serde(rename = "type", default)]
+    pub param_type: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Null,
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+const DATETIME_FORMATS: [&str; 4] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f", "%Y-%m-%d %H:%M"];
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"];
+
+impl QueryParam {
+    pub fn to_value(&self, index: usize) -> Result<ParamValue, String> {
+        let raw = match &self.value {
+            Some(v) => v.as_str(),
+            None => return Ok(ParamValue::Null),
+        };
+        let trimmed = raw.trim();
+        let invalid = |kind: &str| format!("Tham số #{}: '{}' không phải {}", index + 1, raw, kind);
+        let ty = self.param_type.as_deref().unwrap_or("string").to_lowercase();
+        match ty.as_str() {
+            "string" | "text" | "decimal" => Ok(ParamValue::Text(raw.to_string())),
+            "int" | "integer" | "bigint" => trimmed.parse().map(ParamValue::Int).map_err(|_| invalid("số nguyên")),
+            "float" | "double" | "number" => trimmed.parse().map(ParamValue::Float).map_err(|_| invalid("số")),
+            "bool" | "boolean" => match trimmed.to_lowercase().as_str() {
+                "true" | "1" => Ok(ParamValue::Bool(true)),
+                "false" | "0" => Ok(ParamValue::Bool(false)),
+                _ => Err(invalid("true/false")),
+            },
+            "date" => DATE_FORMATS.iter()
+                .find_map(|f| NaiveDate::parse_from_str(trimmed, f).ok())
+                .map(ParamValue::Date)
+                .ok_or_else(|| invalid("ngày (yyyy-MM-dd)")),
+            "datetime" | "timestamp" => DATETIME_FORMATS.iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(trimmed, f).ok())
+                .map(ParamValue::DateTime)
+                .ok_or_else(|| invalid("ngày giờ (yyyy-MM-dd HH:mm:ss)")),
+            other => Err(format!("Tham số #{}: kiểu '{}' không được hỗ trợ", index + 1, other)),
+        }
+    }
+}
+
+impl ToSql for ParamValue {
+    fn to_sql(&self) -> ColumnData<'_> {
+        match self {
+            ParamValue::Null => ColumnData::String(None),
+            ParamValue::Text(s) => s.to_sql(),
+            ParamValue::Int(n) => n.to_sql(),
+            ParamValue::Float(f) => f.to_sql(),
+            ParamValue::Bool(b) => b.to_sql(),
+            ParamValue::Date(d) => d.to_sql(),
+            ParamValue::DateTime(dt) => dt.to_sql(),
+        }
+    }
+}
+
+/// Rewrites generic `?` placeholders to the driver's native form: `@P1` (mssql), `$1` (postgres),
+/// `:1` (oracle). MySQL keeps `?`. Placeholders inside strings and comments are left alone.
+pub fn native_placeholders(sql: &str, db_type: &str) -> String {
+    let marker = match db_type {
+        "mssql" => "@P",
+        "postgres" => "$",
+        "oracle" => ":",
+        _ => return sql.to_string(),
+    };
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    let mut n = 0;
+    for t in sql_analysis::tokenize(sql) {
+        if t.kind == TokenKind::Symbol && t.text == "?" {
+            n += 1;
+            out.push_str(&sql[last..t.start]);
+            out.push_str(&format!("{}{}", marker, n));
+            last = t.end;
+        }
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+/// Runs `query` with `?` (or native) placeholders bound to `params` in order.
+#[tauri::command]
+pub async fn execute_query_params(profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, config: DbConfig, query: String, params: Vec<QueryParam>, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let values = params.iter().enumerate().map(|(i, p)| p.to_value(i)).collect::<Result<Vec<_>, _>>()?;
+    let query = native_placeholders(&query, &config.db_type);
+    let options = db::FetchOptions { params: values, ..Default::default() };
+    let started = std::time::Instant::now();
+    let result = db::run_query_with(&config, &query, &options).await;
+    history.record(&config, &query, started.elapsed(), &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(value: &str, ty: &str) -> QueryParam {
+        QueryParam { value: Some(value.to_string()), param_type: Some(ty.to_string()) }
+    }
+
+    #[test]
+    fn test_param_values() {
+        assert_eq!(param("42", "int").to_value(0), Ok(ParamValue::Int(42)));
+        assert_eq!(param("東京 '本社'", "string").to_value(0), Ok(ParamValue::Text("東京 '本社'".to_string())));
+        assert_eq!(param("2024/03/01", "date").to_value(0), Ok(ParamValue::Date(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())));
+        assert_eq!(QueryParam { value: None, param_type: None }.to_value(0), Ok(ParamValue::Null));
+        assert_eq!(param("abc", "int").to_value(1), Err("Tham số #2: 'abc' không phải số nguyên".to_string()));
+    }
+
+    #[test]
+    fn test_native_placeholders() {
+        let sql = "SELECT * FROM t WHERE a = ? AND b = '?' -- ?\nAND c = ?";
+        assert_eq!(native_placeholders(sql, "mssql"), "SELECT * FROM t WHERE a = @P1 AND b = '?' -- ?\nAND c = @P2");
+        assert_eq!(native_placeholders(sql, "postgres"), "SELECT * FROM t WHERE a = $1 AND b = '?' -- ?\nAND c = $2");
+        assert_eq!(native_placeholders(sql, "mysql"), sql);
+    }
+}
@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::result_ops::NULL_CELL;
+use crate::{db, DbConfig, QueryResult};
+
+/// Operators whose own cost is at least this share of the whole plan are highlighted.
+const EXPENSIVE_COST_SHARE: f64 = 0.25;
+const BATCH_OPERATOR: &str = "Batch";
+
+/// Dialect-independent execution plan tree.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PlanNode {
+    pub operator: String,
+    pub detail: Option<String>,
+    pub estimated_rows: Option<f64>,
+    pub actual_rows: Option<f64>,
+    /// Estimated subtree cost (includes children), as reported by the server.
+    pub cost: Option<f64>,
+    #[serde(default)]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn own_cost(&self) -> Option<f64> {
+        let total = self.cost?;
+        let children: f64 = self.children.iter().filter_map(|c| c.cost).sum();
+        Some((total - children).max(0.0))
+    }
+}
+
+fn format_rows(n: f64) -> String {
+    if n.fract() == 0.0 { format!("{}", n as i64) } else { format!("{:.1}", n) }
+}
+
+fn edge_label(node: &PlanNode) -> Option<String> {
+    match (node.estimated_rows, node.actual_rows) {
+        (Some(e), Some(a)) => Some(format!("est {} / act {}", format_rows(e), format_rows(a))),
+        (Some(e), None) => Some(format!("est {}", format_rows(e))),
+        (None, Some(a)) => Some(format!("act {}", format_rows(a))),
+        (None, None) => None,
+    }
+}
+
+struct PlanRenderer {
+    output: String,
+    node_counter: usize,
+    total_cost: Option<f64>,
+}
+
+impl PlanRenderer {
+    fn next_id(&mut self) -> String {
+        self.node_counter += 1;
+        format!("P{}", self.node_counter)
+    }
+
+    fn render(&mut self, node: &PlanNode) -> String {
+        let id = self.next_id();
+        let mut label = node.operator.clone();
+        if let Some(detail) = &node.detail {
+            label.push_str(&format!("<br/>{}", detail));
+        }
+        if let Some(cost) = node.cost {
+            label.push_str(&format!("<br/>cost {:.2}", cost));
+        }
+        let safe_label = label.replace('"', "'").replace('\n', " ");
+
+        let expensive = match (node.own_cost(), self.total_cost) {
+            (Some(own), Some(total)) if total > 0.0 => own / total >= EXPENSIVE_COST_SHARE,
+            _ => false,
+        };
+        let style = if expensive { "external" } else { "internal" };
+        self.output.push_str(&format!("    {}[\"{}\"]:::{}\n", id, safe_label, style));
+
+        // Rows flow from child operators up into their parent
+        for child in &node.children {
+            let child_id = self.render(child);
+            let arrow = match edge_label(child) {
+                Some(l) => format!("-->|{}|", l),
+                None => "-->".to_string(),
+            };
+            self.output.push_str(&format!("    {} {} {}\n", child_id, arrow, id));
+        }
+        id
+    }
+}
+
+/// Renders a plan tree as a Mermaid flowchart, reusing the class names of the Java flow diagrams.
+pub fn plan_to_mermaid(plan: &PlanNode) -> String {
+    let mut renderer = PlanRenderer {
+        output: String::from("flowchart BT\n"),
+        node_counter: 0,
+        total_cost: plan.cost,
+    };
+    renderer.render(plan);
+
+    let mut output = renderer.output;
+    output.push_str("  classDef internal fill:#e1f5fe,stroke:#01579b,stroke-width:1px;\n");
+    output.push_str("  classDef external fill:#ffe0b2,stroke:#e65100,stroke-width:1px,stroke-dasharray: 5 5;\n");
+    output
+}
+
+#[tauri::command]
+pub fn generate_plan_mermaid(plan: PlanNode) -> String {
+    plan_to_mermaid(&plan)
+}
+
+/// Estimated plan of a query, without running it.
+#[derive(Serialize, Clone, Debug)]
+pub struct ExplainResult {
+    pub plan: PlanNode,
+    /// Plan as returned by the server (SHOWPLAN XML, EXPLAIN JSON); None for MySQL's tabular EXPLAIN.
+    pub raw: Option<String>,
+}
+
+fn xml_number(node: roxmltree::Node, name: &str) -> Option<f64> {
+    node.attribute(name).and_then(|v| v.parse().ok())
+}
+
+/// The nearest `RelOp` elements below `node`, skipping the operator-specific wrappers between them.
+fn child_relops<'a, 'input>(node: roxmltree::Node<'a, 'input>) -> Vec<roxmltree::Node<'a, 'input>> {
+    let mut found = Vec::new();
+    for child in node.children().filter(|c| c.is_element()) {
+        if child.has_tag_name("RelOp") {
+            found.push(child);
+        } else {
+            found.extend(child_relops(child));
+        }
+    }
+    found
+}
+
+/// `Object` referenced by the operator itself (not by a nested RelOp), as `table.index`.
+fn relop_object(relop: roxmltree::Node) -> Option<String> {
+    let mut pending: Vec<roxmltree::Node> = relop.children().filter(|c| c.is_element() && !c.has_tag_name("RelOp")).collect();
+    while let Some(node) = pending.pop() {
+        if node.has_tag_name("Object") {
+            let trim = |s: &str| s.trim_matches(|c| c == '[' || c == ']').to_string();
+            let parts: Vec<String> = ["Table", "Index"].iter().filter_map(|a| node.attribute(*a)).map(trim).collect();
+            if !parts.is_empty() {
+                return Some(parts.join("."));
+            }
+        }
+        pending.extend(node.children().filter(|c| c.is_element() && !c.has_tag_name("RelOp")));
+    }
+    None
+}
+
+fn relop_node(relop: roxmltree::Node) -> PlanNode {
+    let operator = relop.attribute("PhysicalOp").or_else(|| relop.attribute("LogicalOp")).unwrap_or("?").to_string();
+    PlanNode {
+        operator,
+        detail: relop_object(relop),
+        estimated_rows: xml_number(relop, "EstimateRows"),
+        actual_rows: None,
+        cost: xml_number(relop, "EstimatedTotalSubtreeCost"),
+        children: child_relops(relop).into_iter().map(relop_node).collect(),
+    }
+}
+
+/// One statement as is; several under a "Batch" node carrying their total cost.
+fn batch_node(statements: Vec<PlanNode>) -> Result<PlanNode, String> {
+    match statements.len() {
+        0 => Err("SHOWPLAN không trả về câu lệnh nào".to_string()),
+        1 => Ok(statements.into_iter().next().unwrap()),
+        _ => Ok(PlanNode {
+            operator: BATCH_OPERATOR.to_string(),
+            cost: Some(statements.iter().filter_map(|s| s.cost).sum()),
+            children: statements,
+            ..Default::default()
+        }),
+    }
+}
+
+/// Converts `SET SHOWPLAN_XML ON` output. A batch with several statements becomes a
+/// "Batch" node with one child per statement.
+pub fn parse_showplan_xml(xml: &str) -> Result<PlanNode, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| format!("Không đọc được SHOWPLAN XML: {}", e))?;
+    let statements: Vec<PlanNode> = doc.descendants()
+        .filter(|n| n.has_tag_name("StmtSimple"))
+        .map(|stmt| PlanNode {
+            operator: stmt.attribute("StatementType").unwrap_or("STATEMENT").to_string(),
+            detail: stmt.attribute("StatementText").map(|t| t.split_whitespace().collect::<Vec<_>>().join(" ")),
+            estimated_rows: xml_number(stmt, "StatementEstRows"),
+            actual_rows: None,
+            cost: xml_number(stmt, "StatementSubTreeCost"),
+            children: child_relops(stmt).into_iter().map(relop_node).collect(),
+        })
+        .collect();
+    batch_node(statements)
+}
+
+fn pg_plan_node(plan: &Value) -> PlanNode {
+    let text = |key: &str| plan.get(key).and_then(Value::as_str);
+    let mut detail: Vec<String> = Vec::new();
+    if let Some(relation) = text("Relation Name") {
+        detail.push(match text("Alias").filter(|a| *a != relation) {
+            Some(alias) => format!("{} {}", relation, alias),
+            None => relation.to_string(),
+        });
+    }
+    if let Some(index) = text("Index Name") {
+        detail.push(format!("index {}", index));
+    }
+    if let Some(join) = text("Join Type") {
+        detail.push(format!("{} join", join.to_lowercase()));
+    }
+    PlanNode {
+        operator: text("Node Type").unwrap_or("?").to_string(),
+        detail: (!detail.is_empty()).then(|| detail.join(", ")),
+        estimated_rows: plan.get("Plan Rows").and_then(Value::as_f64),
+        actual_rows: plan.get("Actual Rows").and_then(Value::as_f64),
+        cost: plan.get("Total Cost").and_then(Value::as_f64),
+        children: plan.get("Plans").and_then(Value::as_array).map(|c| c.iter().map(pg_plan_node).collect()).unwrap_or_default(),
+    }
+}
+
+/// Converts Postgres `EXPLAIN (FORMAT JSON)` output (`[{"Plan": {...}}]`).
+pub fn parse_pg_plan(json: &Value) -> Result<PlanNode, String> {
+    json.get(0).and_then(|p| p.get("Plan")).map(pg_plan_node)
+        .ok_or_else(|| "EXPLAIN không trả về kế hoạch".to_string())
+}
+
+/// MySQL's tabular EXPLAIN has one row per table access in join order; each becomes a child
+/// of a "Query" root.
+pub fn parse_mysql_explain(result: &QueryResult) -> PlanNode {
+    let column = |name: &str| result.columns.iter().position(|c| c.eq_ignore_ascii_case(name));
+    let (select_type, table, access, key, rows, extra) = (column("select_type"), column("table"), column("type"), column("key"), column("rows"), column("Extra"));
+    let cell = |row: &Vec<String>, i: Option<usize>| i.and_then(|i| row.get(i)).filter(|v| !v.is_empty() && *v != NULL_CELL).cloned();
+    let children = result.rows.iter()
+        .map(|row| {
+            let operator = [cell(row, select_type), cell(row, access)].into_iter().flatten().collect::<Vec<_>>().join(" ");
+            let detail: Vec<String> = [cell(row, table), cell(row, key).map(|k| format!("key {}", k)), cell(row, extra)].into_iter().flatten().collect();
+            PlanNode {
+                operator,
+                detail: (!detail.is_empty()).then(|| detail.join(", ")),
+                estimated_rows: cell(row, rows).and_then(|r| r.parse().ok()),
+                ..Default::default()
+            }
+        })
+        .collect();
+    PlanNode { operator: "Query".to_string(), children, ..Default::default() }
+}
+
+async fn explain_mssql(config: &DbConfig, query: &str) -> Result<ExplainResult, String> {
+    let mut client = db::connect_mssql(config).await?;
+    // SHOWPLAN must be alone in its batch, and both go out as plain batches (not sp_executesql)
+    // so the setting holds for the query; the connection is dropped afterwards
+    client.simple_query("SET SHOWPLAN_XML ON").await.map_err(|e| e.to_string())?
+        .into_results().await.map_err(|e| e.to_string())?;
+    let results = client.simple_query(query).await.map_err(|e| e.to_string())?
+        .into_results().await.map_err(|e| e.to_string())?;
+    let xml: Vec<String> = results.iter().flatten()
+        .filter_map(|row| row.try_get::<&str, usize>(0).ok().flatten().map(String::from))
+        .collect();
+    let mut statements = Vec::new();
+    for plan in &xml {
+        match parse_showplan_xml(plan)? {
+            batch if batch.operator == BATCH_OPERATOR => statements.extend(batch.children),
+            statement => statements.push(statement),
+        }
+    }
+    Ok(ExplainResult { plan: batch_node(statements)?, raw: Some(xml.join("\n")) })
+}
+
+async fn explain_postgres(config: &DbConfig, query: &str) -> Result<ExplainResult, String> {
+    let mut conn = db::connect_pg(config).await?;
+    let row = sqlx::query(&format!("EXPLAIN (FORMAT JSON) {}", query))
+        .fetch_one(&mut *conn).await.map_err(|e: sqlx::Error| e.to_string())?;
+    let json: Value = row.try_get(0).map_err(|e: sqlx::Error| e.to_string())?;
+    Ok(ExplainResult { plan: parse_pg_plan(&json)?, raw: Some(json.to_string()) })
+}
+
+/// `EXPLAIN ANALYZE` (or an option list such as `(ANALYZE)`) runs the statement on Postgres and
+/// MySQL, so the query may not start with one.
+fn starts_with_explain_option(query: &str) -> bool {
+    let first = query.trim_start().split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+    query.trim_start().starts_with('(') || first.eq_ignore_ascii_case("analyze") || first.eq_ignore_ascii_case("analyse")
+}
+
+/// Estimated execution plan for `query`: SHOWPLAN_XML on MSSQL, EXPLAIN (FORMAT JSON) on
+/// Postgres, EXPLAIN on MySQL. The query itself is not executed.
+#[tauri::command]
+pub async fn explain_query(profile: State<'_, ActiveProfile>, config: DbConfig, query: String) -> Result<ExplainResult, String> {
+    let query = query.trim().trim_end_matches(';');
+    if starts_with_explain_option(query) {
+        return Err("Chỉ xem kế hoạch ước tính; không dùng ANALYZE".to_string());
+    }
+    profile.check_sql(query, &config)?;
+    match config.db_type.as_str() {
+        "mssql" => explain_mssql(&config, query).await,
+        "postgres" => explain_postgres(&config, query).await,
+        "mysql" => {
+            let result = db::run_query(&config, &format!("EXPLAIN {}", query)).await?;
+            Ok(ExplainResult { plan: parse_mysql_explain(&result), raw: None })
+        }
+        other => Err(format!("Chưa hỗ trợ xem kế hoạch thực thi cho {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_mermaid() {
+        let plan = PlanNode {
+            operator: "Hash Join".to_string(),
+            cost: Some(10.0),
+            estimated_rows: Some(100.0),
+            children: vec![
+                PlanNode { operator: "Seq Scan".to_string(), detail: Some("orders".to_string()), cost: Some(8.0), estimated_rows: Some(5000.0), actual_rows: Some(4800.0), ..Default::default() },
+                PlanNode { operator: "Index Seek".to_string(), cost: Some(0.5), estimated_rows: Some(1.0), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        let mermaid = plan_to_mermaid(&plan);
+        assert!(mermaid.starts_with("flowchart BT"));
+        assert!(mermaid.contains("P2[\"Seq Scan<br/>orders<br/>cost 8.00\"]:::external"));
+        assert!(mermaid.contains("P3[\"Index Seek<br/>cost 0.50\"]:::internal"));
+        assert!(mermaid.contains("P2 -->|est 5000 / act 4800| P1"));
+    }
+
+    #[test]
+    fn test_starts_with_explain_option() {
+        assert!(starts_with_explain_option("ANALYZE DELETE FROM orders"));
+        assert!(starts_with_explain_option("  analyse\nUPDATE orders SET total = 0"));
+        assert!(starts_with_explain_option("(ANALYZE, BUFFERS) DELETE FROM orders"));
+        assert!(!starts_with_explain_option("SELECT * FROM analyze_runs"));
+        assert!(!starts_with_explain_option("analyzed_orders"));
+    }
+
+    #[test]
+    fn test_parse_server_plans() {
+        let xml = r#"<ShowPlanXML xmlns="http://schemas.microsoft.com/sqlserver/2004/07/showplan"><BatchSequence><Batch><Statements>
+            <StmtSimple StatementText="SELECT * FROM dbo.Orders o JOIN dbo.Customers c ON c.Id = o.CustomerId" StatementType="SELECT" StatementSubTreeCost="0.5" StatementEstRows="120">
+              <QueryPlan><RelOp PhysicalOp="Hash Match" LogicalOp="Inner Join" EstimateRows="120" EstimatedTotalSubtreeCost="0.5"><Hash>
+                <RelOp PhysicalOp="Clustered Index Scan" EstimateRows="120" EstimatedTotalSubtreeCost="0.3"><IndexScan><Object Database="[Shop]" Schema="[dbo]" Table="[Orders]" Index="[PK_Orders]" /></IndexScan></RelOp>
+                <RelOp PhysicalOp="Index Seek" EstimateRows="1" EstimatedTotalSubtreeCost="0.1"><IndexScan><Object Table="[Customers]" Index="[IX_Id]" /></IndexScan></RelOp>
+              </Hash></RelOp></QueryPlan>
+            </StmtSimple></Statements></Batch></BatchSequence></ShowPlanXML>"#;
+        let plan = parse_showplan_xml(xml).unwrap();
+        assert_eq!(plan.operator, "SELECT");
+        let join = &plan.children[0];
+        assert_eq!((join.operator.as_str(), join.detail.as_deref(), join.children.len()), ("Hash Match", None, 2));
+        assert_eq!(join.children[0].detail.as_deref(), Some("Orders.PK_Orders"));
+        assert_eq!(join.children[1].estimated_rows, Some(1.0));
+
+        let json: Value = serde_json::from_str(r#"[{"Plan": {"Node Type": "Nested Loop", "Join Type": "Inner", "Total Cost": 8.3, "Plan Rows": 1,
+            "Plans": [{"Node Type": "Index Scan", "Relation Name": "orders", "Alias": "o", "Index Name": "orders_pkey", "Total Cost": 4.1, "Plan Rows": 1}]}}]"#).unwrap();
+        let plan = parse_pg_plan(&json).unwrap();
+        assert_eq!(plan.detail.as_deref(), Some("inner join"));
+        assert_eq!(plan.children[0].detail.as_deref(), Some("orders o, index orders_pkey"));
+    }
+}
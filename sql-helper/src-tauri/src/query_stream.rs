@@ -0,0 +1,73 @@
+use serde::Serialize;
+use tauri::{Manager, State};
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::tasks::BackgroundTasks;
+use crate::{db, query_guard, DbConfig};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RowsEvent {
+    pub stream_id: String,
+    /// Which result set of the batch these rows belong to.
+    pub result_index: usize,
+    /// 0-based, in arrival order.
+    pub batch_index: usize,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StreamDone {
+    pub stream_id: String,
+    pub total_rows: usize,
+    pub error: Option<String>,
+}
+
+/// Runs the query in the background, emitting `query://rows` per batch of rows and a final
+/// `query://done` (also on error). Returns the stream id; cancel it with `cancel_background_task`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn execute_query_stream(window: tauri::Window, tasks: State<'_, BackgroundTasks>, profile: State<'_, ActiveProfile>, config: DbConfig, query: String, batch_size: Option<usize>, row_filter: Option<String>, confirm_dangerous: Option<bool>) -> Result<String, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let stream_id = tasks.next_id("query");
+    let id = stream_id.clone();
+
+    let handle = tauri::async_runtime::spawn(async move {
+        let (sender, mut receiver) = db::batch_channel();
+        let options = db::FetchOptions { row_filter, batch_sink: Some(sender), batch_size: batch_size.unwrap_or(0), ..Default::default() };
+
+        let started = std::time::Instant::now();
+        let run = async {
+            let result = db::run_query_with(&config, &query, &options).await;
+            // Closes the channel so the forwarder below finishes
+            drop(options);
+            result
+        };
+        let forward = async {
+            let (mut batch_index, mut total_rows) = (0, 0);
+            while let Some(batch) = receiver.recv().await {
+                total_rows += batch.rows.len();
+                window.emit("query://rows", RowsEvent { stream_id: id.clone(), result_index: batch.result_index, batch_index, columns: batch.columns, rows: batch.rows }).ok();
+                batch_index += 1;
+            }
+            total_rows
+        };
+        let (result, total_rows) = tokio::join!(run, forward);
+        let error = result.as_ref().err().cloned();
+        window.state::<QueryHistory>().record_summary(&config, &query, started.elapsed(), error.is_none().then_some(total_rows as u64), None, error);
+
+        let error = match result {
+            // No rows at all: still report the columns so the grid can show headers
+            Ok(result) if total_rows == 0 => {
+                window.emit("query://rows", RowsEvent { stream_id: id.clone(), result_index: 0, batch_index: 0, columns: result.columns, rows: Vec::new() }).ok();
+                None
+            }
+            Ok(_) => None,
+            Err(e) => Some(e),
+        };
+        window.emit("query://done", StreamDone { stream_id: id, total_rows, error }).ok();
+    });
+    tasks.register(stream_id.clone(), handle);
+    Ok(stream_id)
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::result_ops::{column_index, NULL_CELL};
+use crate::result_store::ResultStore;
+use crate::{failover, query_guard, DbConfig, QueryResult};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RowChange {
+    pub key: Vec<String>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    pub changed_columns: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Changes to one column across all changed rows.
+#[derive(Serialize, Clone, Debug)]
+pub struct ColumnChangeStats {
+    pub column: String,
+    /// Changed rows in which this column differs.
+    pub changed_rows: usize,
+    /// Most frequent old/new values among those rows, commonest first.
+    pub before_values: Vec<ValueCount>,
+    pub after_values: Vec<ValueCount>,
+    pub distinct_before: usize,
+    pub distinct_after: usize,
+}
+
+const TOP_VALUES: usize = 10;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ResultDiff {
+    pub columns: Vec<String>,
+    pub key_columns: Vec<String>,
+    pub added: Vec<Vec<String>>,
+    pub removed: Vec<Vec<String>>,
+    pub changed: Vec<RowChange>,
+    pub unchanged_count: usize,
+    /// Only columns that changed in at least one row, in column order.
+    pub column_stats: Vec<ColumnChangeStats>,
+}
+
+impl ResultDiff {
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty() || !self.changed.is_empty()
+    }
+}
+
+fn top_values<'a>(values: impl Iterator<Item = &'a String>) -> (Vec<ValueCount>, usize) {
+    let mut counts: HashMap<&String, usize> = HashMap::new();
+    for v in values {
+        *counts.entry(v).or_default() += 1;
+    }
+    let distinct = counts.len();
+    let mut top: Vec<ValueCount> = counts.into_iter().map(|(value, count)| ValueCount { value: value.clone(), count }).collect();
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    top.truncate(TOP_VALUES);
+    (top, distinct)
+}
+
+fn column_stats(columns: &[String], changed: &[RowChange]) -> Vec<ColumnChangeStats> {
+    columns.iter().enumerate().filter_map(|(i, column)| {
+        let rows: Vec<&RowChange> = changed.iter().filter(|c| c.changed_columns.contains(column)).collect();
+        if rows.is_empty() {
+            return None;
+        }
+        let (before_values, distinct_before) = top_values(rows.iter().filter_map(|c| c.before.get(i)));
+        let (after_values, distinct_after) = top_values(rows.iter().filter_map(|c| c.after.get(i)));
+        Some(ColumnChangeStats { column: column.clone(), changed_rows: rows.len(), before_values, after_values, distinct_before, distinct_after })
+    }).collect()
+}
+
+/// Re-orders `before`'s cells into `after`'s column order (missing columns become NULL).
+fn align_rows(before: &QueryResult, columns: &[String]) -> Vec<Vec<String>> {
+    let idx: Vec<Option<usize>> = columns.iter().map(|c| column_index(before, c).ok()).collect();
+    before.rows.iter()
+        .map(|row| idx.iter().map(|i| i.and_then(|i| row.get(i).cloned()).unwrap_or_else(|| NULL_CELL.to_string())).collect())
+        .collect()
+}
+
+/// Aligns rows of two results by `key_columns` and classifies them as added/removed/changed.
+/// Without key columns the whole row is the key, so differences show up as add+remove pairs.
+/// When a key occurs more than once, only its first row takes part in the comparison.
+pub fn diff(before: &QueryResult, after: &QueryResult, key_columns: &[String]) -> Result<ResultDiff, String> {
+    let columns = after.columns.clone();
+    let key_idx: Vec<usize> = if key_columns.is_empty() {
+        (0..columns.len()).collect()
+    } else {
+        key_columns.iter().map(|k| column_index(after, k)).collect::<Result<Vec<_>, _>>()?
+    };
+    for k in key_columns {
+        column_index(before, k)?;
+    }
+
+    let before_rows = align_rows(before, &columns);
+    let key_of = |row: &Vec<String>| -> Vec<String> { key_idx.iter().map(|&i| row.get(i).cloned().unwrap_or_default()).collect() };
+
+    let mut before_map: HashMap<Vec<String>, &Vec<String>> = HashMap::new();
+    for row in &before_rows {
+        before_map.entry(key_of(row)).or_insert(row);
+    }
+
+    let mut result = ResultDiff {
+        columns: columns.clone(),
+        key_columns: key_idx.iter().map(|&i| columns[i].clone()).collect(),
+        ..Default::default()
+    };
+    let mut seen: HashMap<Vec<String>, bool> = HashMap::new();
+
+    for row in &after.rows {
+        let key = key_of(row);
+        if seen.insert(key.clone(), true).is_some() {
+            continue;
+        }
+        match before_map.get(&key) {
+            None => result.added.push(row.clone()),
+            Some(old) => {
+                let changed_columns: Vec<String> = columns.iter().enumerate()
+                    .filter(|(i, _)| old.get(*i) != row.get(*i))
+                    .map(|(_, c)| c.clone())
+                    .collect();
+                if changed_columns.is_empty() {
+                    result.unchanged_count += 1;
+                } else {
+                    result.changed.push(RowChange { key, before: (*old).clone(), after: row.clone(), changed_columns });
+                }
+            }
+        }
+    }
+
+    let mut removed_seen: HashMap<Vec<String>, bool> = HashMap::new();
+    for row in &before_rows {
+        let key = key_of(row);
+        if !seen.contains_key(&key) && removed_seen.insert(key, true).is_none() {
+            result.removed.push(row.clone());
+        }
+    }
+
+    result.column_stats = column_stats(&columns, &result.changed);
+    Ok(result)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiffSummary {
+    pub rows_a: usize,
+    pub rows_b: usize,
+    pub only_in_a: usize,
+    pub only_in_b: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConnectionComparison {
+    pub summary: DiffSummary,
+    /// `removed` = rows only on A, `added` = rows only on B.
+    pub diff: ResultDiff,
+}
+
+pub fn summarize(a: &QueryResult, b: &QueryResult, diff: &ResultDiff) -> DiffSummary {
+    DiffSummary {
+        rows_a: a.rows.len(),
+        rows_b: b.rows.len(),
+        only_in_a: diff.removed.len(),
+        only_in_b: diff.added.len(),
+        changed: diff.changed.len(),
+        unchanged: diff.unchanged_count,
+    }
+}
+
+/// One side of `diff_results`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DiffSource {
+    /// A result kept in the `ResultStore`, e.g. `result_store::LATEST_HANDLE` from before a fix.
+    Held { handle: String },
+    /// Runs the query; its first result set is compared.
+    Query { config: DbConfig, query: String },
+}
+
+/// Through the connection's jump host and failover hosts, like `execute_query`.
+async fn run_on(config: &DbConfig, query: &str) -> Result<QueryResult, String> {
+    failover::connect(config).await?.run_query(query).await
+}
+
+async fn load_source(store: &ResultStore, profile: &ActiveProfile, source: DiffSource) -> Result<Arc<QueryResult>, String> {
+    match source {
+        DiffSource::Held { handle } => store.get(&handle),
+        DiffSource::Query { config, query } => {
+            profile.check_sql(&query, &config)?;
+            query_guard::check(&config, &query, false)?;
+            run_on(&config, &query).await.map(Arc::new)
+        }
+    }
+}
+
+/// Diffs two results aligned by `key_columns` (see `diff`). Queries run one after the other,
+/// `before` first, so both sides can read the same tables.
+#[tauri::command]
+pub async fn diff_results(store: State<'_, ResultStore>, profile: State<'_, ActiveProfile>, before: DiffSource, after: DiffSource, key_columns: Vec<String>) -> Result<ResultDiff, String> {
+    let before = load_source(&store, &profile, before).await.map_err(|e| format!("before: {}", e))?;
+    let after = load_source(&store, &profile, after).await.map_err(|e| format!("after: {}", e))?;
+    diff(&before, &after, &key_columns)
+}
+
+/// Runs the same SQL on two connections concurrently and diffs the results (A = before, B = after).
+/// A comparison has no business changing data, so production connections never take
+/// statements `query_guard` flags.
+#[tauri::command]
+pub async fn compare_across_connections(profile: State<'_, ActiveProfile>, config_a: DbConfig, config_b: DbConfig, query: String, key_columns: Vec<String>) -> Result<ConnectionComparison, String> {
+    for config in [&config_a, &config_b] {
+        profile.check_sql(&query, config)?;
+        query_guard::check(config, &query, false)?;
+    }
+    let (a, b) = tokio::join!(run_on(&config_a, &query), run_on(&config_b, &query));
+    let a = a.map_err(|e| format!("{}: {}", config_a.name, e))?;
+    let b = b.map_err(|e| format!("{}: {}", config_b.name, e))?;
+
+    let diff = diff(&a, &b, &key_columns)?;
+    Ok(ConnectionComparison { summary: summarize(&a, &b, &diff), diff })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(columns: &[&str], rows: &[&[&str]]) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows.iter().map(|r| r.iter().map(|c| c.to_string()).collect()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_by_key() {
+        let before = result(&["ID", "STATUS"], &[&["1", "NEW"], &["2", "NEW"], &["3", "DONE"]]);
+        let after = result(&["ID", "STATUS"], &[&["1", "NEW"], &["2", "RUNNING"], &["4", "NEW"]]);
+        let d = diff(&before, &after, &["ID".to_string()]).unwrap();
+        assert_eq!(d.unchanged_count, 1);
+        assert_eq!(d.added, vec![vec!["4", "NEW"]]);
+        assert_eq!(d.removed, vec![vec!["3", "DONE"]]);
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(d.changed[0].changed_columns, vec!["STATUS"]);
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let before = result(&["ID", "STATUS", "NAME"], &[&["1", "NEW", "a"], &["2", "NEW", "b"], &["3", "DONE", "c"]]);
+        let after = result(&["ID", "STATUS", "NAME"], &[&["1", "DONE", "a"], &["2", "DONE", "b"], &["3", "DONE", "c2"]]);
+        let d = diff(&before, &after, &["ID".to_string()]).unwrap();
+        let names: Vec<&str> = d.column_stats.iter().map(|s| s.column.as_str()).collect();
+        assert_eq!(names, vec!["STATUS", "NAME"]);
+        let status = &d.column_stats[0];
+        assert_eq!(status.changed_rows, 2);
+        assert_eq!(status.before_values, vec![ValueCount { value: "NEW".to_string(), count: 2 }]);
+        assert_eq!(status.after_values, vec![ValueCount { value: "DONE".to_string(), count: 2 }]);
+    }
+
+    #[test]
+    fn test_diff_reordered_columns() {
+        let before = result(&["STATUS", "ID"], &[&["NEW", "1"]]);
+        let after = result(&["ID", "STATUS"], &[&["1", "NEW"]]);
+        let d = diff(&before, &after, &["ID".to_string()]).unwrap();
+        assert!(!d.has_changes());
+    }
+}
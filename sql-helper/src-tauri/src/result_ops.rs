@@ -0,0 +1,514 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::Deserialize;
+use tauri::State;
+use unicode_width::UnicodeWidthStr;
+use crate::filter_expr::RowFilter;
+use crate::result_store::{ResultHandle, ResultStore};
+use crate::sorting::{self, SortMode};
+use crate::QueryResult;
+
+pub const NULL_CELL: &str = "[NULL]";
+
+/// Characters kept per cell in results sent to the grid.
+pub const DEFAULT_MAX_CELL_CHARS: usize = 10_000;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinType {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+/// Pair of column names used to match rows; `right` defaults to `left` when omitted.
+#[derive(Deserialize, Clone, Debug)]
+pub struct JoinKey {
+    pub left: String,
+    pub right: Option<String>,
+}
+
+pub fn column_index(result: &QueryResult, name: &str) -> Result<usize, String> {
+    result.columns.iter()
+        .position(|c| c.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Column not found: {}", name))
+}
+
+fn row_key(row: &[String], indexes: &[usize]) -> Vec<String> {
+    indexes.iter().map(|&i| row.get(i).cloned().unwrap_or_default()).collect()
+}
+
+/// Joins two result sets (typically from different connections) on the given key columns.
+/// Output columns are all columns of `a` followed by the non-key columns of `b`;
+/// names that clash with a column of `a` get a `_2` suffix.
+pub fn join(a: &QueryResult, b: &QueryResult, keys: &[JoinKey], join_type: JoinType) -> Result<QueryResult, String> {
+    if keys.is_empty() {
+        return Err("At least one join key is required".to_string());
+    }
+
+    let mut left_idx = Vec::new();
+    let mut right_idx = Vec::new();
+    for key in keys {
+        left_idx.push(column_index(a, &key.left)?);
+        right_idx.push(column_index(b, key.right.as_deref().unwrap_or(&key.left))?);
+    }
+
+    // Output layout
+    let right_extra: Vec<usize> = (0..b.columns.len()).filter(|i| !right_idx.contains(i)).collect();
+    let mut columns = a.columns.clone();
+    for &i in &right_extra {
+        let name = &b.columns[i];
+        if a.columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            columns.push(format!("{}_2", name));
+        } else {
+            columns.push(name.clone());
+        }
+    }
+
+    // Index right side by key, preserving row order for duplicates
+    let mut right_map: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+    for (i, row) in b.rows.iter().enumerate() {
+        let key = row_key(row, &right_idx);
+        if key.iter().any(|k| k == NULL_CELL) {
+            continue; // NULL never matches, same as SQL
+        }
+        right_map.entry(key).or_default().push(i);
+    }
+
+    let mut matched_right = vec![false; b.rows.len()];
+    let mut rows = Vec::new();
+
+    for left_row in &a.rows {
+        let key = row_key(left_row, &left_idx);
+        match right_map.get(&key) {
+            Some(matches) => {
+                for &ri in matches {
+                    matched_right[ri] = true;
+                    let mut out = left_row.clone();
+                    out.extend(right_extra.iter().map(|&i| b.rows[ri].get(i).cloned().unwrap_or_default()));
+                    rows.push(out);
+                }
+            }
+            None => {
+                if join_type == JoinType::Left || join_type == JoinType::Full {
+                    let mut out = left_row.clone();
+                    out.extend(right_extra.iter().map(|_| NULL_CELL.to_string()));
+                    rows.push(out);
+                }
+            }
+        }
+    }
+
+    if join_type == JoinType::Right || join_type == JoinType::Full {
+        for (ri, right_row) in b.rows.iter().enumerate() {
+            if matched_right[ri] {
+                continue;
+            }
+            // Left columns are NULL except the key columns, which take the right side values
+            let mut out = vec![NULL_CELL.to_string(); a.columns.len()];
+            for (k, &li) in left_idx.iter().enumerate() {
+                out[li] = right_row.get(right_idx[k]).cloned().unwrap_or_default();
+            }
+            out.extend(right_extra.iter().map(|&i| right_row.get(i).cloned().unwrap_or_default()));
+            rows.push(out);
+        }
+    }
+
+    Ok(QueryResult { columns, rows, ..Default::default() })
+}
+
+/// Compares two cell values: NULL sorts first, numbers compare numerically, everything else as text.
+pub fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a == NULL_CELL, b == NULL_CELL) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        _ => {}
+    }
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AggFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Aggregate {
+    pub func: AggFunc,
+    /// `None` means `COUNT(*)`.
+    pub column: Option<String>,
+    pub alias: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub mode: SortMode,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TransformOp {
+    Filter { expression: String },
+    Sort { keys: Vec<SortKey> },
+    GroupBy { columns: Vec<String>, aggregates: Vec<Aggregate> },
+    Project { columns: Vec<String> },
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Computes one aggregate over the given cell values. NULLs are ignored (except for COUNT(*)).
+pub fn aggregate_values<'a>(func: AggFunc, values: impl Iterator<Item = &'a str>, count_all: bool) -> String {
+    let values: Vec<&str> = if count_all { values.collect() } else { values.filter(|v| *v != NULL_CELL).collect() };
+    match func {
+        AggFunc::Count => values.len().to_string(),
+        AggFunc::Sum | AggFunc::Avg => {
+            let nums: Vec<f64> = values.iter().filter_map(|v| v.trim().parse::<f64>().ok()).collect();
+            if nums.is_empty() {
+                return NULL_CELL.to_string();
+            }
+            let sum: f64 = nums.iter().sum();
+            if func == AggFunc::Sum { format_number(sum) } else { format_number(sum / nums.len() as f64) }
+        }
+        AggFunc::Min => values.iter().min_by(|a, b| compare_cells(a, b)).map(|v| v.to_string()).unwrap_or_else(|| NULL_CELL.to_string()),
+        AggFunc::Max => values.iter().max_by(|a, b| compare_cells(a, b)).map(|v| v.to_string()).unwrap_or_else(|| NULL_CELL.to_string()),
+    }
+}
+
+fn group_by(input: &QueryResult, columns: &[String], aggregates: &[Aggregate]) -> Result<QueryResult, String> {
+    let group_idx = columns.iter().map(|c| column_index(input, c)).collect::<Result<Vec<_>, _>>()?;
+    let agg_idx = aggregates.iter()
+        .map(|a| a.column.as_ref().map(|c| column_index(input, c)).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Keep groups in order of first appearance
+    let mut order: Vec<Vec<String>> = Vec::new();
+    let mut groups: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+    for (i, row) in input.rows.iter().enumerate() {
+        let key = row_key(row, &group_idx);
+        groups.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            Vec::new()
+        }).push(i);
+    }
+
+    let mut out_columns: Vec<String> = group_idx.iter().map(|&i| input.columns[i].clone()).collect();
+    for agg in aggregates {
+        let name = agg.alias.clone().unwrap_or_else(|| {
+            let func = format!("{:?}", agg.func).to_uppercase();
+            format!("{}({})", func, agg.column.as_deref().unwrap_or("*"))
+        });
+        out_columns.push(name);
+    }
+
+    let mut rows = Vec::new();
+    for key in order {
+        let members = &groups[&key];
+        let mut out = key.clone();
+        for (agg, idx) in aggregates.iter().zip(&agg_idx) {
+            let value = match idx {
+                Some(ci) => aggregate_values(agg.func, members.iter().map(|&r| input.rows[r].get(*ci).map(|s| s.as_str()).unwrap_or(NULL_CELL)), false),
+                None => aggregate_values(agg.func, members.iter().map(|_| ""), true),
+            };
+            out.push(value);
+        }
+        rows.push(out);
+    }
+
+    Ok(QueryResult { columns: out_columns, rows, ..Default::default() })
+}
+
+/// Applies the transformation pipeline in order and returns the refined result.
+pub fn apply_ops(input: &QueryResult, ops: &[TransformOp]) -> Result<QueryResult, String> {
+    let mut current = QueryResult { columns: input.columns.clone(), rows: input.rows.clone(), ..Default::default() };
+
+    for op in ops {
+        current = match op {
+            TransformOp::Filter { expression } => {
+                let filter = RowFilter::compile(expression, &current.columns)?;
+                let rows = current.rows.into_iter().filter(|r| filter.matches(r)).collect();
+                QueryResult { columns: current.columns, rows, ..Default::default() }
+            }
+            TransformOp::Sort { keys } => {
+                // sort_by is stable, so equal keys keep their original order
+                let mut sorted = QueryResult { columns: current.columns, rows: current.rows, ..Default::default() };
+                sorting::sort_rows(&mut sorted, keys)?;
+                sorted
+            }
+            TransformOp::GroupBy { columns, aggregates } => group_by(&current, columns, aggregates)?,
+            TransformOp::Project { columns } => {
+                let idx = columns.iter().map(|c| column_index(&current, c)).collect::<Result<Vec<_>, _>>()?;
+                let out_columns = idx.iter().map(|&i| current.columns[i].clone()).collect();
+                let rows = current.rows.iter().map(|r| row_key(r, &idx)).collect();
+                QueryResult { columns: out_columns, rows, ..Default::default() }
+            }
+        };
+    }
+
+    Ok(current)
+}
+
+/// Builds a cross-tab: one row per distinct `row_keys` combination and one column per
+/// distinct value of `pivot_column` (in order of first appearance).
+pub fn pivot(input: &QueryResult, row_keys: &[String], pivot_column: &str, value_column: &str, func: AggFunc) -> Result<QueryResult, String> {
+    let key_idx = row_keys.iter().map(|c| column_index(input, c)).collect::<Result<Vec<_>, _>>()?;
+    let pivot_idx = column_index(input, pivot_column)?;
+    let value_idx = column_index(input, value_column)?;
+
+    let mut pivot_values: Vec<String> = Vec::new();
+    let mut pivot_pos: HashMap<String, usize> = HashMap::new();
+    let mut row_order: Vec<Vec<String>> = Vec::new();
+    let mut cells: HashMap<Vec<String>, HashMap<usize, Vec<String>>> = HashMap::new();
+
+    for row in &input.rows {
+        let pv = row.get(pivot_idx).cloned().unwrap_or_default();
+        let pos = *pivot_pos.entry(pv.clone()).or_insert_with(|| {
+            pivot_values.push(pv);
+            pivot_values.len() - 1
+        });
+        let key = row_key(row, &key_idx);
+        let entry = cells.entry(key.clone()).or_insert_with(|| {
+            row_order.push(key);
+            HashMap::new()
+        });
+        entry.entry(pos).or_default().push(row.get(value_idx).cloned().unwrap_or_default());
+    }
+
+    let mut columns: Vec<String> = key_idx.iter().map(|&i| input.columns[i].clone()).collect();
+    columns.extend(pivot_values.iter().cloned());
+
+    let mut rows = Vec::new();
+    for key in row_order {
+        let values = &cells[&key];
+        let mut out = key.clone();
+        for pos in 0..pivot_values.len() {
+            out.push(match values.get(&pos) {
+                Some(v) => aggregate_values(func, v.iter().map(|s| s.as_str()), false),
+                None => NULL_CELL.to_string(),
+            });
+        }
+        rows.push(out);
+    }
+
+    Ok(QueryResult { columns, rows, ..Default::default() })
+}
+
+/// Reverse of `pivot`: turns each of `value_columns` into its own (name, value) row.
+pub fn unpivot(input: &QueryResult, key_columns: &[String], value_columns: &[String], name_column: &str, value_name: &str) -> Result<QueryResult, String> {
+    let key_idx = key_columns.iter().map(|c| column_index(input, c)).collect::<Result<Vec<_>, _>>()?;
+    let value_idx: Vec<usize> = if value_columns.is_empty() {
+        (0..input.columns.len()).filter(|i| !key_idx.contains(i)).collect()
+    } else {
+        value_columns.iter().map(|c| column_index(input, c)).collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut columns: Vec<String> = key_idx.iter().map(|&i| input.columns[i].clone()).collect();
+    columns.push(name_column.to_string());
+    columns.push(value_name.to_string());
+
+    let mut rows = Vec::new();
+    for row in &input.rows {
+        let key = row_key(row, &key_idx);
+        for &vi in &value_idx {
+            let mut out = key.clone();
+            out.push(input.columns[vi].clone());
+            out.push(row.get(vi).cloned().unwrap_or_default());
+            rows.push(out);
+        }
+    }
+
+    Ok(QueryResult { columns, rows, ..Default::default() })
+}
+
+/// Cuts cells longer than `max_chars` characters (multi-megabyte NTEXT/CLOB values) and lists
+/// them in `truncated_cells`; 0 keeps every cell whole.
+pub fn truncate_long_cells(result: &mut QueryResult, max_chars: usize) {
+    if max_chars == 0 {
+        return;
+    }
+    result.truncated_cells = truncate_rows(&mut result.rows, max_chars);
+    for set in result.more_results.iter_mut() {
+        set.truncated_cells = truncate_rows(&mut set.rows, max_chars);
+    }
+}
+
+pub fn truncate_rows(rows: &mut [Vec<String>], max_chars: usize) -> Vec<(usize, usize)> {
+    let mut truncated = Vec::new();
+    for (r, row) in rows.iter_mut().enumerate() {
+        for (c, cell) in row.iter_mut().enumerate() {
+            if let Some((end, _)) = cell.char_indices().nth(max_chars) {
+                cell.truncate(end);
+                truncated.push((r, c));
+            }
+        }
+    }
+    truncated
+}
+
+/// Columns `text` takes in a monospaced grid: full-width characters (kanji, kana, full-width
+/// letters) count 2, half-width kana 1; for multi-line text, its widest line.
+pub fn display_width(text: &str) -> usize {
+    text.lines().map(UnicodeWidthStr::width).max().unwrap_or(0)
+}
+
+/// Widest header or cell of each column, as `display_width` measures it.
+pub fn display_widths(columns: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    columns.iter().enumerate()
+        .map(|(c, name)| rows.iter().filter_map(|row| row.get(c)).map(|cell| display_width(cell)).fold(display_width(name), usize::max))
+        .collect()
+}
+
+/// Fills `display_widths` of the result and of each further result set.
+pub fn set_display_widths(result: &mut QueryResult) {
+    result.display_widths = display_widths(&result.columns, &result.rows);
+    for set in result.more_results.iter_mut() {
+        set.display_widths = display_widths(&set.columns, &set.rows);
+    }
+}
+
+#[tauri::command]
+pub fn join_results(result_a: QueryResult, result_b: QueryResult, keys: Vec<JoinKey>, join_type: JoinType) -> Result<QueryResult, String> {
+    join(&result_a, &result_b, &keys, join_type)
+}
+
+/// Runs `ops` over a held result and stores the output under a new handle.
+#[tauri::command]
+pub fn transform_result(store: State<'_, ResultStore>, handle: String, ops: Vec<TransformOp>) -> Result<ResultHandle, String> {
+    let input = store.get(&handle)?;
+    let output = apply_ops(&input, &ops)?;
+    Ok(store.insert(output))
+}
+
+#[tauri::command]
+pub fn pivot_result(store: State<'_, ResultStore>, handle: String, row_keys: Vec<String>, pivot_column: String, value_column: String, aggregate: AggFunc) -> Result<ResultHandle, String> {
+    let input = store.get(&handle)?;
+    let output = pivot(&input, &row_keys, &pivot_column, &value_column, aggregate)?;
+    Ok(store.insert(output))
+}
+
+#[tauri::command]
+pub fn unpivot_result(store: State<'_, ResultStore>, handle: String, key_columns: Vec<String>, value_columns: Option<Vec<String>>, name_column: Option<String>, value_name: Option<String>) -> Result<ResultHandle, String> {
+    let input = store.get(&handle)?;
+    let output = unpivot(
+        &input,
+        &key_columns,
+        &value_columns.unwrap_or_default(),
+        name_column.as_deref().unwrap_or("COLUMN_NAME"),
+        value_name.as_deref().unwrap_or("VALUE"),
+    )?;
+    Ok(store.insert(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(columns: &[&str], rows: &[&[&str]]) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows.iter().map(|r| r.iter().map(|c| c.to_string()).collect()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_join_types() {
+        let a = result(&["ID", "NAME"], &[&["1", "A"], &["2", "B"]]);
+        let b = result(&["ID", "NAME"], &[&["2", "B2"], &["3", "C2"]]);
+        let keys = vec![JoinKey { left: "id".to_string(), right: None }];
+
+        let inner = join(&a, &b, &keys, JoinType::Inner).unwrap();
+        assert_eq!(inner.columns, vec!["ID", "NAME", "NAME_2"]);
+        assert_eq!(inner.rows, vec![vec!["2", "B", "B2"]]);
+
+        let full = join(&a, &b, &keys, JoinType::Full).unwrap();
+        assert_eq!(full.rows.len(), 3);
+        assert_eq!(full.rows[0], vec!["1", "A", NULL_CELL]);
+        assert_eq!(full.rows[2], vec!["3", NULL_CELL, "C2"]);
+    }
+
+    #[test]
+    fn test_transform_pipeline() {
+        let input = result(&["DEPT", "NAME", "SALARY"], &[
+            &["A", "x", "100"],
+            &["B", "y", "50"],
+            &["A", "z", "300"],
+            &["B", "w", "9"],
+        ]);
+        let ops = vec![
+            TransformOp::Filter { expression: "SALARY > 10".to_string() },
+            TransformOp::GroupBy {
+                columns: vec!["DEPT".to_string()],
+                aggregates: vec![
+                    Aggregate { func: AggFunc::Count, column: None, alias: Some("CNT".to_string()) },
+                    Aggregate { func: AggFunc::Sum, column: Some("SALARY".to_string()), alias: None },
+                ],
+            },
+            TransformOp::Sort { keys: vec![SortKey { column: "SUM(SALARY)".to_string(), descending: true, mode: SortMode::Auto }] },
+        ];
+        let out = apply_ops(&input, &ops).unwrap();
+        assert_eq!(out.columns, vec!["DEPT", "CNT", "SUM(SALARY)"]);
+        assert_eq!(out.rows, vec![vec!["A", "2", "400"], vec!["B", "1", "50"]]);
+    }
+
+    #[test]
+    fn test_pivot_roundtrip() {
+        let tall = result(&["YEAR", "MONTH", "SALES"], &[
+            &["2024", "JAN", "10"],
+            &["2024", "FEB", "20"],
+            &["2025", "JAN", "5"],
+            &["2024", "JAN", "1"],
+        ]);
+        let wide = pivot(&tall, &["YEAR".to_string()], "MONTH", "SALES", AggFunc::Sum).unwrap();
+        assert_eq!(wide.columns, vec!["YEAR", "JAN", "FEB"]);
+        assert_eq!(wide.rows, vec![vec!["2024", "11", "20"], vec!["2025", "5", NULL_CELL]]);
+
+        let back = unpivot(&wide, &["YEAR".to_string()], &[], "MONTH", "SALES").unwrap();
+        assert_eq!(back.columns, vec!["YEAR", "MONTH", "SALES"]);
+        assert_eq!(back.rows.len(), 4);
+        assert_eq!(back.rows[1], vec!["2024", "FEB", "20"]);
+    }
+
+    #[test]
+    fn test_join_unknown_column() {
+        let a = result(&["ID"], &[]);
+        let b = result(&["CODE"], &[]);
+        let keys = vec![JoinKey { left: "ID".to_string(), right: None }];
+        assert!(join(&a, &b, &keys, JoinType::Inner).is_err());
+    }
+
+    #[test]
+    fn test_truncate_long_cells() {
+        let mut input = result(&["ID", "MEMO"], &[&["1", "short"], &["2", "東京都千代田区"]]);
+        truncate_long_cells(&mut input, 5);
+        assert_eq!(input.rows[1], vec!["2", "東京都千代"]);
+        assert_eq!(input.truncated_cells, vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_display_widths() {
+        let mut input = result(&["ID", "住所"], &[&["1", "ﾄｳｷｮｳ"], &["22", "東京都\nA"]]);
+        assert_eq!(display_width("東京都A"), 7);
+        set_display_widths(&mut input);
+        assert_eq!(input.display_widths, vec![2, 6]);
+    }
+}
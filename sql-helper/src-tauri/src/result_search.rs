@@ -0,0 +1,212 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::filter_expr::RowFilter;
+use crate::result_ops::{self, column_index};
+use crate::result_store::ResultStore;
+use crate::QueryResult;
+
+// Ctrl+F and row filtering over a held result: the scan runs here and only match coordinates, or
+// one page of matching rows, go to the webview.
+
+pub const DEFAULT_PAGE_SIZE: usize = 1000;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SearchOptions {
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Treat the pattern as a regular expression.
+    #[serde(default)]
+    pub regex: bool,
+    /// The whole cell must match, not just a substring.
+    #[serde(default)]
+    pub whole_cell: bool,
+    /// Only search these columns; all columns when empty.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Index of the first match to return.
+    #[serde(default)]
+    pub offset: usize,
+    /// Matches per page; 0 means `DEFAULT_PAGE_SIZE`.
+    #[serde(default)]
+    pub limit: usize,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct CellMatch {
+    pub row: usize,
+    pub column: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchPage {
+    /// In row-major order.
+    pub matches: Vec<CellMatch>,
+    pub total_matches: usize,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// A page of the rows that pass `filter_result`.
+#[derive(Serialize, Clone, Debug)]
+pub struct FilteredRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Position of each returned row in the held result.
+    pub row_indices: Vec<usize>,
+    pub total_rows: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    /// (row, column) of the returned cells cut at `max_cell_chars`, as in `QueryResult::truncated_cells`.
+    pub truncated_cells: Vec<(usize, usize)>,
+}
+
+fn build_matcher(pattern: &str, options: &SearchOptions) -> Result<Regex, String> {
+    let body = if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+    let body = if options.whole_cell { format!("^(?:{})$", body) } else { body };
+    RegexBuilder::new(&body)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Biểu thức tìm kiếm không hợp lệ: {}", e))
+}
+
+pub fn search(result: &QueryResult, pattern: &str, options: &SearchOptions) -> Result<SearchPage, String> {
+    if pattern.is_empty() {
+        return Ok(SearchPage { matches: Vec::new(), total_matches: 0, offset: options.offset, has_more: false });
+    }
+    let matcher = build_matcher(pattern, options)?;
+    let columns: Vec<usize> = if options.columns.is_empty() {
+        (0..result.columns.len()).collect()
+    } else {
+        let mut idx = options.columns.iter().map(|c| column_index(result, c)).collect::<Result<Vec<_>, _>>()?;
+        idx.sort_unstable();
+        idx.dedup();
+        idx
+    };
+    let limit = if options.limit == 0 { DEFAULT_PAGE_SIZE } else { options.limit };
+
+    let mut matches = Vec::new();
+    let mut total_matches = 0;
+    for (r, row) in result.rows.iter().enumerate() {
+        for &c in &columns {
+            if row.get(c).map_or(false, |cell| matcher.is_match(cell)) {
+                if total_matches >= options.offset && matches.len() < limit {
+                    matches.push(CellMatch { row: r, column: c });
+                }
+                total_matches += 1;
+            }
+        }
+    }
+    let has_more = options.offset + matches.len() < total_matches;
+    Ok(SearchPage { matches, total_matches, offset: options.offset, has_more })
+}
+
+/// Rows passing the `filter_expr` expression that also contain `pattern` in one of the searched
+/// columns; either may be empty. `offset`/`limit` of the options page through the matching rows.
+/// Matching sees whole cells; the returned ones are cut like the grid's (0 keeps them whole).
+pub fn filter_rows(result: &QueryResult, expression: &str, pattern: &str, options: &SearchOptions, max_cell_chars: usize) -> Result<FilteredRows, String> {
+    let filter = match expression.trim() {
+        "" => None,
+        text => Some(RowFilter::compile(text, &result.columns)?),
+    };
+    let matcher = if pattern.is_empty() { None } else { Some(build_matcher(pattern, options)?) };
+    let columns: Vec<usize> = if options.columns.is_empty() {
+        (0..result.columns.len()).collect()
+    } else {
+        options.columns.iter().map(|c| column_index(result, c)).collect::<Result<Vec<_>, _>>()?
+    };
+    let limit = if options.limit == 0 { DEFAULT_PAGE_SIZE } else { options.limit };
+
+    let mut page = FilteredRows {
+        columns: result.columns.clone(),
+        rows: Vec::new(),
+        row_indices: Vec::new(),
+        total_rows: 0,
+        offset: options.offset,
+        has_more: false,
+        truncated_cells: Vec::new(),
+    };
+    for (r, row) in result.rows.iter().enumerate() {
+        let passes = filter.as_ref().map_or(true, |f| f.matches(row))
+            && matcher.as_ref().map_or(true, |m| columns.iter().any(|&c| row.get(c).map_or(false, |cell| m.is_match(cell))));
+        if passes {
+            if page.total_rows >= options.offset && page.rows.len() < limit {
+                page.rows.push(row.clone());
+                page.row_indices.push(r);
+            }
+            page.total_rows += 1;
+        }
+    }
+    page.has_more = options.offset + page.rows.len() < page.total_rows;
+    if max_cell_chars > 0 {
+        page.truncated_cells = result_ops::truncate_rows(&mut page.rows, max_cell_chars);
+    }
+    Ok(page)
+}
+
+/// Filters a held result (e.g. `result_store::LATEST_HANDLE`) without sending it to the webview.
+#[tauri::command]
+pub fn filter_result(store: State<'_, ResultStore>, handle: String, expression: Option<String>, pattern: Option<String>, options: Option<SearchOptions>, max_cell_chars: Option<usize>) -> Result<FilteredRows, String> {
+    let result = store.get(&handle)?;
+    let max_cell_chars = max_cell_chars.unwrap_or(result_ops::DEFAULT_MAX_CELL_CHARS);
+    filter_rows(&result, expression.as_deref().unwrap_or(""), pattern.as_deref().unwrap_or(""), &options.unwrap_or_default(), max_cell_chars)
+}
+
+#[tauri::command]
+pub fn search_result(store: State<'_, ResultStore>, handle: String, pattern: String, options: Option<SearchOptions>) -> Result<SearchPage, String> {
+    let result = store.get(&handle)?;
+    search(&result, &pattern, &options.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let result = QueryResult {
+            columns: vec!["CODE".to_string(), "NAME".to_string()],
+            rows: vec![
+                vec!["A1".to_string(), "東京支店".to_string()],
+                vec!["a10".to_string(), "大阪".to_string()],
+                vec!["B2".to_string(), "東京本社".to_string()],
+            ],
+            ..Default::default()
+        };
+        let page = search(&result, "a1", &SearchOptions::default()).unwrap();
+        assert_eq!(page.matches, vec![CellMatch { row: 0, column: 0 }, CellMatch { row: 1, column: 0 }]);
+
+        let options = SearchOptions { limit: 1, offset: 1, ..Default::default() };
+        let page = search(&result, "東京", &options).unwrap();
+        assert_eq!((page.total_matches, page.has_more), (2, false));
+        assert_eq!(page.matches, vec![CellMatch { row: 2, column: 1 }]);
+
+        let options = SearchOptions { regex: true, whole_cell: true, case_sensitive: true, columns: vec!["code".to_string()], ..Default::default() };
+        let page = search(&result, "[A-Z]\\d", &options).unwrap();
+        assert_eq!(page.matches, vec![CellMatch { row: 0, column: 0 }, CellMatch { row: 2, column: 0 }]);
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let result = QueryResult {
+            columns: vec!["CODE".to_string(), "NOTE".to_string()],
+            rows: vec![
+                vec!["A1".to_string(), "東京支店".to_string()],
+                vec!["a10".to_string(), "大阪".to_string()],
+                vec!["B2".to_string(), format!("{}東京", "x".repeat(20))],
+            ],
+            ..Default::default()
+        };
+        let page = filter_rows(&result, "CODE LIKE '%1%'", "東京", &SearchOptions::default(), 0).unwrap();
+        assert_eq!((page.row_indices, page.total_rows), (vec![0], 1));
+        let options = SearchOptions { limit: 1, ..Default::default() };
+        let page = filter_rows(&result, "", "東京", &options, 0).unwrap();
+        assert_eq!((page.rows.len(), page.total_rows, page.has_more), (1, 2, true));
+        assert!(filter_rows(&result, "MISSING = 1", "", &options, 0).is_err());
+
+        // Matches past the cut are found; the returned cell is cut like the grid's
+        let page = filter_rows(&result, "CODE = 'B2'", "東京", &SearchOptions::default(), 10).unwrap();
+        assert_eq!(page.row_indices, vec![2]);
+        assert_eq!((page.rows[0][1].as_str(), page.truncated_cells), ("xxxxxxxxxx", vec![(0, 1)]));
+    }
+}
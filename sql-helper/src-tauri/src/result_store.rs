@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tauri::State;
+use crate::QueryResult;
+
+/// Where `execute_query` keeps its latest result (the rows it returned, with long cells whole),
+/// replaced on every run. Also visible to local queries as `@latest`.
+pub const LATEST_HANDLE: &str = "@latest";
+
+/// Result sets held in the backend so large results can be refined/paged
+/// without shipping every row through the webview.
+#[derive(Default)]
+pub struct ResultStore {
+    results: Mutex<HashMap<String, Arc<QueryResult>>>,
+    counter: AtomicU64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ResultHandle {
+    pub handle: String,
+    pub columns: Vec<String>,
+    pub row_count: usize,
+}
+
+impl ResultStore {
+    pub fn insert(&self, result: QueryResult) -> ResultHandle {
+        let id = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let handle = format!("result-{}", id);
+        self.insert_as(&handle, result)
+    }
+
+    /// Stores a result under a fixed handle, replacing any previous one.
+    pub fn insert_as(&self, handle: &str, result: QueryResult) -> ResultHandle {
+        let info = ResultHandle {
+            handle: handle.to_string(),
+            columns: result.columns.clone(),
+            row_count: result.rows.len(),
+        };
+        self.results.lock().unwrap().insert(handle.to_string(), Arc::new(result));
+        info
+    }
+
+    pub fn get(&self, handle: &str) -> Result<Arc<QueryResult>, String> {
+        self.results.lock().unwrap()
+            .get(handle)
+            .cloned()
+            .ok_or_else(|| format!("Result handle not found: {}", handle))
+    }
+
+    pub fn remove(&self, handle: &str) -> bool {
+        self.results.lock().unwrap().remove(handle).is_some()
+    }
+}
+
+#[tauri::command]
+pub fn hold_result(store: State<'_, ResultStore>, result: QueryResult) -> ResultHandle {
+    store.insert(result)
+}
+
+#[tauri::command]
+pub fn get_result_page(store: State<'_, ResultStore>, handle: String, offset: usize, limit: usize) -> Result<QueryResult, String> {
+    let result = store.get(&handle)?;
+    let rows = result.rows.iter().skip(offset).take(limit).cloned().collect();
+    Ok(QueryResult { columns: result.columns.clone(), rows, ..Default::default() })
+}
+
+#[tauri::command]
+pub fn release_result(store: State<'_, ResultStore>, handle: String) -> bool {
+    store.remove(&handle)
+}
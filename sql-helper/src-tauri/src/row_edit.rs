@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::dialect;
+use crate::profiles::{ActiveProfile, Permission};
+use crate::result_ops::NULL_CELL;
+use crate::{db, schema, DbConfig};
+
+/// A grid edit against a single-table result. `original` is the row as it was fetched
+/// (in result column order); `values` maps column name to its new value (`None` = NULL).
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RowEdit {
+    Update { original: Vec<String>, values: HashMap<String, Option<String>> },
+    Insert { values: HashMap<String, Option<String>> },
+    Delete { original: Vec<String> },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EditPreview {
+    pub table: String,
+    pub key_columns: Vec<String>,
+    pub statements: Vec<String>,
+}
+
+fn literal(db_type: &str, value: Option<&str>) -> String {
+    match value {
+        None => "NULL".to_string(),
+        Some(v) if v == NULL_CELL => "NULL".to_string(),
+        // Quoted even for numbers: every supported server converts string literals implicitly
+        Some(v) => dialect::quote_string(db_type, v),
+    }
+}
+
+fn where_clause(db_type: &str, columns: &[String], key_columns: &[String], original: &[String]) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for key in key_columns {
+        let idx = columns.iter().position(|c| c.eq_ignore_ascii_case(key))
+            .ok_or_else(|| format!("Cột khóa '{}' không có trong kết quả", key))?;
+        let value = original.get(idx).map(|s| s.as_str()).unwrap_or(NULL_CELL);
+        let ident = dialect::quote_ident(db_type, key);
+        if value == NULL_CELL {
+            parts.push(format!("{} IS NULL", ident));
+        } else {
+            parts.push(format!("{} = {}", ident, literal(db_type, Some(value))));
+        }
+    }
+    Ok(parts.join(" AND "))
+}
+
+/// Generates one UPDATE/INSERT/DELETE per edit. Rows are located by `key_columns` only.
+pub fn generate_statements(db_type: &str, table: &str, columns: &[String], key_columns: &[String], edits: &[RowEdit]) -> Result<Vec<String>, String> {
+    if key_columns.is_empty() {
+        return Err("Bảng không có khóa chính, không thể sinh câu lệnh sửa dữ liệu".to_string());
+    }
+    let table_ident = dialect::quote_qualified(db_type, table);
+    let mut statements = Vec::new();
+
+    for edit in edits {
+        let sql = match edit {
+            RowEdit::Update { original, values } => {
+                if values.is_empty() {
+                    continue;
+                }
+                // Column order follows the result so the generated SQL is stable
+                let sets: Vec<String> = columns.iter()
+                    .filter_map(|c| values.get(c).map(|v| format!("{} = {}", dialect::quote_ident(db_type, c), literal(db_type, v.as_deref()))))
+                    .collect();
+                if sets.len() != values.len() {
+                    let unknown: Vec<&String> = values.keys().filter(|k| !columns.contains(*k)).collect();
+                    return Err(format!("Cột không tồn tại trong kết quả: {:?}", unknown));
+                }
+                format!("UPDATE {} SET {} WHERE {}", table_ident, sets.join(", "), where_clause(db_type, columns, key_columns, original)?)
+            }
+            RowEdit::Insert { values } => {
+                let cols: Vec<&String> = columns.iter().filter(|c| values.contains_key(*c)).collect();
+                if cols.is_empty() {
+                    continue;
+                }
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table_ident,
+                    cols.iter().map(|c| dialect::quote_ident(db_type, c)).collect::<Vec<_>>().join(", "),
+                    cols.iter().map(|c| literal(db_type, values[*c].as_deref())).collect::<Vec<_>>().join(", ")
+                )
+            }
+            RowEdit::Delete { original } => {
+                format!("DELETE FROM {} WHERE {}", table_ident, where_clause(db_type, columns, key_columns, original)?)
+            }
+        };
+        statements.push(sql);
+    }
+    Ok(statements)
+}
+
+/// Generates the DML for review. When `key_columns` is not given, the table's primary key is looked up.
+#[tauri::command]
+pub async fn preview_row_edits(config: DbConfig, table: String, columns: Vec<String>, key_columns: Option<Vec<String>>, edits: Vec<RowEdit>) -> Result<EditPreview, String> {
+    let key_columns = match key_columns.filter(|k| !k.is_empty()) {
+        Some(keys) => keys,
+        None => schema::primary_key_columns(&config, &table).await?,
+    };
+    let statements = generate_statements(&config.db_type, &table, &columns, &key_columns, &edits)?;
+    Ok(EditPreview { table, key_columns, statements })
+}
+
+/// Executes confirmed statements in a single transaction; each must affect exactly one row.
+#[tauri::command]
+pub async fn apply_row_edits(profile: State<'_, ActiveProfile>, config: DbConfig, statements: Vec<String>) -> Result<Vec<u64>, String> {
+    profile.require_for(&config, Permission::ModifyData)?;
+    db::execute_in_transaction(&config, &statements, true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_statements() {
+        let columns = vec!["ID".to_string(), "NAME".to_string(), "NOTE".to_string()];
+        let keys = vec!["ID".to_string()];
+        let original = vec!["7".to_string(), "Old".to_string(), NULL_CELL.to_string()];
+        let edits = vec![
+            RowEdit::Update { original: original.clone(), values: [("NAME".to_string(), Some("新".to_string())), ("NOTE".to_string(), None)].into_iter().collect() },
+            RowEdit::Delete { original },
+            RowEdit::Insert { values: [("ID".to_string(), Some("8".to_string()))].into_iter().collect() },
+        ];
+        let sql = generate_statements("mssql", "dbo.USERS", &columns, &keys, &edits).unwrap();
+        assert_eq!(sql[0], "UPDATE [dbo].[USERS] SET [NAME] = N'新', [NOTE] = NULL WHERE [ID] = N'7'");
+        assert_eq!(sql[1], "DELETE FROM [dbo].[USERS] WHERE [ID] = N'7'");
+        assert_eq!(sql[2], "INSERT INTO [dbo].[USERS] ([ID]) VALUES (N'8')");
+    }
+
+    #[test]
+    fn test_requires_key() {
+        let edits = vec![RowEdit::Delete { original: vec![] }];
+        assert!(generate_statements("mysql", "T", &[], &[], &edits).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::text_normalize::{self, NormalizeOptions};
+use crate::{db, result_ops, DbConfig, QueryResult};
+
+// Safety limit for execute_query: a result stops after `max_rows` rows and comes back `partial`
+// with a continuation handle; fetch_more_rows then reads the next chunk only when asked.
+
+/// Rows per chunk when `AppSettings::max_rows` is not set.
+pub const DEFAULT_MAX_ROWS: usize = 50_000;
+
+/// What is needed to run the query again and skip the rows already shown.
+struct Continuation {
+    config: DbConfig,
+    query: String,
+    row_filter: Option<String>,
+    normalize: Option<NormalizeOptions>,
+    max_cell_chars: usize,
+    next_row: usize,
+    chunk: usize,
+}
+
+/// Open continuations of partial results, by handle.
+#[derive(Default)]
+pub struct Continuations {
+    entries: Mutex<HashMap<String, Continuation>>,
+    counter: AtomicU64,
+}
+
+impl Continuations {
+    fn insert(&self, continuation: Continuation) -> String {
+        let id = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let handle = format!("more-{}", id);
+        self.entries.lock().unwrap().insert(handle.clone(), continuation);
+        handle
+    }
+}
+
+/// `AppSettings::max_rows`; 0 turns the guard off.
+pub fn max_rows(handle: &tauri::AppHandle) -> usize {
+    crate::read_db_settings(handle).ok().and_then(|s| s.max_rows).unwrap_or(DEFAULT_MAX_ROWS)
+}
+
+/// Marks a first chunk that hit the limit as partial and keeps what `fetch_more_rows` needs;
+/// later chunks default to the same size.
+pub fn mark_partial(store: &Continuations, result: &mut QueryResult, config: &DbConfig, query: &str, row_filter: Option<String>, normalize: Option<NormalizeOptions>, max_cell_chars: usize) {
+    let chunk = result.rows.len();
+    result.partial = true;
+    result.continuation = Some(store.insert(Continuation {
+        config: config.clone(),
+        query: query.to_string(),
+        row_filter,
+        normalize,
+        max_cell_chars,
+        next_row: chunk,
+        chunk,
+    }));
+}
+
+/// Next chunk of a partial result. The query runs again and skips the rows already returned,
+/// so rows changed in between can shift. The handle stays valid while more rows remain.
+#[tauri::command]
+pub async fn fetch_more_rows(store: State<'_, Continuations>, profile: State<'_, ActiveProfile>, handle: String, max_rows: Option<usize>) -> Result<QueryResult, String> {
+    let (config, query, options, normalize, max_cell_chars, skip, chunk) = {
+        let entries = store.entries.lock().unwrap();
+        let c = entries.get(&handle).ok_or_else(|| format!("Continuation not found: {}", handle))?;
+        let options = db::FetchOptions { row_filter: c.row_filter.clone(), ..Default::default() };
+        (c.config.clone(), c.query.clone(), options, c.normalize.clone(), c.max_cell_chars, c.next_row, max_rows.filter(|n| *n > 0).unwrap_or(c.chunk))
+    };
+    profile.check_sql(&query, &config)?;
+    let (mut result, has_more) = db::fetch_window(&config, &query, &options, skip, chunk).await?;
+    if let Some(options) = normalize {
+        text_normalize::normalize_result(&mut result, &options);
+    }
+    result_ops::truncate_long_cells(&mut result, max_cell_chars);
+    result_ops::set_display_widths(&mut result);
+
+    let mut entries = store.entries.lock().unwrap();
+    if has_more {
+        if let Some(c) = entries.get_mut(&handle) {
+            c.next_row = skip + chunk;
+        }
+        result.partial = true;
+        result.continuation = Some(handle);
+    } else {
+        entries.remove(&handle);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn release_continuation(store: State<'_, Continuations>, handle: String) -> bool {
+    store.entries.lock().unwrap().remove(&handle).is_some()
+}
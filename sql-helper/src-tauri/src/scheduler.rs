@@ -0,0 +1,528 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use crate::export::{self, CsvExportOptions, CsvSink, XlsxExportOptions};
+use crate::history::QueryHistory;
+use crate::profiles;
+use crate::storage::{self, app_data_subdir, safe_file_name};
+use crate::{db, failover, query_guard, DbConfig};
+
+// Queries run unattended on a cron-like schedule (hourly monitoring checks and the like). Each
+// run writes its rows to a new timestamped CSV/XLSX file in the schedule's output folder and
+// emits `schedule://completed` or `schedule://error`. Schedules are saved under
+// `<app data>/schedules` and run while the app is open.
+
+const SCHEDULES_DIR: &str = "schedules";
+/// Far enough to find the next run of any valid spec, including Feb 29.
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduledQuery {
+    pub name: String,
+    /// `DbConfig::id` (or name, for connections without an id) in the settings; the password
+    /// is read from the settings at run time.
+    pub connection_id: String,
+    pub query: String,
+    /// See `parse_schedule`.
+    pub schedule: String,
+    /// "csv" (default) or "xlsx".
+    #[serde(default)]
+    pub format: Option<String>,
+    pub output_dir: String,
+    #[serde(default)]
+    pub csv_options: Option<CsvExportOptions>,
+    #[serde(default)]
+    pub xlsx_options: Option<XlsxExportOptions>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_run: Option<ScheduleRun>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduleRun {
+    pub name: String,
+    pub started_at: String,
+    pub elapsed_ms: u128,
+    /// The file written; None when the run failed.
+    pub path: Option<String>,
+    pub rows: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub schedule: ScheduledQuery,
+    /// None for disabled schedules.
+    pub next_run: Option<String>,
+}
+
+/// Names of the schedules currently running, so a slow run is not started again on the next tick.
+#[derive(Default)]
+pub struct RunningSchedules(Mutex<HashSet<String>>);
+
+/// Allowed values of one cron field, indexed from the field's minimum.
+#[derive(Clone, Debug, PartialEq)]
+struct CronField {
+    allowed: Vec<bool>,
+    /// Starts with `*`: an unrestricted day of month / day of week does not take part in the OR.
+    any: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronSpec {
+    minutes: CronField,
+    hours: CronField,
+    days: CronField,
+    months: CronField,
+    weekdays: CronField,
+}
+
+fn parse_field(text: &str, min: u32, max: u32) -> Result<CronField, String> {
+    let invalid = || format!("Trường lịch không hợp lệ: '{}' ({}-{})", text, min, max);
+    let mut allowed = vec![false; (max - min + 1) as usize];
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (a.parse().map_err(|_| invalid())?, b.parse().map_err(|_| invalid())?),
+                // `5/15` runs from 5 to the end of the range
+                None => {
+                    let value: u32 = range.parse().map_err(|_| invalid())?;
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[(value - min) as usize] = true;
+        }
+    }
+    Ok(CronField { allowed, any: text.starts_with('*') })
+}
+
+/// `every 15m` / `every 2h` / `every 1d`, as the equivalent cron steps.
+fn every(spec: &str) -> Result<String, String> {
+    let amount = spec.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let n: u32 = amount.trim().parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("Khoảng lặp không hợp lệ: every {}", spec))?;
+    match spec[amount.len()..].trim() {
+        "m" | "min" | "minutes" if n < 60 => Ok(format!("*/{} * * * *", n)),
+        "h" | "hours" if n < 24 => Ok(format!("0 */{} * * *", n)),
+        "d" | "days" if n < 32 => Ok(format!("0 0 */{} * *", n)),
+        _ => Err(format!("Khoảng lặp không hợp lệ: every {} (dùng m < 60, h < 24 hoặc d < 32)", spec)),
+    }
+}
+
+/// Five cron fields (`minute hour day-of-month month day-of-week`, with `*`, lists, ranges and
+/// `/steps`; Sunday is 0 or 7), `@hourly` / `@daily` / `@weekly` / `@monthly`, or
+/// `every <n>m|h|d`, which steps from the top of the hour/day rather than from the last run.
+pub fn parse_schedule(spec: &str) -> Result<CronSpec, String> {
+    let spec = spec.trim();
+    let expanded = match spec.to_lowercase().as_str() {
+        "@hourly" => "0 * * * *".to_string(),
+        "@daily" => "0 0 * * *".to_string(),
+        "@weekly" => "0 0 * * 0".to_string(),
+        "@monthly" => "0 0 1 * *".to_string(),
+        lower => match lower.strip_prefix("every ") {
+            Some(rest) => every(rest.trim())?,
+            None => spec.to_string(),
+        },
+    };
+    let fields: Vec<&str> = expanded.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!("Lịch cần 5 trường (phút giờ ngày tháng thứ): '{}'", spec));
+    }
+    let mut weekdays = parse_field(fields[4], 0, 7)?;
+    if weekdays.allowed[7] {
+        weekdays.allowed[0] = true;
+    }
+    Ok(CronSpec {
+        minutes: parse_field(fields[0], 0, 59)?,
+        hours: parse_field(fields[1], 0, 23)?,
+        days: parse_field(fields[2], 1, 31)?,
+        months: parse_field(fields[3], 1, 12)?,
+        weekdays,
+    })
+}
+
+impl CronSpec {
+    /// Like cron, when both day of month and day of week are restricted either one matching is enough.
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days.allowed[date.day0() as usize];
+        let weekday = self.weekdays.allowed[date.weekday().num_days_from_sunday() as usize];
+        match (self.days.any, self.weekdays.any) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    pub fn matches(&self, at: NaiveDateTime) -> bool {
+        self.months.allowed[at.month0() as usize]
+            && self.day_matches(at.date())
+            && self.hours.allowed[at.hour() as usize]
+            && self.minutes.allowed[at.minute() as usize]
+    }
+
+    /// First matching minute strictly after `after`.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut at = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let limit = after + ChronoDuration::days(MAX_LOOKAHEAD_DAYS);
+        while at <= limit {
+            if !self.months.allowed[at.month0() as usize] || !self.day_matches(at.date()) {
+                at = at.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !self.hours.allowed[at.hour() as usize] {
+                at = at.with_minute(0)? + ChronoDuration::hours(1);
+            } else if !self.minutes.allowed[at.minute() as usize] {
+                at += ChronoDuration::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+}
+
+/// `<name>_<YYYYmmdd_HHMMSS>.<ext>`
+pub fn output_file_name(name: &str, format: &str, at: NaiveDateTime) -> String {
+    format!("{}_{}.{}", safe_file_name(name), at.format("%Y%m%d_%H%M%S"), format)
+}
+
+fn schedule_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(handle, SCHEDULES_DIR)?.join(format!("{}.json", safe_file_name(name))))
+}
+
+fn load_all(handle: &tauri::AppHandle) -> Result<Vec<ScheduledQuery>, String> {
+    let dir = app_data_subdir(handle, SCHEDULES_DIR)?;
+    let mut schedules = Vec::new();
+    for name in storage::list_json_names(&dir)? {
+        match storage::read_json::<ScheduledQuery>(&dir.join(format!("{}.json", name))) {
+            Ok(schedule) => schedules.push(schedule),
+            Err(e) => eprintln!("Skipping unreadable schedule {}: {}", name, e),
+        }
+    }
+    Ok(schedules)
+}
+
+fn load(handle: &tauri::AppHandle, name: &str) -> Result<ScheduledQuery, String> {
+    let path = schedule_path(handle, name)?;
+    if !path.exists() {
+        return Err(format!("Không tìm thấy lịch chạy: {}", name));
+    }
+    storage::read_json(&path)
+}
+
+fn format_of(schedule: &ScheduledQuery) -> Result<&str, String> {
+    match schedule.format.as_deref().unwrap_or("csv") {
+        format @ ("csv" | "xlsx") => Ok(format),
+        other => Err(format!("Định dạng không hợp lệ: {} (csv hoặc xlsx)", other)),
+    }
+}
+
+fn find_connection(handle: &tauri::AppHandle, connection_id: &str) -> Result<DbConfig, String> {
+    crate::load_db_settings(handle.clone())?
+        .connections
+        .into_iter()
+        .find(|c| if c.id.is_empty() { c.name == connection_id } else { c.id == connection_id })
+        .ok_or_else(|| format!("Không tìm thấy kết nối: {}", connection_id))
+}
+
+/// Only reads run unattended, whatever the profile allows interactively. Nobody is there to
+/// confirm a dangerous statement, so the production guard never counts as confirmed.
+fn check_query(schedule: &ScheduledQuery, config: &DbConfig) -> Result<(), String> {
+    if !profiles::is_read_only_sql(&schedule.query, &config.db_type) {
+        return Err("Lịch chạy chỉ nhận câu lệnh đọc (SELECT)".to_string());
+    }
+    query_guard::check(config, &schedule.query, false)
+}
+
+fn info(schedule: ScheduledQuery) -> ScheduleInfo {
+    let next_run = schedule.enabled
+        .then(|| parse_schedule(&schedule.schedule).ok()?.next_after(chrono::Local::now().naive_local()))
+        .flatten()
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string());
+    ScheduleInfo { schedule, next_run }
+}
+
+/// Where a run's rows go as they arrive. CSV is written batch by batch; a workbook is built in
+/// memory anyway, so xlsx rows are collected (up to Excel's limit) and written at the end.
+enum Output {
+    Csv { sink: CsvSink<BufWriter<File>>, header: bool },
+    Xlsx { columns: Vec<String>, rows: Vec<Vec<String>> },
+}
+
+impl Output {
+    fn open(schedule: &ScheduledQuery, format: &str, path: &Path) -> Result<Self, String> {
+        Ok(match format {
+            "xlsx" => Output::Xlsx { columns: Vec::new(), rows: Vec::new() },
+            _ => {
+                let options = schedule.csv_options.clone().unwrap_or_default();
+                let sink = CsvSink::new(export::create_file(&path.to_string_lossy())?, &options)?;
+                Output::Csv { sink, header: options.include_header.unwrap_or(true) }
+            }
+        })
+    }
+
+    fn batch(&mut self, batch: db::RowBatch) -> Result<(), String> {
+        match self {
+            Output::Csv { sink, header } => {
+                if std::mem::take(header) {
+                    sink.header(&batch.columns)?;
+                }
+                batch.rows.iter().try_for_each(|row| sink.row(row))
+            }
+            Output::Xlsx { columns, rows } => {
+                if rows.len() + batch.rows.len() > export::XLSX_MAX_ROWS {
+                    return Err(format!("Excel chỉ chứa được {} dòng; hãy xuất CSV", export::XLSX_MAX_ROWS));
+                }
+                if columns.is_empty() {
+                    *columns = batch.columns;
+                }
+                rows.extend(batch.rows);
+                Ok(())
+            }
+        }
+    }
+
+    /// Completes the file and returns the rows written; `columns` (from the query) head a file
+    /// without rows.
+    fn finish(self, handle: &tauri::AppHandle, schedule: &ScheduledQuery, path: &Path, columns: Vec<String>) -> Result<u64, String> {
+        match self {
+            Output::Csv { mut sink, header } => {
+                if header {
+                    sink.header(&columns)?;
+                }
+                sink.finish().map(|(rows, _)| rows)
+            }
+            Output::Xlsx { columns: streamed, rows } => {
+                let columns = if streamed.is_empty() { columns } else { streamed };
+                let options = schedule.xlsx_options.clone().unwrap_or_default();
+                export::write_xlsx(&path.to_string_lossy(), &columns, &rows, &[], &options, export::header_color(handle, &options))?;
+                Ok(rows.len() as u64)
+            }
+        }
+    }
+}
+
+async fn execute(handle: &tauri::AppHandle, schedule: &ScheduledQuery, started_at: NaiveDateTime) -> Result<(String, u64), String> {
+    let format = format_of(schedule)?;
+    let config = find_connection(handle, &schedule.connection_id)?;
+    check_query(schedule, &config)?;
+    fs::create_dir_all(&schedule.output_dir).map_err(|e| format!("Không thể tạo thư mục {}: {}", schedule.output_dir, e))?;
+    let path = Path::new(&schedule.output_dir).join(output_file_name(&schedule.name, format, started_at));
+
+    // Rows are streamed into the file, so a large result is never held whole (xlsx aside)
+    let started = Instant::now();
+    let (sender, receiver) = db::batch_channel();
+    let fetch = db::FetchOptions { batch_sink: Some(sender), ..Default::default() };
+    let run = async {
+        let result = match failover::connect(&config).await {
+            Ok(mut served) => served.run_query_with(&schedule.query, &fetch).await,
+            Err(e) => Err(e),
+        };
+        // Closes the channel so the writer below finishes
+        drop(fetch);
+        result
+    };
+    let write = async {
+        // Owned here so a failed write drops it, which stops the query
+        let mut receiver = receiver;
+        let mut output = Output::open(schedule, format, &path)?;
+        let mut rows_received: u64 = 0;
+        while let Some(batch) = receiver.recv().await {
+            if batch.result_index > 0 {
+                continue;
+            }
+            rows_received += batch.rows.len() as u64;
+            output.batch(batch)?;
+        }
+        Ok::<_, String>((output, rows_received))
+    };
+    let (result, written) = tokio::join!(run, write);
+    // A write failure drops the receiver, which the query reports as cancelled; show the cause
+    let error = written.as_ref().err().or(result.as_ref().err()).cloned();
+    let rows = written.as_ref().ok().map(|(_, rows)| *rows).filter(|_| error.is_none());
+    handle.state::<QueryHistory>().record_summary(&config, &schedule.query, started.elapsed(), rows, None, error);
+
+    let finished = match (result, written) {
+        (Ok(result), Ok((output, _))) => {
+            let (job, target, schedule) = (handle.clone(), path.clone(), schedule.clone());
+            tokio::task::spawn_blocking(move || output.finish(&job, &schedule, &target, result.columns))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|finished| finished)
+        }
+        (_, Err(e)) | (Err(e), _) => Err(e),
+    };
+    match finished {
+        Ok(rows) => Ok((path.to_string_lossy().to_string(), rows)),
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Runs one schedule, saves the outcome as its `last_run` and emits it. A schedule that is still
+/// running is not started again.
+async fn run(handle: &tauri::AppHandle, schedule: ScheduledQuery) -> ScheduleRun {
+    let now = chrono::Local::now().naive_local();
+    let started = Instant::now();
+    let outcome = if handle.state::<RunningSchedules>().0.lock().unwrap().insert(schedule.name.clone()) {
+        let outcome = execute(handle, &schedule, now).await;
+        handle.state::<RunningSchedules>().0.lock().unwrap().remove(&schedule.name);
+        outcome
+    } else {
+        Err(format!("Lịch '{}' vẫn đang chạy lần trước", schedule.name))
+    };
+    let (path, rows) = match &outcome {
+        Ok((path, rows)) => (Some(path.clone()), Some(*rows)),
+        Err(_) => (None, None),
+    };
+    let record = ScheduleRun {
+        name: schedule.name.clone(),
+        started_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        elapsed_ms: started.elapsed().as_millis(),
+        path,
+        rows,
+        error: outcome.err(),
+    };
+    // Re-read so an edit saved while the query ran is kept
+    if let Ok(mut saved) = load(handle, &schedule.name) {
+        saved.last_run = Some(record.clone());
+        if let Err(e) = schedule_path(handle, &saved.name).and_then(|p| storage::write_json(&p, &saved)) {
+            eprintln!("Could not save the last run of schedule {}: {}", saved.name, e);
+        }
+    }
+    let event = if record.error.is_some() { "schedule://error" } else { "schedule://completed" };
+    handle.emit_all(event, record.clone()).ok();
+    record
+}
+
+/// Wakes at the start of every minute and starts the enabled schedules due then, each in its own task.
+pub fn start_scheduler(handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_checked: Option<NaiveDateTime> = None;
+        loop {
+            let millis = chrono::Local::now().timestamp_millis().rem_euclid(60_000) as u64;
+            tokio::time::sleep(Duration::from_millis(60_000 - millis)).await;
+            let minute = match chrono::Local::now().naive_local().with_second(0).and_then(|t| t.with_nanosecond(0)) {
+                Some(m) => m,
+                None => continue,
+            };
+            // A wake-up slightly before the boundary would see the same minute twice
+            if last_checked == Some(minute) {
+                continue;
+            }
+            last_checked = Some(minute);
+            let schedules = match load_all(&handle) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Could not read schedules: {}", e);
+                    continue;
+                }
+            };
+            for schedule in schedules.into_iter().filter(|s| s.enabled) {
+                if parse_schedule(&schedule.schedule).map_or(false, |spec| spec.matches(minute)) {
+                    let job = handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        run(&job, schedule).await;
+                    });
+                }
+            }
+        }
+    });
+}
+
+/// Creates or replaces the schedule with this name. The spec, format and connection are checked
+/// here so a broken schedule fails now rather than at its first run.
+#[tauri::command]
+pub fn save_schedule(handle: tauri::AppHandle, mut schedule: ScheduledQuery) -> Result<ScheduleInfo, String> {
+    if schedule.name.trim().is_empty() {
+        return Err("Tên lịch chạy không được để trống".to_string());
+    }
+    if schedule.output_dir.trim().is_empty() {
+        return Err("Chưa chọn thư mục lưu kết quả".to_string());
+    }
+    parse_schedule(&schedule.schedule)?;
+    format_of(&schedule)?;
+    let config = find_connection(&handle, &schedule.connection_id)?;
+    check_query(&schedule, &config)?;
+    let path = schedule_path(&handle, &schedule.name)?;
+    if schedule.last_run.is_none() && path.exists() {
+        schedule.last_run = storage::read_json::<ScheduledQuery>(&path).ok().and_then(|s| s.last_run);
+    }
+    storage::write_json(&path, &schedule)?;
+    Ok(info(schedule))
+}
+
+/// Sorted by name, with the next run time of each enabled schedule.
+#[tauri::command]
+pub fn list_schedules(handle: tauri::AppHandle) -> Result<Vec<ScheduleInfo>, String> {
+    Ok(load_all(&handle)?.into_iter().map(info).collect())
+}
+
+#[tauri::command]
+pub fn delete_schedule(handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    fs::remove_file(schedule_path(&handle, &name)?).map_err(|e| e.to_string())
+}
+
+/// Runs a schedule immediately, enabled or not, and returns the outcome (also emitted as for a
+/// scheduled run).
+#[tauri::command]
+pub async fn run_schedule_now(handle: tauri::AppHandle, name: String) -> Result<ScheduleRun, String> {
+    let schedule = load(&handle, &name)?;
+    Ok(run(&handle, schedule).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let hourly = parse_schedule("@hourly").unwrap();
+        assert!(hourly.matches(at("2024-03-01 10:00")));
+        assert!(!hourly.matches(at("2024-03-01 10:30")));
+        assert_eq!(hourly.next_after(at("2024-03-01 10:00")), Some(at("2024-03-01 11:00")));
+
+        let every = parse_schedule("every 15m").unwrap();
+        assert_eq!(every.next_after(at("2024-03-01 23:50")), Some(at("2024-03-02 00:00")));
+
+        // Weekdays 9:30 and 17:30
+        let office = parse_schedule("30 9,17 * * 1-5").unwrap();
+        assert_eq!(office.next_after(at("2024-03-01 18:00")), Some(at("2024-03-04 09:30")));
+        let leap = parse_schedule("0 0 29 2 *").unwrap();
+        assert_eq!(leap.next_after(at("2024-03-01 00:00")), Some(at("2028-02-29 00:00")));
+        // Day of month or Sunday (7 = 0)
+        let either = parse_schedule("0 8 1 * 7").unwrap();
+        assert_eq!(either.next_after(at("2024-03-01 09:00")), Some(at("2024-03-03 08:00")));
+
+        assert!(parse_schedule("* * *").is_err());
+        assert!(parse_schedule("60 * * * *").is_err());
+        assert!(parse_schedule("*/0 * * * *").is_err());
+        assert!(parse_schedule("every 90m").is_err());
+
+        assert_eq!(output_file_name("Đơn hàng/giờ", "csv", at("2024-03-01 10:00")), "Đơn hàng_giờ_20240301_100000.csv");
+    }
+}
@@ -0,0 +1,300 @@
+use serde::Serialize;
+use crate::dialect;
+use crate::result_ops::NULL_CELL;
+use crate::{db, DbConfig, QueryResult};
+
+/// Splits `schema.table` into its parts (schema is optional).
+pub fn split_table(table: &str) -> (Option<String>, String) {
+    let clean = |s: &str| s.trim().trim_matches(|c| c == '[' || c == ']' || c == '"' || c == '`').to_string();
+    match table.rsplit_once('.') {
+        Some((schema, name)) => (Some(clean(schema)), clean(name)),
+        None => (None, clean(table)),
+    }
+}
+
+fn list_indexes_sql(db_type: &str, table: &str) -> Result<String, String> {
+    let (schema, name) = split_table(table);
+    let sql = match db_type {
+        "mssql" => {
+            let object = dialect::quote_string("mssql", &dialect::quote_qualified("mssql", table));
+            let column_list = |included: u8| format!(
+                "STUFF((SELECT ', ' + c.name + CASE WHEN ic.is_descending_key = 1 THEN ' DESC' ELSE '' END \
+                   FROM sys.index_columns ic JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id \
+                   WHERE ic.object_id = i.object_id AND ic.index_id = i.index_id AND ic.is_included_column = {} \
+                   ORDER BY ic.key_ordinal, ic.index_column_id FOR XML PATH('')), 1, 2, '')",
+                included
+            );
+            format!(
+                "SELECT i.name AS index_name, i.type_desc, i.is_unique, i.is_primary_key, \
+                   {} AS key_columns, {} AS included_columns, i.filter_definition \
+                 FROM sys.indexes i WHERE i.object_id = OBJECT_ID({}) AND i.type > 0 ORDER BY i.index_id",
+                column_list(0), column_list(1), object
+            )
+        }
+        "postgres" => format!(
+            "SELECT indexname AS index_name, indexdef AS definition FROM pg_indexes \
+             WHERE tablename = {} AND schemaname = {} ORDER BY indexname",
+            dialect::quote_string("postgres", &name),
+            schema.map(|s| dialect::quote_string("postgres", &s)).unwrap_or_else(|| "current_schema()".to_string())
+        ),
+        "mysql" => format!(
+            "SELECT INDEX_NAME AS index_name, INDEX_TYPE AS index_type, NON_UNIQUE = 0 AS is_unique, \
+               GROUP_CONCAT(COLUMN_NAME ORDER BY SEQ_IN_INDEX SEPARATOR ', ') AS key_columns \
+             FROM information_schema.STATISTICS \
+             WHERE TABLE_SCHEMA = {} AND TABLE_NAME = {} \
+             GROUP BY INDEX_NAME, INDEX_TYPE, NON_UNIQUE ORDER BY INDEX_NAME",
+            schema.map(|s| dialect::quote_string("mysql", &s)).unwrap_or_else(|| "DATABASE()".to_string()),
+            dialect::quote_string("mysql", &name)
+        ),
+        _ => return Err("Unsupported database type".to_string()),
+    };
+    Ok(sql)
+}
+
+#[tauri::command]
+pub async fn list_indexes(config: DbConfig, table: String) -> Result<QueryResult, String> {
+    let sql = list_indexes_sql(&config.db_type, &table)?;
+    db::run_query(&config, &sql).await
+}
+
+fn primary_key_sql(db_type: &str, table: &str) -> Result<String, String> {
+    let (schema, name) = split_table(table);
+    let sql = match db_type {
+        "mssql" => format!(
+            "SELECT c.name FROM sys.indexes i \
+             JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id \
+             JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id \
+             WHERE i.is_primary_key = 1 AND i.object_id = OBJECT_ID({}) ORDER BY ic.key_ordinal",
+            dialect::quote_string("mssql", &dialect::quote_qualified("mssql", table))
+        ),
+        "postgres" => format!(
+            "SELECT kcu.column_name FROM information_schema.table_constraints tc \
+             JOIN information_schema.key_column_usage kcu \
+               ON kcu.constraint_name = tc.constraint_name AND kcu.table_schema = tc.table_schema \
+             WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_name = {} AND tc.table_schema = {} \
+             ORDER BY kcu.ordinal_position",
+            dialect::quote_string("postgres", &name),
+            schema.map(|s| dialect::quote_string("postgres", &s)).unwrap_or_else(|| "current_schema()".to_string())
+        ),
+        "mysql" => format!(
+            "SELECT COLUMN_NAME FROM information_schema.KEY_COLUMN_USAGE \
+             WHERE CONSTRAINT_NAME = 'PRIMARY' AND TABLE_SCHEMA = {} AND TABLE_NAME = {} ORDER BY ORDINAL_POSITION",
+            schema.map(|s| dialect::quote_string("mysql", &s)).unwrap_or_else(|| "DATABASE()".to_string()),
+            dialect::quote_string("mysql", &name)
+        ),
+        _ => return Err("Unsupported database type".to_string()),
+    };
+    Ok(sql)
+}
+
+pub async fn primary_key_columns(config: &DbConfig, table: &str) -> Result<Vec<String>, String> {
+    let result = db::run_query(config, &primary_key_sql(&config.db_type, table)?).await?;
+    Ok(result.rows.into_iter().filter_map(|r| r.into_iter().next()).collect())
+}
+
+#[tauri::command]
+pub async fn get_primary_key(config: DbConfig, table: String) -> Result<Vec<String>, String> {
+    primary_key_columns(&config, &table).await
+}
+
+fn column_catalog_sql(db_type: &str) -> Result<&'static str, String> {
+    match db_type {
+        "mssql" => Ok("SELECT TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME FROM INFORMATION_SCHEMA.COLUMNS \
+                       ORDER BY TABLE_SCHEMA, TABLE_NAME, ORDINAL_POSITION"),
+        "postgres" => Ok("SELECT table_schema, table_name, column_name FROM information_schema.columns \
+                          WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+                          ORDER BY table_schema, table_name, ordinal_position"),
+        "mysql" => Ok("SELECT TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME FROM information_schema.COLUMNS \
+                       WHERE TABLE_SCHEMA = DATABASE() ORDER BY TABLE_NAME, ORDINAL_POSITION"),
+        _ => Err("Unsupported database type".to_string()),
+    }
+}
+
+/// `(schema, table, column)` for every table and view column in the current database.
+pub async fn column_catalog(config: &DbConfig) -> Result<Vec<(String, String, String)>, String> {
+    let result = db::run_query(config, column_catalog_sql(&config.db_type)?).await?;
+    Ok(result.rows.into_iter()
+        .filter_map(|r| match r.as_slice() {
+            [schema, table, column] => Some((schema.clone(), table.clone(), column.clone())),
+            _ => None,
+        })
+        .collect())
+}
+
+fn list_databases_sql(db_type: &str) -> Result<&'static str, String> {
+    match db_type {
+        "mssql" => Ok("SELECT name FROM sys.databases WHERE state_desc = 'ONLINE' AND HAS_DBACCESS(name) = 1 ORDER BY name"),
+        "postgres" => Ok("SELECT datname FROM pg_database WHERE NOT datistemplate AND datallowconn ORDER BY datname"),
+        "mysql" => Ok("SELECT SCHEMA_NAME FROM information_schema.SCHEMATA ORDER BY SCHEMA_NAME"),
+        _ => Err("Unsupported database type".to_string()),
+    }
+}
+
+/// Database names the login can open, for the connection editor's picker.
+#[tauri::command]
+pub async fn list_databases(config: DbConfig) -> Result<Vec<String>, String> {
+    let sql = list_databases_sql(&config.db_type)?;
+    let result = db::run_query(&config, sql).await?;
+    Ok(result.rows.into_iter().filter_map(|r| r.into_iter().next()).collect())
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SchemaObject {
+    pub schema: String,
+    pub name: String,
+    /// "table", "view" or "procedure".
+    pub kind: String,
+}
+
+fn schema_objects_sql(db_type: &str) -> Result<&'static str, String> {
+    match db_type {
+        "mssql" => Ok("SELECT s.name, o.name, CASE o.type WHEN 'U' THEN 'table' WHEN 'V' THEN 'view' ELSE 'procedure' END \
+                       FROM sys.objects o JOIN sys.schemas s ON s.schema_id = o.schema_id \
+                       WHERE o.type IN ('U', 'V', 'P') AND o.is_ms_shipped = 0 ORDER BY s.name, o.name"),
+        "postgres" => Ok("SELECT table_schema, table_name, CASE table_type WHEN 'VIEW' THEN 'view' ELSE 'table' END \
+                          FROM information_schema.tables WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+                          UNION ALL \
+                          SELECT routine_schema, routine_name, 'procedure' FROM information_schema.routines \
+                          WHERE routine_type = 'PROCEDURE' AND routine_schema NOT IN ('pg_catalog', 'information_schema') \
+                          ORDER BY 1, 2"),
+        "mysql" => Ok("SELECT TABLE_SCHEMA, TABLE_NAME, CASE TABLE_TYPE WHEN 'VIEW' THEN 'view' ELSE 'table' END \
+                       FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() \
+                       UNION ALL \
+                       SELECT ROUTINE_SCHEMA, ROUTINE_NAME, 'procedure' FROM information_schema.ROUTINES \
+                       WHERE ROUTINE_TYPE = 'PROCEDURE' AND ROUTINE_SCHEMA = DATABASE() \
+                       ORDER BY 1, 2"),
+        "oracle" => Ok("SELECT owner, object_name, LOWER(object_type) FROM all_objects \
+                        WHERE object_type IN ('TABLE', 'VIEW', 'PROCEDURE') AND owner = USER ORDER BY owner, object_name"),
+        _ => Err("Unsupported database type".to_string()),
+    }
+}
+
+/// Tables, views and stored procedures of the current database, for the object explorer tree.
+#[tauri::command]
+pub async fn get_schema_objects(config: DbConfig) -> Result<Vec<SchemaObject>, String> {
+    let sql = schema_objects_sql(&config.db_type)?;
+    let result = db::run_query(&config, sql).await?;
+    Ok(result.rows.into_iter()
+        .filter_map(|r| match r.as_slice() {
+            [schema, name, kind] => Some(SchemaObject { schema: schema.clone(), name: name.clone(), kind: kind.clone() }),
+            _ => None,
+        })
+        .collect())
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// Type as written in DDL, with length/precision (`nvarchar(50)`, `numeric(10,2)`).
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+    pub primary_key: bool,
+    /// `schema.table.column` referenced by a foreign key on this column.
+    pub references: Option<String>,
+    /// SQL Server IDENTITY, computed and rowversion columns: the server fills them and bulk
+    /// load does not send them. Always false on other databases.
+    pub generated: bool,
+}
+
+fn describe_table_sql(db_type: &str, table: &str) -> Result<String, String> {
+    let (schema, name) = split_table(table);
+    let sql = match db_type {
+        "mssql" => format!(
+            "SELECT c.name, TYPE_NAME(c.user_type_id) + CASE \
+               WHEN TYPE_NAME(c.user_type_id) IN ('varchar', 'char', 'varbinary', 'binary') \
+                 THEN '(' + IIF(c.max_length = -1, 'max', CAST(c.max_length AS varchar(10))) + ')' \
+               WHEN TYPE_NAME(c.user_type_id) IN ('nvarchar', 'nchar') \
+                 THEN '(' + IIF(c.max_length = -1, 'max', CAST(c.max_length / 2 AS varchar(10))) + ')' \
+               WHEN TYPE_NAME(c.user_type_id) IN ('decimal', 'numeric') \
+                 THEN '(' + CAST(c.precision AS varchar(10)) + ',' + CAST(c.scale AS varchar(10)) + ')' \
+               WHEN TYPE_NAME(c.user_type_id) IN ('time', 'datetime2', 'datetimeoffset') \
+                 THEN '(' + CAST(c.scale AS varchar(10)) + ')' \
+               ELSE '' END, \
+               c.is_nullable, OBJECT_DEFINITION(c.default_object_id), \
+               CASE WHEN EXISTS (SELECT 1 FROM sys.index_columns ic JOIN sys.indexes i ON i.object_id = ic.object_id AND i.index_id = ic.index_id \
+                 WHERE i.is_primary_key = 1 AND ic.object_id = c.object_id AND ic.column_id = c.column_id) THEN 1 ELSE 0 END, \
+               (SELECT TOP 1 OBJECT_SCHEMA_NAME(fk.referenced_object_id) + '.' + OBJECT_NAME(fk.referenced_object_id) + '.' \
+                  + COL_NAME(fk.referenced_object_id, fk.referenced_column_id) \
+                FROM sys.foreign_key_columns fk WHERE fk.parent_object_id = c.object_id AND fk.parent_column_id = c.column_id), \
+               CASE WHEN c.is_identity = 1 OR c.is_computed = 1 OR TYPE_NAME(c.system_type_id) = 'timestamp' THEN 1 ELSE 0 END \
+             FROM sys.columns c WHERE c.object_id = OBJECT_ID({}) ORDER BY c.column_id",
+            dialect::quote_string("mssql", &dialect::quote_qualified("mssql", table))
+        ),
+        "postgres" => format!(
+            "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod), NOT a.attnotnull, pg_get_expr(d.adbin, d.adrelid), \
+               EXISTS (SELECT 1 FROM pg_constraint p WHERE p.conrelid = a.attrelid AND p.contype = 'p' AND a.attnum = ANY(p.conkey)), \
+               (SELECT f.confrelid::regclass::text || '.' || fa.attname::text FROM pg_constraint f \
+                  JOIN pg_attribute fa ON fa.attrelid = f.confrelid AND fa.attnum = f.confkey[array_position(f.conkey, a.attnum)] \
+                WHERE f.conrelid = a.attrelid AND f.contype = 'f' AND a.attnum = ANY(f.conkey) LIMIT 1), false \
+             FROM pg_attribute a LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+             WHERE a.attrelid = {}::regclass AND a.attnum > 0 AND NOT a.attisdropped ORDER BY a.attnum",
+            dialect::quote_string("postgres", &dialect::quote_qualified("postgres", table))
+        ),
+        "mysql" => format!(
+            "SELECT c.COLUMN_NAME, c.COLUMN_TYPE, c.IS_NULLABLE, c.COLUMN_DEFAULT, c.COLUMN_KEY = 'PRI', \
+               (SELECT CONCAT(k.REFERENCED_TABLE_SCHEMA, '.', k.REFERENCED_TABLE_NAME, '.', k.REFERENCED_COLUMN_NAME) \
+                FROM information_schema.KEY_COLUMN_USAGE k \
+                WHERE k.TABLE_SCHEMA = c.TABLE_SCHEMA AND k.TABLE_NAME = c.TABLE_NAME AND k.COLUMN_NAME = c.COLUMN_NAME \
+                  AND k.REFERENCED_TABLE_NAME IS NOT NULL LIMIT 1), 0 \
+             FROM information_schema.COLUMNS c WHERE c.TABLE_SCHEMA = {} AND c.TABLE_NAME = {} ORDER BY c.ORDINAL_POSITION",
+            schema.map(|s| dialect::quote_string("mysql", &s)).unwrap_or_else(|| "DATABASE()".to_string()),
+            dialect::quote_string("mysql", &name)
+        ),
+        "oracle" => format!(
+            "SELECT c.column_name, c.data_type || CASE \
+               WHEN c.data_type IN ('VARCHAR2', 'NVARCHAR2', 'CHAR', 'NCHAR', 'RAW') THEN '(' || c.char_length || ')' \
+               WHEN c.data_type = 'NUMBER' AND c.data_precision IS NOT NULL THEN '(' || c.data_precision || ',' || c.data_scale || ')' END, \
+               c.nullable, c.data_default, \
+               CASE WHEN EXISTS (SELECT 1 FROM all_constraints k JOIN all_cons_columns kc ON kc.owner = k.owner AND kc.constraint_name = k.constraint_name \
+                 WHERE k.constraint_type = 'P' AND k.owner = c.owner AND k.table_name = c.table_name AND kc.column_name = c.column_name) THEN 1 ELSE 0 END, \
+               (SELECT r.owner || '.' || r.table_name || '.' || r.column_name FROM all_constraints f \
+                  JOIN all_cons_columns fc ON fc.owner = f.owner AND fc.constraint_name = f.constraint_name \
+                  JOIN all_cons_columns r ON r.owner = f.r_owner AND r.constraint_name = f.r_constraint_name AND r.position = fc.position \
+                WHERE f.constraint_type = 'R' AND f.owner = c.owner AND f.table_name = c.table_name AND fc.column_name = c.column_name AND ROWNUM = 1), 0 \
+             FROM all_tab_columns c WHERE c.owner = {} AND c.table_name = {} ORDER BY c.column_id",
+            schema.map(|s| dialect::quote_string("oracle", &s.to_uppercase())).unwrap_or_else(|| "USER".to_string()),
+            dialect::quote_string("oracle", &name.to_uppercase())
+        ),
+        _ => return Err("Unsupported database type".to_string()),
+    };
+    Ok(sql)
+}
+
+/// Boolean-ish catalog values: bit, bool, `YES`/`NO`, Oracle `Y`/`N`.
+fn catalog_flag(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "t" | "yes" | "y")
+}
+
+fn catalog_text(value: &str) -> Option<String> {
+    let value = value.trim();
+    (value != NULL_CELL && !value.is_empty()).then(|| value.to_string())
+}
+
+/// Columns of `schema.table` (schema optional) with types, nullability, defaults and keys.
+#[tauri::command]
+pub async fn describe_table(config: DbConfig, schema: Option<String>, table: String) -> Result<Vec<ColumnInfo>, String> {
+    let qualified = match schema.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(schema) => format!("{}.{}", schema, table),
+        None => table.clone(),
+    };
+    let sql = describe_table_sql(&config.db_type, &qualified)?;
+    let result = db::run_query(&config, &sql).await?;
+    if result.rows.is_empty() {
+        return Err(format!("Không tìm thấy bảng: {}", qualified));
+    }
+    Ok(result.rows.into_iter()
+        .filter_map(|r| match r.as_slice() {
+            [name, data_type, nullable, default_value, primary_key, references, generated] => Some(ColumnInfo {
+                name: name.clone(),
+                data_type: data_type.clone(),
+                nullable: catalog_flag(nullable),
+                default_value: catalog_text(default_value),
+                primary_key: catalog_flag(primary_key),
+                references: catalog_text(references),
+                generated: catalog_flag(generated),
+            }),
+            _ => None,
+        })
+        .collect())
+}
@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+use crate::local_query::{self, quote, unique_columns};
+use crate::result_ops::NULL_CELL;
+use crate::result_store::ResultStore;
+use crate::sorting::{self, SortMode};
+use crate::storage::app_data_subdir;
+use crate::QueryResult;
+
+// A local SQLite file (`<app data>/scratchpad/scratchpad.db`) that results are copied into as
+// tables, so they can be queried, joined with each other and looked at again after the server
+// connection is gone. Unlike `local_query`, tables outlive the session.
+
+const SCRATCHPAD_DIR: &str = "scratchpad";
+const SCRATCHPAD_FILE: &str = "scratchpad.db";
+/// One row per saved table; the leading underscore keeps it apart from snapshot names.
+const META_TABLE: &str = "_snapshots";
+/// Above this many significant digits a number does not survive a REAL and is kept as text.
+const MAX_REAL_DIGITS: usize = 15;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ScratchTable {
+    pub name: String,
+    pub saved_at: String,
+    pub connection_name: Option<String>,
+    pub query: Option<String>,
+    pub columns: Vec<String>,
+    /// The source's column types, as in `QueryResult::column_types`.
+    pub column_types: Vec<String>,
+    pub row_count: usize,
+}
+
+fn sqlite_error(e: rusqlite::Error) -> String {
+    format!("Lỗi scratchpad: {}", e)
+}
+
+fn scratchpad_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(handle, SCRATCHPAD_DIR)?.join(SCRATCHPAD_FILE))
+}
+
+pub fn open(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(sqlite_error)?;
+    init(&conn)?;
+    Ok(conn)
+}
+
+fn init(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        &format!("CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY, saved_at TEXT NOT NULL, connection_name TEXT, query TEXT, columns TEXT NOT NULL, column_types TEXT NOT NULL, row_count INTEGER NOT NULL)", META_TABLE),
+        [],
+    ).map_err(sqlite_error)?;
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('_') || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("Tên bảng không hợp lệ: {} (chữ, số, '_'; không bắt đầu bằng '_')", name));
+    }
+    Ok(())
+}
+
+/// Number columns by their type; results built on this side have no types, so there a column
+/// counts when every cell reads back unchanged as a number (codes such as `007` stay text).
+fn numeric_columns(result: &QueryResult, columns: usize) -> Vec<bool> {
+    (0..columns)
+        .map(|i| match result.column_types.get(i).filter(|t| !t.is_empty()) {
+            Some(t) => sorting::mode_for_type(t) == SortMode::Number,
+            None => {
+                let mut cells = result.rows.iter().filter_map(|r| r.get(i)).filter(|c| c.as_str() != NULL_CELL).peekable();
+                cells.peek().is_some() && cells.all(|c| {
+                    c.parse::<i64>().map(|n| n.to_string() == *c)
+                        .or_else(|_| c.parse::<f64>().map(|f| f.is_finite() && f.to_string() == *c))
+                        .unwrap_or(false)
+                })
+            }
+        })
+        .collect()
+}
+
+/// Cells of number columns become INTEGER/REAL so comparisons and SUM work without a CAST;
+/// everything else, dates included, stays text as shown in the grid.
+fn cell_value(cell: &str, numeric: bool) -> Value {
+    if cell == NULL_CELL {
+        return Value::Null;
+    }
+    if numeric {
+        if let Ok(i) = cell.parse::<i64>() {
+            return Value::Integer(i);
+        }
+        let digits = cell.chars().filter(|c| c.is_ascii_digit()).skip_while(|c| *c == '0').count();
+        if digits <= MAX_REAL_DIGITS {
+            if let Ok(f) = cell.parse::<f64>() {
+                return Value::Real(f);
+            }
+        }
+    }
+    Value::Text(cell.to_string())
+}
+
+/// Replaces any table of that name, in one transaction.
+pub fn save(conn: &mut Connection, name: &str, result: &QueryResult, connection_name: Option<&str>, query: Option<&str>) -> Result<ScratchTable, String> {
+    check_name(name)?;
+    let columns = unique_columns(&result.columns);
+    let numeric = numeric_columns(result, columns.len());
+    let table = ScratchTable {
+        name: name.to_string(),
+        saved_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        connection_name: connection_name.map(String::from),
+        query: query.map(String::from),
+        columns: columns.clone(),
+        column_types: result.column_types.clone(),
+        row_count: result.rows.len(),
+    };
+
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    tx.execute(&format!("DROP TABLE IF EXISTS {}", quote(name)), []).map_err(sqlite_error)?;
+    let defs: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+    tx.execute(&format!("CREATE TABLE {} ({})", quote(name), defs.join(", ")), []).map_err(sqlite_error)?;
+    {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut stmt = tx.prepare(&format!("INSERT INTO {} VALUES ({})", quote(name), placeholders)).map_err(sqlite_error)?;
+        for row in &result.rows {
+            let values = (0..columns.len()).map(|i| cell_value(row.get(i).map(String::as_str).unwrap_or(NULL_CELL), numeric[i]));
+            stmt.execute(rusqlite::params_from_iter(values)).map_err(sqlite_error)?;
+        }
+    }
+    tx.execute(
+        &format!("INSERT OR REPLACE INTO {} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", META_TABLE),
+        params![
+            table.name,
+            table.saved_at,
+            table.connection_name,
+            table.query,
+            serde_json::to_string(&table.columns).map_err(|e| e.to_string())?,
+            serde_json::to_string(&table.column_types).map_err(|e| e.to_string())?,
+            table.row_count as i64,
+        ],
+    ).map_err(sqlite_error)?;
+    tx.commit().map_err(sqlite_error)?;
+    Ok(table)
+}
+
+/// Newest first.
+pub fn list(conn: &Connection) -> Result<Vec<ScratchTable>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT name, saved_at, connection_name, query, columns, column_types, row_count FROM {} ORDER BY saved_at DESC, name", META_TABLE))
+        .map_err(sqlite_error)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, i64>(6)?,
+        ))
+    }).map_err(sqlite_error)?;
+    let mut tables = Vec::new();
+    for row in rows {
+        let (name, saved_at, connection_name, query, columns, column_types, row_count) = row.map_err(sqlite_error)?;
+        tables.push(ScratchTable {
+            name,
+            saved_at,
+            connection_name,
+            query,
+            columns: serde_json::from_str(&columns).unwrap_or_default(),
+            column_types: serde_json::from_str(&column_types).unwrap_or_default(),
+            row_count: row_count as usize,
+        });
+    }
+    Ok(tables)
+}
+
+/// Returns whether the table existed.
+pub fn drop_table(conn: &mut Connection, name: &str) -> Result<bool, String> {
+    check_name(name)?;
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    let existed = tx.query_row(&format!("SELECT 1 FROM {} WHERE name = ?1", META_TABLE), [name], |_| Ok(()))
+        .optional()
+        .map_err(sqlite_error)?
+        .is_some();
+    tx.execute(&format!("DROP TABLE IF EXISTS {}", quote(name)), []).map_err(sqlite_error)?;
+    tx.execute(&format!("DELETE FROM {} WHERE name = ?1", META_TABLE), [name]).map_err(sqlite_error)?;
+    tx.commit().map_err(sqlite_error)?;
+    Ok(existed)
+}
+
+/// One SQLite statement. Statements that return no rows (CREATE VIEW, UPDATE, ...) report
+/// `affected_rows` instead.
+pub fn run(conn: &Connection, sql: &str) -> Result<QueryResult, String> {
+    // `changes()` keeps the count of the last INSERT/UPDATE/DELETE, even after DDL
+    let total_changes = || conn.query_row("SELECT total_changes()", [], |row| row.get::<_, i64>(0)).map_err(sqlite_error);
+    let before = total_changes()?;
+    let mut result = local_query::query_rows(conn, sql)?;
+    if result.columns.is_empty() {
+        result.affected_rows = Some((total_changes()? - before) as u64);
+    }
+    Ok(result)
+}
+
+/// Copies a result (given directly or by `ResultStore` handle) into the scratchpad as table
+/// `name`, replacing an earlier one.
+#[tauri::command]
+pub fn save_to_scratchpad(handle: tauri::AppHandle, store: State<'_, ResultStore>, name: String, result: Option<QueryResult>, result_handle: Option<String>, connection_name: Option<String>, query: Option<String>) -> Result<ScratchTable, String> {
+    let result = match (result, result_handle) {
+        (Some(r), _) => r,
+        (None, Some(h)) => (*store.get(&h)?).clone(),
+        (None, None) => return Err("Cần result hoặc result_handle".to_string()),
+    };
+    let mut conn = open(&scratchpad_path(&handle)?)?;
+    save(&mut conn, &name, &result, connection_name.as_deref(), query.as_deref())
+}
+
+#[tauri::command]
+pub fn list_scratchpad_tables(handle: tauri::AppHandle) -> Result<Vec<ScratchTable>, String> {
+    list(&open(&scratchpad_path(&handle)?)?)
+}
+
+#[tauri::command]
+pub fn query_scratchpad(handle: tauri::AppHandle, sql: String) -> Result<QueryResult, String> {
+    run(&open(&scratchpad_path(&handle)?)?, &sql)
+}
+
+#[tauri::command]
+pub fn drop_scratchpad_table(handle: tauri::AppHandle, name: String) -> Result<bool, String> {
+    drop_table(&mut open(&scratchpad_path(&handle)?)?, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratchpad() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        let cells = |rows: &[&[&str]]| rows.iter().map(|r| r.iter().map(|c| c.to_string()).collect()).collect();
+        let orders = QueryResult {
+            columns: vec!["ID".to_string(), "CODE".to_string(), "AMOUNT".to_string()],
+            column_types: vec!["int".to_string(), "varchar".to_string(), "decimal".to_string()],
+            rows: cells(&[&["1", "007", "900.5"], &["2", "010", "1200"], &["3", NULL_CELL, "12345678901234567890"]]),
+            ..Default::default()
+        };
+        let customers = QueryResult {
+            columns: vec!["ORDER_ID".to_string(), "NAME".to_string()],
+            rows: cells(&[&["1", "山田"], &["2", "佐藤"]]),
+            ..Default::default()
+        };
+        save(&mut conn, "orders", &orders, Some("prod"), Some("SELECT * FROM orders")).unwrap();
+        save(&mut conn, "customers", &customers, None, None).unwrap();
+
+        // Numbers compare numerically, text keeps its leading zeros
+        let r = run(&conn, "SELECT o.CODE, c.NAME FROM orders o JOIN customers c ON c.ORDER_ID = o.ID WHERE o.AMOUNT > 1000").unwrap();
+        assert_eq!(r.rows, cells(&[&["010", "佐藤"]]));
+        let r = run(&conn, "SELECT AMOUNT FROM orders WHERE CODE IS NULL").unwrap();
+        assert_eq!(r.rows, cells(&[&["12345678901234567890"]]));
+
+        save(&mut conn, "orders", &customers, None, None).unwrap();
+        let tables = list(&conn).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert!(tables.iter().any(|t| t.name == "orders" && t.columns == vec!["ORDER_ID", "NAME"] && t.query.is_none()));
+
+        assert_eq!(run(&conn, "CREATE VIEW v AS SELECT 1").unwrap().affected_rows, Some(0));
+        assert_eq!(run(&conn, "UPDATE customers SET NAME = NAME").unwrap().affected_rows, Some(2));
+        assert!(drop_table(&mut conn, "orders").unwrap());
+        assert!(!drop_table(&mut conn, "orders").unwrap());
+        assert!(save(&mut conn, "_snapshots", &customers, None, None).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::db::{self, FetchOptions};
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::session_recorder::SessionRecorder;
+use crate::shutdown::ActiveOperations;
+use crate::sql_analysis::{self, ScriptBatch};
+use crate::{import, query_guard, result_ops, sessions, DbConfig, QueryResult};
+
+// Scripts run batch by batch on one dedicated connection, so SET options and #temp tables
+// created by an earlier batch are still there for the later ones.
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ScriptOptions {
+    /// Keep going after a failed batch, as SSMS does. Off by default so a migration stops at
+    /// its first error.
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Confirms DELETE/UPDATE without WHERE, TRUNCATE and DROP on production connections.
+    #[serde(default)]
+    pub confirm_dangerous: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchOutcome {
+    pub index: usize,
+    /// Character offsets of the batch in the script, `end` exclusive.
+    pub start: usize,
+    pub end: usize,
+    /// Result of the last run for `GO n` batches.
+    pub result: Option<QueryResult>,
+    pub error: Option<String>,
+    pub elapsed_ms: u128,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScriptProgress {
+    pub path: String,
+    pub batches_done: usize,
+    pub total_batches: usize,
+    pub failed: usize,
+    /// Error of the batch that just finished, if it failed.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScriptSummary {
+    pub batches: Vec<BatchOutcome>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Set when a failure stopped the script before its last batch.
+    pub stopped: bool,
+    pub elapsed_ms: u128,
+}
+
+/// One run of a batch; the flag is set when a timeout left the connection mid-statement.
+async fn run_once(session: &mut sessions::Session, config: &DbConfig, sql: &str) -> (Result<QueryResult, String>, bool) {
+    let run = sessions::run(session, config, sql, &FetchOptions::default());
+    match db::query_timeout(config) {
+        Some(limit) => match tokio::time::timeout(limit, run).await {
+            Ok(result) => (result, false),
+            Err(_) => (Err(db::query_timeout_error(limit)), true),
+        },
+        None => (run.await, false),
+    }
+}
+
+/// Runs the batches in order on one connection; `on_batch` sees each outcome as it completes.
+/// A timed-out batch always ends the script, since its connection cannot be reused.
+pub async fn run_batches(config: &DbConfig, batches: &[ScriptBatch], options: &ScriptOptions, history: &QueryHistory, recorder: &SessionRecorder, mut on_batch: impl FnMut(&BatchOutcome)) -> Result<ScriptSummary, String> {
+    let started = Instant::now();
+    let mut session = sessions::connect(config).await?;
+    let mut summary = ScriptSummary { batches: Vec::new(), succeeded: 0, failed: 0, stopped: false, elapsed_ms: 0 };
+
+    for (index, batch) in batches.iter().enumerate() {
+        let batch_started = Instant::now();
+        let sql = batch.span.text.as_str();
+        let (mut result, mut connection_lost) = (Ok(QueryResult::default()), false);
+        for _ in 0..batch.repeat {
+            (result, connection_lost) = run_once(&mut session, config, sql).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        recorder.record(config, sql, Vec::new(), result.as_ref().err().cloned());
+        history.record(config, sql, batch_started.elapsed(), &result);
+
+        let failed = result.is_err();
+        let (result, error) = match result {
+            Ok(mut r) => {
+                result_ops::truncate_long_cells(&mut r, result_ops::DEFAULT_MAX_CELL_CHARS);
+                result_ops::set_display_widths(&mut r);
+                (Some(r), None)
+            }
+            Err(e) => (None, Some(e)),
+        };
+        let outcome = BatchOutcome { index, start: batch.span.start, end: batch.span.end, result, error, elapsed_ms: batch_started.elapsed().as_millis() };
+        on_batch(&outcome);
+        summary.batches.push(outcome);
+        if failed {
+            summary.failed += 1;
+            if connection_lost || !options.continue_on_error {
+                summary.stopped = index + 1 < batches.len();
+                break;
+            }
+        } else {
+            summary.succeeded += 1;
+        }
+    }
+    summary.elapsed_ms = started.elapsed().as_millis();
+    Ok(summary)
+}
+
+/// Splits the script into batches (`GO` for MSSQL, statements elsewhere) and runs them in order
+/// on one connection, reporting a result or error per batch.
+#[tauri::command]
+pub async fn execute_script(ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, recorder: State<'_, SessionRecorder>, config: DbConfig, script: String, options: Option<ScriptOptions>) -> Result<ScriptSummary, String> {
+    let options = options.unwrap_or_default();
+    profile.check_sql(&script, &config)?;
+    query_guard::check(&config, &script, options.confirm_dangerous)?;
+    let _op = ops.begin();
+    let batches = sql_analysis::script_batches(&script, &config.db_type);
+    run_batches(&config, &batches, &options, &history, &recorder, |_| {}).await
+}
+
+/// With an explicit `encoding` the file is decoded as `import::decode_text` does; otherwise
+/// UTF-8 (BOM or not) is tried first and Shift-JIS is the fallback.
+fn read_script(path: &str, encoding: Option<&str>) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Không thể đọc file: {}", e))?;
+    if encoding.is_some() {
+        return import::decode_text(&bytes, encoding);
+    }
+    import::decode_text(&bytes, None).or_else(|_| import::decode_text(&bytes, Some("shift_jis")))
+}
+
+/// Runs a .sql file from disk batch by batch (see `execute_script`), emitting
+/// `script://progress` after each batch.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_sql_file(window: tauri::Window, ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, recorder: State<'_, SessionRecorder>, config: DbConfig, path: String, encoding: Option<String>, options: Option<ScriptOptions>) -> Result<ScriptSummary, String> {
+    let options = options.unwrap_or_default();
+    let script = read_script(&path, encoding.as_deref())?;
+    profile.check_sql(&script, &config)?;
+    query_guard::check(&config, &script, options.confirm_dangerous)?;
+    let _op = ops.begin();
+    let batches = sql_analysis::script_batches(&script, &config.db_type);
+    let total_batches = batches.len();
+    let mut failed = 0;
+    run_batches(&config, &batches, &options, &history, &recorder, |outcome| {
+        failed += outcome.error.is_some() as usize;
+        let progress = ScriptProgress { path: path.clone(), batches_done: outcome.index + 1, total_batches, failed, error: outcome.error.clone() };
+        window.emit("script://progress", progress).ok();
+    }).await
+}
@@ -0,0 +1,74 @@
+use crate::DbConfig;
+
+// Connection passwords live in the OS credential store (Windows Credential Manager, macOS
+// Keychain, Secret Service); db_settings.json only keeps `password_ref`, the account name of the
+// entry. Where no store is available the password stays in the file, as before.
+
+const SERVICE: &str = "sql-helper";
+
+fn account(config: &DbConfig) -> String {
+    format!("connection:{}", if config.id.is_empty() { &config.name } else { &config.id })
+}
+
+fn entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, account).map_err(|e| e.to_string())
+}
+
+fn forget(account: &str) {
+    match keyring::Entry::new(SERVICE, account).and_then(|e| e.delete_password()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => eprintln!("Could not remove stored password {}: {}", account, e),
+    }
+}
+
+/// Moves each non-empty password into the credential store, leaving its reference. Returns
+/// whether any password moved.
+pub fn store_passwords(connections: &mut [DbConfig]) -> bool {
+    let mut moved = false;
+    for config in connections.iter_mut().filter(|c| !c.password.is_empty()) {
+        let account = account(config);
+        match entry(&account).and_then(|e| e.set_password(&config.password).map_err(|e| e.to_string())) {
+            Ok(()) => {
+                config.password.clear();
+                config.password_ref = Some(account);
+                moved = true;
+            }
+            Err(e) => {
+                eprintln!("Credential store unavailable, keeping the password of '{}' in the settings file: {}", config.name, e);
+                config.password_ref = None;
+            }
+        }
+    }
+    moved
+}
+
+/// For settings coming back from the UI, whose passwords were resolved on load: stores them and
+/// drops the entries of removed connections. An empty password keeps the stored one, so saving
+/// settings whose passwords could not be resolved loses nothing.
+pub fn save_passwords(previous: &[DbConfig], connections: &mut [DbConfig]) {
+    for config in connections.iter_mut().filter(|c| c.password.is_empty() && c.password_ref.is_none()) {
+        let account = account(config);
+        config.password_ref = previous.iter()
+            .filter_map(|c| c.password_ref.as_deref())
+            .find(|r| *r == account)
+            .map(str::to_string);
+    }
+    store_passwords(connections);
+    for account in previous.iter().filter_map(|c| c.password_ref.as_deref()) {
+        if !connections.iter().any(|c| c.password_ref.as_deref() == Some(account)) {
+            forget(account);
+        }
+    }
+}
+
+/// Fills in the passwords of connections that only hold a reference.
+pub fn resolve_passwords(connections: &mut [DbConfig]) {
+    for config in connections.iter_mut().filter(|c| c.password.is_empty()) {
+        if let Some(account) = &config.password_ref {
+            match entry(account).and_then(|e| e.get_password().map_err(|e| e.to_string())) {
+                Ok(password) => config.password = password,
+                Err(e) => eprintln!("Stored password of '{}' not found: {}", config.name, e),
+            }
+        }
+    }
+}
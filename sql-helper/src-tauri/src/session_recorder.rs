@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::storage::{self, app_data_subdir, safe_file_name};
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+const SESSIONS_DIR: &str = "sessions";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedStatement {
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    pub connection_id: String,
+    pub connection_name: String,
+    pub executed_at: String,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedSession {
+    pub name: String,
+    pub started_at: String,
+    pub statements: Vec<RecordedStatement>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecordingStatus {
+    pub active: bool,
+    pub name: Option<String>,
+    pub statement_count: usize,
+}
+
+/// Captures executed statements while a "unit of work" recording is active.
+#[derive(Default)]
+pub struct SessionRecorder {
+    active: Mutex<Option<RecordedSession>>,
+}
+
+impl SessionRecorder {
+    pub fn record(&self, config: &DbConfig, sql: &str, params: Vec<serde_json::Value>, error: Option<String>) {
+        if let Some(session) = self.active.lock().unwrap().as_mut() {
+            session.statements.push(RecordedStatement {
+                sql: sql.to_string(),
+                params,
+                connection_id: config.id.clone(),
+                connection_name: config.name.clone(),
+                executed_at: now(),
+                error,
+            });
+        }
+    }
+
+    /// Writes the active recording to disk without stopping it (used on shutdown).
+    pub fn save_active(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        let active = self.active.lock().unwrap().clone();
+        match active {
+            Some(session) => save_session(handle, &session),
+            None => Ok(()),
+        }
+    }
+
+    fn status(&self) -> RecordingStatus {
+        let active = self.active.lock().unwrap();
+        RecordingStatus {
+            active: active.is_some(),
+            name: active.as_ref().map(|s| s.name.clone()),
+            statement_count: active.as_ref().map_or(0, |s| s.statements.len()),
+        }
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn save_session(handle: &tauri::AppHandle, session: &RecordedSession) -> Result<(), String> {
+    let dir = app_data_subdir(handle, SESSIONS_DIR)?;
+    storage::write_json(&dir.join(format!("{}.json", safe_file_name(&session.name))), session)
+}
+
+#[tauri::command]
+pub fn start_recording(recorder: State<'_, SessionRecorder>, name: String) -> Result<RecordingStatus, String> {
+    {
+        let mut active = recorder.active.lock().unwrap();
+        if let Some(session) = active.as_ref() {
+            return Err(format!("Đang ghi phiên '{}'", session.name));
+        }
+        *active = Some(RecordedSession { name, started_at: now(), statements: Vec::new() });
+    }
+    Ok(recorder.status())
+}
+
+#[tauri::command]
+pub fn get_recording_status(recorder: State<'_, SessionRecorder>) -> RecordingStatus {
+    recorder.status()
+}
+
+/// Stops recording and saves the session; with `discard` the captured statements are dropped.
+#[tauri::command]
+pub fn stop_recording(handle: tauri::AppHandle, recorder: State<'_, SessionRecorder>, discard: Option<bool>) -> Result<Option<RecordedSession>, String> {
+    let session = recorder.active.lock().unwrap().take();
+    match session {
+        Some(session) if !discard.unwrap_or(false) => {
+            save_session(&handle, &session)?;
+            Ok(Some(session))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub fn list_recorded_sessions(handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    storage::list_json_names(&app_data_subdir(&handle, SESSIONS_DIR)?)
+}
+
+pub fn load_session(handle: &tauri::AppHandle, name: &str) -> Result<RecordedSession, String> {
+    let dir = app_data_subdir(handle, SESSIONS_DIR)?;
+    storage::read_json(&dir.join(format!("{}.json", safe_file_name(name))))
+}
+
+#[tauri::command]
+pub fn load_recorded_session(handle: tauri::AppHandle, name: String) -> Result<RecordedSession, String> {
+    load_session(&handle, &name)
+}
+
+#[tauri::command]
+pub fn delete_recorded_session(handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    let dir = app_data_subdir(&handle, SESSIONS_DIR)?;
+    std::fs::remove_file(dir.join(format!("{}.json", safe_file_name(&name)))).map_err(|e| e.to_string())
+}
+
+/// Replays one statement of a saved session against `config`. The frontend walks the
+/// statements one by one so each can be confirmed before it runs.
+#[tauri::command]
+pub async fn replay_session_statement(handle: tauri::AppHandle, profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, config: DbConfig, name: String, index: usize, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    let session = load_session(&handle, &name)?;
+    let statement = session.statements.get(index)
+        .ok_or_else(|| format!("Không có câu lệnh #{} trong phiên '{}'", index, name))?;
+    profile.check_sql(&statement.sql, &config)?;
+    query_guard::check(&config, &statement.sql, confirm_dangerous.unwrap_or(false))?;
+    let started = std::time::Instant::now();
+    let result = db::run_query(&config, &statement.sql).await;
+    history.record(&config, &statement.sql, started.elapsed(), &result);
+    result
+}
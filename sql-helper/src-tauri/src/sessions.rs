@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tauri::State;
+use crate::db::{self, FetchOptions, MssqlClient};
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::session_recorder::SessionRecorder;
+use crate::ssh_tunnel::{self, Tunnel};
+use crate::{oracle_db, query_guard, result_ops, sql_analysis, DbConfig, QueryResult};
+
+// Session mode: one live connection per connection id, so #temp tables, SET options and
+// session variables survive between queries (execute_query opens a new connection each time).
+
+enum SessionConnection {
+    Mssql(MssqlClient),
+    Sqlx(db::SqlxConnection),
+    // Blocking driver: shared with the blocking pool for each call
+    Oracle(Arc<Mutex<oracle::Connection>>),
+}
+
+/// One live connection; also used on its own by callers that run several statements on the
+/// same connection without registering a session (scripts).
+pub struct Session {
+    conn: SessionConnection,
+    _tunnel: Option<Tunnel>,
+}
+
+/// Bookkeeping lives outside the connection lock so it can be listed while a query runs.
+struct SessionSlot {
+    /// Connection settings the session was opened with; a change reopens it.
+    fingerprint: u64,
+    opened_at: chrono::DateTime<chrono::Local>,
+    query_count: AtomicU64,
+    /// Connected by the first query; concurrent first queries wait for the same connection.
+    session: tokio::sync::OnceCell<tokio::sync::Mutex<Session>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionInfo {
+    pub connection_id: String,
+    pub opened_at: String,
+    pub query_count: u64,
+}
+
+/// Each session sits behind its own async lock, so queries on one connection run one at a time
+/// while different connections stay independent.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: Mutex<HashMap<String, Arc<SessionSlot>>>,
+}
+
+/// Fields that never change what the connection is; every other field (including ones added
+/// later) reopens the session when it changes.
+const NOT_CONNECTION_FIELDS: [&str; 5] = ["name", "verified", "query_timeout_secs", "use_queue", "production"];
+
+fn fingerprint(config: &DbConfig) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut fields = match serde_json::to_value(config) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => Default::default(),
+    };
+    fields.retain(|k, _| !NOT_CONNECTION_FIELDS.contains(&k.as_str()));
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    serde_json::Value::Object(fields).to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+pub async fn connect(config: &DbConfig) -> Result<Session, String> {
+    let (target, tunnel) = ssh_tunnel::tunneled(config).await?;
+    connect_target(&target, tunnel).await
+}
+
+/// `connect` with settings already routed through `tunnel`, which the session then keeps open.
+pub async fn connect_target(target: &DbConfig, tunnel: Option<Tunnel>) -> Result<Session, String> {
+    let conn = match target.db_type.as_str() {
+        "mssql" => SessionConnection::Mssql(db::connect_mssql(target).await?),
+        "oracle" => {
+            let owned = target.clone();
+            let conn = db::with_connect_timeout(target, oracle_db::blocking(move || oracle_db::connect(&owned))).await?;
+            SessionConnection::Oracle(Arc::new(Mutex::new(conn)))
+        }
+        _ => SessionConnection::Sqlx(db::connect_sqlx(target).await?),
+    };
+    Ok(Session { conn, _tunnel: tunnel })
+}
+
+impl SessionSlot {
+    fn new(config: &DbConfig) -> Self {
+        SessionSlot {
+            fingerprint: fingerprint(config),
+            opened_at: chrono::Local::now(),
+            query_count: AtomicU64::new(0),
+            session: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// A failed connect leaves the slot empty, so the next query tries again.
+    async fn session(&self, config: &DbConfig) -> Result<&tokio::sync::Mutex<Session>, String> {
+        self.session.get_or_try_init(|| async { connect(config).await.map(tokio::sync::Mutex::new) }).await
+    }
+}
+
+pub async fn run(session: &mut Session, config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+    let non_query = sql_analysis::is_non_query(query, &config.db_type);
+    let mut result = match &mut session.conn {
+        SessionConnection::Mssql(client) if non_query => {
+            QueryResult { affected_rows: Some(db::execute_mssql(client, query, &options.params).await?), ..Default::default() }
+        }
+        SessionConnection::Mssql(client) => db::fetch_mssql(client, config, query, options).await?,
+        SessionConnection::Sqlx(conn) if non_query => {
+            QueryResult { affected_rows: Some(db::execute_sqlx(conn, query, &options.params).await?), ..Default::default() }
+        }
+        SessionConnection::Sqlx(conn) => db::fetch_sqlx(conn, query, options).await?,
+        SessionConnection::Oracle(conn) => {
+            let (conn, query, options) = (conn.clone(), query.to_string(), options.clone());
+            oracle_db::blocking(move || {
+                let conn = conn.lock().unwrap();
+                if non_query {
+                    let affected = oracle_db::execute_on(&conn, &query, &options.params)?;
+                    Ok(QueryResult { affected_rows: Some(affected), ..Default::default() })
+                } else {
+                    oracle_db::query_on(&conn, &query, &options)
+                }
+            }).await?
+        }
+    };
+    result.statement_kind = Some(sql_analysis::statement_kind(query).to_string());
+    Ok(result)
+}
+
+impl Sessions {
+    /// The connection's slot, replaced when its settings changed. Looked up and inserted under one
+    /// lock, so concurrent queries share a slot (and its connection) instead of each opening one.
+    fn slot(&self, config: &DbConfig) -> Arc<SessionSlot> {
+        let mut map = self.sessions.lock().unwrap();
+        match map.get(&config.id) {
+            Some(slot) if slot.fingerprint == fingerprint(config) => slot.clone(),
+            _ => Self::replace(&mut map, config),
+        }
+    }
+
+    fn replace(map: &mut HashMap<String, Arc<SessionSlot>>, config: &DbConfig) -> Arc<SessionSlot> {
+        let slot = Arc::new(SessionSlot::new(config));
+        map.insert(config.id.clone(), slot.clone());
+        slot
+    }
+
+    /// Drops `slot` unless it was already replaced by a newer one.
+    fn discard(&self, connection_id: &str, slot: &Arc<SessionSlot>) {
+        let mut map = self.sessions.lock().unwrap();
+        if map.get(connection_id).map_or(false, |current| Arc::ptr_eq(current, slot)) {
+            map.remove(connection_id);
+        }
+    }
+
+    /// Runs `query` on the connection's session, opening it on first use. A timed-out query
+    /// leaves the connection mid-statement, so its session is dropped and the next query reconnects.
+    pub async fn execute(&self, config: &DbConfig, query: &str, options: &FetchOptions) -> Result<QueryResult, String> {
+        let slot = self.slot(config);
+        let mut guard = slot.session(config).await?.lock().await;
+        slot.query_count.fetch_add(1, Ordering::SeqCst);
+        match db::query_timeout(config) {
+            Some(limit) => match tokio::time::timeout(limit, run(&mut guard, config, query, options)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    drop(guard);
+                    self.discard(&config.id, &slot);
+                    Err(db::query_timeout_error(limit))
+                }
+            },
+            None => run(&mut guard, config, query, options).await,
+        }
+    }
+
+    pub fn close(&self, connection_id: &str) -> bool {
+        self.sessions.lock().unwrap().remove(connection_id).is_some()
+    }
+}
+
+#[tauri::command]
+pub async fn open_session(sessions: State<'_, Sessions>, config: DbConfig) -> Result<(), String> {
+    let slot = Sessions::replace(&mut sessions.sessions.lock().unwrap(), &config);
+    slot.session(&config).await?;
+    Ok(())
+}
+
+/// Closing drops the connection, which ends the server session (temp tables included).
+#[tauri::command]
+pub fn close_session(sessions: State<'_, Sessions>, connection_id: String) -> bool {
+    sessions.close(&connection_id)
+}
+
+#[tauri::command]
+pub fn list_sessions(sessions: State<'_, Sessions>) -> Vec<SessionInfo> {
+    let map = sessions.sessions.lock().unwrap();
+    let mut list: Vec<SessionInfo> = map.iter()
+        .filter(|(_, s)| s.session.initialized())
+        .map(|(id, s)| SessionInfo {
+            connection_id: id.clone(),
+            opened_at: s.opened_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            query_count: s.query_count.load(Ordering::SeqCst),
+        })
+        .collect();
+    list.sort_by(|a, b| a.connection_id.cmp(&b.connection_id));
+    list
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_session_query(sessions: State<'_, Sessions>, recorder: State<'_, SessionRecorder>, history: State<'_, QueryHistory>, profile: State<'_, ActiveProfile>, config: DbConfig, query: String, row_filter: Option<String>, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    profile.check_sql(&query, &config)?;
+    query_guard::check(&config, &query, confirm_dangerous.unwrap_or(false))?;
+    let options = FetchOptions { row_filter, ..Default::default() };
+    let started = std::time::Instant::now();
+    let result = sessions.execute(&config, &query, &options).await;
+    recorder.record(&config, &query, Vec::new(), result.as_ref().err().cloned());
+    history.record(&config, &query, started.elapsed(), &result);
+    let mut result = result?;
+    result_ops::truncate_long_cells(&mut result, result_ops::DEFAULT_MAX_CELL_CHARS);
+    result_ops::set_display_widths(&mut result);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let config = DbConfig { id: "c1".to_string(), db_type: "postgres".to_string(), host: "db1".to_string(), password: "secret".to_string(), ..Default::default() };
+        let renamed = DbConfig { name: "Reporting".to_string(), query_timeout_secs: Some(30), production: Some(true), ..config.clone() };
+        assert_eq!(fingerprint(&config), fingerprint(&renamed));
+        let changed = [
+            DbConfig { password: "rotated".to_string(), ..config.clone() },
+            DbConfig { init_sql: Some("SET search_path TO app".to_string()), ..config.clone() },
+            DbConfig { default_schema: Some("app".to_string()), ..config.clone() },
+            DbConfig { result_encoding: Some("shift_jis".to_string()), ..config.clone() },
+            DbConfig { read_only: Some(true), ..config.clone() },
+            DbConfig { ssh_user: Some("jump".to_string()), ..config.clone() },
+            DbConfig { ssh_key: Some("~/.ssh/id_ed25519".to_string()), ..config.clone() },
+            DbConfig { ssh_host_key: Some("SHA256:abc".to_string()), ..config.clone() },
+        ];
+        for other in &changed {
+            assert_ne!(fingerprint(&config), fingerprint(other));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::Serialize;
+use tauri::{Manager, State};
+use crate::session_recorder::SessionRecorder;
+use crate::storage;
+use crate::tasks::BackgroundTasks;
+
+pub const TEMP_DIR: &str = "tmp";
+
+/// Counts in-flight commands (queries, imports, exports) so closing the window can warn about them.
+#[derive(Default)]
+pub struct ActiveOperations {
+    count: AtomicUsize,
+}
+
+pub struct OperationGuard<'a> {
+    ops: &'a ActiveOperations,
+}
+
+impl ActiveOperations {
+    pub fn begin(&self) -> OperationGuard<'_> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        OperationGuard { ops: self }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.ops.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RunningTasks {
+    pub active_operations: usize,
+    pub background_tasks: Vec<String>,
+}
+
+fn running_tasks(handle: &tauri::AppHandle) -> RunningTasks {
+    RunningTasks {
+        active_operations: handle.state::<ActiveOperations>().count(),
+        background_tasks: handle.state::<BackgroundTasks>().ids(),
+    }
+}
+
+/// Stops background work, saves in-progress state and removes temp files.
+pub fn cleanup(handle: &tauri::AppHandle) {
+    handle.state::<BackgroundTasks>().cancel_all();
+    if let Err(e) = handle.state::<SessionRecorder>().save_active(handle) {
+        eprintln!("Could not save recording on shutdown: {}", e);
+    }
+    if let Ok(dir) = storage::app_data_subdir(handle, TEMP_DIR) {
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+/// Window close hook: exits right away when idle, otherwise keeps the window open and emits
+/// `app://tasks-running` so the frontend can ask the user (then call `confirm_shutdown`).
+pub fn on_close_requested(window: &tauri::Window, api: &tauri::CloseRequestApi) {
+    let handle = window.app_handle();
+    let running = running_tasks(&handle);
+    api.prevent_close();
+    if running.active_operations == 0 && running.background_tasks.is_empty() {
+        cleanup(&handle);
+        handle.exit(0);
+    } else {
+        window.emit("app://tasks-running", running).ok();
+    }
+}
+
+#[tauri::command]
+pub fn get_running_tasks(handle: tauri::AppHandle) -> RunningTasks {
+    running_tasks(&handle)
+}
+
+/// Called after the user confirmed closing. Running queries are dropped with the process;
+/// dropping their connections makes the server roll back any open transaction.
+#[tauri::command]
+pub fn confirm_shutdown(handle: tauri::AppHandle, ops: State<'_, ActiveOperations>) {
+    if ops.count() > 0 {
+        eprintln!("Shutting down with {} operation(s) still running", ops.count());
+    }
+    cleanup(&handle);
+    handle.exit(0);
+}
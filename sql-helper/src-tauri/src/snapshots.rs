@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::storage::{self, app_data_subdir, safe_file_name};
+use crate::result_ops::column_index;
+use crate::QueryResult;
+
+pub const PINNED_DIR: &str = "pinned";
+/// Notes sit next to `<name>.json` as `<name>.notes`, so they are not listed as snapshots.
+const NOTES_EXTENSION: &str = "notes";
+
+/// A result kept on disk as a named "before" snapshot.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PinnedSnapshot {
+    pub name: String,
+    pub pinned_at: String,
+    pub query: Option<String>,
+    pub connection_name: Option<String>,
+    pub result: QueryResult,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PinnedInfo {
+    pub name: String,
+    pub pinned_at: String,
+    pub query: Option<String>,
+    pub connection_name: Option<String>,
+    pub columns: Vec<String>,
+    pub row_count: usize,
+}
+
+/// Investigation note on one cell, or on a whole row when `column` is None.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CellNote {
+    pub row: usize,
+    pub column: Option<String>,
+    pub text: String,
+    pub updated_at: String,
+}
+
+pub fn snapshot_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(handle, PINNED_DIR)?.join(format!("{}.json", safe_file_name(name))))
+}
+
+fn notes_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(snapshot_path(handle, name)?.with_extension(NOTES_EXTENSION))
+}
+
+pub fn load_notes(handle: &tauri::AppHandle, name: &str) -> Result<Vec<CellNote>, String> {
+    let path = notes_path(handle, name)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    storage::read_json(&path)
+}
+
+pub fn load_snapshot(handle: &tauri::AppHandle, name: &str) -> Result<PinnedSnapshot, String> {
+    let path = snapshot_path(handle, name)?;
+    if !path.exists() {
+        return Err(format!("Không tìm thấy snapshot: {}", name));
+    }
+    storage::read_json(&path)
+}
+
+#[tauri::command]
+pub fn pin_result(handle: tauri::AppHandle, name: String, result: QueryResult, query: Option<String>, connection_name: Option<String>) -> Result<PinnedInfo, String> {
+    let snapshot = PinnedSnapshot {
+        name: name.clone(),
+        pinned_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        query,
+        connection_name,
+        result,
+    };
+    storage::write_json(&snapshot_path(&handle, &name)?, &snapshot)?;
+    Ok(info(&snapshot))
+}
+
+fn info(snapshot: &PinnedSnapshot) -> PinnedInfo {
+    PinnedInfo {
+        name: snapshot.name.clone(),
+        pinned_at: snapshot.pinned_at.clone(),
+        query: snapshot.query.clone(),
+        connection_name: snapshot.connection_name.clone(),
+        columns: snapshot.result.columns.clone(),
+        row_count: snapshot.result.rows.len(),
+    }
+}
+
+#[tauri::command]
+pub fn list_pinned(handle: tauri::AppHandle) -> Result<Vec<PinnedInfo>, String> {
+    let dir = app_data_subdir(&handle, PINNED_DIR)?;
+    let mut pinned = Vec::new();
+    for name in storage::list_json_names(&dir)? {
+        match storage::read_json::<PinnedSnapshot>(&dir.join(format!("{}.json", name))) {
+            Ok(snapshot) => pinned.push(info(&snapshot)),
+            Err(e) => eprintln!("Skipping unreadable snapshot {}: {}", name, e),
+        }
+    }
+    // Newest first
+    pinned.sort_by(|a, b| b.pinned_at.cmp(&a.pinned_at));
+    Ok(pinned)
+}
+
+#[tauri::command]
+pub fn load_pinned(handle: tauri::AppHandle, name: String) -> Result<PinnedSnapshot, String> {
+    load_snapshot(&handle, &name)
+}
+
+#[tauri::command]
+pub fn delete_pinned(handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    let notes = notes_path(&handle, &name)?;
+    if notes.exists() {
+        std::fs::remove_file(notes).map_err(|e| e.to_string())?;
+    }
+    std::fs::remove_file(snapshot_path(&handle, &name)?).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_pinned_notes(handle: tauri::AppHandle, name: String) -> Result<Vec<CellNote>, String> {
+    load_notes(&handle, &name)
+}
+
+/// Adds or replaces the note on a cell (or row, without `column`); empty text removes it.
+#[tauri::command]
+pub fn set_pinned_note(handle: tauri::AppHandle, name: String, row: usize, column: Option<String>, text: String) -> Result<Vec<CellNote>, String> {
+    let snapshot = load_snapshot(&handle, &name)?;
+    if row >= snapshot.result.rows.len() {
+        return Err(format!("Dòng {} không tồn tại trong snapshot", row + 1));
+    }
+    if let Some(c) = &column {
+        column_index(&snapshot.result, c)?;
+    }
+    let mut notes = load_notes(&handle, &name)?;
+    notes.retain(|n| !(n.row == row && n.column == column));
+    if !text.trim().is_empty() {
+        let updated_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        notes.push(CellNote { row, column, text, updated_at });
+        notes.sort_by(|a, b| a.row.cmp(&b.row).then_with(|| a.column.cmp(&b.column)));
+    }
+    storage::write_json(&notes_path(&handle, &name)?, &notes)?;
+    Ok(notes)
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+/// Markdown report of a snapshot: header, the table with `[^n]` markers on annotated cells
+/// (row notes go in a trailing "Note" column) and the notes as footnotes.
+pub fn to_markdown(snapshot: &PinnedSnapshot, notes: &[CellNote]) -> String {
+    let result = &snapshot.result;
+    let has_row_notes = notes.iter().any(|n| n.column.is_none());
+    let marker = |row: usize, column: Option<&str>| -> String {
+        notes.iter().enumerate()
+            .filter(|(_, n)| n.row == row && n.column.as_deref().map_or(column.is_none(), |c| column.map_or(false, |col| c.eq_ignore_ascii_case(col))))
+            .map(|(i, _)| format!("[^{}]", i + 1))
+            .collect()
+    };
+
+    let mut out = format!("## {}\n\n- Pinned: {}\n", snapshot.name, snapshot.pinned_at);
+    if let Some(connection) = &snapshot.connection_name {
+        out.push_str(&format!("- Connection: {}\n", connection));
+    }
+    if let Some(query) = &snapshot.query {
+        out.push_str(&format!("\n```sql\n{}\n```\n", query.trim()));
+    }
+    out.push('\n');
+
+    let mut header: Vec<String> = result.columns.iter().map(|c| markdown_cell(c)).collect();
+    if has_row_notes {
+        header.push("Note".to_string());
+    }
+    out.push_str(&format!("| {} |\n|{}\n", header.join(" | "), " --- |".repeat(header.len())));
+    for (r, row) in result.rows.iter().enumerate() {
+        let mut cells: Vec<String> = row.iter().zip(&result.columns)
+            .map(|(v, c)| format!("{}{}", markdown_cell(v), marker(r, Some(c))))
+            .collect();
+        if has_row_notes {
+            cells.push(marker(r, None));
+        }
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+
+    if !notes.is_empty() {
+        out.push('\n');
+        for (i, n) in notes.iter().enumerate() {
+            out.push_str(&format!("[^{}]: {}\n", i + 1, n.text.trim().replace('\n', " ")));
+        }
+    }
+    out
+}
+
+#[tauri::command]
+pub fn export_pinned_markdown(handle: tauri::AppHandle, name: String, path: String) -> Result<(), String> {
+    let snapshot = load_snapshot(&handle, &name)?;
+    let notes = load_notes(&handle, &name)?;
+    std::fs::write(&path, to_markdown(&snapshot, &notes)).map_err(|e| format!("Không thể ghi file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_with_notes() {
+        let snapshot = PinnedSnapshot {
+            name: "before fix".to_string(),
+            pinned_at: "2024-05-01 10:00:00".to_string(),
+            query: None,
+            connection_name: None,
+            result: QueryResult {
+                columns: vec!["ID".to_string(), "STATUS".to_string()],
+                rows: vec![vec!["1".to_string(), "A|B".to_string()], vec!["2".to_string(), "OK".to_string()]],
+                ..Default::default()
+            },
+        };
+        let note = |row, column: Option<&str>, text: &str| CellNote { row, column: column.map(String::from), text: text.to_string(), updated_at: String::new() };
+        let notes = vec![note(0, Some("STATUS"), "wrong status"), note(1, None, "duplicate")];
+        let md = to_markdown(&snapshot, &notes);
+        assert!(md.contains("| ID | STATUS | Note |\n| --- | --- | --- |\n"));
+        assert!(md.contains("| 1 | A\\|B[^1] |  |\n| 2 | OK | [^2] |\n"));
+        assert!(md.ends_with("[^1]: wrong status\n[^2]: duplicate\n"));
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::locale;
+use serde::Deserialize;
+use tauri::State;
+use crate::result_ops::{column_index, SortKey, NULL_CELL};
+use crate::result_store::{ResultHandle, ResultStore};
+use crate::{db, QueryResult};
+
+// Typed comparisons for sorting held results. NULL always sorts first (last when descending).
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    /// Numbers numerically, dates chronologically, anything else naturally.
+    #[default]
+    Auto,
+    Number,
+    Date,
+    /// Digit runs compare as numbers: "A2" < "A10".
+    Natural,
+    /// ICU Japanese collation (kana order, width/case insensitive first) with numeric digit runs.
+    Japanese,
+    /// Plain code point order.
+    Text,
+}
+
+const DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%Y/%m/%d"];
+const DATETIME_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f"];
+
+pub fn parse_number(value: &str) -> Option<f64> {
+    value.trim().replace(',', "").parse::<f64>().ok()
+}
+
+/// Datetime formats cells are read back with: the display setting first, then the fixed ones,
+/// each also followed by the offset `db::format_datetime_offset` appends.
+pub fn date_formats(display_format: &str) -> Vec<String> {
+    std::iter::once(display_format).chain(DATETIME_FORMATS)
+        .flat_map(|f| [f.to_string(), format!("{} %:z", f), format!("{}%:z", f)])
+        .collect()
+}
+
+pub fn parse_date(value: &str, formats: &[String]) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    formats.iter()
+        .find_map(|f| match f.ends_with("%:z") {
+            // Values with an offset compare by instant
+            true => DateTime::parse_from_str(value, f).ok().map(|dt| dt.naive_utc()),
+            false => NaiveDateTime::parse_from_str(value, f).ok(),
+        })
+        .or_else(|| DATE_FORMATS.iter().find_map(|f| NaiveDate::parse_from_str(value, f).ok()).and_then(|d| d.and_hms_opt(0, 0, 0)))
+}
+
+/// Splits off the leading run of digits (or non-digits).
+fn next_chunk(s: &str) -> (&str, &str) {
+    let is_digit = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s.find(|c: char| c.is_ascii_digit() != is_digit).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Natural order: digit runs by numeric value, text runs case-insensitively, ties by raw text.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a, b);
+    while !x.is_empty() && !y.is_empty() {
+        let (cx, rx) = next_chunk(x);
+        let (cy, ry) = next_chunk(y);
+        let both_digits = cx.starts_with(|c: char| c.is_ascii_digit()) && cy.starts_with(|c: char| c.is_ascii_digit());
+        let ord = if both_digits {
+            let (nx, ny) = (cx.trim_start_matches('0'), cy.trim_start_matches('0'));
+            nx.len().cmp(&ny.len()).then_with(|| nx.cmp(ny))
+        } else {
+            cx.to_lowercase().cmp(&cy.to_lowercase())
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+        x = rx;
+        y = ry;
+    }
+    x.len().cmp(&y.len()).then_with(|| a.cmp(b))
+}
+
+/// What `Auto` means for a column of this `QueryResult::column_types` type: numbers and dates by
+/// value, character data by Japanese collation. Unknown types keep guessing from the values.
+pub fn mode_for_type(column_type: &str) -> SortMode {
+    let base = column_type.trim_end_matches(" unsigned");
+    match base {
+        "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "int2" | "int4" | "int8" | "real" | "float"
+        | "double" | "double precision" | "float4" | "float8" | "binary_float" | "binary_double" | "decimal" | "numeric"
+        | "number" | "money" | "smallmoney" => SortMode::Number,
+        "date" | "datetime" | "datetime2" | "smalldatetime" | "datetimeoffset" | "timestamp" | "timestamptz" => SortMode::Date,
+        "char" | "nchar" | "varchar" | "nvarchar" | "varchar2" | "nvarchar2" | "text" | "ntext" | "tinytext" | "mediumtext"
+        | "longtext" | "bpchar" | "character" | "character varying" | "name" | "citext" | "clob" | "nclob" => SortMode::Japanese,
+        _ if base.starts_with("timestamp") => SortMode::Date,
+        _ => SortMode::Auto,
+    }
+}
+
+/// A cell's number/date value, parsed once per sort instead of on every comparison.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParsedCell {
+    number: Option<f64>,
+    date: Option<NaiveDateTime>,
+    /// Position among the column's distinct values (see `text_ranks`).
+    rank: Option<u32>,
+}
+
+/// One comparator per sort key, so the collator is built once per sort.
+pub struct CellComparator {
+    mode: SortMode,
+    collator: Option<Collator>,
+    date_formats: Vec<String>,
+}
+
+impl CellComparator {
+    pub fn new(mode: SortMode) -> Result<Self, String> {
+        let collator = match mode {
+            SortMode::Japanese => {
+                let mut options = CollatorOptions::new();
+                options.strength = Some(Strength::Tertiary);
+                options.numeric = Some(Numeric::On);
+                Some(Collator::try_new(&locale!("ja").into(), options).map_err(|e| e.to_string())?)
+            }
+            _ => None,
+        };
+        let date_formats = match mode {
+            SortMode::Date | SortMode::Auto => date_formats(&db::datetime_format()),
+            _ => Vec::new(),
+        };
+        Ok(CellComparator { mode, collator, date_formats })
+    }
+
+    /// Only what this mode compares on is parsed.
+    pub fn parse(&self, cell: &str) -> ParsedCell {
+        let number = matches!(self.mode, SortMode::Number | SortMode::Auto).then(|| parse_number(cell)).flatten();
+        let date = match self.mode {
+            SortMode::Date => parse_date(cell, &self.date_formats),
+            SortMode::Auto if number.is_none() => parse_date(cell, &self.date_formats),
+            _ => None,
+        };
+        ParsedCell { number, date, rank: None }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.compare_parsed(a, &self.parse(a), b, &self.parse(b))
+    }
+
+    pub fn compare_parsed(&self, a: &str, pa: &ParsedCell, b: &str, pb: &ParsedCell) -> Ordering {
+        if let (Some(x), Some(y)) = (pa.rank, pb.rank) {
+            return x.cmp(&y);
+        }
+        match (a == NULL_CELL, b == NULL_CELL) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        // Values that do not parse as the requested type go after those that do
+        let typed = |x: Option<Ordering>, a_ok: bool, b_ok: bool| match (a_ok, b_ok) {
+            (true, true) => x.unwrap_or(Ordering::Equal),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => natural_cmp(a, b),
+        };
+        match self.mode {
+            SortMode::Number => {
+                let (x, y) = (pa.number, pb.number);
+                typed(x.zip(y).and_then(|(x, y)| x.partial_cmp(&y)), x.is_some(), y.is_some())
+            }
+            SortMode::Date => {
+                let (x, y) = (pa.date, pb.date);
+                typed(x.zip(y).map(|(x, y)| x.cmp(&y)), x.is_some(), y.is_some())
+            }
+            SortMode::Natural => natural_cmp(a, b),
+            SortMode::Japanese => match &self.collator {
+                Some(c) => c.compare(a, b).then_with(|| a.cmp(b)),
+                None => natural_cmp(a, b),
+            },
+            SortMode::Text => a.cmp(b),
+            SortMode::Auto => {
+                if let (Some(x), Some(y)) = (pa.number, pb.number) {
+                    return x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+                }
+                if let (Some(x), Some(y)) = (pa.date, pb.date) {
+                    return x.cmp(&y);
+                }
+                natural_cmp(a, b)
+            }
+        }
+    }
+}
+
+fn cell(row: &[String], ci: usize) -> &str {
+    row.get(ci).map(|s| s.as_str()).unwrap_or(NULL_CELL)
+}
+
+/// Rank of each row's cell in the order of the column's distinct values, so text sorts run the
+/// collator over the distinct values only; code and name columns repeat a lot.
+fn text_ranks(cmp: &CellComparator, rows: &[Vec<String>], ci: usize) -> Vec<u32> {
+    let mut distinct: Vec<&str> = rows.iter().map(|row| cell(row, ci)).collect::<HashSet<_>>().into_iter().collect();
+    distinct.sort_by(|a, b| cmp.compare(a, b));
+    let rank: HashMap<&str, u32> = distinct.into_iter().enumerate().map(|(i, v)| (v, i as u32)).collect();
+    rows.iter().map(|row| rank[cell(row, ci)]).collect()
+}
+
+/// Stable multi-key sort of the result rows in place. `Auto` keys follow the column type when the
+/// result carries one (see `mode_for_type`).
+pub fn sort_rows(result: &mut QueryResult, keys: &[SortKey]) -> Result<(), String> {
+    let idx = keys.iter().map(|k| column_index(result, &k.column)).collect::<Result<Vec<_>, _>>()?;
+    let comparators = keys.iter().zip(&idx)
+        .map(|(k, &ci)| match (k.mode, result.column_types.get(ci)) {
+            (SortMode::Auto, Some(column_type)) => CellComparator::new(mode_for_type(column_type)),
+            (mode, _) => CellComparator::new(mode),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let parsed: Vec<Vec<ParsedCell>> = comparators.iter().zip(&idx)
+        .map(|(cmp, &ci)| {
+            let mut cells: Vec<ParsedCell> = result.rows.iter().map(|row| cmp.parse(cell(row, ci))).collect();
+            if matches!(cmp.mode, SortMode::Japanese | SortMode::Natural) {
+                for (parsed, rank) in cells.iter_mut().zip(text_ranks(cmp, &result.rows, ci)) {
+                    parsed.rank = Some(rank);
+                }
+            }
+            cells
+        })
+        .collect();
+    let rows = &result.rows;
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&x, &y| {
+        for (k, (key, &ci)) in keys.iter().zip(&idx).enumerate() {
+            let ord = comparators[k].compare_parsed(cell(&rows[x], ci), &parsed[k][x], cell(&rows[y], ci), &parsed[k][y]);
+            let ord = if key.descending { ord.reverse() } else { ord };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
+    });
+    let mut rows: Vec<Option<Vec<String>>> = std::mem::take(&mut result.rows).into_iter().map(Some).collect();
+    result.rows = order.into_iter().filter_map(|i| rows[i].take()).collect();
+    Ok(())
+}
+
+/// Re-orders a held result in place, so following `get_result_page` calls page through the sorted rows.
+#[tauri::command]
+pub fn sort_result(store: State<'_, ResultStore>, handle: String, keys: Vec<SortKey>) -> Result<ResultHandle, String> {
+    let mut result = (*store.get(&handle)?).clone();
+    sort_rows(&mut result, &keys)?;
+    Ok(store.insert_as(&handle, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mode: SortMode, values: &[&str]) -> Vec<String> {
+        let cmp = CellComparator::new(mode).unwrap();
+        let mut v: Vec<String> = values.iter().map(|s| s.to_string()).collect();
+        v.sort_by(|a, b| cmp.compare(a, b));
+        v
+    }
+
+    #[test]
+    fn test_sort_modes() {
+        assert_eq!(sorted(SortMode::Auto, &["A10", "A2", "a1", NULL_CELL]), vec![NULL_CELL, "a1", "A2", "A10"]);
+        assert_eq!(sorted(SortMode::Auto, &["10", "9", "1,200"]), vec!["9", "10", "1,200"]);
+        assert_eq!(sorted(SortMode::Date, &["2024/1/5", "2023-12-31 23:59:00", "n/a"]), vec!["2023-12-31 23:59:00", "2024/1/5", "n/a"]);
+        assert_eq!(sorted(SortMode::Text, &["A10", "A2"]), vec!["A10", "A2"]);
+        assert_eq!(sorted(SortMode::Japanese, &["さくら", "アイ", "かき", "あお"]), vec!["アイ", "あお", "かき", "さくら"]);
+        assert_eq!(mode_for_type("bigint unsigned"), SortMode::Number);
+        assert_eq!(mode_for_type("timestamp with time zone"), SortMode::Date);
+        assert_eq!(mode_for_type("nvarchar"), SortMode::Japanese);
+        assert_eq!(mode_for_type("uniqueidentifier"), SortMode::Auto);
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        let formats = date_formats("%d/%m/%Y %H:%M");
+        let at = |h, m| NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(h, m, 0);
+        assert_eq!(parse_date("05/01/2024 10:30", &formats), at(10, 30));
+        assert_eq!(parse_date("05/01/2024 10:30 +09:00", &formats), at(1, 30));
+        assert_eq!(parse_date("2024-01-05 10:30:00.25 -02:00", &formats).map(|d| d.format("%H:%M:%S%.f").to_string()), Some("12:30:00.250".to_string()));
+        assert_eq!(parse_date("2024-01-05T10:30:00+09:00", &formats), at(1, 30));
+        assert_eq!(parse_date("2024/1/5", &formats), at(0, 0));
+        assert_eq!(parse_date("5 Jan 2024", &formats), None);
+    }
+}
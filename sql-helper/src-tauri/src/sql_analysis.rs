@@ -0,0 +1,529 @@
+// Lightweight SQL lexing shared by the editor helpers. This is not a full parser: it only
+// needs to be string/comment aware and find keywords and identifiers reliably.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Word,
+    /// `[x]`, `"x"` or `` `x` ``; `text` holds the unquoted name.
+    QuotedIdent,
+    Str,
+    Number,
+    Symbol,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+
+    pub fn is_identifier(&self) -> bool {
+        self.kind == TokenKind::Word || self.kind == TokenKind::QuotedIdent
+    }
+}
+
+/// Tokenizes `sql`, skipping whitespace and comments. Offsets are byte offsets into `sql`.
+pub fn tokenize(sql: &str) -> Vec<Token> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c == b'-' && bytes.get(i + 1) == Some(&b'-') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if c == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+        } else if c == b'\'' || ((c == b'N' || c == b'n') && bytes.get(i + 1) == Some(&b'\'')) {
+            if c != b'\'' {
+                i += 1;
+            }
+            i += 1;
+            while i < bytes.len() {
+                if bytes[i] == b'\'' {
+                    if bytes.get(i + 1) == Some(&b'\'') {
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Str, text: sql[start..i].to_string(), start, end: i });
+        } else if c == b'[' || c == b'"' || c == b'`' {
+            let close = if c == b'[' { b']' } else { c };
+            i += 1;
+            while i < bytes.len() && bytes[i] != close {
+                i += 1;
+            }
+            let inner_end = i;
+            i = (i + 1).min(bytes.len());
+            tokens.push(Token { kind: TokenKind::QuotedIdent, text: sql[start + 1..inner_end].to_string(), start, end: i });
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Number, text: sql[start..i].to_string(), start, end: i });
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'@' || c == b'#' || c >= 0x80 {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'@' || bytes[i] == b'#' || bytes[i] == b'$' || bytes[i] >= 0x80) {
+                i += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Word, text: sql[start..i].to_string(), start, end: i });
+        } else {
+            // Multi-byte UTF-8 is handled by the identifier branch, so this is always ASCII
+            i += 1;
+            tokens.push(Token { kind: TokenKind::Symbol, text: sql[start..i].to_string(), start, end: i });
+        }
+    }
+    tokens
+}
+
+/// Reads a possibly qualified name (`db.dbo.[Orders]`) starting at `tokens[pos]`.
+/// Returns the dotted name and the index after it.
+pub fn read_qualified_name(tokens: &[Token], pos: usize) -> Option<(String, usize)> {
+    let mut parts = Vec::new();
+    let mut i = pos;
+    loop {
+        let token = tokens.get(i)?;
+        if !token.is_identifier() {
+            return None;
+        }
+        parts.push(token.text.clone());
+        i += 1;
+        if tokens.get(i).map_or(false, |t| t.is_symbol(".")) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    Some((parts.join("."), i))
+}
+
+/// For a plain `SELECT ... FROM <one table> [WHERE/ORDER BY ...]`, returns that table.
+/// Joins, set operations, grouping, DISTINCT and derived tables make the result non-editable.
+pub fn single_table_source(sql: &str) -> Option<String> {
+    let tokens = tokenize(sql);
+    let tokens: Vec<Token> = match tokens.last() {
+        Some(t) if t.is_symbol(";") => tokens[..tokens.len() - 1].to_vec(),
+        _ => tokens,
+    };
+    if !tokens.first()?.is_keyword("SELECT") {
+        return None;
+    }
+    const BLOCKERS: [&str; 10] = ["JOIN", "UNION", "INTERSECT", "EXCEPT", "GROUP", "HAVING", "DISTINCT", "INTO", "APPLY", "PIVOT"];
+    if tokens.iter().any(|t| BLOCKERS.iter().any(|b| t.is_keyword(b)) || t.is_symbol(";")) {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut from_pos = None;
+    for (i, t) in tokens.iter().enumerate() {
+        if t.is_symbol("(") {
+            depth += 1;
+        } else if t.is_symbol(")") {
+            depth -= 1;
+        } else if depth == 0 && t.is_keyword("FROM") {
+            if from_pos.is_some() {
+                return None;
+            }
+            from_pos = Some(i);
+        } else if depth > 0 && t.is_keyword("SELECT") {
+            return None; // subqueries
+        }
+    }
+
+    let (table, next) = read_qualified_name(&tokens, from_pos? + 1)?;
+    // Allow an alias, then only WHERE/ORDER/OPTION/LIMIT/OFFSET/FETCH/FOR may follow
+    let mut i = next;
+    if tokens.get(i).map_or(false, |t| t.is_keyword("AS")) {
+        i += 1;
+    }
+    const TAIL: [&str; 7] = ["WHERE", "ORDER", "OPTION", "LIMIT", "OFFSET", "FETCH", "FOR"];
+    if let Some(t) = tokens.get(i) {
+        if t.is_identifier() && !TAIL.iter().any(|k| t.is_keyword(k)) {
+            i += 1;
+        }
+    }
+    match tokens.get(i) {
+        None => Some(table),
+        Some(t) if TAIL.iter().any(|k| t.is_keyword(k)) => Some(table),
+        Some(t) if t.is_symbol(",") => None,
+        Some(_) => None,
+    }
+}
+
+/// Whether any of `keywords` appears outside parentheses (i.e. applies to the outer query).
+pub fn has_top_level_keyword(sql: &str, keywords: &[&str]) -> bool {
+    let mut depth = 0;
+    for t in tokenize(sql) {
+        if t.is_symbol("(") {
+            depth += 1;
+        } else if t.is_symbol(")") {
+            depth -= 1;
+        } else if depth == 0 && keywords.iter().any(|k| t.is_keyword(k)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Coarse kind of a statement from its leading keyword: "select", "insert", "update", "delete",
+/// "merge", "ddl", "exec" or "other".
+pub fn statement_kind(sql: &str) -> &'static str {
+    let tokens = tokenize(sql);
+    let first = match tokens.iter().find(|t| !t.is_symbol("(")) {
+        Some(t) if t.kind == TokenKind::Word => t.text.to_uppercase(),
+        _ => return "other",
+    };
+    match first.as_str() {
+        "SELECT" | "WITH" | "VALUES" | "SHOW" | "DESCRIBE" | "EXPLAIN" => "select",
+        "INSERT" => "insert",
+        "UPDATE" => "update",
+        "DELETE" => "delete",
+        "MERGE" => "merge",
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "RENAME" | "COMMENT" | "GRANT" | "REVOKE" => "ddl",
+        "EXEC" | "EXECUTE" | "CALL" => "exec",
+        _ => "other",
+    }
+}
+
+/// A single DML/DDL statement that produces no rows (no OUTPUT/RETURNING clause).
+pub fn is_non_query(sql: &str, dialect: &str) -> bool {
+    let kind = statement_kind(sql);
+    matches!(kind, "insert" | "update" | "delete" | "merge" | "ddl")
+        && !has_top_level_keyword(sql, &["OUTPUT", "RETURNING"])
+        && statement_spans(sql, dialect).len() <= 1
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatementSpan {
+    pub text: String,
+    /// Character (not byte) offsets into the script, `end` exclusive.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A script batch: everything between two MSSQL `GO` lines, or one statement for other dialects.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ScriptBatch {
+    #[serde(flatten)]
+    pub span: StatementSpan,
+    /// Count from `GO 5`; 1 otherwise.
+    pub repeat: u32,
+}
+
+/// Repeat count of a `GO` / `GO 5` line (MSSQL batch separator), if the line is one.
+fn go_count(line: &str) -> Option<u32> {
+    let mut parts = line.split_whitespace();
+    if !matches!(parts.next(), Some(w) if w.eq_ignore_ascii_case("GO")) {
+        return None;
+    }
+    let count = match parts.next() {
+        Some(n) if n.chars().all(|c| c.is_ascii_digit()) => n.parse().unwrap_or(u32::MAX).max(1),
+        Some(_) => return None,
+        None => 1,
+    };
+    parts.next().is_none().then_some(count)
+}
+
+/// Length of a Postgres dollar-quote tag (`$$`, `$body$`) starting at `i`, if any.
+fn dollar_tag_len(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes.get(i + 1).map_or(false, |b| b.is_ascii_digit()) {
+        return None; // $1 parameter
+    }
+    let mut j = i + 1;
+    while j < bytes.len() && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_') {
+        j += 1;
+    }
+    (bytes.get(j) == Some(&b'$')).then(|| j + 1 - i)
+}
+
+/// Byte ranges of the script's pieces with their `GO` repeat count. `;` (or the current MySQL
+/// `DELIMITER`) only separates when `semicolons` is set; MSSQL `GO` lines always do.
+fn split_ranges(sql: &str, dialect: &str, semicolons: bool) -> Vec<(usize, usize, u32)> {
+    let bytes = sql.as_bytes();
+    let mut byte_ranges = Vec::new();
+    let mut delimiter = ";".to_string();
+    let mut seg_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if i == 0 || bytes[i - 1] == b'\n' {
+            let line_end = sql[i..].find('\n').map_or(bytes.len(), |p| i + p);
+            let line = sql[i..line_end].trim();
+            let is_delimiter_line = dialect == "mysql" && line.len() > 10 && line.is_char_boundary(10) && line[..10].eq_ignore_ascii_case("DELIMITER ");
+            let go = if dialect == "mssql" { go_count(line) } else { None };
+            if go.is_some() || is_delimiter_line {
+                if is_delimiter_line {
+                    delimiter = line[10..].trim().to_string();
+                }
+                byte_ranges.push((seg_start, i, go.unwrap_or(1)));
+                i = line_end;
+                seg_start = i;
+                continue;
+            }
+        }
+
+        if (c == b'-' && bytes.get(i + 1) == Some(&b'-')) || (c == b'#' && dialect == "mysql") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if c == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+        } else if c == b'\'' || c == b'"' || c == b'`' || (c == b'[' && dialect == "mssql") {
+            let close = if c == b'[' { b']' } else { c };
+            let backslash_escapes = dialect == "mysql" && c != b'`';
+            i += 1;
+            while i < bytes.len() {
+                let escaped = backslash_escapes && bytes[i] == b'\\';
+                if escaped || (bytes[i] == close && bytes.get(i + 1) == Some(&close)) {
+                    i += 2;
+                } else if bytes[i] == close {
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+            i = (i + 1).min(bytes.len());
+        } else if let Some(len) = (c == b'$' && dialect == "postgres").then(|| dollar_tag_len(bytes, i)).flatten() {
+            let tag = &sql[i..i + len];
+            i = sql[i + len..].find(tag).map_or(bytes.len(), |p| i + len + p + len);
+        } else if semicolons && sql[i..].starts_with(delimiter.as_str()) {
+            byte_ranges.push((seg_start, i, 1));
+            i += delimiter.len();
+            seg_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    byte_ranges.push((seg_start, bytes.len(), 1));
+    byte_ranges
+}
+
+/// Trimmed spans of the ranges, with character offsets; pieces consisting only of
+/// whitespace/comments are dropped.
+fn trimmed_spans(sql: &str, byte_ranges: Vec<(usize, usize, u32)>) -> Vec<ScriptBatch> {
+    // Ranges only move forward, so byte -> char conversion can be done incrementally
+    let (mut last_byte, mut last_char) = (0, 0);
+    let mut to_char = |byte: usize| {
+        last_char += sql[last_byte..byte].chars().count();
+        last_byte = byte;
+        last_char
+    };
+    let mut spans = Vec::new();
+    for (start, end, repeat) in byte_ranges {
+        let raw = &sql[start..end];
+        let text = raw.trim();
+        if tokenize(text).is_empty() {
+            continue;
+        }
+        let text_start = start + (raw.len() - raw.trim_start().len());
+        let start = to_char(text_start);
+        let end = to_char(text_start + text.len());
+        spans.push(ScriptBatch { span: StatementSpan { text: text.to_string(), start, end }, repeat });
+    }
+    spans
+}
+
+/// Splits a script into statements on `;` (or the current MySQL `DELIMITER`) and MSSQL `GO`
+/// lines, ignoring separators inside strings, quoted identifiers, comments and `$$` bodies.
+/// Statements consisting only of whitespace/comments are dropped.
+pub fn statement_spans(sql: &str, dialect: &str) -> Vec<StatementSpan> {
+    trimmed_spans(sql, split_ranges(sql, dialect, true)).into_iter().map(|b| b.span).collect()
+}
+
+/// Units a script runs in: T-SQL batches between `GO` lines (semicolons stay inside the batch,
+/// as in SSMS), single statements for the other dialects.
+pub fn script_batches(sql: &str, dialect: &str) -> Vec<ScriptBatch> {
+    trimmed_spans(sql, split_ranges(sql, dialect, dialect != "mssql"))
+}
+
+#[tauri::command]
+pub fn split_statements(sql: String, dialect: String) -> Vec<StatementSpan> {
+    statement_spans(&sql, &dialect)
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TableRef {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+/// Tables named after FROM/JOIN/UPDATE/INTO (including `FROM a, b` lists) with their aliases,
+/// in order of appearance. Derived tables are skipped; their inner FROMs are still picked up.
+pub fn table_refs(sql: &str) -> Vec<TableRef> {
+    const INTRODUCERS: [&str; 5] = ["FROM", "JOIN", "UPDATE", "INTO", "APPLY"];
+    let tokens = tokenize(sql);
+    let mut refs = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !INTRODUCERS.iter().any(|k| tokens[i].is_keyword(k)) {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        while let Some((name, next)) = read_qualified_name(&tokens, i) {
+            i = next;
+            // Optional alias, then keep going only for comma-separated FROM lists
+            if tokens.get(i).map_or(false, |t| t.is_keyword("AS")) {
+                i += 1;
+            }
+            let alias = match tokens.get(i) {
+                Some(t) if t.is_identifier() && !(t.kind == TokenKind::Word && is_clause_keyword(&t.text)) => {
+                    i += 1;
+                    Some(t.text.clone())
+                }
+                _ => None,
+            };
+            refs.push(TableRef { name, alias });
+            if tokens.get(i).map_or(false, |t| t.is_symbol(",")) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    refs
+}
+
+/// Distinct table names from `table_refs`, in order of first appearance.
+pub fn referenced_tables(sql: &str) -> Vec<String> {
+    let mut tables: Vec<String> = Vec::new();
+    for r in table_refs(sql) {
+        if !tables.iter().any(|t| t.eq_ignore_ascii_case(&r.name)) {
+            tables.push(r.name);
+        }
+    }
+    tables
+}
+
+fn is_clause_keyword(word: &str) -> bool {
+    const KEYWORDS: [&str; 23] = [
+        "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "OUTER", "ON", "GROUP", "ORDER", "HAVING",
+        "UNION", "EXCEPT", "INTERSECT", "SET", "VALUES", "SELECT", "LIMIT", "OPTION", "WITH", "OUTPUT", "USING",
+    ];
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StatementContext {
+    pub statement: StatementSpan,
+    pub tables: Vec<String>,
+}
+
+/// The statement containing the character `offset`; when the cursor sits between statements
+/// (e.g. right after a `;`), the one before it.
+pub fn statement_at(sql: &str, offset: usize, dialect: &str) -> Option<StatementContext> {
+    let spans = statement_spans(sql, dialect);
+    let index = spans.iter().position(|s| s.start <= offset && offset <= s.end)
+        .or_else(|| spans.iter().rposition(|s| s.end <= offset))
+        .or_else(|| (!spans.is_empty()).then_some(0))?;
+    let statement = spans.into_iter().nth(index)?;
+    let tables = referenced_tables(&statement.text);
+    Some(StatementContext { statement, tables })
+}
+
+#[tauri::command]
+pub fn get_statement_at(sql: String, offset: usize, dialect: String) -> Option<StatementContext> {
+    statement_at(&sql, offset, &dialect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_skips_comments_and_strings() {
+        let tokens = tokenize("SELECT 'a -- b', [My Col] /* x */ FROM t -- tail");
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["SELECT", "'a -- b'", ",", "My Col", "FROM", "t"]);
+    }
+
+    #[test]
+    fn test_single_table_source() {
+        assert_eq!(single_table_source("SELECT * FROM dbo.[ORDERS] o WHERE o.ID = 1;").as_deref(), Some("dbo.ORDERS"));
+        assert_eq!(single_table_source("select id, name from users order by id").as_deref(), Some("users"));
+        assert_eq!(single_table_source("SELECT * FROM a JOIN b ON a.id = b.id"), None);
+        assert_eq!(single_table_source("SELECT * FROM a, b"), None);
+        assert_eq!(single_table_source("SELECT * FROM (SELECT 1 x) t"), None);
+        assert_eq!(single_table_source("UPDATE a SET x = 1"), None);
+    }
+
+    #[test]
+    fn test_statement_kind() {
+        assert_eq!(statement_kind("-- refresh\nUPDATE t SET a = 1"), "update");
+        assert_eq!(statement_kind("with x as (select 1) select * from x"), "select");
+        assert_eq!(statement_kind("TRUNCATE TABLE t"), "ddl");
+        assert!(is_non_query("DELETE FROM t WHERE id = 1;", "mssql"));
+        assert!(!is_non_query("DELETE FROM t OUTPUT deleted.id WHERE id = 1", "mssql"));
+        assert!(!is_non_query("UPDATE t SET a = 1; SELECT * FROM t", "mssql"));
+        assert!(!is_non_query("INSERT INTO t VALUES (1) RETURNING id", "postgres"));
+    }
+
+    #[test]
+    fn test_statement_spans() {
+        let sql = "SELECT ';' FROM t; -- a;b\nUPDATE [x;y] SET n = N'日本;'\nGO\n/* only; comment */";
+        let spans = statement_spans(sql, "mssql");
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["SELECT ';' FROM t", "-- a;b\nUPDATE [x;y] SET n = N'日本;'"]);
+        let chars: Vec<char> = sql.chars().collect();
+        let second: String = chars[spans[1].start..spans[1].end].iter().collect();
+        assert_eq!(second, spans[1].text);
+    }
+
+    #[test]
+    fn test_statement_spans_dialects() {
+        let pg = "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END $$ LANGUAGE plpgsql; SELECT $1";
+        assert_eq!(statement_spans(pg, "postgres").len(), 2);
+        let my = "SELECT 'a\\';b' # c;\nDELIMITER //\nCREATE PROCEDURE p() BEGIN SELECT 1; END//\nDELIMITER ;\nSELECT 2;";
+        let texts: Vec<String> = statement_spans(my, "mysql").into_iter().map(|s| s.text).collect();
+        assert_eq!(texts, vec!["SELECT 'a\\';b' # c;", "CREATE PROCEDURE p() BEGIN SELECT 1; END", "SELECT 2"]);
+    }
+
+    #[test]
+    fn test_script_batches() {
+        let script = "CREATE PROCEDURE p AS\nBEGIN\n  SELECT 1;\n  SELECT 2;\nEND\ngo\nEXEC p;\nGO 3\n-- done\n";
+        let batches = script_batches(script, "mssql");
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].span.text, "CREATE PROCEDURE p AS\nBEGIN\n  SELECT 1;\n  SELECT 2;\nEND");
+        assert_eq!((batches[1].span.text.as_str(), batches[1].span.start, batches[1].repeat), ("EXEC p;", 59, 3));
+        assert_eq!(script_batches("SELECT 1; SELECT 2", "postgres").len(), 2);
+    }
+
+    #[test]
+    fn test_statement_at() {
+        let sql = "SELECT * FROM dbo.A a JOIN B ON a.id = B.id;\n\nUPDATE C SET x = (SELECT 1 FROM D, E e WHERE 1 = 1)";
+        let first = statement_at(sql, 3, "mssql").unwrap();
+        assert_eq!(first.tables, vec!["dbo.A", "B"]);
+        // Just after the ';' still belongs to the first statement
+        assert_eq!(statement_at(sql, 45, "mssql").unwrap().statement.start, 0);
+        let second = statement_at(sql, sql.chars().count(), "mssql").unwrap();
+        assert_eq!(second.tables, vec!["C", "D", "E"]);
+    }
+}
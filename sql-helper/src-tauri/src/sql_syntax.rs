@@ -0,0 +1,219 @@
+use std::ops::ControlFlow;
+use serde::Serialize;
+use sqlparser::ast::{Expr, Ident, ObjectName, Query, TableFactor, Visit, Visitor};
+use sqlparser::dialect::{Dialect, GenericDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::parser::{Parser, ParserError};
+use crate::sql_analysis::{self, TableRef};
+
+// Offline syntax check with sqlparser. Its grammar is narrower than the servers' (T-SQL control
+// flow, PL/SQL, vendor extensions), so the errors are advisory and never block execution.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    /// Byte offsets of the failing statement in the script, `statement_end` exclusive.
+    pub statement_start: usize,
+    pub statement_end: usize,
+    /// Byte offset of the error; the statement end when the parser ran out of input.
+    pub offset: usize,
+}
+
+pub fn dialect_for(db_type: &str) -> Box<dyn Dialect> {
+    match db_type {
+        "mssql" => Box::new(MsSqlDialect {}),
+        "mysql" => Box::new(MySqlDialect {}),
+        "postgres" => Box::new(PostgreSqlDialect {}),
+        _ => Box::new(GenericDialect {}),
+    }
+}
+
+/// Splits sqlparser's " at Line: 3, Column 11" suffix (1-based, columns in characters) off the
+/// message and turns it into a byte offset within `text`.
+fn locate(text: &str, message: &str) -> (String, Option<usize>) {
+    let at = match message.rfind(" at Line: ") {
+        Some(at) => at,
+        None => return (message.to_string(), None),
+    };
+    let mut numbers = message[at..].split(|c: char| !c.is_ascii_digit()).filter(|n| !n.is_empty()).map(|n| n.parse::<usize>().unwrap_or(0));
+    let (line, column) = (numbers.next().unwrap_or(1), numbers.next().unwrap_or(1));
+    let line_start: usize = text.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+    let offset = text[line_start..].char_indices().nth(column.saturating_sub(1)).map_or(text.len(), |(i, _)| line_start + i);
+    (message[..at].to_string(), Some(offset))
+}
+
+/// Parses each statement of the script on its own; statements sqlparser accepts are omitted.
+pub fn syntax_errors(sql: &str, db_type: &str) -> Vec<SyntaxError> {
+    let dialect = dialect_for(db_type);
+    let mut errors = Vec::new();
+    // Spans come in order with character offsets, so their byte offsets are found in one pass
+    let mut chars = sql.char_indices().enumerate();
+    for span in sql_analysis::statement_spans(sql, db_type) {
+        let start = chars.find(|(n, _)| *n == span.start).map_or(sql.len(), |(_, (i, _))| i);
+        let end = start + span.text.len();
+        let message = match Parser::parse_sql(dialect.as_ref(), &span.text) {
+            Ok(_) => continue,
+            Err(ParserError::ParserError(m)) | Err(ParserError::TokenizerError(m)) => m,
+            Err(e) => e.to_string(),
+        };
+        let (message, offset) = locate(&span.text, &message);
+        errors.push(SyntaxError { message, statement_start: start, statement_end: end, offset: start + offset.unwrap_or(span.text.len()) });
+    }
+    errors
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ColumnRef {
+    /// Table the column is qualified with, aliases resolved; None for bare column names.
+    pub table: Option<String>,
+    pub name: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct SqlAnalysis {
+    /// Each distinct table/alias pair in order of appearance; CTE names are left out.
+    pub tables: Vec<TableRef>,
+    pub columns: Vec<ColumnRef>,
+    /// sqlparser's error for a statement it could not parse; that statement's tables then come
+    /// from `sql_analysis::table_refs` and its columns are missing.
+    pub parse_error: Option<String>,
+}
+
+fn object_name(name: &ObjectName) -> String {
+    name.0.iter().map(|i| i.value.as_str()).collect::<Vec<_>>().join(".")
+}
+
+#[derive(Default)]
+struct References {
+    tables: Vec<TableRef>,
+    /// Set between a table factor and the visit of its name, which is then already recorded.
+    in_table_factor: bool,
+    ctes: Vec<String>,
+    columns: Vec<(Vec<String>, String)>,
+}
+
+impl Visitor for References {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        if let Some(with) = &query.with {
+            self.ctes.extend(with.cte_tables.iter().map(|c| c.alias.name.value.clone()));
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, factor: &TableFactor) -> ControlFlow<()> {
+        if let TableFactor::Table { name, alias, .. } = factor {
+            self.tables.push(TableRef { name: object_name(name), alias: alias.as_ref().map(|a| a.name.value.clone()) });
+            self.in_table_factor = true;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Targets outside FROM lists (INSERT INTO, CREATE/DROP TABLE).
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+        if !std::mem::take(&mut self.in_table_factor) {
+            self.tables.push(TableRef { name: object_name(relation), alias: None });
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        let parts: Vec<&Ident> = match expr {
+            Expr::Identifier(ident) => vec![ident],
+            Expr::CompoundIdentifier(idents) => idents.iter().collect(),
+            _ => return ControlFlow::Continue(()),
+        };
+        if let Some((column, qualifier)) = parts.split_last() {
+            // T-SQL variables parse as identifiers
+            if !column.value.starts_with('@') {
+                self.columns.push((qualifier.iter().map(|i| i.value.clone()).collect(), column.value.clone()));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Tables, aliases and columns referenced by the statements of `sql`.
+pub fn analyze(sql: &str, db_type: &str) -> SqlAnalysis {
+    let dialect = dialect_for(db_type);
+    let mut analysis = SqlAnalysis::default();
+    for span in sql_analysis::statement_spans(sql, db_type) {
+        let statements = match Parser::parse_sql(dialect.as_ref(), &span.text) {
+            Ok(statements) => statements,
+            Err(e) => {
+                analysis.parse_error.get_or_insert_with(|| e.to_string());
+                for table in sql_analysis::table_refs(&span.text) {
+                    if !analysis.tables.contains(&table) {
+                        analysis.tables.push(table);
+                    }
+                }
+                continue;
+            }
+        };
+        let mut refs = References::default();
+        let _ = statements.visit(&mut refs);
+        let is_cte = |name: &str| refs.ctes.iter().any(|c| c.eq_ignore_ascii_case(name));
+        let statement_tables: Vec<TableRef> = refs.tables.iter().filter(|t| !is_cte(&t.name)).cloned().collect();
+        for (qualifier, name) in refs.columns {
+            // `o.id` -> ORDERS when o is an alias; `dbo.ORDERS.id` keeps its qualifier
+            let table = (!qualifier.is_empty()).then(|| {
+                let qualifier = qualifier.join(".");
+                statement_tables.iter()
+                    .find(|t| t.alias.as_deref().map_or(false, |a| a.eq_ignore_ascii_case(&qualifier)))
+                    .map_or(qualifier, |t| t.name.clone())
+            });
+            let column = ColumnRef { table, name };
+            if !analysis.columns.contains(&column) {
+                analysis.columns.push(column);
+            }
+        }
+        for table in statement_tables {
+            if !analysis.tables.contains(&table) {
+                analysis.tables.push(table);
+            }
+        }
+    }
+    analysis
+}
+
+#[tauri::command]
+pub fn analyze_sql(sql: String, dialect: String) -> SqlAnalysis {
+    analyze(&sql, &dialect)
+}
+
+#[tauri::command]
+pub fn validate_sql(sql: String, dialect: String) -> Vec<SyntaxError> {
+    syntax_errors(&sql, &dialect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(syntax_errors("SELECT TOP 10 * FROM [受注] WHERE id = 1", "mssql").is_empty());
+        let sql = "SELECT 1;\n-- 確認\nSELECT a\nFROM t\nWHERE a = = 1";
+        let errors = syntax_errors(sql, "postgres");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Expected an expression:, found: =");
+        assert_eq!(&sql[errors[0].statement_start..errors[0].offset], "-- 確認\nSELECT a\nFROM t\nWHERE a = ");
+        assert_eq!(syntax_errors("SELECT * FROM t WHERE", "mysql")[0].offset, 21);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let sql = "WITH recent AS (SELECT CUSTOMER_ID FROM ORDERS WHERE ORDER_DATE > @from)\n\
+                   SELECT c.NAME, r.CUSTOMER_ID FROM dbo.CUSTOMER c JOIN recent r ON r.CUSTOMER_ID = c.ID";
+        let analysis = analyze(sql, "mssql");
+        let tables: Vec<(&str, Option<&str>)> = analysis.tables.iter().map(|t| (t.name.as_str(), t.alias.as_deref())).collect();
+        assert_eq!(tables, vec![("ORDERS", None), ("dbo.CUSTOMER", Some("c"))]);
+        let columns: Vec<String> = analysis.columns.iter().map(|c| format!("{}.{}", c.table.as_deref().unwrap_or("?"), c.name)).collect();
+        assert_eq!(columns, vec!["?.CUSTOMER_ID", "?.ORDER_DATE", "dbo.CUSTOMER.NAME", "r.CUSTOMER_ID", "dbo.CUSTOMER.ID"]);
+        assert_eq!(analysis.parse_error, None);
+
+        let fallback = analyze("INSERT INTO LOG SELECT * FROM T1 WITH (NOLOCK) OPTION (MAXDOP 1)", "mssql");
+        assert!(fallback.parse_error.is_some());
+        assert_eq!(fallback.tables.len(), 2);
+    }
+}
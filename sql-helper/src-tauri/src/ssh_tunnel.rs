@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+use russh::client;
+use russh::keys::{check_known_hosts, load_secret_key, ssh_key, PrivateKeyWithHashAlg};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use crate::{db, DbConfig};
+
+// Port-forward through a jump host: a local listener on 127.0.0.1 whose connections are
+// relayed over `direct-tcpip` channels to the DB host as seen from the jump host.
+
+const DEFAULT_SSH_PORT: u16 = 22;
+
+struct TunnelClient {
+    host: String,
+    port: u16,
+    /// `DbConfig::ssh_host_key`
+    pinned: Option<String>,
+    /// Why the host key was refused; shown instead of russh's generic error.
+    rejected: Arc<Mutex<Option<String>>>,
+}
+
+/// Fingerprints compare with or without the `SHA256:` prefix and base64 padding.
+fn same_fingerprint(pinned: &str, actual: &str) -> bool {
+    let bare = |f: &str| f.trim().trim_start_matches("SHA256:").trim_end_matches('=').to_string();
+    bare(pinned) == bare(actual)
+}
+
+impl TunnelClient {
+    /// The key must match the pinned fingerprint, or `~/.ssh/known_hosts` without one. Unknown
+    /// keys are refused too: the error carries the fingerprint so the user can check it and pin it.
+    fn verify(&self, key: &ssh_key::PublicKey) -> Result<(), String> {
+        let fingerprint = key.fingerprint(ssh_key::HashAlg::Sha256).to_string();
+        match self.pinned.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            Some(pinned) if same_fingerprint(pinned, &fingerprint) => Ok(()),
+            Some(_) => Err(format!("Host key của {} không khớp ssh_host_key đã lưu (máy chủ gửi {})", self.host, fingerprint)),
+            None => match check_known_hosts(&self.host, self.port, key) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("Host key của {} chưa có trong known_hosts: {}. Hãy kiểm tra rồi lưu vào ssh_host_key", self.host, fingerprint)),
+                Err(e) => Err(format!("Host key của {} khác với known_hosts ({}), có thể đang bị giả mạo", self.host, e)),
+            },
+        }
+    }
+}
+
+impl client::Handler for TunnelClient {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, key: &ssh_key::PublicKey) -> Result<bool, Self::Error> {
+        match self.verify(key) {
+            Ok(()) => Ok(true),
+            Err(reason) => {
+                *self.rejected.lock().unwrap() = Some(reason);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Keeps the forward alive; dropping it closes the listener and the SSH session.
+pub struct Tunnel {
+    pub local_port: u16,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// `jump` / `jump:2222` -> (host, port)
+fn split_host_port(value: &str) -> (String, u16) {
+    match value.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => match port.parse() {
+            Ok(p) => (host.to_string(), p),
+            Err(_) => (value.to_string(), DEFAULT_SSH_PORT),
+        },
+        _ => (value.to_string(), DEFAULT_SSH_PORT),
+    }
+}
+
+/// The DB port when `port` is left at 0 in the saved connection.
+fn target_port(config: &DbConfig) -> u16 {
+    if config.port != 0 {
+        return config.port;
+    }
+    match config.db_type.as_str() {
+        "mysql" => 3306,
+        "postgres" => 5432,
+        "oracle" => 1521,
+        _ => 1433,
+    }
+}
+
+async fn open(config: &DbConfig, jump: &str) -> Result<Tunnel, String> {
+    let user = config.ssh_user.as_deref().filter(|u| !u.trim().is_empty()).ok_or("Thiếu ssh_user cho SSH tunnel")?;
+    let key_path = config.ssh_key.as_deref().filter(|k| !k.trim().is_empty()).ok_or("Thiếu ssh_key cho SSH tunnel")?;
+    let key = load_secret_key(key_path, None).map_err(|e| format!("Không đọc được SSH key: {}", e))?;
+
+    let (ssh_host, ssh_port) = split_host_port(jump);
+    let ssh_config = Arc::new(client::Config::default());
+    let rejected = Arc::new(Mutex::new(None));
+    let handler = TunnelClient { host: ssh_host.clone(), port: ssh_port, pinned: config.ssh_host_key.clone(), rejected: rejected.clone() };
+    let mut session = client::connect(ssh_config, (ssh_host.as_str(), ssh_port), handler)
+        .await
+        .map_err(|e| match rejected.lock().unwrap().take() {
+            Some(reason) => reason,
+            None => format!("Lỗi kết nối SSH {}:{}: {}", ssh_host, ssh_port, e),
+        })?;
+    let hash_alg = session.best_supported_rsa_hash().await.map_err(|e| e.to_string())?.flatten();
+    let auth = session
+        .authenticate_publickey(user, PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !auth.success() {
+        return Err(format!("Xác thực SSH thất bại cho {}@{}", user, ssh_host));
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await.map_err(|e| e.to_string())?;
+    let local_port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let (target_host, target_port) = (db::split_instance(&config.host).0.to_string(), target_port(config));
+    let session = Arc::new(session);
+
+    let task = tokio::spawn(async move {
+        while let Ok((mut socket, peer)) = listener.accept().await {
+            let session = session.clone();
+            let target_host = target_host.clone();
+            tokio::spawn(async move {
+                let channel = match session
+                    .channel_open_direct_tcpip(target_host, target_port as u32, peer.ip().to_string(), peer.port() as u32)
+                    .await
+                {
+                    Ok(c) => c,
+                    Err(_) => return,
+                };
+                let mut stream = channel.into_stream();
+                tokio::io::copy_bidirectional(&mut socket, &mut stream).await.ok();
+            });
+        }
+    });
+    Ok(Tunnel { local_port, task })
+}
+
+/// Config to actually connect with: unchanged without `ssh_host`, otherwise pointed at a fresh
+/// local forward, with `ssh_host` cleared so connecting with it does not tunnel again. Keep the
+/// returned tunnel alive for as long as the connection is used; the `db` connect functions
+/// call this themselves.
+/// (MSSQL over a tunnel usually needs `trust_server_certificate`, since the certificate names the real host.)
+pub async fn tunneled(config: &DbConfig) -> Result<(DbConfig, Option<Tunnel>), String> {
+    let jump = match config.ssh_host.as_deref().map(str::trim).filter(|h| !h.is_empty()) {
+        Some(h) => h.to_string(),
+        None => return Ok((config.clone(), None)),
+    };
+    // The SQL Browser answers over UDP, which the forward cannot carry
+    if config.db_type == "mssql" && config.port == 0 && db::split_instance(&config.host).1.is_some() {
+        return Err("Named instance qua SSH tunnel: hãy nhập port TCP của instance".to_string());
+    }
+    let tunnel = open(config, &jump).await?;
+    let mut local = config.clone();
+    local.host = "127.0.0.1".to_string();
+    local.port = tunnel.local_port;
+    local.ssh_host = None;
+    Ok((local, Some(tunnel)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("jump.example.com"), ("jump.example.com".to_string(), 22));
+        assert_eq!(split_host_port("10.0.0.5:2222"), ("10.0.0.5".to_string(), 2222));
+        let config = DbConfig { db_type: "postgres".to_string(), ..Default::default() };
+        assert_eq!(target_port(&config), 5432);
+    }
+
+    #[test]
+    fn test_same_fingerprint() {
+        let actual = "SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8";
+        assert!(same_fingerprint(actual, actual));
+        assert!(same_fingerprint(" nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8= ", actual));
+        assert!(!same_fingerprint("SHA256:AAAAg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8", actual));
+        assert!(!same_fingerprint("", actual));
+    }
+}
@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Returns `<app data dir>/<name>`, creating it if needed.
+pub fn app_data_subdir(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let base = handle.path_resolver().app_data_dir().ok_or("Could not find app data dir")?;
+    let dir = base.join(name);
+    fs::create_dir_all(&dir).map_err(|e: std::io::Error| e.to_string())?;
+    Ok(dir)
+}
+
+/// Turns a user supplied name into something safe to use as a file name on Windows.
+pub fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name.trim()
+        .chars()
+        .map(|c| if c.is_control() || "<>:\"/\\|?*".contains(c) { '_' } else { c })
+        .collect();
+    if cleaned.is_empty() { "untitled".to_string() } else { cleaned }
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value).map_err(|e: serde_json::Error| e.to_string())?;
+    fs::write(path, content).map_err(|e: std::io::Error| e.to_string())
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = fs::read_to_string(path).map_err(|e: std::io::Error| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e: serde_json::Error| e.to_string())
+}
+
+/// Names (without extension) of the `.json` files in `dir`, sorted.
+pub fn list_json_names(dir: &Path) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e: std::io::Error| e.to_string())? {
+        let path = entry.map_err(|e: std::io::Error| e.to_string())?.path();
+        if path.extension().map_or(false, |e| e == "json") {
+            if let Some(stem) = path.file_stem() {
+                names.push(stem.to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+use serde::Serialize;
+use tauri::State;
+use crate::profiles::ActiveProfile;
+use crate::result_diff::{self, ResultDiff};
+use crate::tasks::BackgroundTasks;
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+const MIN_INTERVAL_SECS: u64 = 1;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WatchSnapshot {
+    pub watch_id: String,
+    pub result: QueryResult,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WatchChanges {
+    pub watch_id: String,
+    pub polled_at: String,
+    pub diff: ResultDiff,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WatchError {
+    pub watch_id: String,
+    pub error: String,
+}
+
+/// Accepts either a table name or a full query.
+pub fn watch_query(query_or_table: &str) -> String {
+    let trimmed = query_or_table.trim().trim_end_matches(';');
+    if trimmed.contains(char::is_whitespace) {
+        trimmed.to_string()
+    } else {
+        format!("SELECT * FROM {}", trimmed)
+    }
+}
+
+/// Re-runs the query every `interval_secs`, emitting `watch://snapshot` for the first poll and
+/// `watch://changes` whenever rows are added, changed or removed. Poll errors are reported
+/// as `watch://error` without stopping the watcher.
+#[tauri::command]
+pub async fn watch_table(window: tauri::Window, tasks: State<'_, BackgroundTasks>, profile: State<'_, ActiveProfile>, config: DbConfig, query_or_table: String, interval_secs: u64, key_columns: Vec<String>) -> Result<String, String> {
+    let query = watch_query(&query_or_table);
+    profile.check_sql(&query, &config)?;
+    // Polling a dangerous statement would repeat it on every tick; it is never confirmed here
+    query_guard::check(&config, &query, false)?;
+    // First poll runs inline so an invalid query fails the command instead of the background task
+    let first = db::run_query(&config, &query).await?;
+    for k in &key_columns {
+        crate::result_ops::column_index(&first, k)?;
+    }
+
+    let watch_id = tasks.next_id("watch");
+    let id = watch_id.clone();
+    window.emit("watch://snapshot", WatchSnapshot { watch_id: id.clone(), result: first.clone() }).ok();
+
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut previous = first;
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs.max(MIN_INTERVAL_SECS)));
+        ticker.tick().await; // the first tick fires immediately
+        loop {
+            ticker.tick().await;
+            match db::run_query(&config, &query).await {
+                Ok(current) => {
+                    match result_diff::diff(&previous, &current, &key_columns) {
+                        Ok(diff) if diff.has_changes() => {
+                            window.emit("watch://changes", WatchChanges {
+                                watch_id: id.clone(),
+                                polled_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                                diff,
+                            }).ok();
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            window.emit("watch://error", WatchError { watch_id: id.clone(), error: e }).ok();
+                        }
+                    }
+                    previous = current;
+                }
+                Err(e) => {
+                    window.emit("watch://error", WatchError { watch_id: id.clone(), error: e }).ok();
+                }
+            }
+        }
+    });
+    tasks.register(watch_id.clone(), handle);
+    Ok(watch_id)
+}
+
+#[tauri::command]
+pub fn unwatch_table(tasks: State<'_, BackgroundTasks>, watch_id: String) -> bool {
+    tasks.cancel(&watch_id)
+}
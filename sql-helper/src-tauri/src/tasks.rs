@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::State;
+
+/// Long-running backend tasks (subscriptions, watchers, ...) that the frontend can cancel by id.
+#[derive(Default)]
+pub struct BackgroundTasks {
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+    counter: AtomicU64,
+}
+
+impl BackgroundTasks {
+    pub fn next_id(&self, prefix: &str) -> String {
+        let id = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        format!("{}-{}", prefix, id)
+    }
+
+    pub fn register(&self, id: String, handle: JoinHandle<()>) {
+        if let Some(previous) = self.tasks.lock().unwrap().insert(id, handle) {
+            previous.abort();
+        }
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tasks.lock().unwrap().remove(id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        for (_, handle) in self.tasks.lock().unwrap().drain() {
+            handle.abort();
+        }
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.tasks.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+#[tauri::command]
+pub fn list_background_tasks(tasks: State<'_, BackgroundTasks>) -> Vec<String> {
+    tasks.ids()
+}
+
+#[tauri::command]
+pub fn cancel_background_task(tasks: State<'_, BackgroundTasks>, id: String) -> bool {
+    tasks.cancel(&id)
+}
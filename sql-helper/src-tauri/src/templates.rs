@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+use crate::dialect;
+use crate::history::QueryHistory;
+use crate::profiles::ActiveProfile;
+use crate::storage::{self, app_data_subdir};
+use crate::{db, query_guard, DbConfig, QueryResult};
+
+pub const TEMPLATES_DIR: &str = "templates";
+const TEMPLATES_FILE: &str = "query_templates.json";
+
+/// Saved SQL with typed placeholders such as `{order_id:int}` or `{from:date}`.
+/// Append `[]` to the type (`{ids:int[]}`) to accept a list rendered as `a, b, c`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueryTemplate {
+    pub id: String,
+    pub name: String,
+    pub sql: String,
+    pub description: Option<String>,
+    /// Key combination that runs this favorite, e.g. `Ctrl+Shift+1` (stored normalized).
+    #[serde(default)]
+    pub shortcut: Option<String>,
+    /// Connection id used when the favorite is run from its shortcut.
+    #[serde(default)]
+    pub default_connection_id: Option<String>,
+}
+
+/// What the UI needs after a shortcut fires: the favorite, the values to prompt for and where it runs.
+#[derive(Serialize, Clone, Debug)]
+pub struct ShortcutAction {
+    pub template: QueryTemplate,
+    pub placeholders: Vec<Placeholder>,
+    pub connection: Option<DbConfig>,
+}
+
+const MODIFIERS: [&str; 4] = ["Ctrl", "Alt", "Shift", "Meta"];
+
+/// Canonical `Ctrl+Alt+Shift+Meta+Key` form so `shift+ctrl+k` and `Ctrl+Shift+K` match.
+pub fn normalize_shortcut(shortcut: &str) -> Result<String, String> {
+    let mut modifiers = [false; 4];
+    let mut key = None;
+    for part in shortcut.split('+').map(str::trim).filter(|p| !p.is_empty()) {
+        let modifier = match part.to_lowercase().as_str() {
+            "ctrl" | "control" => Some(0),
+            "alt" | "option" => Some(1),
+            "shift" => Some(2),
+            "meta" | "cmd" | "command" | "win" => Some(3),
+            _ => None,
+        };
+        match modifier {
+            Some(i) => modifiers[i] = true,
+            None if key.is_none() => key = Some(if part.chars().count() == 1 { part.to_uppercase() } else { part.to_string() }),
+            None => return Err(format!("Phím tắt không hợp lệ: {}", shortcut)),
+        }
+    }
+    let key = key.ok_or_else(|| format!("Phím tắt không hợp lệ: {}", shortcut))?;
+    let mut parts: Vec<String> = MODIFIERS.iter().zip(modifiers).filter(|(_, on)| *on).map(|(m, _)| m.to_string()).collect();
+    parts.push(key);
+    Ok(parts.join("+"))
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Placeholder {
+    pub name: String,
+    pub kind: String,
+    pub is_list: bool,
+}
+
+/// (byte range, placeholder) for every `{name:type}` in `sql`.
+pub fn scan_placeholders(sql: &str) -> Vec<(std::ops::Range<usize>, Placeholder)> {
+    let mut found = Vec::new();
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' {
+            if let Some(len) = sql[i + 1..].find('}') {
+                let inner = &sql[i + 1..i + 1 + len];
+                if let Some((name, kind)) = inner.split_once(':') {
+                    let valid_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+                    if valid_name && !kind.is_empty() {
+                        let (kind, is_list) = match kind.strip_suffix("[]") {
+                            Some(k) => (k, true),
+                            None => (kind, false),
+                        };
+                        found.push((i..i + len + 2, Placeholder { name: name.to_string(), kind: kind.to_lowercase(), is_list }));
+                        i += len + 2;
+                        continue;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+pub fn placeholders(sql: &str) -> Vec<Placeholder> {
+    let mut unique: Vec<Placeholder> = Vec::new();
+    for (_, p) in scan_placeholders(sql) {
+        if !unique.iter().any(|u| u.name == p.name) {
+            unique.push(p);
+        }
+    }
+    unique
+}
+
+fn render_scalar(db_type: &str, p: &Placeholder, value: &Value) -> Result<String, String> {
+    if value.is_null() {
+        return Ok("NULL".to_string());
+    }
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    };
+    let invalid = || format!("Giá trị không hợp lệ cho {{{}:{}}}: {}", p.name, p.kind, text);
+    match p.kind.as_str() {
+        "int" | "integer" | "long" => text.parse::<i64>().map(|n| n.to_string()).map_err(|_| invalid()),
+        "decimal" | "number" | "float" => {
+            text.parse::<f64>().map_err(|_| invalid())?;
+            Ok(text)
+        }
+        "bool" | "boolean" => match text.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(dialect::quote_bool(db_type, true)),
+            "false" | "0" | "no" => Ok(dialect::quote_bool(db_type, false)),
+            _ => Err(invalid()),
+        },
+        "date" => {
+            chrono::NaiveDate::parse_from_str(&text, "%Y-%m-%d").map_err(|_| invalid())?;
+            Ok(format!("'{}'", text))
+        }
+        "datetime" | "timestamp" => {
+            chrono::NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S").map_err(|_| invalid())?;
+            Ok(format!("'{}'", text))
+        }
+        "string" | "text" | "str" => Ok(dialect::quote_string(db_type, match value { Value::String(s) => s, _ => &text })),
+        "ident" => Ok(dialect::quote_qualified(db_type, &text)),
+        other => Err(format!("Kiểu placeholder không hỗ trợ: {}", other)),
+    }
+}
+
+/// Validates `values` against the placeholder types and substitutes them with dialect-correct literals.
+pub fn render(sql: &str, values: &HashMap<String, Value>, db_type: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for (range, p) in scan_placeholders(sql) {
+        let value = values.get(&p.name).ok_or_else(|| format!("Thiếu giá trị cho {{{}}}", p.name))?;
+        let rendered = if p.is_list {
+            let items = match value {
+                Value::Array(items) if !items.is_empty() => items.clone(),
+                Value::Array(_) => return Err(format!("Danh sách {{{}}} rỗng", p.name)),
+                other => vec![other.clone()],
+            };
+            items.iter().map(|v| render_scalar(db_type, &p, v)).collect::<Result<Vec<_>, _>>()?.join(", ")
+        } else {
+            render_scalar(db_type, &p, value)?
+        };
+        out.push_str(&sql[last..range.start]);
+        out.push_str(&rendered);
+        last = range.end;
+    }
+    out.push_str(&sql[last..]);
+    Ok(out)
+}
+
+fn templates_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_subdir(handle, TEMPLATES_DIR)?.join(TEMPLATES_FILE))
+}
+
+pub fn load_templates(handle: &tauri::AppHandle) -> Result<Vec<QueryTemplate>, String> {
+    let path = templates_path(handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    storage::read_json(&path)
+}
+
+fn default_connection(handle: &tauri::AppHandle, template: &QueryTemplate) -> Result<Option<DbConfig>, String> {
+    let id = match template.default_connection_id.as_deref() {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    Ok(crate::load_db_settings(handle.clone())?.connections.into_iter().find(|c| c.id == id))
+}
+
+pub fn find_template(handle: &tauri::AppHandle, id: &str) -> Result<QueryTemplate, String> {
+    load_templates(handle)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Không tìm thấy template: {}", id))
+}
+
+#[tauri::command]
+pub fn list_templates(handle: tauri::AppHandle) -> Result<Vec<QueryTemplate>, String> {
+    load_templates(&handle)
+}
+
+/// Inserts or replaces (by id) a template.
+#[tauri::command]
+pub fn save_template(handle: tauri::AppHandle, mut template: QueryTemplate) -> Result<Vec<Placeholder>, String> {
+    let mut templates = load_templates(&handle)?;
+    let found = placeholders(&template.sql);
+    template.shortcut = template.shortcut.as_deref().filter(|s| !s.trim().is_empty()).map(normalize_shortcut).transpose()?;
+    if let Some(shortcut) = &template.shortcut {
+        if let Some(other) = templates.iter().find(|t| t.id != template.id && t.shortcut.as_ref() == Some(shortcut)) {
+            return Err(format!("Phím tắt {} đã được gán cho '{}'", shortcut, other.name));
+        }
+    }
+    match templates.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template,
+        None => templates.push(template),
+    }
+    storage::write_json(&templates_path(&handle)?, &templates)?;
+    Ok(found)
+}
+
+#[tauri::command]
+pub fn delete_template(handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    let mut templates = load_templates(&handle)?;
+    templates.retain(|t| t.id != id);
+    storage::write_json(&templates_path(&handle)?, &templates)
+}
+
+/// Looks up the favorite bound to a key press; None when the keys are not bound.
+#[tauri::command]
+pub fn resolve_template_shortcut(handle: tauri::AppHandle, shortcut: String) -> Result<Option<ShortcutAction>, String> {
+    let shortcut = normalize_shortcut(&shortcut)?;
+    let template = match load_templates(&handle)?.into_iter().find(|t| t.shortcut.as_ref() == Some(&shortcut)) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let connection = default_connection(&handle, &template)?;
+    Ok(Some(ShortcutAction { placeholders: placeholders(&template.sql), connection, template }))
+}
+
+#[tauri::command]
+pub fn get_template_placeholders(sql: String) -> Vec<Placeholder> {
+    placeholders(&sql)
+}
+
+#[tauri::command]
+pub fn render_template(handle: tauri::AppHandle, id: String, values: HashMap<String, Value>, db_type: String) -> Result<String, String> {
+    let template = find_template(&handle, &id)?;
+    render(&template.sql, &values, &db_type)
+}
+
+#[tauri::command]
+/// Without `config` the template's default connection is used.
+pub async fn run_template(handle: tauri::AppHandle, profile: State<'_, ActiveProfile>, history: State<'_, QueryHistory>, config: Option<DbConfig>, id: String, values: HashMap<String, Value>, confirm_dangerous: Option<bool>) -> Result<QueryResult, String> {
+    let template = find_template(&handle, &id)?;
+    let config = match config {
+        Some(c) => c,
+        None => default_connection(&handle, &template)?
+            .ok_or_else(|| format!("Template '{}' chưa có kết nối mặc định", template.name))?,
+    };
+    let sql = render(&template.sql, &values, &config.db_type)?;
+    profile.check_sql(&sql, &config)?;
+    query_guard::check(&config, &sql, confirm_dangerous.unwrap_or(false))?;
+    let started = std::time::Instant::now();
+    let result = db::run_query(&config, &sql).await;
+    history.record(&config, &sql, started.elapsed(), &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_placeholders() {
+        let sql = "SELECT * FROM T WHERE ID = {id:int} AND D >= {from:date} AND ID2 = {id:int} AND X = '{not a placeholder}'";
+        let found = placeholders(sql);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], Placeholder { name: "id".to_string(), kind: "int".to_string(), is_list: false });
+    }
+
+    #[test]
+    fn test_render_per_dialect() {
+        let sql = "SELECT * FROM T WHERE NAME = {name:string} AND ID IN ({ids:int[]}) AND ACTIVE = {active:bool}";
+        let values: HashMap<String, Value> = [
+            ("name".to_string(), json!("山田'")),
+            ("ids".to_string(), json!([1, "2"])),
+            ("active".to_string(), json!(true)),
+        ].into_iter().collect();
+        assert_eq!(
+            render(sql, &values, "mssql").unwrap(),
+            "SELECT * FROM T WHERE NAME = N'山田''' AND ID IN (1, 2) AND ACTIVE = 1"
+        );
+        assert_eq!(
+            render(sql, &values, "postgres").unwrap(),
+            "SELECT * FROM T WHERE NAME = '山田''' AND ID IN (1, 2) AND ACTIVE = TRUE"
+        );
+    }
+
+    #[test]
+    fn test_normalize_shortcut() {
+        assert_eq!(normalize_shortcut("shift+ctrl+k").unwrap(), "Ctrl+Shift+K");
+        assert_eq!(normalize_shortcut("Alt + F9").unwrap(), "Alt+F9");
+        assert!(normalize_shortcut("Ctrl+Shift").is_err());
+        assert!(normalize_shortcut("Ctrl+A+B").is_err());
+    }
+
+    #[test]
+    fn test_render_rejects_bad_values() {
+        let values: HashMap<String, Value> = [("id".to_string(), json!("1 OR 1=1"))].into_iter().collect();
+        assert!(render("SELECT {id:int}", &values, "mssql").is_err());
+        assert!(render("SELECT {missing:int}", &values, "mssql").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use serde::Deserialize;
+use crate::result_ops::NULL_CELL;
+use crate::QueryResult;
+
+const HALF_KANA: &str = "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝﾞﾟ";
+const FULL_KANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+const HALF_DAKUTEN: char = 'ﾞ';
+const HALF_HANDAKUTEN: char = 'ﾟ';
+const IDEOGRAPHIC_SPACE: char = '\u{3000}';
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Width {
+    Half,
+    Full,
+}
+
+/// What to apply to each cell; everything is off by default.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct NormalizeOptions {
+    /// Trim ASCII and full-width (U+3000) spaces at both ends.
+    #[serde(default)]
+    pub trim: bool,
+    /// Convert ASCII letters, digits, symbols and spaces.
+    #[serde(default)]
+    pub alnum: Option<Width>,
+    /// Convert katakana (half-width ｶﾞ <-> full-width ガ).
+    #[serde(default)]
+    pub katakana: Option<Width>,
+}
+
+fn kana_index(table: &str, c: char) -> Option<usize> {
+    table.chars().position(|k| k == c)
+}
+
+fn kana_at(table: &str, i: usize) -> char {
+    table.chars().nth(i).unwrap()
+}
+
+/// ｶ + ﾞ -> ガ, ﾊ + ﾟ -> パ, ｳ + ﾞ -> ヴ
+fn combine_voiced(base: char, mark: char) -> Option<char> {
+    let voiced = |c: char| ('カ'..='ト').contains(&c) || ('ハ'..='ホ').contains(&c);
+    let semi_voiced = ('ハ'..='ホ').contains(&base);
+    match mark {
+        HALF_DAKUTEN if base == 'ウ' => Some('ヴ'),
+        // Plain kana in these rows are directly followed by their voiced form (small ッ excepted)
+        HALF_DAKUTEN if voiced(base) && base != 'ッ' => char::from_u32(base as u32 + 1),
+        HALF_HANDAKUTEN if semi_voiced => char::from_u32(base as u32 + 2),
+        _ => None,
+    }
+}
+
+/// Reverse of `combine_voiced`: ガ -> (カ, ﾞ)
+fn split_voiced(c: char) -> Option<(char, char)> {
+    if c == 'ヴ' {
+        return Some(('ウ', HALF_DAKUTEN));
+    }
+    [(1, HALF_DAKUTEN), (2, HALF_HANDAKUTEN)].iter().find_map(|&(offset, mark)| {
+        let base = char::from_u32((c as u32).checked_sub(offset)?)?;
+        let plain = kana_index(FULL_KANA, base).is_some();
+        (plain && combine_voiced(base, mark) == Some(c)).then(|| (base, mark))
+    })
+}
+
+fn kana_to_full(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match kana_index(HALF_KANA, c) {
+            Some(i) => {
+                let full = kana_at(FULL_KANA, i);
+                match chars.peek().and_then(|&m| combine_voiced(full, m)) {
+                    Some(voiced) => {
+                        chars.next();
+                        out.push(voiced);
+                    }
+                    None => out.push(full),
+                }
+            }
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+fn kana_to_half(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some(i) = kana_index(FULL_KANA, c) {
+            out.push(kana_at(HALF_KANA, i));
+        } else if let Some((base, mark)) = split_voiced(c) {
+            match kana_index(FULL_KANA, base) {
+                Some(i) => {
+                    out.push(kana_at(HALF_KANA, i));
+                    out.push(mark);
+                }
+                None => out.push(c),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn alnum_to_half(text: &str) -> String {
+    text.chars().map(|c| match c {
+        IDEOGRAPHIC_SPACE => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }).collect()
+}
+
+fn alnum_to_full(text: &str) -> String {
+    text.chars().map(|c| match c {
+        ' ' => IDEOGRAPHIC_SPACE,
+        '!'..='~' => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+        _ => c,
+    }).collect()
+}
+
+pub fn normalize_text(text: &str, options: &NormalizeOptions) -> String {
+    let mut value = text.to_string();
+    if let Some(width) = options.katakana {
+        value = match width {
+            Width::Full => kana_to_full(&value),
+            Width::Half => kana_to_half(&value),
+        };
+    }
+    if let Some(width) = options.alnum {
+        value = match width {
+            Width::Half => alnum_to_half(&value),
+            Width::Full => alnum_to_full(&value),
+        };
+    }
+    if options.trim {
+        value = value.trim_matches(|c: char| c == ' ' || c == IDEOGRAPHIC_SPACE || c == '\t').to_string();
+    }
+    value
+}
+
+/// Normalizes every cell in place, leaving NULL markers untouched.
+pub fn normalize_result(result: &mut QueryResult, options: &NormalizeOptions) {
+    for row in result.rows.iter_mut() {
+        for cell in row.iter_mut() {
+            if cell != NULL_CELL {
+                *cell = normalize_text(cell, options);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn normalize_cells(mut result: QueryResult, options: NormalizeOptions) -> QueryResult {
+    normalize_result(&mut result, &options);
+    result
+}
+
+#[tauri::command]
+pub fn normalize_value(text: String, options: NormalizeOptions) -> String {
+    normalize_text(&text, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(trim: bool, alnum: Option<Width>, katakana: Option<Width>) -> NormalizeOptions {
+        NormalizeOptions { trim, alnum, katakana }
+    }
+
+    #[test]
+    fn test_trim_fullwidth_spaces() {
+        assert_eq!(normalize_text("山田\u{3000}\u{3000} ", &opts(true, None, None)), "山田");
+    }
+
+    #[test]
+    fn test_alnum_width() {
+        assert_eq!(normalize_text("ＡＢＣ１２３！", &opts(false, Some(Width::Half), None)), "ABC123!");
+        assert_eq!(normalize_text("A1 ", &opts(false, Some(Width::Full), None)), "Ａ１\u{3000}");
+    }
+
+    #[test]
+    fn test_katakana_width() {
+        assert_eq!(normalize_text("ｶﾞｷﾞﾊﾟﾝｳﾞｧｯﾄﾞ", &opts(false, None, Some(Width::Full))), "ガギパンヴァッド");
+        assert_eq!(normalize_text("ガギパンヴァッド", &opts(false, None, Some(Width::Half))), "ｶﾞｷﾞﾊﾟﾝｳﾞｧｯﾄﾞ");
+        assert_eq!(normalize_text("ヅボ", &opts(false, None, Some(Width::Half))), "ﾂﾞﾎﾞ");
+    }
+}
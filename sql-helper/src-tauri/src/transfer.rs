@@ -0,0 +1,215 @@
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use tiberius::TokenRow;
+use crate::db::MssqlClient;
+use crate::history::QueryHistory;
+use crate::import::{self, ColumnMapping, ImportConnection};
+use crate::profiles::{ActiveProfile, Permission};
+use crate::result_ops::NULL_CELL;
+use crate::schema::{self, ColumnInfo};
+use crate::shutdown::ActiveOperations;
+use crate::{db, failover, query_guard, DbConfig};
+
+// Copies the rows of a query on one connection into a table on another. Rows stream from the
+// source in batches and each batch is written as it arrives: TDS bulk load into SQL Server,
+// multi-row INSERTs elsewhere (the statements `import_csv` uses).
+
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TransferOptions {
+    /// Defaults to every source column whose name matches a table column (case-insensitive).
+    #[serde(default)]
+    pub mapping: Option<Vec<ColumnMapping>>,
+    /// Rows per bulk load request; INSERTs are further capped by the driver's parameter limit.
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// INSERTs even into SQL Server, e.g. for tables whose triggers must fire.
+    #[serde(default)]
+    pub no_bulk: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferProgress {
+    pub table: String,
+    pub rows_done: u64,
+    pub batch: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferSummary {
+    pub table: String,
+    pub rows_copied: u64,
+    pub batches: usize,
+    pub elapsed_ms: u128,
+}
+
+/// Bulk load rows carry every writable table column in ordinal order: the source column each
+/// one is read from (None stays NULL) and its declared type for `import::to_column_data`.
+type BulkLayout = Vec<(Option<usize>, String)>;
+
+fn bulk_layout(table_columns: &[ColumnInfo], mapped: &[(usize, ColumnInfo)]) -> BulkLayout {
+    import::bulk_columns(table_columns).into_iter()
+        .map(|(name, data_type)| (mapped.iter().find(|(_, m)| m.name == name).map(|(i, _)| *i), data_type))
+        .collect()
+}
+
+/// `first_row` counts the rows already written, for error messages.
+async fn bulk_load(client: &mut MssqlClient, table: &str, layout: &[(Option<usize>, String)], rows: &[Vec<String>], first_row: u64) -> Result<(), String> {
+    let mut req = client.bulk_insert(table).await.map_err(|e| e.to_string())?;
+    for (offset, record) in rows.iter().enumerate() {
+        let mut row = TokenRow::new();
+        for (source, data_type) in layout {
+            let value = source.and_then(|i| record.get(i)).map(String::as_str).unwrap_or(NULL_CELL);
+            let data = import::to_column_data(value, data_type)
+                .map_err(|e| format!("Dòng {}: {}", first_row + offset as u64 + 1, e))?;
+            row.push(data);
+        }
+        req.send(row).await.map_err(|e| e.to_string())?;
+    }
+    req.finalize().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn insert_rows(conn: &mut ImportConnection, db_type: &str, table: &str, mapped: &[(usize, ColumnInfo)], rows: &[Vec<String>], first_row: u64) -> Result<(), String> {
+    let columns: Vec<(String, String)> = mapped.iter().map(|(_, c)| (c.name.clone(), c.data_type.clone())).collect();
+    let mut line = first_row;
+    for chunk in rows.chunks(import::rows_per_statement(db_type, columns.len())) {
+        let mut params = Vec::with_capacity(chunk.len() * columns.len());
+        for record in chunk {
+            line += 1;
+            for (source, column) in mapped {
+                let cell = record.get(*source).map(String::as_str).unwrap_or(NULL_CELL);
+                let value = import::import_value(db_type, cell, &column.data_type, None)
+                    .map_err(|e| format!("Dòng {}, cột {}: {}", line, column.name, e))?;
+                params.push(value);
+            }
+        }
+        conn.execute(&import::insert_statement(db_type, table, &columns, chunk.len()), &params).await?;
+    }
+    Ok(())
+}
+
+/// Runs `query` on `source` and appends its rows (first result set) to `table` on `target`,
+/// emitting `transfer://progress` per batch. Batches already written stay when a later one
+/// fails; the error says how many rows were copied.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn copy_table_data(window: tauri::Window, ops: State<'_, ActiveOperations>, profile: State<'_, ActiveProfile>, source: DbConfig, query: String, target: DbConfig, table: String, options: Option<TransferOptions>) -> Result<TransferSummary, String> {
+    profile.check_sql(&query, &source)?;
+    query_guard::check(&source, &query, false)?;
+    profile.require_for(&target, Permission::Import)?;
+    let _op = ops.begin();
+    let started = Instant::now();
+    let options = options.unwrap_or_default();
+    let table_columns = schema::describe_table(target.clone(), None, table.clone()).await?;
+    let mut conn = ImportConnection::open(&target).await?;
+    let bulk = target.db_type == "mssql" && !options.no_bulk && !table_columns.iter().any(|c| !c.generated && import::bulk_unsupported(&c.data_type));
+
+    let (sender, receiver) = db::batch_channel();
+    let batch_size = options.batch_size.filter(|b| *b > 0).unwrap_or(DEFAULT_BATCH_SIZE);
+    let fetch = db::FetchOptions { batch_sink: Some(sender), batch_size, canonical: true, ..Default::default() };
+    let read = async {
+        let result = match failover::connect(&source).await {
+            Ok(mut served) => served.run_query_with(&query, &fetch).await,
+            Err(e) => Err(e),
+        };
+        // Closes the channel so the writer below finishes
+        drop(fetch);
+        result
+    };
+    let write = async {
+        // Owned here so a failed write drops it, which stops the query
+        let mut receiver = receiver;
+        // Set from the first batch, which brings the source columns
+        let mut layout: Option<(Vec<(usize, ColumnInfo)>, BulkLayout)> = None;
+        let (mut rows_done, mut batches) = (0u64, 0usize);
+        while let Some(batch) = receiver.recv().await {
+            if batch.result_index > 0 || batch.rows.is_empty() {
+                continue;
+            }
+            if layout.is_none() {
+                let mapped = import::map_columns(&batch.columns, &table_columns, options.mapping.as_deref())?;
+                let bulk_columns = bulk_layout(&table_columns, &mapped);
+                layout = Some((mapped, bulk_columns));
+            }
+            if let Some((mapped, bulk_columns)) = &layout {
+                let written = match &mut conn {
+                    ImportConnection::Mssql(client) if bulk => bulk_load(client, &table, bulk_columns, &batch.rows, rows_done).await,
+                    conn => insert_rows(conn, &target.db_type, &table, mapped, &batch.rows, rows_done).await,
+                };
+                written.map_err(|e| format!("{} ({} dòng đã chép)", e, rows_done))?;
+            }
+            rows_done += batch.rows.len() as u64;
+            batches += 1;
+            window.emit("transfer://progress", TransferProgress { table: table.clone(), rows_done, batch: batches }).ok();
+        }
+        Ok::<(u64, usize), String>((rows_done, batches))
+    };
+    let (read, written) = tokio::join!(read, write);
+    // A write failure drops the receiver, which the query reports as cancelled; show the cause
+    let error = written.as_ref().err().or(read.as_ref().err()).cloned();
+    let rows = written.as_ref().ok().map(|(rows, _)| *rows).filter(|_| error.is_none());
+    window.state::<QueryHistory>().record_summary(&source, &query, started.elapsed(), rows, None, error);
+    let (rows_copied, batches) = written?;
+    read?;
+    Ok(TransferSummary { table, rows_copied, batches, elapsed_ms: started.elapsed().as_millis() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_params::ParamValue;
+
+    #[test]
+    fn test_bulk_layout() {
+        let column = |name: &str, data_type: &str, generated: bool| ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable: true,
+            default_value: None,
+            primary_key: false,
+            references: None,
+            generated,
+        };
+        let table = vec![column("ID", "int", false), column("NAME", "nvarchar(50)", false), column("NOTE", "NVARCHAR(max)", false)];
+        let mapped = vec![(2, column("ID", "int", false)), (0, column("NAME", "nvarchar(50)", false))];
+        assert_eq!(bulk_layout(&table, &mapped), vec![
+            (Some(2), "int".to_string()),
+            (Some(0), "nvarchar(50)".to_string()),
+            (None, "nvarchar(max)".to_string()),
+        ]);
+
+        // IDENTITY key and computed column: filled by the server, never sent
+        let table = vec![column("ID", "int", true), column("NAME", "nvarchar(50)", false), column("TOTAL", "money", true)];
+        let mapped = vec![(0, column("ID", "int", true)), (1, column("NAME", "nvarchar(50)", false))];
+        assert_eq!(bulk_layout(&table, &mapped), vec![(Some(1), "nvarchar(50)".to_string())]);
+    }
+
+    #[test]
+    fn test_canonical_cells() {
+        use chrono::{NaiveDate, TimeZone};
+        use tiberius::{ColumnData, IntoSql};
+
+        // Binary values longer than the grid preview arrive whole
+        let bytes: Vec<u8> = (0..40).collect();
+        assert!(db::binary_cell(&bytes, false).ends_with("... (40 bytes)"));
+        let cell = db::binary_cell(&bytes, true);
+        assert_eq!(import::to_column_data(&cell, "varbinary(max)"), Ok(ColumnData::Binary(Some(bytes.into()))));
+
+        // Datetimes keep their fractional seconds whatever the display format
+        let dt = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_nano_opt(10, 30, 15, 123_456_700).unwrap();
+        let cell = db::datetime_cell(&dt, true);
+        assert_eq!(cell, "2024-05-01 10:30:15.123456700");
+        assert_eq!(import::to_column_data(&cell, "datetime2(7)"), Ok(dt.into_sql()));
+        assert_eq!(import::import_value("postgres", &cell, "timestamp without time zone", None), Ok(ParamValue::DateTime(dt)));
+
+        // Values with an offset keep their local time in a column without one
+        let offset = chrono::FixedOffset::east_opt(7 * 3600).unwrap().from_local_datetime(&dt).unwrap();
+        let cell = db::datetime_offset_cell(&offset, true);
+        assert_eq!(cell, "2024-05-01 10:30:15.123456700 +07:00");
+        assert_eq!(import::to_column_data(&cell, "datetime2(7)"), Ok(dt.into_sql()));
+        assert_eq!(import::import_value("mssql", &cell, "datetime2", None), Ok(ParamValue::DateTime(dt)));
+    }
+}
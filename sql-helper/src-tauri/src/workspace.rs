@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::shutdown::{ActiveOperations, TEMP_DIR};
+
+// Size accounting and cleanup for the app data directory. Only the disposable folders
+// (temp/export files, spilled results, crash reports) are ever deleted; pinned snapshots,
+// templates, diagrams and recorded sessions are user data and only reported.
+
+pub const SPILL_DIR: &str = "spill";
+pub const CRASH_DIR: &str = "crash";
+const DISPOSABLE_DIRS: [&str; 3] = [TEMP_DIR, SPILL_DIR, CRASH_DIR];
+
+const DEFAULT_MAX_AGE_DAYS: u64 = 7;
+const DEFAULT_MAX_TOTAL_MB: u64 = 1024;
+
+/// Automatic cleanup of the disposable folders, applied on startup.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CleanupPolicy {
+    /// Delete files older than this; None keeps them regardless of age.
+    pub max_age_days: Option<u64>,
+    /// Then delete oldest first until the disposable folders fit; None means no cap.
+    pub max_total_mb: Option<u64>,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        CleanupPolicy { max_age_days: Some(DEFAULT_MAX_AGE_DAYS), max_total_mb: Some(DEFAULT_MAX_TOTAL_MB) }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CategoryUsage {
+    pub name: String,
+    pub bytes: u64,
+    pub files: usize,
+    /// Removed by `clean_workspace` and the cleanup policy.
+    pub disposable: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StorageUsage {
+    pub root: String,
+    pub total_bytes: u64,
+    pub categories: Vec<CategoryUsage>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CleanupReport {
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+fn data_root(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    handle.path_resolver().app_data_dir().ok_or_else(|| "Could not find app data dir".to_string())
+}
+
+/// Every file below `dir`, recursively. A missing directory is empty.
+pub fn collect_files(dir: &Path, out: &mut Vec<FileEntry>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.metadata() {
+            Ok(meta) if meta.is_dir() => collect_files(&path, out),
+            Ok(meta) => out.push(FileEntry { path, size: meta.len(), modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH) }),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Files to delete under `policy`: everything past the age limit, then the oldest of the rest
+/// until the total fits the size cap.
+pub fn plan_cleanup(files: &[FileEntry], policy: &CleanupPolicy, now: SystemTime) -> Vec<PathBuf> {
+    let max_age = policy.max_age_days.map(|d| Duration::from_secs(d * 24 * 60 * 60));
+    let expired = |f: &FileEntry| max_age.map_or(false, |age| now.duration_since(f.modified).unwrap_or_default() > age);
+
+    let mut doomed: Vec<PathBuf> = files.iter().filter(|f| expired(f)).map(|f| f.path.clone()).collect();
+    let mut kept: Vec<&FileEntry> = files.iter().filter(|f| !expired(f)).collect();
+    if let Some(cap) = policy.max_total_mb.map(|mb| mb * 1024 * 1024) {
+        kept.sort_by_key(|f| f.modified);
+        let mut total: u64 = kept.iter().map(|f| f.size).sum();
+        for f in kept {
+            if total <= cap {
+                break;
+            }
+            total -= f.size;
+            doomed.push(f.path.clone());
+        }
+    }
+    doomed
+}
+
+fn remove_files(files: &[FileEntry], paths: &[PathBuf]) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    for f in files.iter().filter(|f| paths.contains(&f.path)) {
+        if fs::remove_file(&f.path).is_ok() {
+            report.files_removed += 1;
+            report.bytes_freed += f.size;
+        }
+    }
+    report
+}
+
+fn disposable_files(root: &Path, categories: &[&str]) -> Vec<FileEntry> {
+    let mut files = Vec::new();
+    for name in categories {
+        collect_files(&root.join(name), &mut files);
+    }
+    files
+}
+
+/// Applies `policy` to the disposable folders; called once at startup.
+pub fn auto_cleanup(handle: &tauri::AppHandle, policy: &CleanupPolicy) -> Result<CleanupReport, String> {
+    let files = disposable_files(&data_root(handle)?, &DISPOSABLE_DIRS);
+    let doomed = plan_cleanup(&files, policy, SystemTime::now());
+    Ok(remove_files(&files, &doomed))
+}
+
+#[tauri::command]
+pub fn get_storage_usage(handle: tauri::AppHandle) -> Result<StorageUsage, String> {
+    let root = data_root(&handle)?;
+    let mut categories = Vec::new();
+    if let Ok(entries) = fs::read_dir(&root) {
+        for entry in entries.flatten().filter(|e| e.path().is_dir()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let mut files = Vec::new();
+            collect_files(&entry.path(), &mut files);
+            categories.push(CategoryUsage {
+                bytes: files.iter().map(|f| f.size).sum(),
+                files: files.len(),
+                disposable: DISPOSABLE_DIRS.contains(&name.as_str()),
+                name,
+            });
+        }
+    }
+    categories.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    Ok(StorageUsage {
+        root: root.to_string_lossy().to_string(),
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        categories,
+    })
+}
+
+/// Deletes everything in the disposable folders (or just `categories`). Refused while queries,
+/// imports or exports are running, since they may still be writing temp files.
+#[tauri::command]
+pub fn clean_workspace(handle: tauri::AppHandle, ops: State<'_, ActiveOperations>, categories: Option<Vec<String>>) -> Result<CleanupReport, String> {
+    if ops.count() > 0 {
+        return Err(format!("Đang có {} thao tác chạy, hãy thử lại sau", ops.count()));
+    }
+    let selected: Vec<&str> = match &categories {
+        Some(names) => {
+            if let Some(bad) = names.iter().find(|n| !DISPOSABLE_DIRS.contains(&n.as_str())) {
+                return Err(format!("Không thể dọn thư mục '{}'", bad));
+            }
+            names.iter().map(|n| n.as_str()).collect()
+        }
+        None => DISPOSABLE_DIRS.to_vec(),
+    };
+    let files = disposable_files(&data_root(&handle)?, &selected);
+    let all: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+    Ok(remove_files(&files, &all))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_cleanup() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 24 * 60 * 60);
+        let day = Duration::from_secs(24 * 60 * 60);
+        let file = |name: &str, mb: u64, days_old: u32| FileEntry { path: PathBuf::from(name), size: mb * 1024 * 1024, modified: now - day * days_old };
+        let files = vec![file("old", 1, 30), file("a", 600, 3), file("b", 600, 2), file("c", 10, 1)];
+
+        let policy = CleanupPolicy { max_age_days: Some(7), max_total_mb: Some(1024) };
+        assert_eq!(plan_cleanup(&files, &policy, now), vec![PathBuf::from("old"), PathBuf::from("a")]);
+
+        let policy = CleanupPolicy { max_age_days: None, max_total_mb: None };
+        assert!(plan_cleanup(&files, &policy, now).is_empty());
+    }
+}